@@ -0,0 +1,160 @@
+//! Contains the zstd compression of fixture files and `.tar.zst` archives, in pure Rust so that
+//! `fpt` does not depend on the `tar` and `zstd` binaries of the machine it runs on.
+//!
+//! The work is blocking, and is moved off of the async runtime's workers.
+
+use color_eyre::{eyre::eyre, Result};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+/// The zstd compression level of the files and archives written by `fpt`, as the `zstd` CLI.
+const COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+/// Compresses a file with zstd.
+///
+/// ## Takes
+/// - `src` - The file to compress.
+/// - `dst` - The path of the compressed file.
+pub(crate) async fn compress_file(src: &Path, dst: &Path) -> Result<()> {
+    let (src, dst) = (src.to_path_buf(), dst.to_path_buf());
+    blocking(move || {
+        let mut reader = BufReader::new(File::open(&src)?);
+        let mut encoder = zstd::Encoder::new(File::create(&dst)?, COMPRESSION_LEVEL)?;
+        io::copy(&mut reader, &mut encoder)?;
+        encoder.finish()?;
+        Ok(())
+    })
+    .await
+}
+
+/// Decompresses a zstd-compressed file.
+///
+/// ## Takes
+/// - `src` - The compressed file.
+/// - `dst` - The path of the decompressed file.
+pub(crate) async fn decompress_file(src: &Path, dst: &Path) -> Result<()> {
+    let (src, dst) = (src.to_path_buf(), dst.to_path_buf());
+    blocking(move || {
+        let mut decoder = zstd::Decoder::new(File::open(&src)?)?;
+        let mut writer = BufWriter::new(File::create(&dst)?);
+        io::copy(&mut decoder, &mut writer)?;
+        Ok(())
+    })
+    .await
+}
+
+/// Archives a directory into a `.tar.zst` archive. Symbolic links are archived as links.
+///
+/// ## Takes
+/// - `dir` - The directory to archive.
+/// - `prefix` - The path of the directory within the archive, e.g. `witness-db`, or `.` for its
+///   contents to be archived at the root.
+/// - `archive` - The path of the archive.
+pub(crate) async fn pack_dir(dir: &Path, prefix: &str, archive: &Path) -> Result<()> {
+    let (dir, prefix, archive) = (dir.to_path_buf(), prefix.to_string(), archive.to_path_buf());
+    blocking(move || {
+        let encoder = zstd::Encoder::new(File::create(&archive)?, COMPRESSION_LEVEL)?;
+        let mut builder = tar::Builder::new(encoder);
+        builder.follow_symlinks(false);
+        builder.append_dir_all(&prefix, &dir)?;
+        builder.into_inner()?.finish()?;
+        Ok(())
+    })
+    .await
+}
+
+/// Extracts a `.tar.zst` archive into a directory.
+///
+/// ## Takes
+/// - `archive` - The archive to extract.
+/// - `dst` - The directory to extract the archive into.
+pub(crate) async fn unpack(archive: &Path, dst: &Path) -> Result<()> {
+    let (archive, dst) = (archive.to_path_buf(), dst.to_path_buf());
+    blocking(move || {
+        let decoder = zstd::Decoder::new(File::open(&archive)?)?;
+        tar::Archive::new(decoder).unpack(&dst)?;
+        Ok(())
+    })
+    .await
+}
+
+/// Extracts the given entries of a `.tar.zst` archive into a directory. Entries that are missing
+/// from the archive are skipped.
+///
+/// ## Takes
+/// - `archive` - The archive to extract from.
+/// - `dst` - The directory to extract the entries into.
+/// - `entries` - The paths of the entries within the archive.
+pub(crate) async fn unpack_entries(archive: &Path, dst: &Path, entries: &[PathBuf]) -> Result<()> {
+    let (archive, dst, entries) = (archive.to_path_buf(), dst.to_path_buf(), entries.to_vec());
+    blocking(move || {
+        let decoder = zstd::Decoder::new(File::open(&archive)?)?;
+        for entry in tar::Archive::new(decoder).entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            if entries.contains(&path) {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(dst.join(parent))?;
+                }
+                entry.unpack_in(&dst)?;
+            }
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Runs blocking work on the blocking thread pool.
+async fn blocking(f: impl FnOnce() -> Result<()> + Send + 'static) -> Result<()> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| eyre!("Archive task failed: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn roundtrip_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let witness_db = dir.path().join("witness-db");
+        fs::create_dir_all(witness_db.join("nested")).unwrap();
+        fs::write(witness_db.join("0x01.txt"), "01").unwrap();
+        fs::write(witness_db.join("nested/0x02.txt"), "02").unwrap();
+
+        let archive = dir.path().join("witness-db.tar.zst");
+        pack_dir(&witness_db, "witness-db", &archive).await.unwrap();
+        let unpacked = dir.path().join("unpacked");
+        unpack(&archive, &unpacked).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(unpacked.join("witness-db/nested/0x02.txt")).unwrap(),
+            "02"
+        );
+
+        let partial = dir.path().join("partial");
+        unpack_entries(
+            &archive,
+            &partial,
+            &["witness-db/0x01.txt".into(), "witness-db/0x03.txt".into()],
+        )
+        .await
+        .unwrap();
+        assert!(partial.join("witness-db/0x01.txt").is_file());
+        assert!(!partial.join("witness-db/nested").exists());
+
+        let genesis = dir.path().join("genesis.json");
+        fs::write(&genesis, "{}").unwrap();
+        compress_file(&genesis, &dir.path().join("genesis.json.zst"))
+            .await
+            .unwrap();
+        fs::remove_file(&genesis).unwrap();
+        decompress_file(&dir.path().join("genesis.json.zst"), &genesis)
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(genesis).unwrap(), "{}");
+    }
+}
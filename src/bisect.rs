@@ -0,0 +1,290 @@
+//! Contains `fpt bisect`, which finds the first step at which two revisions of an FPVM diverge on
+//! a fixture, by running both with the same program to exponentially spaced checkpoints until
+//! their states differ, and then bisecting between the last checkpoint that they agree on and the
+//! first that they do not.
+//!
+//! Each run resumes from the last state that both revisions agree on, so the bisection costs about
+//! twice the steps to the divergence. States are compared by the keccak256 hash of their canonical
+//! JSON, so that differences in formatting are not divergences.
+
+use crate::{
+    cli::BisectConfig,
+    executor::ComponentBuild,
+    fixture::FixtureDir,
+    registry::{
+        platform::{cannon::Cannon, Platform, PlatformKind, PlatformRuntime},
+        FP_REGISTRY,
+    },
+    state::StateDir,
+};
+use alloy_primitives::{keccak256, B256};
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+/// The search for the first step at which two executions diverge. Checkpoints are probed at
+/// exponentially spaced steps until the executions disagree, and then bisected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Bisection {
+    /// The last step at which the executions agree.
+    lo: u64,
+    /// The first step at which the executions are known to disagree, if any.
+    hi: Option<u64>,
+    /// The step beyond which the executions are not probed.
+    max_steps: u64,
+    /// Whether or not both executions exited in agreement.
+    agreed: bool,
+}
+
+impl Bisection {
+    /// Create a new [Bisection], probing up to the given step.
+    pub(crate) fn new(max_steps: u64) -> Self {
+        Self {
+            lo: 0,
+            hi: None,
+            max_steps,
+            agreed: false,
+        }
+    }
+
+    /// Returns the step to probe next, or `None` once the search is complete.
+    pub(crate) fn next_step(&self) -> Option<u64> {
+        if self.agreed {
+            return None;
+        }
+        match self.hi {
+            Some(hi) => (hi - self.lo > 1).then(|| self.lo + (hi - self.lo) / 2),
+            None => (self.lo < self.max_steps)
+                .then(|| self.lo.saturating_mul(2).clamp(1, self.max_steps)),
+        }
+    }
+
+    /// Records the comparison of the executions at a probed step.
+    ///
+    /// ## Takes
+    /// - `step` - The probed step.
+    /// - `agree` - Whether or not the states of the executions are equal at the step.
+    /// - `exited` - Whether or not both executions exited by the step.
+    pub(crate) fn record(&mut self, step: u64, agree: bool, exited: bool) {
+        match (agree, exited) {
+            (true, true) => self.agreed = true,
+            (true, false) => self.lo = step,
+            (false, _) => self.hi = Some(step),
+        }
+    }
+
+    /// Returns the first step at which the executions diverge, once it is found.
+    pub(crate) fn divergence(&self) -> Option<u64> {
+        self.hi.filter(|hi| hi - self.lo <= 1)
+    }
+}
+
+/// The state of an execution at a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Checkpoint {
+    /// The path of the state.
+    path: PathBuf,
+    /// The hash of the state's canonical JSON.
+    hash: B256,
+    /// Whether or not the program exited.
+    exited: bool,
+}
+
+impl Checkpoint {
+    /// Reads the state written by the VM.
+    fn read(path: &Path) -> Result<Self> {
+        let state = serde_json::from_slice::<serde_json::Value>(&fs::read(path)?)
+            .map_err(|e| eyre!("Failed to parse the state {}: {e}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            hash: keccak256(serde_json::to_vec(&state)?),
+            exited: state["exited"].as_bool().unwrap_or(false),
+        })
+    }
+}
+
+/// The first step at which two revisions of an FPVM diverge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Divergence {
+    /// The first step at which the states differ.
+    pub(crate) step: u64,
+    /// The dumped states of each revision at the step, in the order of the revisions.
+    pub(crate) states: [PathBuf; 2],
+}
+
+/// Builds the FPVM at both revisions, and bisects the first step at which their executions of the
+/// program diverge on the fixture, dumping the states of both at the step and the step before.
+///
+/// ## Takes
+/// - `cfg` - The configuration of `fpt bisect`.
+///
+/// ## Returns
+/// - `Result<Option<Divergence>>` - The divergence, or `None` if both revisions agree up to
+///   their exit or the `--max-steps`. Errors if the platform cannot stop at a step, or if a build
+///   or run fails.
+pub(crate) async fn bisect(cfg: &BisectConfig) -> Result<Option<Divergence>> {
+    // Bisection needs to stop the VM at a step, which only cannon supports here.
+    ensure!(
+        cfg.vm == PlatformKind::Cannon,
+        "Bisection is not supported on `{}`; only `cannon` can be stopped at a step",
+        cfg.vm
+    );
+    let program_def = FP_REGISTRY
+        .program
+        .get(&cfg.program)
+        .ok_or_else(|| eyre!("Program `{}` is not in the registry", cfg.program))?;
+    ensure!(
+        program_def.platform_compat.contains(&cfg.vm),
+        "Program `{}` does not run on `{}`",
+        cfg.program,
+        cfg.vm
+    );
+    let platform_def = FP_REGISTRY
+        .platform
+        .get(&cfg.vm)
+        .ok_or_else(|| eyre!("Platform `{}` is not in the registry", cfg.vm))?;
+    let vm_build = platform_def
+        .build
+        .as_ref()
+        .filter(|_| platform_def.runtime == PlatformRuntime::Host)
+        .ok_or_else(|| eyre!("Platform `{}` is not built locally", cfg.vm))?;
+
+    let fixture_dir = FixtureDir::find(&cfg.tests_dir.path, &cfg.test)?;
+    let out_dir = cfg
+        .out_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("bisect-{}", cfg.test.replace('/', "-"))));
+    fs::create_dir_all(&out_dir)?;
+
+    // Build the program, and the VM at both revisions, copying the VM of each out of the checkout.
+    info!(target: "bisect", "Building program: {}", cfg.program);
+    ComponentBuild::of_program(program_def).run(false).await?;
+    let host = program_def
+        .build
+        .get_artifact("host")
+        .ok_or(eyre!("No host artifact"))?;
+    let client = program_def
+        .build
+        .get_artifact("client")
+        .ok_or(eyre!("Failed to get client artifact"))?;
+    let program = cfg.program.get_program(host, client.clone());
+
+    let work = StateDir::get().tempdir()?;
+    let mut vms = Vec::new();
+    for (i, rev) in [&cfg.rev_a, &cfg.rev_b].into_iter().enumerate() {
+        let mut build = vm_build.clone();
+        build.rev = rev.clone();
+        info!(target: "bisect", "Building {} at {rev}", cfg.vm);
+        ComponentBuild::Source(build.clone()).run(false).await?;
+        let workdir = work.path().join(i.to_string());
+        fs::create_dir_all(&workdir)?;
+        let binary = workdir.join("vm");
+        fs::copy(
+            build.get_artifact("vm").ok_or(eyre!("No vm artifact"))?,
+            &binary,
+        )?;
+        let vm = Cannon::new(binary, Cannon::run_flags(None));
+        vm.prepare_client(&client, program_def.client_kind, &workdir)
+            .await?;
+        vms.push((vm, workdir));
+    }
+
+    let scratch_dir = StateDir::get().scratch_dir();
+    fs::create_dir_all(&scratch_dir)?;
+    let scratch = tempfile::Builder::new()
+        .prefix("bisect-")
+        .tempdir_in(scratch_dir)?;
+    let (_, inputs) = fixture_dir.host_inputs(scratch.path()).await?;
+
+    // The loaded states are the first checkpoint.
+    let mut bisection = Bisection::new(cfg.max_steps.unwrap_or(u64::MAX));
+    let mut agreed = vms
+        .iter()
+        .map(|(_, workdir)| Checkpoint::read(&workdir.join("state.json")))
+        .collect::<Result<Vec<_>>>()?;
+    let mut diverged = agreed.clone();
+    bisection.record(0, agreed[0].hash == agreed[1].hash, false);
+
+    while let Some(step) = bisection.next_step() {
+        let mut probes = Vec::with_capacity(2);
+        for ((vm, workdir), from) in vms.iter().zip(agreed.iter()) {
+            let output = workdir.join(format!("state-{step}.json"));
+            vm.run_to_step(&inputs, program.clone(), workdir, &from.path, &output, step)
+                .await?;
+            probes.push(Checkpoint::read(&output)?);
+        }
+        let agree = probes[0].hash == probes[1].hash;
+        info!(
+            target: "bisect",
+            "Step {step}: {}",
+            if agree { "states agree" } else { "states differ" }
+        );
+        bisection.record(step, agree, probes.iter().all(|p| p.exited));
+        if agree {
+            agreed = probes;
+        } else {
+            diverged = probes;
+        }
+    }
+
+    let Some(step) = bisection.divergence() else {
+        return Ok(None);
+    };
+    // Dump the states of both revisions at the divergence, and the agreed states before it.
+    let dump = |checkpoint: &Checkpoint, rev: &str, step: u64| -> Result<PathBuf> {
+        let dst = out_dir.join(format!("{}-{step}.json", rev.replace('/', "-")));
+        fs::copy(&checkpoint.path, &dst)?;
+        Ok(dst)
+    };
+    if step > 0 {
+        dump(&agreed[0], &cfg.rev_a, step - 1)?;
+        dump(&agreed[1], &cfg.rev_b, step - 1)?;
+    }
+    Ok(Some(Divergence {
+        step,
+        states: [
+            dump(&diverged[0], &cfg.rev_a, step)?,
+            dump(&diverged[1], &cfg.rev_b, step)?,
+        ],
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the bisection against executions whose states diverge from the given step.
+    fn search(diverges_at: Option<u64>, exit_at: u64, max_steps: u64) -> (Option<u64>, usize) {
+        let mut bisection = Bisection::new(max_steps);
+        bisection.record(0, diverges_at != Some(0), false);
+        let mut probes = 0;
+        while let Some(step) = bisection.next_step() {
+            probes += 1;
+            let agree = diverges_at.is_none_or(|d| step.min(exit_at) < d);
+            bisection.record(step, agree, step >= exit_at);
+        }
+        (bisection.divergence(), probes)
+    }
+
+    #[test]
+    fn bisect_divergence() {
+        // 2^20 is probed first, then the 19 steps between 2^19 and 2^20 are bisected.
+        assert_eq!(
+            search(Some(1_000_000), u64::MAX, u64::MAX),
+            (Some(1_000_000), 21 + 19)
+        );
+        assert_eq!(search(Some(1), u64::MAX, u64::MAX), (Some(1), 1));
+        assert_eq!(search(Some(0), u64::MAX, u64::MAX).0, Some(0));
+
+        // Executions that exit in agreement, or agree up to the maximum step, do not diverge.
+        assert_eq!(search(None, 5000, u64::MAX), (None, 14));
+        assert_eq!(search(None, u64::MAX, 100), (None, 8));
+        assert_eq!(search(Some(1000), u64::MAX, 100).0, None);
+    }
+}
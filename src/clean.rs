@@ -0,0 +1,182 @@
+//! Contains the [CleanPlan], the files removed by `fpt clean`: the components directory, the
+//! decompressed fixtures that interrupted and `--keep-decompressed` runs leave behind, and stale
+//! build stamps.
+
+use crate::{
+    cli::CleanConfig,
+    fixture::{FixtureDir, TestFixture},
+    registry::components::{disk_usage, InstalledComponent},
+    state::StateDir,
+};
+use color_eyre::Result;
+use std::{fmt::Display, fs, path::PathBuf};
+
+/// The kind of a [CleanItem].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CleanKind {
+    /// The directory that the components are cloned and built in.
+    Components,
+    /// A decompressed fixture within the scratch directory, or a decompressed file left within a
+    /// fixture directory by earlier versions of `fpt`.
+    Fixture,
+    /// A build stamp or build log of a component that no longer describes a build.
+    BuildStamp,
+}
+
+impl Display for CleanKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Components => write!(f, "components"),
+            Self::Fixture => write!(f, "fixture"),
+            Self::BuildStamp => write!(f, "build stamp"),
+        }
+    }
+}
+
+/// A file or directory removed by `fpt clean`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CleanItem {
+    /// The kind of the item.
+    pub(crate) kind: CleanKind,
+    /// The path of the item.
+    pub(crate) path: PathBuf,
+    /// The disk usage of the item, in bytes.
+    pub(crate) size: u64,
+}
+
+/// The items selected for removal by `fpt clean`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CleanPlan {
+    /// The items to remove.
+    pub(crate) items: Vec<CleanItem>,
+}
+
+impl CleanPlan {
+    /// Collects the items selected by the configuration. Stale build stamps are only collected if
+    /// the components are kept, as they are removed along with them otherwise.
+    ///
+    /// ## Takes
+    /// - `cfg` - The configuration of the `clean` subcommand.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The plan, or Err if the tests directory cannot be read.
+    pub(crate) fn new(cfg: &CleanConfig) -> Result<Self> {
+        let mut plan = Self::default();
+
+        let components_dir = StateDir::get().components_dir();
+        if (cfg.components || cfg.all) && components_dir.exists() {
+            plan.push(CleanKind::Components, components_dir);
+        } else if cfg.stamps || cfg.all {
+            for component in InstalledComponent::list()? {
+                for path in component.stale_stamps() {
+                    plan.push(CleanKind::BuildStamp, path);
+                }
+            }
+        }
+
+        if cfg.fixtures || cfg.all {
+            if let Ok(entries) = fs::read_dir(StateDir::get().scratch_dir()) {
+                for entry in entries {
+                    plan.push(CleanKind::Fixture, entry?.path());
+                }
+            }
+            if cfg.tests_dir.path.is_dir() {
+                for fixture_dir in FixtureDir::find_all(&cfg.tests_dir.path)? {
+                    for path in TestFixture::decompressed_files(&fixture_dir.path) {
+                        plan.push(CleanKind::Fixture, path);
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Returns the total disk usage of the items, in bytes.
+    pub(crate) fn size(&self) -> u64 {
+        self.items.iter().map(|item| item.size).sum()
+    }
+
+    /// Removes every item from disk.
+    pub(crate) fn execute(&self) -> Result<()> {
+        for item in self.items.iter() {
+            if item.path.is_dir() {
+                fs::remove_dir_all(&item.path)?;
+            } else {
+                fs::remove_file(&item.path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the item at the given path to the plan.
+    fn push(&mut self, kind: CleanKind, path: PathBuf) {
+        let size = if path.is_dir() {
+            disk_usage(&path)
+        } else {
+            fs::metadata(&path).map_or(0, |m| m.len())
+        };
+        self.items.push(CleanItem { kind, path, size });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, CliSubcommand};
+    use clap::Parser;
+
+    #[test]
+    fn plan_fixture_cleanup() {
+        let tests_dir = tempfile::tempdir().unwrap();
+        let interrupted = tests_dir.path().join("interrupted");
+        fs::create_dir_all(interrupted.join("witness-db")).unwrap();
+        fs::write(interrupted.join("witness-db/preimage"), [0; 32]).unwrap();
+        for file in ["fixture.toml", "genesis.json.zst", "genesis.json"] {
+            fs::write(interrupted.join(file), "{}").unwrap();
+        }
+        // Fixtures that are stored uncompressed are left as-is.
+        let uncompressed = tests_dir.path().join("uncompressed");
+        fs::create_dir_all(uncompressed.join("witness-db")).unwrap();
+        for file in ["fixture.toml", "genesis.json"] {
+            fs::write(uncompressed.join(file), "{}").unwrap();
+        }
+
+        let cfg = match Cli::parse_from([
+            "fpt",
+            "clean",
+            "--fixtures",
+            "--tests-dir",
+            tests_dir.path().to_str().unwrap(),
+        ])
+        .subcommand
+        {
+            Some(CliSubcommand::Clean(cfg)) => cfg,
+            _ => unreachable!(),
+        };
+        // Leave the scratch directory, which other tests decompress fixtures into.
+        let in_tests_dir = || {
+            let mut plan = CleanPlan::new(&cfg).unwrap();
+            plan.items
+                .retain(|item| item.path.starts_with(tests_dir.path()));
+            plan
+        };
+        let plan = in_tests_dir();
+        assert_eq!(
+            plan.items
+                .iter()
+                .map(|item| (item.kind, item.path.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (CleanKind::Fixture, interrupted.join("genesis.json")),
+                (CleanKind::Fixture, interrupted.join("witness-db")),
+            ]
+        );
+        assert_eq!(plan.size(), 34);
+
+        plan.execute().unwrap();
+        assert!(in_tests_dir().items.is_empty());
+        assert!(interrupted.join("genesis.json.zst").exists());
+        assert!(uncompressed.join("genesis.json").exists());
+    }
+}
@@ -1,46 +1,210 @@
 //! CLI definition for `fpt`.
 
 use crate::{
-    generator::TestCaseGenerator,
-    pipeline::TestPipeline,
-    registry::{platform::PlatformKind, program::ProgramKind, FP_REGISTRY},
+    bisect,
+    clean::CleanPlan,
+    devnet::{
+        self,
+        scenario::{Scenario, DEVNET_PREFUNDED_KEY},
+        DevnetEndpoints, DEVNET_ENCLAVE,
+    },
+    fixture::{FixtureDir, FixtureInputs, TestFixture, WitnessStatus},
+    generator::WITNESS_DB_DIR_NAME,
+    generator::{
+        batch::{generate_batch, BlockRange},
+        chain_config::ChainConfigSource,
+        claim::InvalidClaim,
+        rpc_cache::RpcCache,
+        TestCaseGenerator,
+    },
+    onchain,
+    output::OutputMode,
+    perf::{self, FixtureBench, FixtureComparison},
+    pipeline::{
+        self,
+        chaos::{parse_rate, ChaosConfig},
+        dashboard::{self, EventWriter},
+        heap_profile::HeapProfiler,
+        partition::Partition,
+        quarantine::Quarantine,
+        rate_limit::parse_rps,
+        retention::RetentionPolicy,
+        upload::UploadTarget,
+        witness_reads::WitnessReads,
+        JSON_STDOUT,
+    },
+    preimage::{boot_info::BootInfo, faults::PreimageFault, server::PreimageServer},
+    process::ProcessOptions,
+    registry::{
+        self, commands,
+        components::InstalledComponent,
+        platform::{PlatformKind, PlatformRuntime},
+        program::{ProgramHostInputs, ProgramHostSource, ProgramKind},
+        FP_REGISTRY,
+    },
+    report::{
+        build_info::BuildInfo,
+        compare::Comparison,
+        history::{self, TestHistory},
+        signing, ReportTarget, TestId, TestReport, FPT_VERSION,
+    },
+    rpc::RpcServer,
+    state::StateDir,
+    units::{Bytes, Elapsed},
 };
-use alloy_primitives::B256;
-use clap::{ArgAction, Args, Parser, Subcommand};
+use alloy_primitives::{hex, Address, B256};
+use clap::{error::ErrorKind, ArgAction, ArgGroup, Args, CommandFactory, Parser, Subcommand};
 use cli_table::{Cell, Style, Table};
-use color_eyre::{eyre::eyre, owo_colors::OwoColorize, Result};
+use color_eyre::{
+    eyre::{ensure, eyre},
+    owo_colors::OwoColorize,
+    Result,
+};
+use itertools::Itertools;
+use std::{
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
 use tracing::Level;
 
+/// The default directory containing the test fixtures: the repository's `tests` directory.
+pub(crate) const DEFAULT_TESTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests");
+
+/// The default address that `fpt rpc` serves JSON-RPC requests on.
+const DEFAULT_RPC_ADDR: &str = "127.0.0.1:7547";
+
 /// The CLI options for `fpt`.
 #[derive(Parser, Debug, Clone)]
+#[command(disable_version_flag = true)]
 pub(crate) struct Cli {
     /// Verbosity level (0-2)
     #[arg(long, short, action = ArgAction::Count)]
     pub v: u8,
+    /// Print the version
+    #[arg(long, short = 'V')]
+    pub version: bool,
+    /// With `--version`, print the build info: the git commit, embedded registry hash, and
+    /// compiled features
+    #[arg(long, requires = "version")]
+    pub verbose: bool,
+    /// Load the registry from the given TOML file rather than the registry embedded in `fpt`
+    /// (e.g. to test unreleased forks of platforms and programs without rebuilding `fpt`)
+    #[arg(long, global = true, env = "FPT_REGISTRY")]
+    pub registry: Option<PathBuf>,
+    /// Keep all state (components, run history, the registry overlay, and scratch files) under the
+    /// given directory rather than `~/.fpt`, ignoring the global git configuration, so that CI runs
+    /// cannot be influenced by leftover local state
+    #[arg(long, global = true, env = "FPT_HERMETIC", value_name = "DIR")]
+    pub hermetic: Option<PathBuf>,
+    /// Print plain lines rather than progress bars and colors, e.g. for CI logs (the default when
+    /// the output is not a terminal)
+    #[arg(long, global = true)]
+    pub no_progress: bool,
+    /// Print only warnings, errors, and the results of tests, as plain lines; implies
+    /// `--no-progress`
+    #[arg(long, global = true)]
+    pub quiet: bool,
     /// The subcommand to run.
     #[clap(subcommand)]
-    pub subcommand: CliSubcommand,
+    pub subcommand: Option<CliSubcommand>,
 }
 
 impl Cli {
     /// Parses the CLI arguments and runs the application.
     pub(crate) async fn run(self) -> Result<()> {
-        match self.subcommand {
-            CliSubcommand::Generate(cfg) => {
-                TestCaseGenerator::new(&cfg)?.generate().await?;
-            }
-            CliSubcommand::Test(cfg) => {
-                let matrix = FP_REGISTRY.resolve_matrix(Some(&cfg));
-                TestPipeline::new(&cfg, matrix)
-                    .setup()
-                    .await?
-                    .run()
-                    .await?
-                    .teardown()
-                    .await?
+        if self.version {
+            if self.verbose {
+                print!("{}", BuildInfo::current());
+            } else {
+                println!("fpt {FPT_VERSION}");
+            }
+            return Ok(());
+        }
+        let Some(subcommand) = self.subcommand else {
+            Cli::command()
+                .error(ErrorKind::MissingSubcommand, "A subcommand is required")
+                .exit();
+        };
+        if let Some(dir) = self.hermetic.as_deref() {
+            StateDir::set_hermetic(dir)?;
+        }
+        registry::load_registry(self.registry.as_deref())?;
+
+        match subcommand {
+            CliSubcommand::Generate(mut cfg) => {
+                // Resolve the enclave once, if the devnet's endpoints or chain configuration are used.
+                if cfg.devnet
+                    || matches!(
+                        ChainConfigSource::from_cfg(&cfg)?,
+                        ChainConfigSource::Kurtosis(_)
+                    )
+                {
+                    cfg.enclave = devnet::resolve_enclave(&cfg.enclave).await?;
+                }
+                if cfg.devnet {
+                    DevnetEndpoints::inspect(&cfg.enclave).await?.fill(&mut cfg);
+                }
+                let mut cfg = if cfg.interactive {
+                    cfg.prompt().await?
+                } else {
+                    cfg
+                };
+                if cfg.minimize {
+                    WitnessReads::check_strace()?;
+                }
+                // The proxies must outlive the generation, which sends all RPC traffic to them.
+                let cache =
+                    (!cfg.no_rpc_cache).then(|| Arc::new(RpcCache::new(cfg.rpc_rate_limit)));
+                let _proxies = match cache.as_ref() {
+                    Some(cache) => cache.proxy(&mut cfg).await?,
+                    None => Vec::new(),
+                };
+                match cfg.l2_block_range {
+                    Some(range) => generate_batch(&cfg, range).await?,
+                    None => TestCaseGenerator::new(&cfg)?.generate().await?,
+                }
+                if let Some(cache) = cache {
+                    cache.log_stats();
+                }
+            }
+            CliSubcommand::Devnet(DevnetSubcommand::Up(cfg)) => {
+                let endpoints = devnet::up(&cfg).await?;
+                println!(
+                    "{} `fpt generate --devnet` uses the endpoints below.\n{}",
+                    "Devnet is ready;".green().bold(),
+                    endpoints.exports()
+                );
+            }
+            CliSubcommand::Devnet(DevnetSubcommand::Down) => {
+                devnet::down().await?;
+                println!("{}", "Devnet stopped".green().bold());
+            }
+            CliSubcommand::Devnet(DevnetSubcommand::Env) => {
+                print!(
+                    "{}",
+                    DevnetEndpoints::inspect(DEVNET_ENCLAVE).await?.exports()
+                );
+            }
+            CliSubcommand::Scenario(cfg) => cfg.scenario.run(&cfg).await?,
+            CliSubcommand::Test(mut cfg) => {
+                cfg.normalize()?;
+                let cancel = CancellationToken::new();
+                let interrupt = tokio::spawn(pipeline::cancel_on_ctrl_c(cancel.clone()));
+                let failures = pipeline::run_tests(&cfg, cancel).await;
+                interrupt.abort();
+                let failures = failures?;
+
+                // Failed tests fail the process, so that scripts and CI jobs can act on them.
+                if failures > 0 {
+                    std::process::exit(1);
+                }
             }
             CliSubcommand::Matrix => {
-                let matrix = FP_REGISTRY.resolve_matrix(None);
+                let matrix = FP_REGISTRY.resolve_matrix(None)?;
 
                 let mut table_contents = Vec::with_capacity(matrix.len());
                 matrix.iter().for_each(|pair| {
@@ -71,16 +235,393 @@ impl Cli {
                     .bold(true);
                 cli_table::print_stdout(table)?;
             }
-            CliSubcommand::Clean => {
-                // TODO: Comamnd for cleaning decompressed fixture files if they are left behind
-                // due to an error.
-                unimplemented!()
+            CliSubcommand::Report(ReportSubcommand::Validate { file }) => {
+                let report = TestReport::read(&file)?;
+                println!(
+                    "{} {} (schema version {}, produced by fpt v{}, {} results{})",
+                    "Valid report:".green().bold(),
+                    file.display(),
+                    report.schema_version,
+                    report.fpt_version,
+                    report.results.len(),
+                    if report.incomplete {
+                        ", incomplete"
+                    } else {
+                        ""
+                    }
+                );
+                if let Some(build) = report.build {
+                    print!("{build}");
+                }
+            }
+            CliSubcommand::Report(ReportSubcommand::Merge {
+                files,
+                out,
+                signing_key,
+            }) => {
+                let signing_key = signing_key
+                    .as_deref()
+                    .map(signing::read_signing_key)
+                    .transpose()?;
+                let reports = files
+                    .iter()
+                    .map(|f| TestReport::read(f))
+                    .collect::<Result<Vec<_>>>()?;
+                let merged = TestReport::merge(reports)?;
+                merged.write(&out)?;
+                if let Some(key) = signing_key.as_ref() {
+                    signing::sign(&out, key)?;
+                }
+                println!(
+                    "{} {} reports ({} results) into {}",
+                    "Merged".green().bold(),
+                    files.len(),
+                    merged.results.len(),
+                    out.display()
+                );
+            }
+            CliSubcommand::Report(ReportSubcommand::Verify { file, public_key }) => {
+                signing::verify(&file, &public_key)?;
+                println!(
+                    "{} {} (signed by {})",
+                    "Verified report:".green().bold(),
+                    file.display(),
+                    hex::encode(public_key.as_bytes())
+                );
+            }
+            CliSubcommand::Compare {
+                run_a,
+                run_b,
+                threshold,
+            } => {
+                let comparison = Comparison::new(
+                    &TestReport::read(&run_a)?,
+                    &TestReport::read(&run_b)?,
+                    threshold,
+                );
+                cli_table::print_stdout(comparison.table())?;
+                println!(
+                    "{} tests compared: {} changed status, {} changed wall time by more than {threshold}%",
+                    comparison.tests.len(),
+                    comparison.status_changes(),
+                    comparison.duration_changes()
+                );
+            }
+            CliSubcommand::History(HistorySubcommand::Stats { top }) => {
+                let mut histories = TestHistory::from_reports(&history::load()?);
+                histories.sort_by(|a, b| b.flakiness().total_cmp(&a.flakiness()));
+
+                let table = histories
+                    .iter()
+                    .take(top)
+                    .map(|h| {
+                        let streak = h.streak().map_or("-".to_string(), |(status, len)| {
+                            format!("{len}x {status:?}")
+                        });
+                        vec![
+                            h.id.to_string().cell(),
+                            h.statuses.len().cell(),
+                            h.failures().cell(),
+                            streak.cell(),
+                            format!("{:.2}", h.flakiness()).cell(),
+                        ]
+                    })
+                    .table()
+                    .title(vec![
+                        "Test".cell(),
+                        "Runs".cell(),
+                        "Failures".cell(),
+                        "Streak".cell(),
+                        "Flakiness".cell(),
+                    ])
+                    .bold(true);
+                cli_table::print_stdout(table)?;
+            }
+            CliSubcommand::Quarantine(QuarantineConfig { tests_dir, action }) => {
+                let mut quarantine = Quarantine::load(&tests_dir.path)?;
+                match action {
+                    QuarantineSubcommand::Add { id, reason } => {
+                        quarantine.add(id.clone(), reason)?;
+                        quarantine.write(&tests_dir.path)?;
+                        println!("{} {id}", "Quarantined".yellow().bold());
+                    }
+                    QuarantineSubcommand::Remove { id } => {
+                        quarantine.remove(&id)?;
+                        quarantine.write(&tests_dir.path)?;
+                        println!("{} {id}", "Released".green().bold());
+                    }
+                    QuarantineSubcommand::List => {
+                        let table = quarantine
+                            .tests
+                            .iter()
+                            .map(|t| {
+                                vec![
+                                    t.id.to_string().cell(),
+                                    t.reason.as_deref().unwrap_or("-").cell(),
+                                ]
+                            })
+                            .table()
+                            .title(vec!["Test".cell(), "Reason".cell()])
+                            .bold(true);
+                        cli_table::print_stdout(table)?;
+                    }
+                }
+            }
+            CliSubcommand::Fixtures(FixturesConfig { tests_dir, action }) => {
+                match action.unwrap_or(FixturesSubcommand::List) {
+                    FixturesSubcommand::List => {
+                        let mut table_contents = Vec::new();
+                        for fixture_dir in FixtureDir::find_all(&tests_dir.path)? {
+                            let fixture = fixture_dir.load()?;
+                            let witness = match fixture.witness_status(&fixture_dir.path).await? {
+                                WitnessStatus::Valid => "valid".green().to_string(),
+                                WitnessStatus::Missing => "missing".yellow().to_string(),
+                                WitnessStatus::Corrupted(_) => "corrupted".red().to_string(),
+                            };
+                            table_contents.push(vec![
+                                fixture.metadata.name.clone().cell(),
+                                fixture_dir.chain.as_deref().unwrap_or("-").cell(),
+                                fixture.inputs.l2_block_number.cell(),
+                                fixture.inputs.l2_chain_id.cell(),
+                                fixture.metadata.expected_status.cell(),
+                                Bytes(TestFixture::stored_size(&fixture_dir.path)).cell(),
+                                witness.cell(),
+                            ]);
+                        }
+
+                        let table = table_contents
+                            .table()
+                            .title(vec![
+                                "Fixture".cell(),
+                                "Chain".cell(),
+                                "L2 Block".cell(),
+                                "Chain ID".cell(),
+                                "Expected Status".cell(),
+                                "Size".cell(),
+                                "Witness".cell(),
+                            ])
+                            .bold(true);
+                        cli_table::print_stdout(table)?;
+                    }
+                    FixturesSubcommand::Show { name } => {
+                        let fixture_dir = FixtureDir::find(&tests_dir.path, &name)?;
+                        let fixture = fixture_dir.load()?;
+                        print!(
+                            "{}",
+                            fs::read_to_string(fixture_dir.path.join("fixture.toml"))?
+                        );
+
+                        println!("\n{}", "Paths".bold());
+                        let dir = &fixture_dir.path;
+                        for (label, path) in [
+                            ("directory", dir.clone()),
+                            ("rollup config", dir.join("rollup.json")),
+                            ("genesis archive", dir.join("genesis.json.zst")),
+                            ("genesis", dir.join("genesis.json")),
+                            (
+                                "witness archive",
+                                dir.join(format!("{WITNESS_DB_DIR_NAME}.tar.zst")),
+                            ),
+                            ("witness db", dir.join(WITNESS_DB_DIR_NAME)),
+                        ] {
+                            let absent = if path.exists() { "" } else { " (absent)" };
+                            println!("{label:<16} {}{}", path.display(), absent.dimmed());
+                        }
+
+                        match fixture.witness_status(dir).await? {
+                            WitnessStatus::Corrupted(problem) => {
+                                println!("\n{} {problem}", "Witness".bold())
+                            }
+                            status => println!("\n{} {status}", "Witness".bold()),
+                        }
+                    }
+                }
+            }
+            CliSubcommand::Components { prune } => {
+                let components = InstalledComponent::list()?;
+
+                let mut table_contents = Vec::with_capacity(components.len());
+                for component in components.iter() {
+                    let mut repo = component.repo.green().to_string();
+                    if !component.referenced {
+                        repo = format!("{repo} (unreferenced)");
+                    }
+                    let commit = component
+                        .commit
+                        .as_ref()
+                        .map_or("-".to_string(), |c| c[..c.len().min(12)].to_string());
+                    let artifacts = component
+                        .artifacts
+                        .iter()
+                        .map(|a| format!("{} ({}, {})", a.name.magenta(), Bytes(a.size), a.hash))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let last_build = component
+                        .last_build
+                        .and_then(|t| t.elapsed().ok())
+                        .map_or("never".to_string(), |d| format!("{} ago", Elapsed(d)));
+                    table_contents.push(vec![
+                        repo.cell(),
+                        commit.cell(),
+                        artifacts.cell(),
+                        last_build.cell(),
+                        Bytes(component.disk_usage).cell(),
+                    ]);
+                }
+
+                let table = table_contents
+                    .table()
+                    .title(vec![
+                        "Repository".cell(),
+                        "Commit".cell(),
+                        "Artifacts".cell(),
+                        "Last Build".cell(),
+                        "Disk Usage".cell(),
+                    ])
+                    .bold(true);
+                cli_table::print_stdout(table)?;
+
+                if prune {
+                    for component in components.iter().filter(|c| !c.referenced) {
+                        component.remove()?;
+                        println!(
+                            "{} {} ({} freed)",
+                            "Pruned".green().bold(),
+                            component.repo,
+                            Bytes(component.disk_usage)
+                        );
+                    }
+                }
+            }
+            CliSubcommand::Cmd { check } => {
+                if check {
+                    commands::check().await?;
+                    println!("{}", "All host flags are accepted".green().bold());
+                } else {
+                    print!("{}", commands::render()?);
+                }
+            }
+            CliSubcommand::VerifyOnchain(cfg) => {
+                let finding = onchain::verify(&cfg).await?;
+                let verdict = if finding.proposed {
+                    "Proposed:".green().bold().to_string()
+                } else {
+                    "Not proposed:".yellow().bold().to_string()
+                };
+                println!(
+                    "{verdict} the claim of {} {} proposed to {} as of L1 block #{}",
+                    cfg.fixture,
+                    if finding.proposed { "was" } else { "was not" },
+                    finding.contract,
+                    finding.l1_block
+                );
+                if let Some(game) = finding.game {
+                    println!("Proposed by the dispute game {game}");
+                }
+                if let Some(root) = finding.conflicting_root {
+                    println!(
+                        "{} {root} was proposed for the fixture's L2 block instead",
+                        "Conflicting root:".red().bold()
+                    );
+                }
+                if !cfg.dry_run {
+                    println!("Recorded the finding in the fixture's `[onchain]` table");
+                }
+            }
+            CliSubcommand::PreimageServer(cfg) => {
+                let inputs = cfg.host_inputs();
+                let boot_info = BootInfo::from_host_inputs(&inputs)?;
+                PreimageServer::new(&boot_info, cfg.datadir)
+                    .with_fault(cfg.fault)
+                    .serve_host_fds()
+                    .await?;
+            }
+            CliSubcommand::Clean(cfg) => {
+                let plan = CleanPlan::new(&cfg)?;
+                if !cfg.dry_run {
+                    plan.execute()?;
+                }
+
+                let action = if cfg.dry_run {
+                    "Would remove".yellow().bold().to_string()
+                } else {
+                    "Removed".green().bold().to_string()
+                };
+                for item in plan.items.iter() {
+                    println!(
+                        "{action} {} {} ({})",
+                        item.kind,
+                        item.path.display(),
+                        Bytes(item.size)
+                    );
+                }
+                println!(
+                    "{} item(s), {} {}",
+                    plan.items.len(),
+                    Bytes(plan.size()),
+                    if cfg.dry_run { "reclaimable" } else { "freed" }
+                );
+            }
+            CliSubcommand::Rpc(cfg) => RpcServer::new()?.serve(cfg.listen).await?,
+            CliSubcommand::Perf(PerfSubcommand::Compare(cfg)) => {
+                let comparisons = perf::compare(&cfg).await?;
+                cli_table::print_stdout(FixtureComparison::table(
+                    &comparisons,
+                    &cfg.rev_a,
+                    &cfg.rev_b,
+                ))?;
+                println!(
+                    "{} fixtures compared over {} runs of each revision: {} slower, {} faster beyond the noise",
+                    comparisons.len(),
+                    cfg.repeat,
+                    comparisons.iter().filter(|c| c.significant() && c.delta_pct() > 0.0).count(),
+                    comparisons.iter().filter(|c| c.significant() && c.delta_pct() < 0.0).count()
+                );
             }
+            CliSubcommand::Bench(cfg) => {
+                let benches = perf::bench(&cfg).await?;
+                cli_table::print_stdout(FixtureBench::table(&benches, cfg.threshold))?;
+                let regressions = benches
+                    .iter()
+                    .filter(|b| b.regressed(cfg.threshold))
+                    .count();
+                println!(
+                    "{} fixtures benchmarked over {} runs of each revision: {regressions} regressed by more than {}%",
+                    benches.len(),
+                    cfg.repeat,
+                    cfg.threshold
+                );
+                ensure!(
+                    regressions == 0,
+                    "{} regressed on {regressions} fixtures against {}",
+                    cfg.candidate,
+                    cfg.baseline
+                );
+            }
+            CliSubcommand::Bisect(cfg) => match bisect::bisect(&cfg).await? {
+                Some(divergence) => {
+                    println!(
+                        "{} and {} first diverge at step {}; their states are dumped to:",
+                        cfg.rev_a.cyan(),
+                        cfg.rev_b.cyan(),
+                        divergence.step.to_string().red()
+                    );
+                    for state in &divergence.states {
+                        println!("  {}", state.display());
+                    }
+                }
+                None => println!(
+                    "{} and {} agree on every step of {}",
+                    cfg.rev_a.cyan(),
+                    cfg.rev_b.cyan(),
+                    cfg.test
+                ),
+            },
         }
         Ok(())
     }
 
-    /// Initializes the tracing subscriber
+    /// Initializes the tracing subscriber, and the output mode of the run
     ///
     /// # Arguments
     /// - `verbosity_level` - The verbosity level (0-2)
@@ -89,13 +630,36 @@ impl Cli {
     /// - `Result<()>` - Ok if successful, Err otherwise.
     pub(crate) fn init_tracing_subscriber(self) -> Result<Self> {
         color_eyre::install()?;
+        // Nobody watches the progress bars of the runs that `fpt rpc` triggers.
+        let rpc = matches!(&self.subcommand, Some(CliSubcommand::Rpc(_)));
+        let mode = OutputMode::detect(self.no_progress || rpc, self.quiet);
+        OutputMode::set(mode)?;
 
+        // Logs are moved to stderr when the structured results of a test run are written to
+        // stdout.
+        let json_stdout = matches!(
+            &self.subcommand,
+            Some(CliSubcommand::Test(cfg)) if cfg.json.as_deref() == Some(Path::new(JSON_STDOUT))
+        );
+        // Logs are captured by the dashboard while it is shown, without colors.
+        let tui = matches!(&self.subcommand, Some(CliSubcommand::Test(cfg)) if cfg.tui);
         let subscriber = tracing_subscriber::fmt()
             .with_max_level(match self.v {
+                0 if mode.quiet => Level::WARN,
                 0 => Level::INFO,
                 1 => Level::DEBUG,
                 _ => Level::TRACE,
             })
+            .with_ansi(!tui && mode.color)
+            .with_writer(move || -> Box<dyn io::Write> {
+                if tui && dashboard::capturing_events() {
+                    Box::new(EventWriter::default())
+                } else if json_stdout {
+                    Box::new(io::stderr())
+                } else {
+                    Box::new(io::stdout())
+                }
+            })
             .finish();
 
         tracing::subscriber::set_global_default(subscriber).map_err(|e| eyre!(e))?;
@@ -112,52 +676,731 @@ pub(crate) enum CliSubcommand {
     Test(TestConfig),
     /// Generate a new test case.
     Generate(GenerateConfig),
-    /// Clean up decompressed fixture files.
-    Clean,
+    /// Manage the local devnet that test cases are generated from.
+    #[clap(subcommand)]
+    Devnet(DevnetSubcommand),
+    /// Drive the devnet into an interesting state, and generate a test case at the affected block.
+    Scenario(ScenarioConfig),
+    /// Inspect test run reports.
+    #[clap(subcommand)]
+    Report(ReportSubcommand),
+    /// Compare the reports of two test runs side by side, highlighting changed statuses and wall
+    /// times.
+    Compare {
+        /// The report of the first run.
+        run_a: PathBuf,
+        /// The report of the second run.
+        run_b: PathBuf,
+        /// The percentage by which the wall time of a test must change to be highlighted
+        #[clap(long, default_value = "20")]
+        threshold: f64,
+    },
+    /// Inspect the history of test runs.
+    #[clap(subcommand)]
+    History(HistorySubcommand),
+    /// Manage the quarantined tests, which are ran but excluded from the pass / fail gate.
+    Quarantine(QuarantineConfig),
+    /// List the test fixtures, or inspect one of them.
+    Fixtures(FixturesConfig),
+    /// List the components installed under the components directory.
+    Components {
+        /// Remove components that are no longer referenced by the registry.
+        #[clap(long)]
+        prune: bool,
+    },
+    /// Print the command lines constructed for each program and platform.
+    Cmd {
+        /// Check the flags against the `--help` output of the built host binaries.
+        #[clap(long)]
+        check: bool,
+    },
+    /// Remove the components directory, decompressed fixtures left behind by interrupted runs,
+    /// and stale build stamps.
+    Clean(CleanConfig),
+    /// Serve a JSON-RPC control interface, through which other tools list tests, trigger test
+    /// runs, and query their results.
+    Rpc(RpcConfig),
+    /// Measure the performance of programs.
+    #[clap(subcommand)]
+    Perf(PerfSubcommand),
+    /// Benchmark a program at a baseline and a candidate revision on an FPVM, reporting the
+    /// instruction count and wall time deltas of each fixture, and flagging the regressions.
+    Bench(BenchConfig),
+    /// Find the first step at which two revisions of an FPVM diverge on a fixture, dumping both
+    /// of their states at the step for inspection.
+    Bisect(BisectConfig),
+    /// Check whether a fixture's claim was proposed on L1, to an L2OutputOracle or by a dispute
+    /// game, and record the finding in its `fixture.toml`.
+    VerifyOnchain(VerifyOnchainConfig),
+    /// Serve a fixture's witness database to a fault proof program client.
+    #[clap(hide = true)]
+    PreimageServer(PreimageServerConfig),
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum DevnetSubcommand {
+    /// Start the devnet, and wait for the L2 chain to finalize its first block.
+    Up(DevnetConfig),
+    /// Stop the devnet and remove its enclave.
+    Down,
+    /// Print the RPC endpoints of the running devnet as shell `export` statements.
+    Env,
 }
 
 #[derive(Args, Debug, Clone)]
-pub(crate) struct TestConfig {
-    /// The test to run (glob pattern supported)
+pub(crate) struct DevnetConfig {
+    /// The version (git ref) of the optimism-package to run
+    #[clap(long, default_value = "main")]
+    pub(crate) package_version: String,
+    /// The network params passed to the optimism-package (YAML). Defaults to a single
+    /// op-geth / op-node L2 on a minimal reth / lighthouse L1
+    #[clap(long)]
+    pub(crate) network_params: Option<PathBuf>,
+    /// The number of minutes to wait for the L2 chain to finalize its first block
+    #[clap(long, default_value = "10")]
+    pub(crate) timeout: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ScenarioConfig {
+    /// The scenario to run
+    pub(crate) scenario: Scenario,
+    /// The name of the test case (default = `<scenario>-<block>`)
+    #[clap(short, long)]
+    pub(crate) name: Option<String>,
+    /// The private key of the funded L1 account that sends transactions (default = the devnet's
+    /// first prefunded account)
+    #[clap(
+        long,
+        env = "L1_PRIVATE_KEY",
+        default_value = DEVNET_PREFUNDED_KEY,
+        hide_default_value = true
+    )]
+    pub(crate) l1_private_key: String,
+    /// The number of seconds that the batcher is stopped for in the `batcher-gap` scenario
+    #[clap(long, default_value = "60")]
+    pub(crate) gap: u64,
+    /// The number of minutes to wait for the scenario, and then for the affected block to be
+    /// finalized
+    #[clap(long, default_value = "10")]
+    pub(crate) timeout: u64,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum ReportSubcommand {
+    /// Validate that a report is compatible with this version of `fpt`.
+    Validate {
+        /// The path to the report.
+        file: PathBuf,
+    },
+    /// Merge the reports of a sharded run into a single report.
+    Merge {
+        /// The paths to the reports of each shard.
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+        /// The path to write the merged report to.
+        #[clap(short, long)]
+        out: PathBuf,
+        /// Sign the merged report with the ed25519 key whose hex-encoded seed is in the given file
+        #[clap(long, env = "FPT_SIGNING_KEY", value_name = "PATH")]
+        signing_key: Option<PathBuf>,
+    },
+    /// Verify that a report was signed by a trusted key, and was not modified since.
+    Verify {
+        /// The path to the report, whose signature is read from `<report>.sig`.
+        file: PathBuf,
+        /// The hex-encoded ed25519 public key of the trusted signer
+        #[clap(long, value_parser = signing::parse_public_key)]
+        public_key: ed25519_dalek::VerifyingKey,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum HistorySubcommand {
+    /// Print the flakiest tests across recent runs.
+    Stats {
+        /// The number of tests to print
+        #[clap(long, default_value = "10")]
+        top: usize,
+    },
+}
+
+/// The `--tests-dir` option, shared by the subcommands that read or write the test fixtures.
+#[derive(Args, Debug, Clone)]
+pub(crate) struct TestsDir {
+    /// The directory containing the test fixtures (default = the repository's `tests` directory)
+    #[clap(
+        id = "tests_dir",
+        long = "tests-dir",
+        value_name = "TESTS_DIR",
+        env = "FPT_TESTS_DIR",
+        default_value = DEFAULT_TESTS_DIR,
+        hide_default_value = true
+    )]
+    pub(crate) path: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+#[clap(group(ArgGroup::new("targets").required(true).multiple(true)))]
+pub(crate) struct CleanConfig {
+    /// Remove the components directory, where the FPVMs and FPPs are cloned and built
+    #[clap(long, group = "targets")]
+    pub(crate) components: bool,
+    /// Remove the decompressed fixtures, left behind by interrupted runs and kept by
+    /// `--keep-decompressed`
+    #[clap(long, group = "targets")]
+    pub(crate) fixtures: bool,
+    /// Remove the build stamps of components that were built at another commit or with another
+    /// build command, and those of build steps that are no longer in the registry
+    #[clap(long, group = "targets")]
+    pub(crate) stamps: bool,
+    /// Remove everything; shorthand for `--components --fixtures`
+    #[clap(long, group = "targets")]
+    pub(crate) all: bool,
+    /// List what would be removed, without removing anything
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct QuarantineConfig {
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+    /// The quarantine operation
+    #[clap(subcommand)]
+    pub(crate) action: QuarantineSubcommand,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct FixturesConfig {
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+    /// The fixture operation (default = list the fixtures)
+    #[clap(subcommand)]
+    pub(crate) action: Option<FixturesSubcommand>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum PerfSubcommand {
+    /// Compare the wall time of a program's host at two revisions, running the selected fixtures
+    /// natively with both.
+    Compare(PerfCompareConfig),
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct PerfCompareConfig {
+    /// The native program to compare, e.g. `kona-native`
+    #[clap(long)]
+    pub(crate) program: ProgramKind,
+    /// The revision of the program to compare against
+    #[clap(long)]
+    pub(crate) rev_a: String,
+    /// The revision of the program to compare
+    #[clap(long)]
+    pub(crate) rev_b: String,
+    /// The fixtures to run (glob pattern supported)
+    #[clap(short, long)]
+    pub(crate) test: Option<String>,
+    /// The number of times to run each fixture with each revision
+    #[clap(long, default_value = "5")]
+    pub(crate) repeat: usize,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct BenchConfig {
+    /// The program to benchmark, e.g. `kona-riscv`
+    #[clap(long)]
+    pub(crate) program: ProgramKind,
+    /// The platform to run the program on (default = the first platform that it is compatible
+    /// with)
+    #[clap(long)]
+    pub(crate) platform: Option<PlatformKind>,
+    /// The revision of the program to benchmark against
+    #[clap(long)]
+    pub(crate) baseline: String,
+    /// The revision of the program to benchmark
+    #[clap(long)]
+    pub(crate) candidate: String,
+    /// The fixtures to run (glob pattern supported)
     #[clap(short, long)]
     pub(crate) test: Option<String>,
-    /// The FPVM to run the tests on (multiple deliniated by commas)
+    /// The number of times to run each fixture with each revision
+    #[clap(long, default_value = "3")]
+    pub(crate) repeat: usize,
+    /// The increase of the instruction count or wall time, in percent, above which the candidate
+    /// is flagged as a regression
+    #[clap(long, value_name = "PCT", default_value = "5")]
+    pub(crate) threshold: f64,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct BisectConfig {
+    /// The FPVM to bisect
+    #[clap(long, default_value = "cannon")]
+    pub(crate) vm: PlatformKind,
+    /// The program to run on the FPVM, e.g. `op-program-mips`
+    #[clap(long)]
+    pub(crate) program: ProgramKind,
+    /// The first revision of the FPVM
+    #[clap(long)]
+    pub(crate) rev_a: String,
+    /// The second revision of the FPVM
+    #[clap(long)]
+    pub(crate) rev_b: String,
+    /// The fixture to run
     #[clap(short, long)]
-    pub(crate) vm: Option<Vec<PlatformKind>>,
-    /// The FPP to run the tests on (multiple deliniated by commas)
+    pub(crate) test: String,
+    /// The step beyond which the revisions are not compared (default = until the program exits)
+    #[clap(long, value_name = "STEPS")]
+    pub(crate) max_steps: Option<u64>,
+    /// The directory to dump the diverging states to (default = `bisect-<fixture>`)
+    #[clap(long)]
+    pub(crate) out_dir: Option<PathBuf>,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+}
+
+#[derive(Args, Debug, Clone)]
+#[clap(group(ArgGroup::new("contract").required(true)))]
+pub(crate) struct VerifyOnchainConfig {
+    /// The name of the fixture, or `<chain>/<name>` to disambiguate between chains
+    pub(crate) fixture: String,
+    /// The L1 RPC
+    #[clap(long, env = "L1_RPC")]
+    pub(crate) l1_rpc: String,
+    /// The address of the L2OutputOracle that outputs are proposed to
+    #[clap(long, value_name = "ADDR", group = "contract")]
+    pub(crate) l2_output_oracle: Option<Address>,
+    /// The address of the DisputeGameFactory that games are created by
+    #[clap(long, value_name = "ADDR", group = "contract")]
+    pub(crate) dispute_game_factory: Option<Address>,
+    /// The number of the factory's latest games that are searched for the claim
+    #[clap(long, default_value = "1000", requires = "dispute_game_factory")]
+    pub(crate) max_games: u64,
+    /// Print the finding without recording it in the fixture
+    #[clap(long)]
+    pub(crate) dry_run: bool,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct RpcConfig {
+    /// The address to serve JSON-RPC requests on
+    #[clap(long, value_name = "ADDR", default_value = DEFAULT_RPC_ADDR)]
+    pub(crate) listen: SocketAddr,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum FixturesSubcommand {
+    /// List the fixtures, with their inputs, size, and the status of their witness database.
+    List,
+    /// Print a fixture's `fixture.toml`, and the paths of its files.
+    Show {
+        /// The name of the fixture, or `<chain>/<name>` to disambiguate between chains
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum QuarantineSubcommand {
+    /// Quarantine a test.
+    Add {
+        /// The test, as `<platform>/<program>/<fixture>`
+        id: TestId,
+        /// Why the test is quarantined
+        #[clap(long)]
+        reason: Option<String>,
+    },
+    /// Release a test from quarantine.
+    Remove {
+        /// The test, as `<platform>/<program>/<fixture>`
+        id: TestId,
+    },
+    /// List the quarantined tests.
+    List,
+}
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct TestConfig {
+    /// The run profile from the registry (e.g. smoke, pr, nightly, release) whose options are
+    /// applied, unless they are given explicitly
+    #[clap(long)]
+    pub(crate) profile: Option<String>,
+    /// The tests to run, by the names of their fixtures or by their `<platform>/<program>/<fixture>`
+    /// ids (glob pattern supported)
     #[clap(short, long)]
+    pub(crate) test: Option<String>,
+    /// The chains whose fixtures to run, by their subdirectory of the tests directory (multiple
+    /// delineated by commas, or by repeating the flag)
+    #[clap(long, value_delimiter = ',')]
+    pub(crate) chain: Option<Vec<String>>,
+    /// The FPVM to run the tests on (multiple delineated by commas, or by repeating the flag)
+    #[clap(short, long, value_delimiter = ',')]
+    pub(crate) vm: Option<Vec<PlatformKind>>,
+    /// The FPP to run the tests on (multiple delineated by commas, or by repeating the flag)
+    #[clap(short, long, value_delimiter = ',')]
     pub(crate) program: Option<Vec<ProgramKind>>,
-    /// The partition of tests to run
+    /// Run the tests on every FPVM in the registry, rather than only the defaults
+    #[clap(long, conflicts_with = "vm")]
+    pub(crate) all_vms: bool,
+    /// Run the tests with every FPP in the registry, rather than only the defaults
+    #[clap(long, conflicts_with = "program")]
+    pub(crate) all_programs: bool,
+    /// Run the complete matrix; shorthand for `--all-vms --all-programs`
+    #[clap(long, conflicts_with_all = ["vm", "program"])]
+    pub(crate) all: bool,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+    /// The partition of tests to run (e.g. 1/4)
+    #[clap(long)]
+    pub(crate) partition: Option<Partition>,
+    /// Cancel the remaining tests after the first test fails or stalls, rather than running every
+    /// test
     #[clap(long)]
-    pub(crate) partition: Option<String>,
+    pub(crate) fail_fast: bool,
+    /// The number of times that a failed, stalled, or timed out test is retried before it is marked
+    /// as such
+    #[clap(long, default_value = "0")]
+    pub(crate) retries: u32,
     /// The number of active workers (default = 4).
     #[clap(long, default_value = "4")]
     pub(crate) workers: usize,
+    /// Serve VM clients with fpt's builtin preimage server instead of the program's host binary.
+    #[clap(long)]
+    pub(crate) builtin_host: bool,
+    /// Inject a fault into the channels of the builtin preimage server, checking that VM clients
+    /// fail safe rather than accept the claim from corrupted data
+    #[clap(long, value_name = "FAULT", requires = "builtin_host")]
+    pub(crate) preimage_fault: Option<PreimageFault>,
+    /// Serve preimages from RPCs rather than the fixtures' witness databases, starting with the L1
+    /// RPC (the RPCs must serve the chain that the fixtures were generated from)
+    #[clap(long, requires_all = ["l1_beacon_rpc", "l2_rpc"], conflicts_with = "builtin_host")]
+    pub(crate) l1_rpc: Option<String>,
+    /// The L1 beacon RPC, for RPC-backed runs
+    #[clap(long, requires = "l1_rpc")]
+    pub(crate) l1_beacon_rpc: Option<String>,
+    /// The L2 RPC, for RPC-backed runs
+    #[clap(long, requires = "l1_rpc")]
+    pub(crate) l2_rpc: Option<String>,
+    /// The latency (in milliseconds) that is added to every RPC response, for RPC-backed runs
+    #[clap(long, value_name = "MS", requires = "l1_rpc")]
+    pub(crate) chaos_latency: Option<u64>,
+    /// The fraction of RPC requests that are answered with an error, for RPC-backed runs
+    #[clap(long, value_name = "RATE", requires = "l1_rpc", value_parser = parse_rate)]
+    pub(crate) chaos_error_rate: Option<f64>,
+    /// The fraction of RPC responses that are truncated, for RPC-backed runs
+    #[clap(long, value_name = "RATE", requires = "l1_rpc", value_parser = parse_rate)]
+    pub(crate) chaos_truncate_rate: Option<f64>,
+    /// The number of requests per second that are sent to each RPC across all workers, for
+    /// RPC-backed runs
+    #[clap(long, value_name = "RPS", requires = "l1_rpc", value_parser = parse_rps)]
+    pub(crate) rpc_rate_limit: Option<f64>,
+    /// Write a report of the test run to `[format:]path`, where the format is `json` (default),
+    /// `ndjson`, or `junit` (may be repeated)
+    #[clap(long)]
+    pub(crate) report: Vec<ReportTarget>,
+    /// Write the structured results of the run as JSON to the given path, or to stdout if no path
+    /// (or `-`) is given
+    #[clap(long, value_name = "PATH", num_args = 0..=1, default_missing_value = JSON_STDOUT)]
+    pub(crate) json: Option<PathBuf>,
+    /// Sign the written reports with the ed25519 key whose hex-encoded seed is in the given file,
+    /// writing each signature to `<report>.sig` (see `fpt report verify`)
+    #[clap(long, env = "FPT_SIGNING_KEY", value_name = "PATH")]
+    pub(crate) signing_key: Option<PathBuf>,
+    /// Write the instructions executed, VM wall time, and peak RSS of each test to the given path,
+    /// as CSV if it ends in `.csv`, and as JSON otherwise
+    #[clap(long, value_name = "PATH")]
+    pub(crate) metrics: Option<PathBuf>,
+    /// Do not record the run in the history that flakiness is scored from
+    #[clap(long)]
+    pub(crate) no_history: bool,
+    /// Skip the tests whose flakiness score across recent runs exceeds the given threshold,
+    /// within [0, 1] (see `fpt history stats`)
+    #[clap(long, value_name = "SCORE", value_parser = parse_rate)]
+    pub(crate) quarantine_flaky: Option<f64>,
+    /// Write the output of each test's platform and host program to
+    /// `<PATH>/<platform>/<program>/<fixture>.log`
+    #[clap(long, value_name = "PATH")]
+    pub(crate) log_dir: Option<PathBuf>,
+    /// Trace the witness database reads of each disk-backed test with `strace`, reporting the keys
+    /// and bytes that each program reads, and its read amplification
+    #[clap(long, conflicts_with = "l1_rpc")]
+    pub(crate) trace_witness_reads: bool,
+    /// Profile the heap of each natively ran Rust-based host (e.g. `kona-native`) with the given
+    /// profiler, writing the profiles to `<artifacts-dir>/heap-profiles/<platform>/<program>/<fixture>`
+    #[clap(
+        long,
+        value_name = "PROFILER",
+        num_args = 0..=1,
+        default_missing_value = "jemalloc"
+    )]
+    pub(crate) heap_profile: Option<HeapProfiler>,
+    /// Compare the two selected programs on each fixture that they run on the same platform,
+    /// failing the run if their exit statuses or reported output roots disagree
+    #[clap(long, requires = "program")]
+    pub(crate) differential: bool,
+    /// Run each disk-backed test on an FPVM again with the builtin preimage server, which ignores
+    /// hints, failing the test if its exit status or reported output root diverges
+    #[clap(long, conflicts_with_all = ["builtin_host", "l1_rpc"])]
+    pub(crate) hint_check: bool,
+    /// What to keep of each test's working directory (including the VM's state) and output once it
+    /// completes (default = `failures` in CI, where `CI` is set, and `none` otherwise)
+    #[clap(long, value_name = "POLICY")]
+    pub(crate) keep: Option<RetentionPolicy>,
+    /// The directory that the kept artifacts of tests are moved to, as
+    /// `<PATH>/<platform>/<program>/<fixture>` (default = `~/.fpt/artifacts`)
+    #[clap(long, value_name = "PATH")]
+    pub(crate) artifacts_dir: Option<PathBuf>,
+    /// Keep the decompressed fixtures once the run completes, and reuse those left by previous runs
+    /// whose archives are unchanged, rather than decompressing them again
+    #[clap(long, visible_alias = "reuse-decompressed")]
+    pub(crate) keep_decompressed: bool,
+    /// Upload the reproduction bundles of failed tests to object storage (e.g. s3://bucket/prefix)
+    #[clap(long)]
+    pub(crate) upload_failures: Option<UploadTarget>,
+    /// The number of seconds without output after which a test is considered stalled, and marked
+    /// as STALLED
+    #[clap(long, value_name = "SECS")]
+    pub(crate) stall_timeout: Option<u64>,
+    /// The number of seconds after which a test is killed, along with any processes that it
+    /// spawned, and marked as TIMEOUT
+    #[clap(long, value_name = "SECS")]
+    pub(crate) timeout: Option<u64>,
+    /// Kill stalled tests, rather than letting them run to completion
+    #[clap(long, requires = "stall_timeout")]
+    pub(crate) kill_stalled: bool,
+    /// Pin each worker's VM processes to its own CPU core, interleaved across NUMA nodes
+    #[clap(long)]
+    pub(crate) pin_cpus: bool,
+    /// Run the platforms' VMs with the given runtime, overriding the registry's (`docker` runs
+    /// them inside of the platforms' container images, rather than building them locally)
+    #[clap(long, value_name = "RUNTIME")]
+    pub(crate) platform_runtime: Option<PlatformRuntime>,
+    /// Rebuild every platform and program in the matrix, even if their artifacts are up to date
+    #[clap(long)]
+    pub(crate) force_rebuild: bool,
+    /// Show an interactive dashboard of the running, queued, and completed tests, the activity of
+    /// each worker, and the output of the selected test, in place of the progress bars
+    #[clap(long)]
+    pub(crate) tui: bool,
+}
+
+impl TestConfig {
+    /// Normalizes the parsed configuration: deduplicates the selected chains, platforms, and
+    /// programs, which may be given both comma-separated and by repeating their flags, and applies
+    /// the options of the selected run profile from the registry, if any, unless they are given
+    /// explicitly.
+    pub(crate) fn normalize(&mut self) -> Result<()> {
+        if let Some(chain) = self.chain.as_mut() {
+            *chain = chain.drain(..).unique().collect();
+        }
+        if let Some(vm) = self.vm.as_mut() {
+            *vm = vm.drain(..).unique().collect();
+        }
+        if let Some(program) = self.program.as_mut() {
+            *program = program.drain(..).unique().collect();
+        }
+
+        if let Some(name) = self.profile.clone() {
+            FP_REGISTRY.get_profile(&name)?.apply(self)?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether or not every FPVM in the registry is selected.
+    pub(crate) fn all_vms(&self) -> bool {
+        self.all || self.all_vms
+    }
+
+    /// Returns whether or not every FPP in the registry is selected.
+    pub(crate) fn all_programs(&self) -> bool {
+        self.all || self.all_programs
+    }
+
+    /// Returns the [RetentionPolicy] of the tests' artifacts.
+    pub(crate) fn retention(&self) -> RetentionPolicy {
+        RetentionPolicy::or_default(self.keep)
+    }
+
+    /// Returns the directory that the kept artifacts of tests are moved to.
+    pub(crate) fn artifacts_dir(&self) -> PathBuf {
+        self.artifacts_dir
+            .clone()
+            .unwrap_or_else(|| StateDir::get().artifacts_dir())
+    }
+
+    /// Returns the RPC-backed preimage source of the tests, if they are ran against RPCs.
+    pub(crate) fn rpc_source(&self) -> Option<ProgramHostSource> {
+        Some(ProgramHostSource::Rpc {
+            l1: self.l1_rpc.clone()?,
+            l1_beacon: self.l1_beacon_rpc.clone()?,
+            l2: self.l2_rpc.clone()?,
+            path: WITNESS_DB_DIR_NAME.into(),
+        })
+    }
+
+    /// Returns the [ChaosConfig] of the proxies between the hosts and their RPCs, if any faults
+    /// are injected.
+    pub(crate) fn chaos(&self) -> Option<ChaosConfig> {
+        (self.chaos_latency.is_some()
+            || self.chaos_error_rate.is_some()
+            || self.chaos_truncate_rate.is_some())
+        .then(|| ChaosConfig {
+            latency: Duration::from_millis(self.chaos_latency.unwrap_or_default()),
+            error_rate: self.chaos_error_rate.unwrap_or_default(),
+            truncate_rate: self.chaos_truncate_rate.unwrap_or_default(),
+        })
+    }
+
+    /// Returns the [ProcessOptions] for supervising the processes spawned by the tests.
+    pub(crate) fn process_options(&self) -> ProcessOptions {
+        ProcessOptions {
+            stall_timeout: self.stall_timeout.map(Duration::from_secs),
+            kill_stalled: self.kill_stalled,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Args, Debug, Clone)]
 pub(crate) struct GenerateConfig {
+    /// Prompt for the configuration interactively, previewing the inputs before generation
+    #[clap(long)]
+    pub(crate) interactive: bool,
+    /// Fill the RPC endpoints that are not given from the devnet started by `fpt devnet up`
+    #[clap(long)]
+    pub(crate) devnet: bool,
+    /// The kurtosis enclave that the devnet runs in, or `auto` for the one running enclave that
+    /// contains the chain configuration artifact
+    #[clap(long, default_value = DEVNET_ENCLAVE)]
+    pub(crate) enclave: String,
     /// The name of the test case
-    #[clap(short, long)]
+    #[clap(
+        short,
+        long,
+        required_unless_present = "interactive",
+        default_value = "",
+        hide_default_value = true
+    )]
     pub(crate) name: String,
+    #[clap(flatten)]
+    pub(crate) tests_dir: TestsDir,
+    /// The chain that the test case is generated on, whose subdirectory of the tests directory the
+    /// fixture is written to
+    #[clap(long, default_value = "devnet")]
+    pub(crate) chain: String,
+    /// The directory to write the test fixture to (default = `<tests-dir>/<chain>/<name>`)
+    #[clap(long, conflicts_with = "l2_block_range")]
+    pub(crate) out_dir: Option<PathBuf>,
+    /// Overwrite the test fixture if it already exists
+    #[clap(long)]
+    pub(crate) force: bool,
+    /// The directory containing the chain's `rollup.json` and `genesis.json` (default = the
+    /// devnet's `op-genesis-configs` artifact)
+    #[clap(long, conflicts_with_all = ["rollup_config_url", "genesis_url"])]
+    pub(crate) chain_config_dir: Option<PathBuf>,
+    /// The URL to download the chain's `rollup.json` from
+    #[clap(long, requires = "genesis_url")]
+    pub(crate) rollup_config_url: Option<String>,
+    /// The URL to download the chain's `genesis.json` from
+    #[clap(long, requires = "rollup_config_url")]
+    pub(crate) genesis_url: Option<String>,
+    /// Build the test fixture from an externally captured witness database in the directory,
+    /// rather than capturing one from the RPCs
+    #[clap(
+        long,
+        value_name = "DIR",
+        requires = "inputs",
+        conflicts_with_all = ["interactive", "devnet", "l2_block_range", "invalid_claim"]
+    )]
+    pub(crate) from_witness: Option<PathBuf>,
+    /// The TOML file of the inputs that the witness database was captured with, in the format of
+    /// the `[inputs]` table of a `fixture.toml` (or a `fixture.toml` itself)
+    #[clap(long, value_name = "FILE", requires = "from_witness")]
+    pub(crate) inputs: Option<PathBuf>,
     /// The L1 RPC
-    #[clap(long, env = "L1_RPC")]
+    #[clap(
+        long,
+        env = "L1_RPC",
+        required_unless_present_any = ["interactive", "devnet", "from_witness"],
+        default_value = "",
+        hide_default_value = true
+    )]
     pub(crate) l1_rpc: String,
     /// The L1 beacon RPC
-    #[clap(long, env = "L1_BEACON_RPC")]
+    #[clap(
+        long,
+        env = "L1_BEACON_RPC",
+        required_unless_present_any = ["interactive", "devnet", "from_witness"],
+        default_value = "",
+        hide_default_value = true
+    )]
     pub(crate) l1_beacon_rpc: String,
     /// The L2 rollup node RPC
-    #[clap(long, env = "L2_NODE_RPC")]
+    #[clap(
+        long,
+        env = "L2_NODE_RPC",
+        required_unless_present_any = ["interactive", "devnet", "from_witness"],
+        default_value = "",
+        hide_default_value = true
+    )]
     pub(crate) l2_node_rpc: String,
     /// The L2 RPC
-    #[clap(long, env = "L2_RPC")]
+    #[clap(
+        long,
+        env = "L2_RPC",
+        required_unless_present_any = ["interactive", "devnet", "from_witness"],
+        default_value = "",
+        hide_default_value = true
+    )]
     pub(crate) l2_rpc: String,
     /// The L2 block number that the test case is for.
-    #[clap(long, env = "L2_BLOCK")]
+    #[clap(
+        long,
+        env = "L2_BLOCK",
+        required_unless_present_any = ["interactive", "l2_block_range", "from_witness"],
+        default_value = "0",
+        hide_default_value = true
+    )]
     pub(crate) l2_block: u64,
+    /// Generate a test case named `<name>-<block>` for each L2 block in the range, optionally every
+    /// `<stride>` blocks (e.g. `100..=110`, or `100..200:10`)
+    #[clap(
+        long,
+        alias = "range",
+        value_name = "RANGE",
+        conflicts_with_all = ["interactive", "l2_block", "l2_claim", "l2_output_root", "l2_head", "l1_head"]
+    )]
+    pub(crate) l2_block_range: Option<BlockRange>,
+    /// The number of test cases to generate in parallel, when generating a range.
+    #[clap(long, default_value = "1", requires = "l2_block_range")]
+    pub(crate) workers: usize,
+    /// Trim the witness database down to the preimages that the reference program reads, by
+    /// replaying the test case with its file accesses traced (requires `strace`)
+    #[clap(long)]
+    pub(crate) minimize: bool,
+    /// Send the RPC requests of the generation straight to the RPCs, rather than through the
+    /// caching proxy that deduplicates identical requests
+    #[clap(long, conflicts_with = "rpc_rate_limit")]
+    pub(crate) no_rpc_cache: bool,
+    /// The number of requests per second that are sent to the RPCs across the generation, after
+    /// cached requests are answered
+    #[clap(long, value_name = "RPS", value_parser = parse_rps)]
+    pub(crate) rpc_rate_limit: Option<f64>,
     /// The L2 claim.
     #[clap(long, env = "L2_CLAIM")]
     pub(crate) l2_claim: Option<B256>,
+    /// Generate a negative test case, whose claim the programs must reject: the given claim, or the
+    /// valid claim with random bits flipped if none is given
+    #[clap(long, value_name = "CLAIM", num_args = 0..=1, default_missing_value = "random")]
+    pub(crate) invalid_claim: Option<InvalidClaim>,
     /// The starting L2 output root.
     #[clap(long, env = "L2_OUTPUT_ROOT")]
     pub(crate) l2_output_root: Option<B256>,
@@ -171,3 +1414,64 @@ pub(crate) struct GenerateConfig {
     #[clap(long, env = "L2_CHAIN_ID")]
     pub(crate) l2_chain_id: Option<u64>,
 }
+
+#[derive(Args, Debug, Clone)]
+pub(crate) struct PreimageServerConfig {
+    /// The L1 head hash.
+    #[clap(long)]
+    pub(crate) l1_head: B256,
+    /// The starting L2 head hash.
+    #[clap(long)]
+    pub(crate) l2_head: B256,
+    /// The starting L2 output root.
+    #[clap(long)]
+    pub(crate) l2_output_root: B256,
+    /// The L2 claim.
+    #[clap(long)]
+    pub(crate) l2_claim: B256,
+    /// The L2 block number of the claim.
+    #[clap(long)]
+    pub(crate) l2_block_number: u64,
+    /// The L2 chain ID.
+    #[clap(long)]
+    pub(crate) l2_chain_id: u64,
+    /// The path to the `rollup.json` file.
+    #[clap(long)]
+    pub(crate) rollup_config: PathBuf,
+    /// The path to the `genesis.json` file.
+    #[clap(long, required_unless_present = "chain_config")]
+    pub(crate) genesis: Option<PathBuf>,
+    /// The path to the JSON encoded L2 chain configuration, read instead of parsing it out of the
+    /// `genesis.json` file.
+    #[clap(long, conflicts_with = "genesis")]
+    pub(crate) chain_config: Option<PathBuf>,
+    /// The witness database directory.
+    #[clap(long)]
+    pub(crate) datadir: PathBuf,
+    /// The fault to inject into the hint and preimage channels.
+    #[clap(long)]
+    pub(crate) fault: Option<PreimageFault>,
+}
+
+impl PreimageServerConfig {
+    /// Returns the [ProgramHostInputs] described by the configuration.
+    pub(crate) fn host_inputs(&self) -> ProgramHostInputs {
+        ProgramHostInputs {
+            fixture_inputs: FixtureInputs {
+                l1_head: self.l1_head,
+                l2_block_number: self.l2_block_number,
+                l2_claim: self.l2_claim,
+                l2_output_root: self.l2_output_root,
+                l2_head: self.l2_head,
+                l2_chain_id: self.l2_chain_id,
+                interop: None,
+            },
+            rollup_cfg_path: self.rollup_config.clone(),
+            genesis_path: self.genesis.clone().unwrap_or_default(),
+            chain_config_path: self.chain_config.clone(),
+            source: ProgramHostSource::Disk {
+                path: self.datadir.clone(),
+            },
+        }
+    }
+}
@@ -0,0 +1,443 @@
+//! Contains the lifecycle management of the local kurtosis devnet that test cases are generated
+//! from.
+
+use crate::{
+    cli::{DevnetConfig, GenerateConfig},
+    generator::CHAIN_CONFIG_ARTIFACT,
+    output,
+};
+use alloy_provider::{network::Ethereum, Provider, ReqwestProvider};
+use alloy_transport_http::reqwest::Url;
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    Result,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, str::SplitWhitespace, time::Duration};
+use tempfile::NamedTempFile;
+use tokio::process::Command;
+use tracing::info;
+
+pub(crate) mod scenario;
+
+/// The name of the kurtosis enclave that the devnet runs in.
+pub(crate) const DEVNET_ENCLAVE: &str = "devnet";
+
+/// The enclave name that selects the running enclave with the chain configuration artifact.
+pub(crate) const AUTO_ENCLAVE: &str = "auto";
+
+/// The kurtosis package that the devnet is run from.
+const OPTIMISM_PACKAGE: &str = "github.com/ethpandaops/optimism-package";
+
+/// The interval at which the readiness of the devnet is polled.
+const READINESS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The default network params for the optimism package; a single `op-geth` / `op-node` L2 on top of
+/// a minimal `reth` / `lighthouse` L1.
+const DEFAULT_NETWORK_PARAMS: &str = r#"optimism_package:
+  participants:
+    - el_type: op-geth
+      cl_type: op-node
+  network_params:
+    seconds_per_slot: 2
+    network_id: 1337
+ethereum_package:
+  participants:
+    - el_type: reth
+      cl_type: lighthouse
+  network_params:
+    preset: minimal
+"#;
+
+/// The RPC endpoints of the devnet's services, as exposed on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DevnetEndpoints {
+    /// The L1 execution client RPC.
+    pub(crate) l1_rpc: String,
+    /// The L1 beacon node RPC.
+    pub(crate) l1_beacon_rpc: String,
+    /// The L2 rollup node RPC.
+    pub(crate) l2_node_rpc: String,
+    /// The L2 execution client RPC.
+    pub(crate) l2_rpc: String,
+}
+
+impl DevnetEndpoints {
+    /// Looks up the endpoints of the devnet running in the enclave from `kurtosis enclave inspect`.
+    ///
+    /// ## Takes
+    /// - `enclave` - The name of the kurtosis enclave.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The endpoints, or Err if the devnet is not running.
+    pub(crate) async fn inspect(enclave: &str) -> Result<Self> {
+        let services = user_services(enclave).await?;
+        Ok(Self {
+            l1_rpc: port_url(enclave, &services, "el-1-", "rpc").await?,
+            l1_beacon_rpc: port_url(enclave, &services, "cl-1-", "http").await?,
+            l2_node_rpc: port_url(enclave, &services, "op-cl-1-", "http").await?,
+            l2_rpc: port_url(enclave, &services, "op-el-1-", "rpc").await?,
+        })
+    }
+
+    /// Fills the RPC endpoints of the [GenerateConfig] that were not passed explicitly.
+    pub(crate) fn fill(&self, cfg: &mut GenerateConfig) {
+        for (field, endpoint) in [
+            (&mut cfg.l1_rpc, &self.l1_rpc),
+            (&mut cfg.l1_beacon_rpc, &self.l1_beacon_rpc),
+            (&mut cfg.l2_node_rpc, &self.l2_node_rpc),
+            (&mut cfg.l2_rpc, &self.l2_rpc),
+        ] {
+            if field.is_empty() {
+                field.clone_from(endpoint);
+            }
+        }
+    }
+
+    /// Returns the endpoints as shell `export` statements for the variables read by
+    /// `fpt generate`.
+    pub(crate) fn exports(&self) -> String {
+        [
+            ("L1_RPC", &self.l1_rpc),
+            ("L1_BEACON_RPC", &self.l1_beacon_rpc),
+            ("L2_NODE_RPC", &self.l2_node_rpc),
+            ("L2_RPC", &self.l2_rpc),
+        ]
+        .iter()
+        .map(|(var, endpoint)| format!("export {var}={endpoint}\n"))
+        .collect()
+    }
+}
+
+/// Starts the devnet, if it is not already running, and waits for the L2 chain to finalize its
+/// first block.
+///
+/// ## Takes
+/// - `cfg` - The devnet configuration.
+///
+/// ## Returns
+/// - `Result<DevnetEndpoints>` - The endpoints of the ready devnet.
+pub(crate) async fn up(cfg: &DevnetConfig) -> Result<DevnetEndpoints> {
+    if user_services(DEVNET_ENCLAVE).await.is_ok() {
+        info!(target: "devnet", "Devnet is already running.");
+    } else {
+        // The package reads its network params from a file, so the defaults are written out.
+        let default_params = NamedTempFile::new()?;
+        let params_path = match cfg.network_params.as_ref() {
+            Some(path) => path.clone(),
+            None => {
+                fs::write(default_params.path(), DEFAULT_NETWORK_PARAMS)?;
+                default_params.path().to_path_buf()
+            }
+        };
+
+        info!(target: "devnet", "Starting devnet from {OPTIMISM_PACKAGE}@{}...", cfg.package_version);
+        let status = Command::new("kurtosis")
+            .args(["run", "--enclave", DEVNET_ENCLAVE])
+            .arg(format!("{OPTIMISM_PACKAGE}@{}", cfg.package_version))
+            .arg("--args-file")
+            .arg(params_path)
+            .status()
+            .await?;
+        ensure!(status.success(), "Failed to start the devnet");
+    }
+
+    let endpoints = DevnetEndpoints::inspect(DEVNET_ENCLAVE).await?;
+    wait_for_finalized_l2(&endpoints, 1, Duration::from_secs(cfg.timeout * 60)).await?;
+    Ok(endpoints)
+}
+
+/// Downloads the chain configuration from the devnet running in the enclave into the
+/// `op-genesis-configs` directory within `dir`.
+pub(crate) async fn download_chain_config(enclave: &str, dir: &Path) -> Result<()> {
+    info!(target: "devnet", "Downloading chain configuration from the `{enclave}` enclave...");
+    let status = Command::new("kurtosis")
+        .args(["files", "download", enclave, CHAIN_CONFIG_ARTIFACT])
+        .current_dir(dir)
+        .status()
+        .await?;
+
+    ensure!(
+        status.success(),
+        "Failed to download chain configuration from the `{enclave}` enclave. Is Kurtosis running?"
+    );
+
+    info!(target: "devnet", "Successfully downloaded chain configuration.");
+    Ok(())
+}
+
+/// Resolves the name of the enclave that the generator uses: the given name, or, if it is `auto`,
+/// the one running enclave that contains the chain configuration artifact.
+///
+/// ## Takes
+/// - `enclave` - The name of the kurtosis enclave, or `auto`.
+///
+/// ## Returns
+/// - `Result<String>` - The name of the enclave, or Err if none or several enclaves qualify.
+pub(crate) async fn resolve_enclave(enclave: &str) -> Result<String> {
+    if enclave != AUTO_ENCLAVE {
+        return Ok(enclave.to_string());
+    }
+
+    let output = Command::new("kurtosis")
+        .args(["enclave", "ls"])
+        .output()
+        .await?;
+    ensure!(
+        output.status.success(),
+        "Failed to list the kurtosis enclaves. Is Kurtosis running?"
+    );
+    let mut found = Vec::new();
+    for enclave in parse_running_enclaves(&String::from_utf8_lossy(&output.stdout)) {
+        let inspect = inspect_enclave(enclave).await?;
+        if parse_section(&inspect, "Files Artifacts").contains(&CHAIN_CONFIG_ARTIFACT) {
+            found.push(enclave.to_string());
+        }
+    }
+
+    match found.as_slice() {
+        [enclave] => {
+            info!(target: "devnet", "Discovered the `{enclave}` enclave.");
+            Ok(enclave.clone())
+        }
+        [] => bail!("No running kurtosis enclave contains the `{CHAIN_CONFIG_ARTIFACT}` artifact"),
+        _ => bail!(
+            "Several running kurtosis enclaves contain the `{CHAIN_CONFIG_ARTIFACT}` artifact: {}; pick one with `--enclave <name>`",
+            found.join(", ")
+        ),
+    }
+}
+
+/// Stops the devnet and removes its enclave.
+pub(crate) async fn down() -> Result<()> {
+    info!(target: "devnet", "Stopping devnet...");
+    let status = Command::new("kurtosis")
+        .args(["enclave", "rm", "--force", DEVNET_ENCLAVE])
+        .status()
+        .await?;
+    ensure!(status.success(), "Failed to remove the devnet enclave");
+    Ok(())
+}
+
+/// Waits for the L2 chain to finalize the given block.
+///
+/// ## Takes
+/// - `endpoints` - The endpoints of the devnet.
+/// - `block` - The L2 block to wait for.
+/// - `timeout` - The maximum time to wait.
+pub(crate) async fn wait_for_finalized_l2(
+    endpoints: &DevnetEndpoints,
+    block: u64,
+    timeout: Duration,
+) -> Result<()> {
+    let url = Url::parse(&endpoints.l2_node_rpc)?;
+
+    let progress_bar = output::progress_bar(ProgressBar::new_spinner());
+    progress_bar.set_style(ProgressStyle::with_template(
+        "{spinner} [{elapsed}] {wide_msg}",
+    )?);
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+    progress_bar.set_message(format!("Waiting for L2 block #{block} to be finalized..."));
+
+    let result = tokio::time::timeout(timeout, async {
+        loop {
+            // The rollup node refuses connections until it has started.
+            if let Ok(status) = sync_status(url.clone()).await {
+                progress_bar.set_message(format!(
+                    "Waiting for L2 block #{block} to be finalized (unsafe head #{}, finalized #{})...",
+                    status.unsafe_l2.number, status.finalized_l2.number
+                ));
+                if status.finalized_l2.number >= block {
+                    return status.finalized_l2.number;
+                }
+            }
+            tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+        }
+    })
+    .await;
+    progress_bar.finish_and_clear();
+
+    let finalized = result.map_err(|_| {
+        eyre!(
+            "Timed out after {}s waiting for L2 block #{block} to be finalized",
+            timeout.as_secs()
+        )
+    })?;
+    info!(target: "devnet", "Finalized L2 block #{finalized}.");
+    Ok(())
+}
+
+/// Returns the output of `kurtosis enclave inspect` for the enclave.
+async fn inspect_enclave(enclave: &str) -> Result<String> {
+    let output = Command::new("kurtosis")
+        .args(["enclave", "inspect", enclave])
+        .output()
+        .await?;
+    ensure!(
+        output.status.success(),
+        "Failed to inspect the `{enclave}` enclave. Is the devnet running? (`fpt devnet up`)"
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the names of the user services running in the enclave.
+async fn user_services(enclave: &str) -> Result<Vec<String>> {
+    Ok(
+        parse_section(&inspect_enclave(enclave).await?, "User Services")
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Returns the name of the first user service in the devnet whose name starts with `prefix`.
+pub(crate) async fn find_service(prefix: &str) -> Result<String> {
+    user_services(DEVNET_ENCLAVE)
+        .await?
+        .into_iter()
+        .find(|name| name.starts_with(prefix))
+        .ok_or_else(|| eyre!("No `{prefix}*` service found in the devnet"))
+}
+
+/// Stops or starts a service of the devnet.
+pub(crate) async fn set_service_running(service: &str, running: bool) -> Result<()> {
+    let action = if running { "start" } else { "stop" };
+    info!(target: "devnet", "Running `kurtosis service {action}` on {service}...");
+    let output = Command::new("kurtosis")
+        .args(["service", action, DEVNET_ENCLAVE, service])
+        .output()
+        .await?;
+    ensure!(
+        output.status.success(),
+        "Failed to {action} the `{service}` service: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+/// Returns the names listed in a section (e.g. `User Services` or `Files Artifacts`) of the output
+/// of `kurtosis enclave inspect`.
+fn parse_section<'a>(inspect: &'a str, section: &str) -> Vec<&'a str> {
+    inspect
+        .lines()
+        .skip_while(|line| !line.contains(section))
+        .skip(1)
+        .take_while(|line| !line.starts_with('='))
+        .filter_map(|line| named_row(line).map(|(name, _)| name))
+        .collect()
+}
+
+/// Returns the names of the running enclaves listed in the output of `kurtosis enclave ls`.
+fn parse_running_enclaves(ls: &str) -> Vec<&str> {
+    ls.lines()
+        .filter_map(|line| {
+            let (name, mut columns) = named_row(line)?;
+            (columns.next() == Some("RUNNING")).then_some(name)
+        })
+        .collect()
+}
+
+/// Returns the name of a row of kurtosis' output that starts with a short UUID, and the columns
+/// that follow it.
+fn named_row(line: &str) -> Option<(&str, SplitWhitespace<'_>)> {
+    let mut columns = line.split_whitespace();
+    let uuid = columns.next()?;
+    if uuid.len() != 12 || !uuid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((columns.next()?, columns))
+}
+
+/// Returns the host URL of a port on the first service whose name starts with `prefix`.
+async fn port_url(enclave: &str, services: &[String], prefix: &str, port: &str) -> Result<String> {
+    let service = services
+        .iter()
+        .find(|name| name.starts_with(prefix))
+        .ok_or_else(|| eyre!("No `{prefix}*` service found in the devnet"))?;
+
+    let output = Command::new("kurtosis")
+        .args(["port", "print", enclave, service, port])
+        .output()
+        .await?;
+    ensure!(
+        output.status.success(),
+        "Failed to look up port `{port}` of the `{service}` service"
+    );
+
+    // Ports without an application protocol are printed without a scheme.
+    let address = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(if address.contains("://") {
+        address
+    } else {
+        format!("http://{address}")
+    })
+}
+
+/// Fetches the sync status of the rollup node.
+pub(crate) async fn sync_status(url: Url) -> Result<SyncStatusResponse> {
+    Ok(ReqwestProvider::<Ethereum>::new_http(url)
+        .raw_request::<(), SyncStatusResponse>("optimism_syncStatus".into(), ())
+        .await?)
+}
+
+/// Partial response for the `optimism_syncStatus` RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SyncStatusResponse {
+    pub(crate) unsafe_l2: MinBlockRef,
+    pub(crate) finalized_l2: MinBlockRef,
+}
+
+/// Partial block reference within the `optimism_syncStatus` RPC response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct MinBlockRef {
+    pub(crate) number: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_inspect_output() {
+        let inspect = r#"Name:            devnet
+UUID:            4a1c2b3d4e5f
+Status:          RUNNING
+
+========================================= Files Artifacts =========================================
+UUID           Name
+0c6a7d8e9f10   el_cl_genesis_data
+
+========================================== User Services ==========================================
+UUID           Name                                 Ports                                         Status
+2b5e8f0a1c3d   cl-1-lighthouse-reth                 http: 4000/tcp -> http://127.0.0.1:32777      RUNNING
+                                                    metrics: 5054/tcp -> http://127.0.0.1:32778
+7d9e1f2a3b4c   el-1-reth-lighthouse                 rpc: 8545/tcp -> 127.0.0.1:32771              RUNNING
+9a8b7c6d5e4f   op-cl-1-op-node-op-geth              http: 8547/tcp -> http://127.0.0.1:32785      RUNNING
+1f2e3d4c5b6a   op-el-1-op-geth-op-node              rpc: 8545/tcp -> 127.0.0.1:32781              RUNNING
+"#;
+        assert_eq!(
+            parse_section(inspect, "User Services"),
+            vec![
+                "cl-1-lighthouse-reth",
+                "el-1-reth-lighthouse",
+                "op-cl-1-op-node-op-geth",
+                "op-el-1-op-geth-op-node"
+            ]
+        );
+        assert_eq!(
+            parse_section(inspect, "Files Artifacts"),
+            vec!["el_cl_genesis_data"]
+        );
+    }
+
+    #[test]
+    fn parse_enclave_list() {
+        let ls = r#"UUID           Name      Status     Creation Time
+4a1c2b3d4e5f   devnet    RUNNING    Tue, 01 Oct 2024 10:00:00 UTC
+5b2d3c4e5f6a   old-net   STOPPED    Mon, 30 Sep 2024 09:00:00 UTC
+6c3e4d5f6a7b   testnet   RUNNING    Tue, 01 Oct 2024 11:00:00 UTC
+"#;
+        assert_eq!(parse_running_enclaves(ls), vec!["devnet", "testnet"]);
+    }
+}
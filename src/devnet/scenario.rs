@@ -0,0 +1,235 @@
+//! Contains the scenarios that drive the devnet into interesting states before a test case is
+//! generated at the affected L2 block.
+
+use super::{
+    download_chain_config, find_service, set_service_running, sync_status, wait_for_finalized_l2,
+    DevnetEndpoints, DEVNET_ENCLAVE,
+};
+use crate::{
+    cli::{GenerateConfig, ScenarioConfig, TestsDir, DEFAULT_TESTS_DIR},
+    generator::{TestCaseGenerator, CHAIN_CONFIG_ARTIFACT},
+};
+use alloy_primitives::Address;
+use alloy_provider::{network::Ethereum, Provider, ReqwestProvider};
+use alloy_rpc_types::BlockTransactionsKind;
+use alloy_transport_http::reqwest::Url;
+use clap::ValueEnum;
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use serde::Deserialize;
+use std::{fmt::Display, fs, time::Duration};
+use tempfile::tempdir;
+use tokio::process::Command;
+use tracing::info;
+
+/// The private key of the first prefunded account of the devnet's L1.
+pub(crate) const DEVNET_PREFUNDED_KEY: &str =
+    "bcdf20249abf0ed6d944c0288fad489e33f66b3960d9e6229c1cd214ed3bbe31";
+
+/// The EIP-2718 type of deposit transactions.
+const DEPOSIT_TX_TYPE: u8 = 0x7E;
+
+/// The interval at which new L2 blocks are polled for.
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A recipe for driving the devnet into an interesting state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub(crate) enum Scenario {
+    /// Submit a deposit on L1, and generate at the L2 block that includes it.
+    Deposit,
+    /// Stop the batcher for a while, and generate at the first L2 block produced during the gap.
+    BatcherGap,
+    /// Restart the sequencer's rollup node, and generate at the first L2 block produced after
+    /// the restart.
+    SequencerRestart,
+}
+
+impl Display for Scenario {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Deposit => write!(f, "deposit"),
+            Self::BatcherGap => write!(f, "batcher-gap"),
+            Self::SequencerRestart => write!(f, "sequencer-restart"),
+        }
+    }
+}
+
+impl Scenario {
+    /// Runs the scenario against the devnet, and generates a test case at the affected L2 block
+    /// once it has been finalized.
+    ///
+    /// ## Takes
+    /// - `cfg` - The scenario configuration.
+    pub(crate) async fn run(&self, cfg: &ScenarioConfig) -> Result<()> {
+        let endpoints = DevnetEndpoints::inspect(DEVNET_ENCLAVE).await?;
+        info!(target: "scenario", "Running the `{self}` scenario...");
+
+        let block = tokio::time::timeout(Duration::from_secs(cfg.timeout * 60), async {
+            match self {
+                Self::Deposit => deposit(&endpoints, cfg).await,
+                Self::BatcherGap => batcher_gap(&endpoints, cfg).await,
+                Self::SequencerRestart => sequencer_restart(&endpoints).await,
+            }
+        })
+        .await
+        .map_err(|_| eyre!("Timed out running the `{self}` scenario"))??;
+        info!(target: "scenario", "The `{self}` scenario affected L2 block #{block}.");
+
+        // Restarted services may be exposed on different ports.
+        let endpoints = DevnetEndpoints::inspect(DEVNET_ENCLAVE).await?;
+        wait_for_finalized_l2(&endpoints, block, Duration::from_secs(cfg.timeout * 60)).await?;
+
+        let mut gen_cfg = GenerateConfig {
+            interactive: false,
+            devnet: true,
+            enclave: DEVNET_ENCLAVE.to_string(),
+            name: cfg
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{self}-{block}")),
+            tests_dir: TestsDir {
+                path: DEFAULT_TESTS_DIR.into(),
+            },
+            chain: "devnet".to_string(),
+            out_dir: None,
+            chain_config_dir: None,
+            rollup_config_url: None,
+            genesis_url: None,
+            from_witness: None,
+            inputs: None,
+            force: false,
+            l1_rpc: String::new(),
+            l1_beacon_rpc: String::new(),
+            l2_node_rpc: String::new(),
+            l2_rpc: String::new(),
+            l2_block: block,
+            l2_block_range: None,
+            workers: 1,
+            minimize: false,
+            no_rpc_cache: false,
+            rpc_rate_limit: None,
+            l2_claim: None,
+            invalid_claim: None,
+            l2_output_root: None,
+            l2_head: None,
+            l1_head: None,
+            l2_chain_id: None,
+        };
+        endpoints.fill(&mut gen_cfg);
+        TestCaseGenerator::new(&gen_cfg)?.generate().await
+    }
+}
+
+/// Sends ETH to the `OptimismPortal` on L1, and returns the L2 block that includes the resulting
+/// deposit.
+async fn deposit(endpoints: &DevnetEndpoints, cfg: &ScenarioConfig) -> Result<u64> {
+    let chain_config = tempdir()?;
+    download_chain_config(DEVNET_ENCLAVE, chain_config.path()).await?;
+    let rollup_config = serde_json::from_slice::<PartialRollupConfig>(&fs::read(
+        chain_config
+            .path()
+            .join(CHAIN_CONFIG_ARTIFACT)
+            .join("rollup.json"),
+    )?)?;
+
+    let first_block = unsafe_head(endpoints).await? + 1;
+
+    // The portal's `receive` function deposits the sent value to the sender on L2.
+    info!(target: "scenario", "Depositing through the portal at {}...", rollup_config.deposit_contract_address);
+    let output = Command::new("cast")
+        .arg("send")
+        .arg("--json")
+        .args(["--rpc-url", &endpoints.l1_rpc])
+        .args(["--private-key", &cfg.l1_private_key])
+        .args(["--value", "1ether"])
+        .arg(rollup_config.deposit_contract_address.to_string())
+        .output()
+        .await?;
+    ensure!(
+        output.status.success(),
+        "Failed to send the deposit: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    let receipt = serde_json::from_slice::<PartialReceipt>(&output.stdout)?;
+    ensure!(receipt.status == "0x1", "The deposit transaction reverted");
+
+    // The first L2 block with a deposit besides the L1 info transaction includes the deposit.
+    let l2_rpc = ReqwestProvider::<Ethereum>::new_http(Url::parse(&endpoints.l2_rpc)?);
+    let mut number = first_block;
+    loop {
+        let Some(block) = l2_rpc
+            .get_block(number.into(), BlockTransactionsKind::Full)
+            .await?
+        else {
+            tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+            continue;
+        };
+
+        let deposits = block
+            .transactions
+            .txns()
+            .filter(|tx| tx.transaction_type == Some(DEPOSIT_TX_TYPE))
+            .count();
+        if deposits > 1 {
+            return Ok(number);
+        }
+        number += 1;
+    }
+}
+
+/// Stops the batcher for the configured gap, and returns the first L2 block produced while it
+/// was stopped.
+async fn batcher_gap(endpoints: &DevnetEndpoints, cfg: &ScenarioConfig) -> Result<u64> {
+    let batcher = find_service("op-batcher").await?;
+
+    set_service_running(&batcher, false).await?;
+    let block = unsafe_head(endpoints).await? + 1;
+    info!(target: "scenario", "Batcher stopped; waiting {}s...", cfg.gap);
+    tokio::time::sleep(Duration::from_secs(cfg.gap)).await;
+    set_service_running(&batcher, true).await?;
+
+    Ok(block)
+}
+
+/// Restarts the sequencer's rollup node, and returns the first L2 block produced after the
+/// restart.
+async fn sequencer_restart(endpoints: &DevnetEndpoints) -> Result<u64> {
+    let sequencer = find_service("op-cl-1-").await?;
+
+    // The execution client stays up, and reports the head while the rollup node is stopped.
+    let l2_rpc = ReqwestProvider::<Ethereum>::new_http(Url::parse(&endpoints.l2_rpc)?);
+    set_service_running(&sequencer, false).await?;
+    let block = l2_rpc.get_block_number().await? + 1;
+    set_service_running(&sequencer, true).await?;
+
+    // Wait for the sequencer to produce blocks again.
+    while l2_rpc.get_block_number().await? < block {
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+    }
+    Ok(block)
+}
+
+/// Returns the unsafe L2 head of the devnet's rollup node.
+async fn unsafe_head(endpoints: &DevnetEndpoints) -> Result<u64> {
+    Ok(sync_status(Url::parse(&endpoints.l2_node_rpc)?)
+        .await?
+        .unsafe_l2
+        .number)
+}
+
+/// Partial rollup configuration, from the `rollup.json` of the devnet.
+#[derive(Debug, Clone, Deserialize)]
+struct PartialRollupConfig {
+    /// The address of the `OptimismPortal` on L1.
+    deposit_contract_address: Address,
+}
+
+/// Partial transaction receipt, from the output of `cast send --json`.
+#[derive(Debug, Clone, Deserialize)]
+struct PartialReceipt {
+    /// The status of the transaction; `0x1` if successful.
+    status: String,
+}
@@ -0,0 +1,324 @@
+//! Contains the [Executor], which runs a fault proof program on a platform. The test pipeline and
+//! the test case generator share it, so that programs are resolved, built, and ran the same way.
+
+use crate::{
+    process::{ProcessOptions, ProcessStalled},
+    registry::{
+        platform::{
+            docker::DockerPlatform, ClientArtifactKind, Platform, PlatformKind, PlatformRuntime,
+        },
+        program::{builtin::BuiltinHost, Program, ProgramHostInputs, ProgramKind},
+        BuildInstructions, FPPDefinition, PlatformDefinition,
+    },
+};
+use color_eyre::{eyre::eyre, Result};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+/// The build of a component that platforms and programs are ran from, which may be shared between
+/// several of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ComponentBuild {
+    /// A component that is built from its repository.
+    Source(BuildInstructions),
+    /// A container image that is pulled, in place of building the component locally.
+    Image(String),
+}
+
+impl ComponentBuild {
+    /// Returns the build of the program.
+    pub(crate) fn of_program(def: &FPPDefinition) -> Self {
+        Self::Source(def.build.clone())
+    }
+
+    /// Returns the build of the platform, if it has one. Platforms that are ran inside of a
+    /// container are pulled rather than built.
+    ///
+    /// ## Returns
+    /// - `Result<Option<Self>>` - Errors if the platform is ran inside of a container, but has no
+    ///   image.
+    pub(crate) fn of_platform(
+        platform_kind: PlatformKind,
+        def: &PlatformDefinition,
+    ) -> Result<Option<Self>> {
+        match def.runtime {
+            PlatformRuntime::Docker if platform_kind != PlatformKind::Native => {
+                let image = def.image.clone().ok_or_else(|| {
+                    eyre!("Platform `{platform_kind}` has no image for the docker runtime")
+                })?;
+                Ok(Some(Self::Image(image)))
+            }
+            _ => Ok(def.build.clone().map(Self::Source)),
+        }
+    }
+
+    /// Deduplicates the builds, and groups them by the repository or image that they build.
+    /// Builds from the same repository share its checkout, so the builds within a group must be
+    /// ran in sequence, while the groups may be ran in parallel.
+    ///
+    /// ## Takes
+    /// - `builds` - The builds, in the order that they were requested.
+    ///
+    /// ## Returns
+    /// - `Vec<Vec<Self>>` - The groups of builds, in the order that they were first requested.
+    pub(crate) fn schedule(builds: impl IntoIterator<Item = Self>) -> Vec<Vec<Self>> {
+        let mut groups = Vec::<Vec<Self>>::new();
+        for build in builds {
+            match groups.iter_mut().find(|g| g[0].key() == build.key()) {
+                Some(group) if group.contains(&build) => {}
+                Some(group) => group.push(build),
+                None => groups.push(vec![build]),
+            }
+        }
+        groups
+    }
+
+    /// Runs the build, if its artifacts are stale or the rebuild is forced. Images are always
+    /// pulled.
+    pub(crate) async fn run(&self, force: bool) -> Result<()> {
+        match self {
+            Self::Source(build) => build.try_build(force).await,
+            Self::Image(image) => DockerPlatform::pull(image).await,
+        }
+    }
+
+    /// Returns the repository or image that the build produces.
+    pub(crate) fn key(&self) -> &str {
+        match self {
+            Self::Source(build) => &build.repo,
+            Self::Image(image) => image,
+        }
+    }
+}
+
+/// Runs a fault proof program on a platform.
+#[derive(Clone)]
+pub(crate) struct Executor {
+    /// The kind of the platform.
+    platform_kind: PlatformKind,
+    /// The platform to run the program on.
+    platform: Arc<dyn Platform + Send + Sync>,
+    /// The program to run.
+    program: Arc<dyn Program + Send + Sync>,
+    /// The client artifact of the program, loaded into the platform.
+    client_artifact: PathBuf,
+    /// The kind of the client artifact.
+    client_kind: ClientArtifactKind,
+}
+
+/// The outcome of a program's execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ExecutionOutcome {
+    /// The exit status of the program, or `None` if it stalled and was killed.
+    pub(crate) exit_status: Option<u8>,
+    /// Whether or not the program stalled, whether or not it was killed for it.
+    pub(crate) stalled: bool,
+    /// The wall time of the execution, including loading the client into the platform.
+    pub(crate) duration: Duration,
+    /// The wall time of the platform's run of the program alone.
+    pub(crate) vm_duration: Duration,
+    /// The number of instructions that the platform executed, if it reports them.
+    pub(crate) instructions: Option<u64>,
+    /// The peak resident set size of the platform's processes, in bytes, if it could be sampled.
+    pub(crate) peak_rss: Option<u64>,
+}
+
+impl Executor {
+    /// Resolves the platform and program from their definitions.
+    ///
+    /// ## Takes
+    /// - `platform_kind` - The kind of the platform.
+    /// - `platform_def` - The definition of the platform.
+    /// - `program_kind` - The kind of the program.
+    /// - `program_def` - The definition of the program.
+    /// - `builtin_host` - The builtin preimage server that serves the client in place of the
+    ///   program's host, if any. The native platform has no client to serve, so the program's
+    ///   host is always ran there.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - Errors if the platform or program is missing an artifact.
+    pub(crate) fn new(
+        platform_kind: PlatformKind,
+        platform_def: &PlatformDefinition,
+        program_kind: ProgramKind,
+        program_def: &FPPDefinition,
+        builtin_host: Option<BuiltinHost>,
+    ) -> Result<Self> {
+        let client_artifact = program_def
+            .build
+            .get_artifact("client")
+            .ok_or(eyre!("Failed to get client artifact"))?;
+
+        let platform =
+            platform_kind.get_platform(platform_def, platform_def.run_flags_for(program_def))?;
+        let program: Arc<dyn Program + Send + Sync> =
+            match builtin_host.filter(|_| platform_kind != PlatformKind::Native) {
+                Some(builtin_host) => Arc::new(builtin_host),
+                None => program_kind.get_program(
+                    program_def
+                        .build
+                        .get_artifact("host")
+                        .ok_or(eyre!("No host artifact"))?,
+                    client_artifact.clone(),
+                ),
+            };
+
+        Ok(Self {
+            platform_kind,
+            platform,
+            program,
+            client_artifact,
+            client_kind: program_def.client_kind,
+        })
+    }
+
+    /// Resolves the platform from its definition, and the program from the given artifacts, e.g.
+    /// copies of the artifacts of the program built at another revision.
+    ///
+    /// ## Takes
+    /// - `platform_kind` - The kind of the platform.
+    /// - `platform_def` - The definition of the platform.
+    /// - `program_kind` - The kind of the program.
+    /// - `program_def` - The definition of the program.
+    /// - `host` - The host artifact of the program.
+    /// - `client` - The client artifact of the program.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - Errors if the platform is missing an artifact.
+    pub(crate) fn with_artifacts(
+        platform_kind: PlatformKind,
+        platform_def: &PlatformDefinition,
+        program_kind: ProgramKind,
+        program_def: &FPPDefinition,
+        host: PathBuf,
+        client: PathBuf,
+    ) -> Result<Self> {
+        Ok(Self {
+            platform_kind,
+            platform: platform_kind
+                .get_platform(platform_def, platform_def.run_flags_for(program_def))?,
+            program: program_kind.get_program(host, client.clone()),
+            client_artifact: client,
+            client_kind: program_def.client_kind,
+        })
+    }
+
+    /// Builds the program, if its artifacts are stale or the rebuild is forced.
+    pub(crate) async fn build_program(
+        program_kind: ProgramKind,
+        def: &FPPDefinition,
+        force: bool,
+    ) -> Result<()> {
+        info!(target: "executor", "Building program: {program_kind}");
+        ComponentBuild::of_program(def).run(force).await
+    }
+
+    /// Loads the client into the platform, and runs the program on it.
+    ///
+    /// ## Takes
+    /// - `inputs` - The inputs to the program.
+    /// - `workdir` - The working directory to run the program in.
+    /// - `opts` - The options for supervising the spawned processes. The native platform is never
+    ///   pinned to a CPU core.
+    ///
+    /// ## Returns
+    /// - `Result<ExecutionOutcome>` - The outcome of the execution. Stalling is an outcome rather
+    ///   than an error.
+    pub(crate) async fn run(
+        &self,
+        inputs: &ProgramHostInputs,
+        workdir: &Path,
+        opts: &ProcessOptions,
+    ) -> Result<ExecutionOutcome> {
+        let start_time = Instant::now();
+
+        // Prepare the client in the platform's state format.
+        self.platform
+            .prepare_client(self.client_artifact.as_path(), self.client_kind, workdir)
+            .await?;
+
+        // Run the program on the platform, sampling the resident set size of its processes and
+        // watching them for stalls.
+        let peak_rss = Arc::new(AtomicU64::new(0));
+        let stalled = Arc::new(AtomicBool::new(false));
+        let opts = ProcessOptions {
+            cpu: opts
+                .cpu
+                .filter(|_| self.platform_kind != PlatformKind::Native),
+            peak_rss: Some(peak_rss.clone()),
+            stalled: Some(stalled.clone()),
+            ..opts.clone()
+        };
+        let vm_start_time = Instant::now();
+        let exit_status = match self
+            .platform
+            .run(inputs, self.program.clone(), workdir, &opts)
+            .await
+        {
+            Ok(status) => Some(status),
+            Err(e) if e.downcast_ref::<ProcessStalled>().is_some() => None,
+            Err(e) => return Err(e),
+        };
+
+        let vm_duration = vm_start_time.elapsed();
+
+        Ok(ExecutionOutcome {
+            exit_status,
+            stalled: stalled.load(Ordering::Relaxed),
+            duration: start_time.elapsed(),
+            vm_duration,
+            instructions: exit_status.and_then(|_| self.platform.executed_steps(workdir)),
+            peak_rss: Some(peak_rss.load(Ordering::Relaxed)).filter(|rss| *rss > 0),
+        })
+    }
+
+    /// Returns the path of the final state that the platform writes to the workdir, if it writes
+    /// one.
+    pub(crate) fn final_state(&self, workdir: &Path) -> Option<PathBuf> {
+        self.platform.final_state(workdir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_component_builds() {
+        let source = |repo: &str, rev: &str| {
+            ComponentBuild::Source(BuildInstructions {
+                repo: repo.to_string(),
+                rev: rev.to_string(),
+                ..Default::default()
+            })
+        };
+        let image = ComponentBuild::Image("cannon:latest".to_string());
+
+        let groups = ComponentBuild::schedule([
+            source("ethereum-optimism/optimism", "v1"),
+            source("ethereum-optimism/asterisc", "v1"),
+            image.clone(),
+            source("ethereum-optimism/optimism", "v1"),
+            source("ethereum-optimism/optimism", "v2"),
+            image.clone(),
+        ]);
+        assert_eq!(
+            groups,
+            vec![
+                vec![
+                    source("ethereum-optimism/optimism", "v1"),
+                    source("ethereum-optimism/optimism", "v2"),
+                ],
+                vec![source("ethereum-optimism/asterisc", "v1")],
+                vec![image],
+            ]
+        );
+    }
+}
@@ -1,7 +1,155 @@
 //! Contains the definition for the test fixture format.
 
-use alloy_primitives::B256;
+use crate::{
+    archive,
+    generator::WITNESS_DB_DIR_NAME,
+    onchain::OnchainFinding,
+    preimage::keccak256_key,
+    registry::{
+        components::disk_usage,
+        program::{ProgramHostInputs, ProgramHostSource, ProgramKind},
+        version::RevVersion,
+    },
+    state::StateDir,
+};
+use alloy_primitives::{hex, keccak256, Bytes, Keccak256, B256, U256};
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    Result,
+};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// The file that stamps a fixture's decompressed files with the content hash of the archives that
+/// they were decompressed from, so that they can be reused between runs.
+const DECOMPRESSED_STAMP: &str = ".decompressed";
+
+/// A fixture directory within the tests directory: `<tests-dir>/<name>`, or
+/// `<tests-dir>/<chain>/<name>` for the fixtures namespaced by the chain they were generated on.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct FixtureDir {
+    /// The chain that the fixture is namespaced by, if any.
+    pub(crate) chain: Option<String>,
+    /// The path of the fixture directory.
+    pub(crate) path: PathBuf,
+}
+
+impl FixtureDir {
+    /// Finds the fixture directories within the tests directory, and within its per-chain
+    /// subdirectories.
+    ///
+    /// ## Takes
+    /// - `tests_dir` - The tests directory.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<Self>>` - The fixture directories, sorted by chain and path.
+    pub(crate) fn find_all(tests_dir: &Path) -> Result<Vec<Self>> {
+        let subdirs = |dir: &Path| -> Result<Vec<PathBuf>> {
+            Ok(fs::read_dir(dir)?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| path.is_dir())
+                .collect())
+        };
+
+        let mut fixture_dirs = Vec::new();
+        for path in subdirs(tests_dir)? {
+            if path.join("fixture.toml").is_file() {
+                fixture_dirs.push(Self { chain: None, path });
+                continue;
+            }
+            let Some(chain) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            for fixture_dir in subdirs(&path)? {
+                if fixture_dir.join("fixture.toml").is_file() {
+                    fixture_dirs.push(Self {
+                        chain: Some(chain.to_string()),
+                        path: fixture_dir,
+                    });
+                }
+            }
+        }
+        fixture_dirs.sort();
+        Ok(fixture_dirs)
+    }
+
+    /// Finds the fixture directory with the given name within the tests directory.
+    ///
+    /// ## Takes
+    /// - `tests_dir` - The tests directory.
+    /// - `name` - The name of the fixture, or `<chain>/<name>` to disambiguate between chains.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The fixture directory, or Err if no single fixture has the name.
+    pub(crate) fn find(tests_dir: &Path, name: &str) -> Result<Self> {
+        let mut matches = Self::find_all(tests_dir)?
+            .into_iter()
+            .filter(|fixture_dir| fixture_dir.path.ends_with(name))
+            .collect::<Vec<_>>();
+        match matches.len() {
+            0 => bail!(
+                "No fixture named `{name}` in the tests directory `{}`",
+                tests_dir.display()
+            ),
+            1 => Ok(matches.remove(0)),
+            _ => bail!(
+                "The fixture name `{name}` is ambiguous between chains, expected one of: {}",
+                matches
+                    .iter()
+                    .filter_map(|m| Some(format!("{}/{name}", m.chain.as_ref()?)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Loads the fixture within the directory.
+    pub(crate) fn load(&self) -> Result<TestFixture> {
+        let path = self.path.join("fixture.toml");
+        toml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| eyre!("Failed to parse `{}`: {e}", path.display()))
+    }
+
+    /// Loads the fixture within the directory, and returns the inputs that a program's host is
+    /// ran with on it, serving preimages from its witness database.
+    ///
+    /// ## Takes
+    /// - `scratch` - The directory that a compressed fixture is decompressed into, under the name
+    ///   of the fixture.
+    ///
+    /// ## Returns
+    /// - `Result<(TestFixture, ProgramHostInputs)>` - The fixture and the inputs. Errors if the
+    ///   fixture cannot be loaded or decompressed.
+    pub(crate) async fn host_inputs(
+        &self,
+        scratch: &Path,
+    ) -> Result<(TestFixture, ProgramHostInputs)> {
+        let fixture = self.load()?;
+        let data_dir = if TestFixture::is_compressed(&self.path) {
+            let dst = scratch.join(&fixture.metadata.name);
+            TestFixture::decompress(&self.path, &dst, false).await?;
+            dst
+        } else {
+            self.path.clone()
+        };
+        let inputs = ProgramHostInputs {
+            fixture_inputs: fixture.inputs.clone(),
+            rollup_cfg_path: self.path.join("rollup.json"),
+            genesis_path: data_dir.join("genesis.json"),
+            chain_config_path: None,
+            source: ProgramHostSource::Disk {
+                path: data_dir.join(WITNESS_DB_DIR_NAME),
+            },
+        };
+        Ok((fixture, inputs))
+    }
+}
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -11,6 +159,252 @@ pub(crate) struct TestFixture {
     pub(crate) metadata: FixtureMetadata,
     /// The inputs to the fault proof program.
     pub(crate) inputs: FixtureInputs,
+    /// The outcome of the reference program's run that the fixture was generated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reference: Option<RunOutcome>,
+}
+
+impl TestFixture {
+    /// Returns whether or not the fixture is stored compressed, in which case its genesis file
+    /// and witness database are decompressed before it is ran.
+    pub(crate) fn is_compressed(fixture_dir: &Path) -> bool {
+        fixture_dir.join("genesis.json.zst").exists()
+    }
+
+    /// Decompresses the genesis file and witness database of the fixture into a scratch
+    /// directory, leaving the fixture directory untouched, and stamps them with the content hash
+    /// of the archives that they were decompressed from.
+    ///
+    /// ## Takes
+    /// - `fixture_dir` - The fixture directory, whose fixture is stored compressed.
+    /// - `dst` - The scratch directory to decompress the fixture into.
+    /// - `reuse` - Whether or not to reuse the files left in the scratch directory by a previous
+    ///   run, if they were decompressed from the current archives.
+    ///
+    /// ## Returns
+    /// - `Result<bool>` - Whether or not the fixture was decompressed, rather than reused.
+    pub(crate) async fn decompress(fixture_dir: &Path, dst: &Path, reuse: bool) -> Result<bool> {
+        let genesis_files = Self::genesis_files(fixture_dir)?;
+        let hash = Self::archives_hash(fixture_dir, &genesis_files).await?;
+        let stamp = dst.join(DECOMPRESSED_STAMP);
+        if reuse
+            && genesis_files.iter().all(|name| dst.join(name).is_file())
+            && dst.join(WITNESS_DB_DIR_NAME).is_dir()
+            && fs::read_to_string(&stamp).is_ok_and(|stamped| stamped.trim() == hash.to_string())
+        {
+            return Ok(false);
+        }
+
+        // Remove any stale or partially decompressed files, e.g. from an interrupted run.
+        if dst.exists() {
+            fs::remove_dir_all(dst)?;
+        }
+        fs::create_dir_all(dst)?;
+
+        // Decompress the genesis files: the fixture's own, and those of the other chains of an
+        // interop fixture. They are shared by every test of the fixture, none of which may modify
+        // them.
+        for name in genesis_files {
+            let genesis = dst.join(&name);
+            archive::decompress_file(&fixture_dir.join(format!("{name}.zst")), &genesis)
+                .await
+                .map_err(|e| eyre!("Failed to decompress genesis file `{name}`: {e}"))?;
+            let mut permissions = fs::metadata(&genesis)?.permissions();
+            permissions.set_readonly(true);
+            fs::set_permissions(&genesis, permissions)?;
+        }
+
+        // Decompress witness database
+        archive::unpack(&fixture_dir.join("witness-db.tar.zst"), dst)
+            .await
+            .map_err(|e| eyre!("Failed to decompress witness database: {e}"))?;
+
+        // Stamp the decompressed files once they are complete.
+        fs::write(stamp, hash.to_string())?;
+        Ok(true)
+    }
+
+    /// Returns the names of the compressed genesis files of the fixture, once decompressed: its
+    /// own `genesis.json`, and the `genesis-<chain-id>.json` of the other chains of an interop
+    /// fixture, in the order of their names.
+    fn genesis_files(fixture_dir: &Path) -> Result<Vec<String>> {
+        let mut interop = Vec::new();
+        for entry in fs::read_dir(fixture_dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if let Some(name) = name.strip_suffix(".zst") {
+                if name.starts_with("genesis-") && name.ends_with(".json") {
+                    interop.push(name.to_string());
+                }
+            }
+        }
+        interop.sort();
+        Ok(std::iter::once("genesis.json".to_string())
+            .chain(interop)
+            .collect())
+    }
+
+    /// Returns the keccak256 hash of the names and contents of the fixture's archives, which
+    /// identifies the files that they decompress to.
+    ///
+    /// ## Takes
+    /// - `fixture_dir` - The fixture directory.
+    /// - `genesis_files` - The names of the fixture's genesis files, as returned by
+    ///   [Self::genesis_files].
+    ///
+    /// ## Returns
+    /// - `Result<B256>` - The hash, or Err if an archive could not be read.
+    async fn archives_hash(fixture_dir: &Path, genesis_files: &[String]) -> Result<B256> {
+        let archives = genesis_files
+            .iter()
+            .map(|name| format!("{name}.zst"))
+            .chain(std::iter::once("witness-db.tar.zst".to_string()))
+            .map(|name| (fixture_dir.join(&name), name))
+            .collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || {
+            let mut hasher = Keccak256::new();
+            let mut buf = vec![0u8; 1 << 16];
+            for (archive, name) in archives {
+                let mut file = fs::File::open(&archive)
+                    .map_err(|e| eyre!("Failed to open `{}`: {e}", archive.display()))?;
+                hasher.update(name.as_bytes());
+                loop {
+                    let n = file.read(&mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                }
+            }
+            Ok(hasher.finalize())
+        })
+        .await
+        .map_err(|e| eyre!("Hashing task failed: {e}"))?
+    }
+
+    /// Checks that the fixture's witness database is intact, i.e. that it contains the block
+    /// headers of the `l1_head` and `l2_head`, so that a corrupted fixture is reported before the
+    /// programs fail on it.
+    ///
+    /// ## Takes
+    /// - `fixture_dir` - The fixture directory, with its witness database decompressed.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors with the problem if the witness database is corrupted.
+    pub(crate) fn check_witness_db(&self, fixture_dir: &Path) -> Result<()> {
+        let name = &self.metadata.name;
+        let kv_dir = fixture_dir.join(WITNESS_DB_DIR_NAME);
+        ensure!(
+            kv_dir.is_dir(),
+            "Fixture `{name}` is corrupted: its witness database is missing. Regenerate it with `fpt generate`."
+        );
+
+        for (label, hash) in [
+            ("L1 head", self.inputs.l1_head),
+            ("L2 head", self.inputs.l2_head),
+        ] {
+            let key = keccak256_key(hash);
+            let preimage = fs::read_to_string(kv_dir.join(format!("{key}.txt")))
+                .ok()
+                .and_then(|encoded| hex::decode(encoded.trim()).ok())
+                .ok_or_else(|| {
+                    eyre!(
+                        "Fixture `{name}` is corrupted: its witness database is missing the {label} header ({hash}). Regenerate it with `fpt generate`."
+                    )
+                })?;
+            ensure!(
+                keccak256(&preimage) == hash,
+                "Fixture `{name}` is corrupted: the {label} header in its witness database does not hash to {hash}. Regenerate it with `fpt generate`."
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the fixture's witness database without decompressing the fixture: the headers
+    /// checked by [Self::check_witness_db] are extracted from the witness database archive alone,
+    /// unless the witness database is already decompressed.
+    ///
+    /// ## Takes
+    /// - `fixture_dir` - The fixture directory.
+    ///
+    /// ## Returns
+    /// - `Result<WitnessStatus>` - The status of the witness database, or Err if the headers could
+    ///   not be extracted.
+    pub(crate) async fn witness_status(&self, fixture_dir: &Path) -> Result<WitnessStatus> {
+        let archive = fixture_dir.join(format!("{WITNESS_DB_DIR_NAME}.tar.zst"));
+        let status = |result: Result<()>| match result {
+            Ok(()) => WitnessStatus::Valid,
+            Err(e) => WitnessStatus::Corrupted(e.to_string()),
+        };
+
+        if fixture_dir.join(WITNESS_DB_DIR_NAME).is_dir() {
+            return Ok(status(self.check_witness_db(fixture_dir)));
+        }
+        if !archive.is_file() {
+            return Ok(WitnessStatus::Missing);
+        }
+
+        // Headers that are missing from the archive are reported by the check.
+        let extract_dir = StateDir::get().tempdir()?;
+        let headers = [self.inputs.l1_head, self.inputs.l2_head].map(|hash| {
+            Path::new(WITNESS_DB_DIR_NAME).join(format!("{}.txt", keccak256_key(hash)))
+        });
+        archive::unpack_entries(&archive, extract_dir.path(), &headers).await?;
+        Ok(status(self.check_witness_db(extract_dir.path())))
+    }
+
+    /// Returns the size of the fixture as it is stored, excluding its decompressed files.
+    pub(crate) fn stored_size(fixture_dir: &Path) -> u64 {
+        let decompressed = Self::decompressed_files(fixture_dir);
+        let Ok(entries) = fs::read_dir(fixture_dir) else {
+            return 0;
+        };
+        entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| !decompressed.contains(path))
+            .map(|path| {
+                if path.is_dir() {
+                    disk_usage(&path)
+                } else {
+                    fs::metadata(&path).map_or(0, |m| m.len())
+                }
+            })
+            .sum()
+    }
+
+    /// Returns the decompressed files that are present within the fixture directory, left behind
+    /// by earlier versions of `fpt`, which decompressed fixtures in place. Fixtures that are
+    /// stored uncompressed have none.
+    pub(crate) fn decompressed_files(fixture_dir: &Path) -> Vec<PathBuf> {
+        if !Self::is_compressed(fixture_dir) {
+            return Vec::new();
+        }
+        ["genesis.json", WITNESS_DB_DIR_NAME, DECOMPRESSED_STAMP]
+            .into_iter()
+            .map(|name| fixture_dir.join(name))
+            .filter(|path| path.exists())
+            .collect()
+    }
+}
+
+/// The status of a fixture's witness database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum WitnessStatus {
+    /// The witness database contains the headers of the fixture's inputs.
+    Valid,
+    /// The fixture has no witness database.
+    Missing,
+    /// The witness database is corrupted, with the problem.
+    Corrupted(String),
+}
+
+impl Display for WitnessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Valid => write!(f, "valid"),
+            Self::Missing => write!(f, "missing"),
+            Self::Corrupted(_) => write!(f, "corrupted"),
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -20,6 +414,107 @@ pub(crate) struct FixtureMetadata {
     pub(crate) name: String,
     /// The expected status byte of the program execution.
     pub(crate) expected_status: u8,
+    /// Whether or not the fixture must run with no other test running concurrently.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) exclusive: bool,
+    /// The maximum number of tests of the fixture that may run concurrently, across the matrix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_parallel: Option<usize>,
+    /// The oldest revision of each program that the fixture is compatible with (e.g. the first
+    /// release that supports the fixture's hardfork).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) min_program_rev: HashMap<ProgramKind, String>,
+    /// The exact revision of each program that the fixture is pinned to.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) rev_overrides: HashMap<ProgramKind, String>,
+    /// The output root that the program is expected to log once it validates the claim, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expected_output_root: Option<B256>,
+    /// The keccak256 hash of the final state that the FPVM is expected to write, if any. Only
+    /// checked on the platforms that write one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expected_state_hash: Option<B256>,
+    /// Whether or not the claim was proposed on L1, if checked by `fpt verify-onchain`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) onchain: Option<OnchainFinding>,
+}
+
+impl FixtureMetadata {
+    /// Checks that the fixture is compatible with the given revision of a program.
+    ///
+    /// ## Takes
+    /// - `program` - The program.
+    /// - `rev` - The revision of the program that the registry builds.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors with the reason if the fixture is pinned to another revision of the
+    ///   program, or requires a newer one.
+    pub(crate) fn check_program_rev(&self, program: ProgramKind, rev: &str) -> Result<()> {
+        if let Some(pinned) = self.rev_overrides.get(&program) {
+            ensure!(
+                pinned == rev,
+                "Pinned to `{program}` {pinned}, but the registry builds {rev}"
+            );
+        }
+
+        if let Some(min_rev) = self.min_program_rev.get(&program) {
+            let (min_version, version) =
+                (min_rev.parse::<RevVersion>()?, rev.parse::<RevVersion>()?);
+            ensure!(
+                version >= min_version,
+                "Requires `{program}` {min_rev} or newer, but the registry builds {rev}"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of a run of the reference program, recorded in the fixture when it is generated.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct RunOutcome {
+    /// The exit status of the reference program.
+    pub(crate) exit_status: u8,
+    /// The wall time of the reference run, in milliseconds.
+    pub(crate) duration_ms: u64,
+    /// The output root that the reference program derived, if it was logged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) output_root: Option<B256>,
+    /// The statistics of the captured witness.
+    pub(crate) witness: WitnessStats,
+}
+
+/// The statistics of a witness captured by the reference program.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct WitnessStats {
+    /// The number of preimages within the witness database.
+    pub(crate) preimages: u64,
+    /// The size of the uncompressed witness database, in bytes.
+    pub(crate) size: u64,
+    /// The number of L1 blocks that derivation traversed.
+    pub(crate) l1_blocks: u64,
+}
+
+impl WitnessStats {
+    /// Measures the witness database, which stores each preimage in its own file.
+    ///
+    /// ## Takes
+    /// - `witness_db` - The directory of the witness database.
+    /// - `l1_blocks` - The number of L1 blocks that derivation traversed.
+    pub(crate) fn measure(witness_db: &Path, l1_blocks: u64) -> Result<Self> {
+        let (preimages, size) = fs::read_dir(witness_db)?
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .fold((0, 0), |(count, size), metadata| {
+                (count + 1, size + metadata.len())
+            });
+        Ok(Self {
+            preimages,
+            size,
+            l1_blocks,
+        })
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -37,4 +532,326 @@ pub(crate) struct FixtureInputs {
     pub(crate) l2_head: B256,
     /// The L2 chain ID.
     pub(crate) l2_chain_id: u64,
+    /// The inputs of a superchain interop fixture, whose claim is a super root over multiple L2
+    /// chains rather than an output root of a single chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) interop: Option<InteropInputs>,
+}
+
+/// The inputs of a superchain interop fixture. The `l2-claim` of the fixture is the claimed super
+/// root, and its `l2-chain-id` the chain whose configuration is stored as the fixture's
+/// `rollup.json` and `genesis.json`.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct InteropInputs {
+    /// The agreed super root, committing to the output roots of every chain at the agreed
+    /// timestamp.
+    pub(crate) agreed_super_root: B256,
+    /// The timestamp of the agreed super root.
+    pub(crate) agreed_timestamp: u64,
+    /// The timestamp of the claimed super root.
+    pub(crate) claimed_timestamp: u64,
+    /// The chains of the superchain.
+    pub(crate) chains: Vec<InteropChain>,
+}
+
+/// A chain of a superchain interop fixture.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct InteropChain {
+    /// The chain ID.
+    pub(crate) chain_id: u64,
+    /// The L2 head hash of the chain at the agreed timestamp.
+    pub(crate) l2_head: B256,
+    /// The output root of the chain at the agreed timestamp.
+    pub(crate) l2_output_root: B256,
+}
+
+impl InteropInputs {
+    /// The version of the super root encoding.
+    const SUPER_ROOT_VERSION: u8 = 1;
+
+    /// Returns the agreed prestate: the encoding of the agreed super root, whose keccak256 hash is
+    /// the super root. The chains are encoded in ascending order of their chain IDs.
+    pub(crate) fn agreed_prestate(&self) -> Bytes {
+        let mut chains = self.chains.iter().collect::<Vec<_>>();
+        chains.sort_by_key(|chain| chain.chain_id);
+
+        let mut prestate = Vec::with_capacity(1 + 8 + chains.len() * 64);
+        prestate.push(Self::SUPER_ROOT_VERSION);
+        prestate.extend_from_slice(&self.agreed_timestamp.to_be_bytes());
+        for chain in chains {
+            prestate.extend_from_slice(&U256::from(chain.chain_id).to_be_bytes::<32>());
+            prestate.extend_from_slice(chain.l2_output_root.as_slice());
+        }
+        prestate.into()
+    }
+
+    /// Checks that the interop inputs are consistent with each other, and include the fixture's
+    /// own chain.
+    ///
+    /// ## Takes
+    /// - `l2_chain_id` - The chain ID of the fixture's own chain.
+    pub(crate) fn check(&self, l2_chain_id: u64) -> Result<()> {
+        ensure!(
+            self.chains
+                .iter()
+                .any(|chain| chain.chain_id == l2_chain_id),
+            "The interop chains do not include the fixture's chain {l2_chain_id}"
+        );
+        ensure!(
+            self.claimed_timestamp > self.agreed_timestamp,
+            "The claimed timestamp {} is not after the agreed timestamp {}",
+            self.claimed_timestamp,
+            self.agreed_timestamp
+        );
+        let super_root = keccak256(self.agreed_prestate());
+        ensure!(
+            super_root == self.agreed_super_root,
+            "The agreed super root {} does not commit to the chains' output roots, whose super root is {super_root}",
+            self.agreed_super_root
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_witness_dbs() {
+        let fixture_dir = tempfile::tempdir().unwrap();
+        let header = |n: u8| vec![0xf9, 0x02, n];
+        let mut fixture = TestFixture {
+            metadata: FixtureMetadata {
+                name: "corrupted".to_string(),
+                ..Default::default()
+            },
+            inputs: FixtureInputs {
+                l1_head: keccak256(header(1)),
+                l2_head: keccak256(header(2)),
+                ..Default::default()
+            },
+            reference: None,
+        };
+        let write_header = |hash: B256, preimage: Vec<u8>| {
+            fs::write(
+                fixture_dir
+                    .path()
+                    .join(WITNESS_DB_DIR_NAME)
+                    .join(format!("{}.txt", keccak256_key(hash))),
+                hex::encode(preimage),
+            )
+            .unwrap()
+        };
+
+        let err = |fixture: &TestFixture| {
+            fixture
+                .check_witness_db(fixture_dir.path())
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(err(&fixture).contains("its witness database is missing"));
+
+        fs::create_dir(fixture_dir.path().join(WITNESS_DB_DIR_NAME)).unwrap();
+        write_header(fixture.inputs.l1_head, header(1));
+        assert!(err(&fixture).contains("missing the L2 head header"));
+
+        write_header(fixture.inputs.l2_head, header(2));
+        fixture.check_witness_db(fixture_dir.path()).unwrap();
+
+        write_header(fixture.inputs.l2_head, header(3));
+        assert!(err(&fixture).contains("the L2 head header in its witness database does not hash"));
+        fixture.inputs.l2_head = keccak256(header(3));
+        assert!(err(&fixture).contains("missing the L2 head header"));
+    }
+
+    #[tokio::test]
+    async fn witness_status_from_archives() {
+        let fixture_dir = tempfile::tempdir().unwrap();
+        let header = |n: u8| vec![0xf9, 0x02, n];
+        let fixture = TestFixture {
+            inputs: FixtureInputs {
+                l1_head: keccak256(header(1)),
+                l2_head: keccak256(header(2)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            fixture.witness_status(fixture_dir.path()).await.unwrap(),
+            WitnessStatus::Missing
+        );
+
+        // Archives the witness database with the given headers, as `fpt generate` does.
+        let witness_db = fixture_dir.path().join(WITNESS_DB_DIR_NAME);
+        let archive_headers = |headers: &'static [u8]| {
+            let witness_db = witness_db.clone();
+            let archive = fixture_dir.path().join("witness-db.tar.zst");
+            async move {
+                fs::create_dir_all(&witness_db).unwrap();
+                for n in headers {
+                    fs::write(
+                        witness_db.join(format!("{}.txt", keccak256_key(keccak256(header(*n))))),
+                        hex::encode(header(*n)),
+                    )
+                    .unwrap();
+                }
+                archive::pack_dir(&witness_db, WITNESS_DB_DIR_NAME, &archive)
+                    .await
+                    .unwrap();
+                fs::remove_dir_all(witness_db).unwrap();
+            }
+        };
+
+        archive_headers(&[1]).await;
+        assert!(matches!(
+            fixture.witness_status(fixture_dir.path()).await.unwrap(),
+            WitnessStatus::Corrupted(problem) if problem.contains("missing the L2 head header")
+        ));
+        archive_headers(&[1, 2]).await;
+        assert_eq!(
+            fixture.witness_status(fixture_dir.path()).await.unwrap(),
+            WitnessStatus::Valid
+        );
+    }
+
+    #[test]
+    fn find_namespaced_fixtures() {
+        let tests_dir = tempfile::tempdir().unwrap();
+        for fixture in [
+            "flat",
+            "devnet/deposit",
+            "op-sepolia/deposit",
+            "op-sepolia/empty",
+        ] {
+            let fixture_dir = tests_dir.path().join(fixture);
+            fs::create_dir_all(&fixture_dir).unwrap();
+            if fixture != "op-sepolia/empty" {
+                fs::write(fixture_dir.join("fixture.toml"), "").unwrap();
+            }
+        }
+        fs::write(tests_dir.path().join("quarantine.toml"), "").unwrap();
+
+        assert_eq!(
+            FixtureDir::find_all(tests_dir.path()).unwrap(),
+            [
+                (None, "flat"),
+                (Some("devnet"), "devnet/deposit"),
+                (Some("op-sepolia"), "op-sepolia/deposit"),
+            ]
+            .map(|(chain, path)| FixtureDir {
+                chain: chain.map(str::to_string),
+                path: tests_dir.path().join(path),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn reuse_decompressed_fixtures() {
+        let fixture_dir = tempfile::tempdir().unwrap();
+        let dir = fixture_dir.path();
+        let sources = tempfile::tempdir().unwrap();
+        let witness_db = sources.path().join(WITNESS_DB_DIR_NAME);
+        fs::create_dir_all(&witness_db).unwrap();
+        fs::write(witness_db.join("0x01.txt"), "01").unwrap();
+        archive::pack_dir(
+            &witness_db,
+            WITNESS_DB_DIR_NAME,
+            &dir.join("witness-db.tar.zst"),
+        )
+        .await
+        .unwrap();
+        let compress_genesis = |genesis: &'static str| {
+            let path = sources.path().join("genesis.json");
+            async move {
+                fs::write(&path, genesis).unwrap();
+                archive::compress_file(&path, &dir.join("genesis.json.zst"))
+                    .await
+                    .unwrap();
+            }
+        };
+        compress_genesis("{}").await;
+        assert!(TestFixture::is_compressed(dir));
+
+        let scratch = tempfile::tempdir().unwrap();
+        let dst = scratch.path().join("fixture");
+        assert!(TestFixture::decompress(dir, &dst, true).await.unwrap());
+        assert!(dst.join("witness-db/0x01.txt").is_file());
+        assert!(!TestFixture::decompress(dir, &dst, true).await.unwrap());
+        assert!(TestFixture::decompress(dir, &dst, false).await.unwrap());
+
+        // Changed archives are decompressed again, even when reusing.
+        compress_genesis(r#"{"config":{}}"#).await;
+        assert!(TestFixture::decompress(dir, &dst, true).await.unwrap());
+        assert_eq!(
+            fs::read_to_string(dst.join("genesis.json")).unwrap(),
+            r#"{"config":{}}"#
+        );
+
+        // So are new or changed genesis files of the other chains of an interop fixture, which
+        // must all be present to be reused.
+        let compress_interop_genesis = |genesis: &'static str| {
+            let path = sources.path().join("genesis-902.json");
+            async move {
+                fs::write(&path, genesis).unwrap();
+                archive::compress_file(&path, &dir.join("genesis-902.json.zst"))
+                    .await
+                    .unwrap();
+            }
+        };
+        compress_interop_genesis("{}").await;
+        assert!(TestFixture::decompress(dir, &dst, true).await.unwrap());
+        assert_eq!(
+            fs::read_to_string(dst.join("genesis-902.json")).unwrap(),
+            "{}"
+        );
+        assert!(!TestFixture::decompress(dir, &dst, true).await.unwrap());
+        compress_interop_genesis(r#"{"config":{}}"#).await;
+        assert!(TestFixture::decompress(dir, &dst, true).await.unwrap());
+        assert_eq!(
+            fs::read_to_string(dst.join("genesis-902.json")).unwrap(),
+            r#"{"config":{}}"#
+        );
+        fs::remove_file(dst.join("genesis-902.json")).unwrap();
+        assert!(TestFixture::decompress(dir, &dst, true).await.unwrap());
+        assert!(dst.join("genesis-902.json").is_file());
+
+        // The fixture directory is left untouched.
+        assert!(TestFixture::decompressed_files(dir).is_empty());
+    }
+
+    #[test]
+    fn check_interop_inputs() {
+        let mut interop = InteropInputs {
+            agreed_timestamp: 10,
+            claimed_timestamp: 12,
+            chains: vec![
+                InteropChain {
+                    chain_id: 902,
+                    l2_output_root: B256::repeat_byte(0x02),
+                    ..Default::default()
+                },
+                InteropChain {
+                    chain_id: 901,
+                    l2_output_root: B256::repeat_byte(0x01),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        // The chains are encoded in ascending order of their chain IDs.
+        let prestate = interop.agreed_prestate();
+        assert_eq!(prestate.len(), 1 + 8 + 2 * 64);
+        assert_eq!(prestate[..9], [1, 0, 0, 0, 0, 0, 0, 0, 10]);
+        assert_eq!(U256::from_be_slice(&prestate[9..41]), U256::from(901));
+        assert_eq!(prestate[41..73], [0x01; 32]);
+
+        assert!(interop.check(901).is_err());
+        interop.agreed_super_root = keccak256(&prestate);
+        interop.check(901).unwrap();
+        assert!(interop.check(903).is_err());
+    }
 }
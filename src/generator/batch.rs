@@ -0,0 +1,177 @@
+//! Batch generation of test cases for a range of L2 blocks, across a pool of workers.
+
+use super::{GeneratorSetup, TestCaseGenerator};
+use crate::cli::GenerateConfig;
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    owo_colors::OwoColorize,
+    Report, Result,
+};
+use std::{fmt::Display, str::FromStr, sync::Arc};
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{error, info};
+
+/// An inclusive range of L2 blocks, e.g. `100..=110`, or `100..110` for an exclusive end. A
+/// `:<stride>` suffix selects every `<stride>`th block of the range, e.g. `100..200:10`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BlockRange {
+    /// The first block of the range.
+    pub(crate) start: u64,
+    /// The last block of the range.
+    pub(crate) end: u64,
+    /// The distance between the selected blocks of the range.
+    pub(crate) stride: u64,
+}
+
+impl BlockRange {
+    /// Returns the selected blocks of the range, in ascending order.
+    pub(crate) fn blocks(&self) -> impl Iterator<Item = u64> {
+        (self.start..=self.end).step_by(self.stride as usize)
+    }
+}
+
+impl FromStr for BlockRange {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (range, stride) = match s.rsplit_once(':') {
+            Some((range, stride)) => (range, stride.trim().parse::<u64>()?),
+            None => (s, 1),
+        };
+        ensure!(
+            stride > 0,
+            "Invalid range `{s}`, the stride must be positive"
+        );
+        let (start, end) = match range.split_once("..=") {
+            Some((start, end)) => (start.trim().parse()?, end.trim().parse()?),
+            None => {
+                let (start, end) = range
+                    .split_once("..")
+                    .ok_or_else(|| eyre!("Invalid range `{s}`, expected `<start>..<end>`"))?;
+                let end = end.trim().parse::<u64>()?;
+                (start.trim().parse()?, end.saturating_sub(1))
+            }
+        };
+        ensure!(start <= end, "Invalid range `{s}`, the range is empty");
+        Ok(Self { start, end, stride })
+    }
+}
+
+impl Display for BlockRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..={}", self.start, self.end)?;
+        if self.stride != 1 {
+            write!(f, ":{}", self.stride)?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates a test case named `<name>-<block>` for each selected block of the range, running up to
+/// `workers` captures in parallel. The chain configuration is downloaded and the reference program
+/// built once for the whole batch. A failed capture does not abort the rest of the batch.
+///
+/// ## Takes
+/// - `cfg` - The generation configuration.
+/// - `range` - The range of L2 blocks to generate test cases for.
+///
+/// ## Returns
+/// - `Result<()>` - Ok if every test case was generated, Err listing the failed blocks otherwise.
+pub(crate) async fn generate_batch(cfg: &GenerateConfig, range: BlockRange) -> Result<()> {
+    info!(target: "test-gen", "Generating test cases for L2 blocks {range} with {} workers...", cfg.workers);
+
+    let setup = Arc::new(GeneratorSetup::prepare(cfg).await?);
+    let semaphore = Arc::new(Semaphore::new(cfg.workers));
+    let mut join_set = JoinSet::new();
+    for block in range.blocks() {
+        let setup = setup.clone();
+        let semaphore = semaphore.clone();
+        let cfg = GenerateConfig {
+            name: format!("{}-{block}", cfg.name),
+            l2_block: block,
+            l2_block_range: None,
+            ..cfg.clone()
+        };
+
+        join_set.spawn(async move {
+            let _permit = semaphore.acquire().await?;
+
+            // Each capture runs in its own temporary directory.
+            let result =
+                async { TestCaseGenerator::with_setup(&cfg, setup)?.generate().await }.await;
+            if let Err(e) = result.as_ref() {
+                error!(target: "test-gen", "Failed to generate {}: {e}", cfg.name);
+            }
+            Ok::<_, Report>((block, result))
+        });
+    }
+
+    let mut failed = Vec::new();
+    while let Some(result) = join_set.join_next().await {
+        let (block, result) = result??;
+        if result.is_err() {
+            failed.push(block);
+        }
+    }
+    failed.sort();
+
+    let total = range.blocks().count() as u64;
+    println!(
+        "{} {} of {total} test cases",
+        "Generated".green().bold(),
+        total - failed.len() as u64
+    );
+    if !failed.is_empty() {
+        bail!(
+            "Failed to generate test cases for L2 blocks: {}",
+            failed
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_block_range() {
+        assert_eq!(
+            "100..=110".parse::<BlockRange>().unwrap(),
+            BlockRange {
+                start: 100,
+                end: 110,
+                stride: 1
+            }
+        );
+        assert_eq!(
+            "100..110".parse::<BlockRange>().unwrap(),
+            BlockRange {
+                start: 100,
+                end: 109,
+                stride: 1
+            }
+        );
+        assert!("110..100".parse::<BlockRange>().is_err());
+
+        let strided = "100..200:10".parse::<BlockRange>().unwrap();
+        assert_eq!(strided.to_string(), "100..=199:10");
+        assert_eq!(
+            strided.blocks().collect::<Vec<_>>(),
+            (100..200).step_by(10).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "100..=110:4"
+                .parse::<BlockRange>()
+                .unwrap()
+                .blocks()
+                .collect::<Vec<_>>(),
+            vec![100, 104, 108]
+        );
+        assert!("100..=110:0".parse::<BlockRange>().is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Contains the [ChainConfigSource], where the generator sources the rollup configuration and
+//! genesis of the chain that a test case is generated on.
+
+use super::{CHAIN_CONFIG_ARTIFACT, CHAIN_CONFIG_FILES};
+use crate::{cli::GenerateConfig, devnet, genesis::GenesisInfo};
+use alloy_transport_http::reqwest::{Client, Url};
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use std::{fs, path::Path, path::PathBuf};
+use tracing::info;
+
+/// The source of the rollup configuration and genesis of the chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ChainConfigSource {
+    /// The `op-genesis-configs` artifact of the Kurtosis devnet, in the named enclave.
+    Kurtosis(String),
+    /// A local directory containing the `rollup.json` and `genesis.json`.
+    Dir(PathBuf),
+    /// The URLs of the `rollup.json` and `genesis.json`.
+    Urls {
+        /// The URL of the rollup configuration.
+        rollup: Url,
+        /// The URL of the genesis.
+        genesis: Url,
+    },
+}
+
+impl ChainConfigSource {
+    /// Returns the source that the [GenerateConfig] selects, defaulting to the Kurtosis devnet.
+    pub(crate) fn from_cfg(cfg: &GenerateConfig) -> Result<Self> {
+        match (
+            &cfg.chain_config_dir,
+            &cfg.rollup_config_url,
+            &cfg.genesis_url,
+        ) {
+            (Some(dir), _, _) => Ok(Self::Dir(dir.clone())),
+            (None, Some(rollup), Some(genesis)) => Ok(Self::Urls {
+                rollup: Url::parse(rollup)?,
+                genesis: Url::parse(genesis)?,
+            }),
+            (None, None, None) => Ok(Self::Kurtosis(cfg.enclave.clone())),
+            _ => Err(eyre!(
+                "`--rollup-config-url` and `--genesis-url` must be given together"
+            )),
+        }
+    }
+
+    /// Fetches the chain configuration into the `op-genesis-configs` directory within `dir`.
+    ///
+    /// ## Takes
+    /// - `dir` - The directory to fetch the chain configuration into.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors if either file could not be fetched.
+    pub(crate) async fn fetch(&self, dir: &Path) -> Result<()> {
+        let chain_config_dir = dir.join(CHAIN_CONFIG_ARTIFACT);
+        match self {
+            Self::Kurtosis(enclave) => return devnet::download_chain_config(enclave, dir).await,
+            Self::Dir(src) => {
+                info!(target: "test-gen", "Copying chain configuration from {}...", src.display());
+                fs::create_dir_all(&chain_config_dir)?;
+                for file in CHAIN_CONFIG_FILES {
+                    let path = src.join(file);
+                    ensure!(
+                        path.is_file(),
+                        "The chain configuration directory `{}` is missing `{file}`",
+                        src.display()
+                    );
+                    fs::copy(&path, chain_config_dir.join(file))?;
+                }
+            }
+            Self::Urls { rollup, genesis } => {
+                fs::create_dir_all(&chain_config_dir)?;
+                let client = Client::new();
+                for (file, url) in CHAIN_CONFIG_FILES.into_iter().zip([rollup, genesis]) {
+                    info!(target: "test-gen", "Downloading {file} from {url}...");
+                    let response = client.get(url.clone()).send().await?;
+                    ensure!(
+                        response.status().is_success(),
+                        "Failed to download {file} from {url}: {}",
+                        response.status()
+                    );
+                    fs::write(chain_config_dir.join(file), response.bytes().await?)?;
+                }
+            }
+        }
+
+        // Catch a wrong file before the reference program fails on it.
+        serde_json::from_slice::<serde_json::Value>(&fs::read(
+            chain_config_dir.join("rollup.json"),
+        )?)
+        .map_err(|e| eyre!("The chain configuration's rollup.json is not valid JSON: {e}"))?;
+        GenesisInfo::parse(&chain_config_dir.join("genesis.json"))?;
+        info!(target: "test-gen", "Fetched chain configuration.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn copy_chain_config_dir() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let source = ChainConfigSource::Dir(src.path().to_path_buf());
+
+        fs::write(
+            src.path().join("rollup.json"),
+            r#"{"l2_chain_id":11155420}"#,
+        )
+        .unwrap();
+        assert!(source.fetch(dst.path()).await.is_err());
+
+        let genesis = r#"{"config":{"chainId":11155420}}"#;
+        fs::write(src.path().join("genesis.json"), genesis).unwrap();
+        source.fetch(dst.path()).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(dst.path().join(CHAIN_CONFIG_ARTIFACT).join("genesis.json"))
+                .unwrap(),
+            genesis
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Checkpointing of interrupted test case generations, so that multi-hour witness captures can be
+//! resumed rather than restarted.
+
+use super::{TestCaseGenerator, CHAIN_CONFIG_ARTIFACT, CHAIN_CONFIG_FILES, WITNESS_DB_DIR_NAME};
+use crate::{archive, fixture::FixtureInputs};
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tracing::info;
+
+/// The name of the checkpoint directory within the fixture directory.
+pub(super) const CHECKPOINT_DIR: &str = ".checkpoint";
+
+/// A resumable checkpoint of an interrupted generation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Checkpoint {
+    /// The inputs of the test case being generated.
+    inputs: FixtureInputs,
+}
+
+impl TestCaseGenerator<'_> {
+    /// Returns the checkpoint directory of the test case.
+    fn checkpoint_dir(&self) -> PathBuf {
+        self.fixture_path().join(CHECKPOINT_DIR)
+    }
+
+    /// Records a checkpoint of the generation, compressing the witness collected so far.
+    pub(super) async fn save_checkpoint(&self, inputs: &FixtureInputs) -> Result<()> {
+        let checkpoint_dir = self.checkpoint_dir();
+        fs::create_dir_all(&checkpoint_dir)?;
+
+        // Compress whatever witness data has been collected.
+        let witness_db = self.workdir.path().join(WITNESS_DB_DIR_NAME);
+        if witness_db.exists() {
+            archive::pack_dir(
+                &witness_db,
+                WITNESS_DB_DIR_NAME,
+                &checkpoint_dir.join(format!("{WITNESS_DB_DIR_NAME}.tar.zst")),
+            )
+            .await
+            .map_err(|e| eyre!("Failed to compress witness database: {e}"))?;
+        }
+
+        // Preserve the chain configuration and inputs, so that the remotes need not be queried
+        // again.
+        for file in CHAIN_CONFIG_FILES {
+            fs::copy(
+                self.workdir.path().join(CHAIN_CONFIG_ARTIFACT).join(file),
+                checkpoint_dir.join(file),
+            )?;
+        }
+        fs::write(
+            checkpoint_dir.join("checkpoint.toml"),
+            toml::to_string_pretty(&Checkpoint {
+                inputs: inputs.clone(),
+            })?,
+        )?;
+
+        info!(
+            target: "test-gen",
+            "Recorded checkpoint at {}. Rerun `fpt generate --name {}` to resume.",
+            checkpoint_dir.display(),
+            self.cfg.name
+        );
+        Ok(())
+    }
+
+    /// Restores the checkpoint of an interrupted generation of the test case into the workdir, if
+    /// one exists.
+    ///
+    /// ## Returns
+    /// - `Result<Option<FixtureInputs>>` - The inputs of the checkpointed test case, if any.
+    pub(super) async fn restore_checkpoint(&self) -> Result<Option<FixtureInputs>> {
+        let checkpoint_dir = self.checkpoint_dir();
+        let Ok(checkpoint) = fs::read_to_string(checkpoint_dir.join("checkpoint.toml")) else {
+            return Ok(None);
+        };
+        let checkpoint = toml::from_str::<Checkpoint>(&checkpoint)?;
+        info!(target: "test-gen", "Resuming from checkpoint at {}...", checkpoint_dir.display());
+
+        let chain_config_dir = self.workdir.path().join(CHAIN_CONFIG_ARTIFACT);
+        fs::create_dir_all(&chain_config_dir)?;
+        for file in CHAIN_CONFIG_FILES {
+            fs::copy(checkpoint_dir.join(file), chain_config_dir.join(file))?;
+        }
+
+        let archive = checkpoint_dir.join(format!("{WITNESS_DB_DIR_NAME}.tar.zst"));
+        if archive.exists() {
+            archive::unpack(&archive, self.workdir.path())
+                .await
+                .map_err(|e| eyre!("Failed to decompress checkpointed witness database: {e}"))?;
+        }
+
+        Ok(Some(checkpoint.inputs))
+    }
+
+    /// Removes the checkpoint of the test case, once it has been generated.
+    pub(super) fn remove_checkpoint(&self) -> Result<()> {
+        let checkpoint_dir = self.checkpoint_dir();
+        if checkpoint_dir.exists() {
+            fs::remove_dir_all(checkpoint_dir)?;
+        }
+        Ok(())
+    }
+}
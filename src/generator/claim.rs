@@ -0,0 +1,80 @@
+//! Contains the [InvalidClaim] mode of the generator, which produces negative test cases whose
+//! claim the programs must reject.
+
+use alloy_primitives::B256;
+use color_eyre::{eyre::ensure, Report, Result};
+use std::str::FromStr;
+
+/// The exit status of a fault proof program that rejects the claim.
+pub(crate) const INVALID_CLAIM_STATUS: u8 = 1;
+
+/// The invalid claim that a negative test case is generated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InvalidClaim {
+    /// The valid claim, with random bits flipped.
+    Random,
+    /// The given claim.
+    Claim(B256),
+}
+
+impl FromStr for InvalidClaim {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Self::Random),
+            claim => Ok(Self::Claim(claim.parse()?)),
+        }
+    }
+}
+
+impl InvalidClaim {
+    /// Returns the invalid claim in place of the valid one.
+    ///
+    /// ## Takes
+    /// - `valid` - The valid claim of the test case.
+    ///
+    /// ## Returns
+    /// - `Result<B256>` - The invalid claim, or Err if the given claim is the valid one.
+    pub(crate) fn apply(&self, valid: B256) -> Result<B256> {
+        let claim = match self {
+            Self::Random => loop {
+                let mask = B256::from(rand::random::<[u8; 32]>());
+                if mask != B256::ZERO {
+                    break valid ^ mask;
+                }
+            },
+            Self::Claim(claim) => *claim,
+        };
+        ensure!(
+            claim != valid,
+            "The invalid claim {claim} is the valid claim of the test case"
+        );
+        Ok(claim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_invalid_claims() {
+        let valid = B256::repeat_byte(0xab);
+        assert_ne!(
+            "random"
+                .parse::<InvalidClaim>()
+                .unwrap()
+                .apply(valid)
+                .unwrap(),
+            valid
+        );
+
+        let claim = B256::repeat_byte(0xde);
+        let given = claim.to_string().parse::<InvalidClaim>().unwrap();
+        assert_eq!(given, InvalidClaim::Claim(claim));
+        assert_eq!(given.apply(valid).unwrap(), claim);
+        assert!(InvalidClaim::Claim(valid).apply(valid).is_err());
+        assert!("0xdead".parse::<InvalidClaim>().is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Building test fixtures from externally captured witness databases (e.g. produced by
+//! `op-challenger` or manual host runs), without access to the RPCs.
+
+use super::{
+    chain_config::ChainConfigSource, claim::INVALID_CLAIM_STATUS, TestCaseGenerator,
+    CHAIN_CONFIG_ARTIFACT, WITNESS_DB_DIR_NAME,
+};
+use crate::{
+    fixture::{FixtureInputs, RunOutcome},
+    genesis::GenesisInfo,
+    registry::program::{ProgramHostInputs, ProgramHostSource},
+};
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    Result,
+};
+use std::{fs, path::Path};
+use tracing::{info, warn};
+
+impl TestCaseGenerator<'_> {
+    /// Prepares the test case from the witness database in `witness_dir` and the inputs that it was
+    /// captured with, and runs the reference program on it offline. The witness database is
+    /// copied into the workdir, leaving the original untouched.
+    ///
+    /// A complete witness lets the reference program decide the claim one way or the other; any
+    /// other outcome means that the witness is incomplete, or was captured with other inputs, so
+    /// the test case is refused.
+    ///
+    /// ## Takes
+    /// - `witness_dir` - The directory of the witness database.
+    ///
+    /// ## Returns
+    /// - `Result<(ProgramHostInputs, RunOutcome)>` - The inputs of the test case, and the outcome of
+    ///   the offline run.
+    pub(super) async fn replay_witness(
+        &self,
+        witness_dir: &Path,
+    ) -> Result<(ProgramHostInputs, RunOutcome)> {
+        let inputs_path = self
+            .cfg
+            .inputs
+            .as_ref()
+            .ok_or(eyre!("`--from-witness` requires `--inputs`"))?;
+        let fixture_inputs = read_inputs(inputs_path)?;
+        ensure!(
+            witness_dir.is_dir(),
+            "The witness database `{}` is not a directory",
+            witness_dir.display()
+        );
+
+        // The chain configuration cannot be taken from the witness, so it is fetched as usual.
+        ChainConfigSource::from_cfg(self.cfg)?
+            .fetch(self.workdir.path())
+            .await?;
+        let chain_config_dir = self.workdir.path().join(CHAIN_CONFIG_ARTIFACT);
+        let genesis = GenesisInfo::parse(&chain_config_dir.join("genesis.json"))?;
+        ensure!(
+            genesis.chain_id == fixture_inputs.l2_chain_id,
+            "The inputs are for chain {}, but the chain configuration is for chain {}",
+            fixture_inputs.l2_chain_id,
+            genesis.chain_id
+        );
+
+        info!(target: "test-gen", "Copying the witness database from {}...", witness_dir.display());
+        let db_dir = self.workdir.path().join(WITNESS_DB_DIR_NAME);
+        copy_dir(witness_dir, &db_dir)?;
+        let inputs = ProgramHostInputs {
+            fixture_inputs,
+            rollup_cfg_path: chain_config_dir.join("rollup.json"),
+            genesis_path: chain_config_dir.join("genesis.json"),
+            chain_config_path: None,
+            source: ProgramHostSource::Disk { path: db_dir },
+        };
+
+        let outcome = tokio::select! {
+            result = self.run_reference_program(&inputs) => result?,
+            _ = tokio::signal::ctrl_c() => {
+                warn!(target: "test-gen", "Interrupted; the witness database was left untouched.");
+                bail!("Interrupted");
+            }
+        };
+        ensure!(
+            outcome.exit_status == 0 || outcome.exit_status == INVALID_CLAIM_STATUS,
+            "Refusing to write an unverifiable fixture: the offline run exited with status {}, rather than accepting or rejecting the claim; is the witness complete, and captured with these inputs?",
+            outcome.exit_status
+        );
+        Ok((inputs, outcome))
+    }
+}
+
+/// Reads the [FixtureInputs] from a TOML file, either the `[inputs]` table of a `fixture.toml`
+/// or a file of the table's keys alone.
+fn read_inputs(path: &Path) -> Result<FixtureInputs> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| eyre!("Failed to read the inputs `{}`: {e}", path.display()))?;
+    let mut table = toml::from_str::<toml::Table>(&contents)?;
+    let inputs = match table.remove("inputs") {
+        Some(inputs) => inputs.try_into::<FixtureInputs>(),
+        None => toml::Value::Table(table).try_into::<FixtureInputs>(),
+    };
+    let inputs = inputs.map_err(|e| eyre!("Invalid inputs `{}`: {e}", path.display()))?;
+    if let Some(interop) = inputs.interop.as_ref() {
+        interop.check(inputs.l2_chain_id)?;
+    }
+    Ok(inputs)
+}
+
+/// Copies the directory recursively.
+fn copy_dir(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &dst.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUTS: &str = r#"
+        l1-head = "0x269e7aa3246958a3c5f63bd77c805776bae29e00ce7388556e6d5604190b6911"
+        l2-block-number = 20
+        l2-claim = "0x65b2fab9d3cf47001d097b96fe7d11129a80f337f84bc7f969db3fa4635ba924"
+        l2-output-root = "0x4192a5d273b4603e5186e52d7e30124d4c42e41960ce8d03da118396f050652c"
+        l2-head = "0xa9b6854b6c6f62df0a52f6c649e5ec94b40bd265bf4a7b873a511e27c489881e"
+        l2-chain-id = 1337
+    "#;
+
+    #[test]
+    fn read_inputs_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let bare = dir.path().join("inputs.toml");
+        fs::write(&bare, INPUTS).unwrap();
+        let inputs = read_inputs(&bare).unwrap();
+        assert_eq!(inputs.l2_block_number, 20);
+        assert_eq!(inputs.l2_chain_id, 1337);
+
+        // The `[inputs]` table of a `fixture.toml` is read as well.
+        let fixture = dir.path().join("fixture.toml");
+        fs::write(
+            &fixture,
+            format!("name = \"basic-success\"\nexpected-status = 0\n\n[inputs]\n{INPUTS}"),
+        )
+        .unwrap();
+        assert_eq!(read_inputs(&fixture).unwrap(), inputs);
+
+        fs::write(&bare, "l2-block-number = 20").unwrap();
+        assert!(read_inputs(&bare).is_err());
+    }
+}
@@ -0,0 +1,677 @@
+//! Contains the [TestCaseGenerator], which facilitates the creation of test cases from the reference program.
+
+use crate::{
+    archive,
+    cli::GenerateConfig,
+    executor::Executor,
+    fixture::{FixtureInputs, FixtureMetadata, RunOutcome, TestFixture, WitnessStats},
+    output,
+    pipeline::witness_reads::WitnessReads,
+    process::ProcessOptions,
+    registry::{
+        platform::PlatformKind,
+        program::{ProgramHostInputs, ProgramHostSource, ProgramKind},
+        FP_REGISTRY,
+    },
+    state::StateDir,
+    units::Bytes,
+};
+use alloy_primitives::{B256, U64};
+use alloy_provider::{network::Ethereum, Provider, ReqwestProvider};
+use alloy_rpc_types::BlockTransactionsKind;
+use alloy_transport_http::reqwest::Url;
+use chain_config::ChainConfigSource;
+use checkpoint::CHECKPOINT_DIR;
+use claim::INVALID_CLAIM_STATUS;
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use progress::CaptureProgress;
+use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+pub(crate) mod batch;
+pub(crate) mod chain_config;
+mod checkpoint;
+pub(crate) mod claim;
+mod from_witness;
+pub(crate) mod progress;
+pub(crate) mod rpc_cache;
+mod wizard;
+
+/// The name of the chain configuration artifact on the kurtosis devnet.
+pub(crate) const CHAIN_CONFIG_ARTIFACT: &str = "op-genesis-configs";
+
+/// The files of the chain configuration artifact.
+pub(crate) const CHAIN_CONFIG_FILES: [&str; 2] = ["rollup.json", "genesis.json"];
+
+/// The name of the witness database directory.
+pub(crate) const WITNESS_DB_DIR_NAME: &str = "witness-db";
+
+/// The file that the `strace` of the witness database reads is written to, when minimizing the
+/// witness database.
+const MINIMIZE_TRACE: &str = "minimize.strace";
+
+/// The test case generator for `fpt`.
+pub(crate) struct TestCaseGenerator<'a> {
+    /// The [GenerateConfig] for the generator.
+    cfg: &'a GenerateConfig,
+    /// The working directory during the generation process.
+    workdir: TempDir,
+    /// The setup shared with the other test cases of a batch, if any.
+    setup: Option<Arc<GeneratorSetup>>,
+}
+
+/// The setup shared by the test cases of a batch: the fetched chain configuration, and the built
+/// reference program.
+pub(crate) struct GeneratorSetup {
+    /// The directory that the chain configuration is fetched into.
+    dir: TempDir,
+    /// The reference program on the native platform.
+    executor: Executor,
+}
+
+impl GeneratorSetup {
+    /// Fetches the chain configuration and builds the reference program.
+    pub(crate) async fn prepare(cfg: &GenerateConfig) -> Result<Self> {
+        let dir = StateDir::get().tempdir()?;
+        ChainConfigSource::from_cfg(cfg)?.fetch(dir.path()).await?;
+        Ok(Self {
+            dir,
+            executor: TestCaseGenerator::reference_executor().await?,
+        })
+    }
+}
+
+impl<'a> TestCaseGenerator<'a> {
+    /// Create a new [TestCaseGenerator] with the provided [GenerateConfig].
+    pub(crate) fn new(cfg: &'a GenerateConfig) -> Result<Self> {
+        Ok(Self {
+            cfg,
+            workdir: StateDir::get().tempdir()?,
+            setup: None,
+        })
+    }
+
+    /// Create a new [TestCaseGenerator] with the provided [GenerateConfig], reusing the chain
+    /// configuration and reference program of the given [GeneratorSetup].
+    pub(crate) fn with_setup(cfg: &'a GenerateConfig, setup: Arc<GeneratorSetup>) -> Result<Self> {
+        Ok(Self {
+            setup: Some(setup),
+            ..Self::new(cfg)?
+        })
+    }
+
+    /// Generate a test case from the reference program.
+    ///
+    /// If a checkpoint of an interrupted generation of the test case exists, the generation is
+    /// resumed from it. Interrupting the generation with Ctrl-C records a new checkpoint. With
+    /// `--from-witness`, the test case is built from an existing witness instead.
+    pub(crate) async fn generate(&self) -> Result<()> {
+        // Refuse to overwrite an existing fixture before spending time on the capture.
+        let existing = self.existing_fixture_files()?;
+        ensure!(
+            existing.is_empty() || self.cfg.force,
+            "The test fixture {} already exists; pass `--force` to overwrite it",
+            self.fixture_path().display()
+        );
+
+        let (inputs, outcome) = if let Some(witness_dir) = self.cfg.from_witness.as_ref() {
+            // Build the test case from an existing witness, which is replayed offline.
+            self.replay_witness(witness_dir).await?
+        } else {
+            let inputs = if let Some(fixture_inputs) = self.restore_checkpoint().await? {
+                self.host_inputs(fixture_inputs)
+            } else {
+                // Fetch the chain configuration, or copy it from the shared setup.
+                match self.setup.as_ref() {
+                    Some(setup) => {
+                        let chain_config_dir = self.workdir.path().join(CHAIN_CONFIG_ARTIFACT);
+                        fs::create_dir_all(&chain_config_dir)?;
+                        for file in CHAIN_CONFIG_FILES {
+                            fs::copy(
+                                setup.dir.path().join(CHAIN_CONFIG_ARTIFACT).join(file),
+                                chain_config_dir.join(file),
+                            )?;
+                        }
+                    }
+                    None => {
+                        ChainConfigSource::from_cfg(self.cfg)?
+                            .fetch(self.workdir.path())
+                            .await?
+                    }
+                }
+
+                // Fetch the inputs for the test case.
+                self.gather_inputs().await?
+            };
+
+            // Preview the inputs before the witness capture, if running interactively.
+            if self.cfg.interactive && !self.confirm_inputs(&inputs.fixture_inputs)? {
+                info!(target: "test-gen", "Generation cancelled.");
+                return Ok(());
+            }
+
+            // Run the reference program, checkpointing the witness collected so far if interrupted.
+            let outcome = tokio::select! {
+                result = self.run_reference_program(&inputs) => result?,
+                _ = tokio::signal::ctrl_c() => {
+                    warn!(target: "test-gen", "Interrupted; checkpointing the collected witness...");
+                    self.save_checkpoint(&inputs.fixture_inputs).await?;
+                    return Ok(());
+                }
+            };
+            (inputs, outcome)
+        };
+
+        // Trim the witness database down to the preimages that the program reads, if requested.
+        let outcome = if self.cfg.minimize {
+            self.minimize_witness(&inputs, outcome).await?
+        } else {
+            outcome
+        };
+
+        // Replay the test case from the captured witness alone, before committing it to disk. An
+        // existing witness was already replayed offline, unless it has been trimmed since.
+        if self.cfg.from_witness.is_none() || self.cfg.minimize {
+            self.verify_offline(&inputs, &outcome).await?;
+        }
+
+        // Flush the test fixture and metadata to disk.
+        self.flush_fixture(inputs, outcome).await?;
+        self.remove_checkpoint()?;
+
+        Ok(())
+    }
+
+    /// Gather the [ProgramHostInputs].
+    async fn gather_inputs(&self) -> Result<ProgramHostInputs> {
+        let GenerateConfig {
+            l2_block,
+            l2_claim,
+            l1_head,
+            l2_output_root,
+            l2_head,
+            l2_chain_id,
+            ..
+        } = self.cfg;
+        info!(target: "test-gen", "Fetching configuration for block #{}...", l2_block);
+
+        let l1_rpc = ReqwestProvider::<Ethereum>::new_http(Url::parse(self.cfg.l1_rpc.as_ref())?);
+        let l2_node_rpc =
+            ReqwestProvider::<Ethereum>::new_http(Url::parse(self.cfg.l2_node_rpc.as_ref())?);
+        let l2_rpc = ReqwestProvider::<Ethereum>::new_http(Url::parse(self.cfg.l2_rpc.as_ref())?);
+
+        let l2_claim = if let Some(l2_claim) = l2_claim {
+            *l2_claim
+        } else {
+            info!(target: "test-gen", "Fetching L2 claim...");
+            let output_at_block = l2_node_rpc
+                .raw_request::<[U64; 1], OutputAtBlockResponse>(
+                    "optimism_outputAtBlock".into(),
+                    [U64::from(*l2_block)],
+                )
+                .await?;
+            output_at_block.output_root
+        };
+        let l2_claim = match self.cfg.invalid_claim {
+            Some(invalid_claim) => {
+                let invalid = invalid_claim.apply(l2_claim)?;
+                info!(target: "test-gen", "Replacing the L2 claim {l2_claim} with the invalid claim {invalid}");
+                invalid
+            }
+            None => l2_claim,
+        };
+
+        let l2_output_root = if let Some(l2_output_root) = l2_output_root {
+            *l2_output_root
+        } else {
+            info!(target: "test-gen", "Fetching starting L2 output root...");
+            let output_at_block = l2_node_rpc
+                .raw_request::<[U64; 1], OutputAtBlockResponse>(
+                    "optimism_outputAtBlock".into(),
+                    [U64::from(*l2_block - 1)],
+                )
+                .await?;
+            output_at_block.output_root
+        };
+
+        let l2_head = if let Some(l2_head) = l2_head {
+            *l2_head
+        } else {
+            info!(target: "test-gen", "Fetching L2 head...");
+            let l2_head = l2_rpc
+                .get_block((*l2_block - 1).into(), BlockTransactionsKind::Hashes)
+                .await?
+                .ok_or(eyre!("Failed to fetch block."))?;
+            l2_head.header.hash
+        };
+
+        let l2_chain_id = if let Some(l2_chain_id) = l2_chain_id {
+            *l2_chain_id
+        } else {
+            info!(target: "test-gen", "Fetching L2 chain ID...");
+            l2_rpc.get_chain_id().await?
+        };
+
+        let l1_head = if let Some(l1_head) = l1_head {
+            *l1_head
+        } else {
+            info!(target: "test-gen", "Fetching L1 head...");
+            // First, fetch the output root response for the starting L2 output root.
+            let output_at_block = l2_node_rpc
+                .raw_request::<[U64; 1], OutputAtBlockResponse>(
+                    "optimism_outputAtBlock".into(),
+                    [U64::from(*l2_block)],
+                )
+                .await?;
+            // Use an L1 head hash 25 blocks ahead of the L1 origin block of the
+            // L1 origin of the disputed block.
+            let l1_head = l1_rpc
+                .get_block(
+                    (output_at_block.block_ref.l1origin.number + 25).into(),
+                    BlockTransactionsKind::Hashes,
+                )
+                .await?
+                .ok_or(eyre!("Failed to fetch block."))?;
+            l1_head.header.hash
+        };
+
+        Ok(self.host_inputs(FixtureInputs {
+            l2_block_number: *l2_block,
+            l1_head,
+            l2_claim,
+            l2_output_root,
+            l2_head,
+            l2_chain_id,
+            interop: None,
+        }))
+    }
+
+    /// Returns the [ProgramHostInputs] for the reference program, sourcing data from the remotes.
+    fn host_inputs(&self, fixture_inputs: FixtureInputs) -> ProgramHostInputs {
+        let chain_config_dir = self.workdir.path().join(CHAIN_CONFIG_ARTIFACT);
+        ProgramHostInputs {
+            fixture_inputs,
+            rollup_cfg_path: chain_config_dir.join("rollup.json"),
+            genesis_path: chain_config_dir.join("genesis.json"),
+            chain_config_path: None,
+            source: ProgramHostSource::Rpc {
+                l1: self.cfg.l1_rpc.clone(),
+                l1_beacon: self.cfg.l1_beacon_rpc.clone(),
+                l2: self.cfg.l2_rpc.clone(),
+                path: WITNESS_DB_DIR_NAME.into(),
+            },
+        }
+    }
+
+    /// Returns the directory of the test fixture being generated.
+    fn fixture_path(&self) -> PathBuf {
+        self.cfg.out_dir.clone().unwrap_or_else(|| {
+            self.cfg
+                .tests_dir
+                .path
+                .join(&self.cfg.chain)
+                .join(&self.cfg.name)
+        })
+    }
+
+    /// Returns the files of an existing fixture in the fixture directory, other than the
+    /// checkpoint of an interrupted generation.
+    fn existing_fixture_files(&self) -> Result<Vec<PathBuf>> {
+        let fixture_path = self.fixture_path();
+        if !fixture_path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_dir(fixture_path)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| !path.ends_with(CHECKPOINT_DIR))
+            .collect())
+    }
+
+    /// Runs the reference program with the given [FixtureInputs].
+    ///
+    /// ## Returns
+    /// - `Result<RunOutcome>` - The outcome of the run, including the statistics of the captured
+    ///   witness.
+    async fn run_reference_program(&self, inputs: &ProgramHostInputs) -> Result<RunOutcome> {
+        let executor = self.executor().await?;
+
+        // Run the program.
+        info!(target: "test-gen", "Executing reference program on the native platform...");
+        // Follow the progress of the capture from the program's logs.
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel::<String>();
+        let progress_bar = output::progress_bar(ProgressBar::new_spinner());
+        progress_bar.set_style(ProgressStyle::with_template(
+            "{spinner} [{elapsed}] {wide_msg}",
+        )?);
+        progress_bar.enable_steady_tick(Duration::from_millis(100));
+        let progress_updater = {
+            let progress_bar = progress_bar.clone();
+            tokio::spawn(async move {
+                let mut progress = CaptureProgress::default();
+                while let Some(line) = lines_rx.recv().await {
+                    progress.observe(&line);
+                    progress_bar.set_message(progress.render());
+                }
+                progress
+            })
+        };
+
+        // The program runs in its own process group, so that Ctrl-C is left to the checkpointing.
+        let opts = ProcessOptions {
+            own_process_group: true,
+            output_lines: Some(lines_tx),
+            ..Default::default()
+        };
+        let outcome = executor.run(inputs, self.workdir.path(), &opts).await;
+        drop(opts);
+        let progress = progress_updater.await?;
+        progress_bar.finish_and_clear();
+        let outcome = outcome?;
+        let exit_status = outcome
+            .exit_status
+            .ok_or(eyre!("The reference program stalled"))?;
+        info!(target: "test-gen", "Successfully executed reference program on the native platform. Exit status: {exit_status}");
+
+        Ok(RunOutcome {
+            exit_status,
+            duration_ms: outcome.duration.as_millis() as u64,
+            output_root: progress.output_root(),
+            witness: WitnessStats::measure(
+                &self.workdir.path().join(WITNESS_DB_DIR_NAME),
+                progress.l1_blocks(),
+            )?,
+        })
+    }
+
+    /// Returns the [Executor] of the reference program on the native platform, building the
+    /// reference program if its artifact is not already present.
+    async fn reference_executor() -> Result<Executor> {
+        // Fetch the reference program and the native platform from the registry.
+        let ref_program_def = FP_REGISTRY
+            .program
+            .get(&ProgramKind::default())
+            .ok_or(eyre!(
+                "Failed to find program definition for reference program."
+            ))?;
+        let native_def = FP_REGISTRY
+            .platform
+            .get(&PlatformKind::Native)
+            .ok_or(eyre!("Failed to find the native platform definition."))?;
+
+        Executor::build_program(ProgramKind::default(), ref_program_def, false).await?;
+        Executor::new(
+            PlatformKind::Native,
+            native_def,
+            ProgramKind::default(),
+            ref_program_def,
+            None,
+        )
+    }
+
+    /// Returns the [Executor] of the reference program, from the shared setup if there is one.
+    async fn executor(&self) -> Result<Executor> {
+        match self.setup.as_ref() {
+            Some(setup) => Ok(setup.executor.clone()),
+            None => Self::reference_executor().await,
+        }
+    }
+
+    /// Replays the test case with the reference program, sourcing data only from the captured
+    /// witness database, as `fpt test` will. A fixture whose witness is incomplete would fail
+    /// every program for reasons unrelated to the program itself, so it is refused.
+    ///
+    /// ## Takes
+    /// - `inputs` - The inputs that the witness was captured with.
+    /// - `outcome` - The outcome of the RPC-backed run.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Ok if the offline replay exits with the same status as the RPC-backed run.
+    async fn verify_offline(&self, inputs: &ProgramHostInputs, outcome: &RunOutcome) -> Result<()> {
+        info!(target: "test-gen", "Replaying the test case offline from the captured witness...");
+        let executor = self.executor().await?;
+        let offline_inputs = ProgramHostInputs {
+            source: ProgramHostSource::Disk {
+                path: self.workdir.path().join(WITNESS_DB_DIR_NAME),
+            },
+            ..inputs.clone()
+        };
+
+        // The replay runs in its own directory, leaving the captured witness untouched.
+        let replay_dir = StateDir::get().tempdir()?;
+        let replay = executor
+            .run(
+                &offline_inputs,
+                replay_dir.path(),
+                &ProcessOptions::default(),
+            )
+            .await?;
+        ensure!(
+            replay.exit_status == Some(outcome.exit_status),
+            "Refusing to write an unverifiable fixture: the offline replay exited with status {}, but the RPC-backed run exited with status {}",
+            replay
+                .exit_status
+                .map_or("none (stalled)".to_string(), |s| s.to_string()),
+            outcome.exit_status
+        );
+        info!(target: "test-gen", "Verified the test case offline. Exit status: {}", outcome.exit_status);
+
+        Ok(())
+    }
+
+    /// Trims the witness database down to the preimages that the reference program reads, by
+    /// replaying the test case from the captured witness with its file accesses traced, and
+    /// removing the preimages that were never opened. The trimmed witness is then verified by
+    /// [Self::verify_offline].
+    ///
+    /// ## Takes
+    /// - `inputs` - The inputs that the witness was captured with.
+    /// - `outcome` - The outcome of the RPC-backed run.
+    ///
+    /// ## Returns
+    /// - `Result<RunOutcome>` - The outcome, with the statistics of the trimmed witness.
+    async fn minimize_witness(
+        &self,
+        inputs: &ProgramHostInputs,
+        mut outcome: RunOutcome,
+    ) -> Result<RunOutcome> {
+        info!(target: "test-gen", "Tracing the witness database reads of the reference program...");
+        let executor = self.executor().await?;
+        let witness_dir = self.workdir.path().join(WITNESS_DB_DIR_NAME);
+        let offline_inputs = ProgramHostInputs {
+            source: ProgramHostSource::Disk {
+                path: witness_dir.clone(),
+            },
+            ..inputs.clone()
+        };
+
+        let replay_dir = StateDir::get().tempdir()?;
+        let trace = replay_dir.path().join(MINIMIZE_TRACE);
+        let replay = executor
+            .run(
+                &offline_inputs,
+                replay_dir.path(),
+                &ProcessOptions {
+                    trace_reads: Some(trace.clone()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        ensure!(
+            replay.exit_status == Some(outcome.exit_status),
+            "Cannot minimize the witness database: the traced replay exited with status {}, but the RPC-backed run exited with status {}",
+            replay
+                .exit_status
+                .map_or("none (stalled)".to_string(), |s| s.to_string()),
+            outcome.exit_status
+        );
+
+        // Remove the preimages that were not opened. An empty trace means that the reads could not
+        // be traced, rather than that no preimage is needed.
+        let opened = WitnessReads::opened_files(&trace, &witness_dir)?;
+        ensure!(
+            !opened.is_empty(),
+            "Cannot minimize the witness database: no reads of it were traced"
+        );
+        for entry in fs::read_dir(witness_dir.canonicalize()?)? {
+            let path = entry?.path();
+            if path.is_file() && !opened.contains(&path) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        let before = outcome.witness.clone();
+        outcome.witness = WitnessStats::measure(&witness_dir, before.l1_blocks)?;
+        info!(
+            target: "test-gen",
+            "Trimmed the witness database from {} preimages ({}) to {} preimages ({})",
+            before.preimages,
+            Bytes(before.size),
+            outcome.witness.preimages,
+            Bytes(outcome.witness.size)
+        );
+        Ok(outcome)
+    }
+
+    /// Flushes the [TestFixture] and metadata to disk.
+    async fn flush_fixture(&self, inputs: ProgramHostInputs, outcome: RunOutcome) -> Result<()> {
+        // The reference program must reject an invalid claim, lest the negative test case expect
+        // it to be accepted.
+        ensure!(
+            self.cfg.invalid_claim.is_none() || outcome.exit_status == INVALID_CLAIM_STATUS,
+            "The reference program did not reject the invalid claim {} (exit status: {})",
+            inputs.fixture_inputs.l2_claim,
+            outcome.exit_status
+        );
+
+        // Remove the fixture being overwritten, if any, so that none of its files are left behind.
+        for path in self.existing_fixture_files()? {
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+        let fixture_path = self.fixture_path();
+        fs::create_dir_all(&fixture_path)?;
+
+        // Write the test fixture to disk.
+        let fixture = TestFixture {
+            metadata: FixtureMetadata {
+                name: self.cfg.name.clone(),
+                expected_status: outcome.exit_status,
+                expected_output_root: outcome.output_root,
+                ..Default::default()
+            },
+            inputs: inputs.fixture_inputs,
+            reference: Some(outcome),
+        };
+        fs::write(
+            fixture_path.join("fixture.toml").as_path(),
+            toml::to_string_pretty(&fixture)?,
+        )?;
+        info!(target: "test-gen", "Wrote test fixture to disk.");
+
+        // Compress the witness database into the fixture.
+        info!(target: "test-gen", "Compressing witness database...");
+        archive::pack_dir(
+            &self.workdir.path().join(WITNESS_DB_DIR_NAME),
+            WITNESS_DB_DIR_NAME,
+            &fixture_path.join(format!("{}.tar.zst", WITNESS_DB_DIR_NAME)),
+        )
+        .await
+        .map_err(|e| eyre!("Failed to compress witness database: {e}"))?;
+        info!(target: "test-gen", "Compressed witness database into test fixture.");
+
+        // Copy the rollup configuration into the fixture, and compress the genesis into it.
+        fs::copy(
+            self.workdir
+                .path()
+                .join(CHAIN_CONFIG_ARTIFACT)
+                .join("rollup.json"),
+            fixture_path.join("rollup.json"),
+        )?;
+        archive::compress_file(
+            &self
+                .workdir
+                .path()
+                .join(CHAIN_CONFIG_ARTIFACT)
+                .join("genesis.json"),
+            &fixture_path.join("genesis.json.zst"),
+        )
+        .await
+        .map_err(|e| eyre!("Failed to compress genesis.json: {e}"))?;
+        info!(target: "test-gen", "Copied chain configuration files into test fixture.");
+
+        Ok(())
+    }
+}
+
+/// Partial response for the `optimism_outputAtBlock` RPC.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputAtBlockResponse {
+    pub(crate) output_root: B256,
+    pub(crate) block_ref: MinL2BlockRef,
+}
+
+/// Partial response for the `optimism_outputAtBlock` RPC.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MinL2BlockRef {
+    pub(crate) l1origin: MinL1BlockRef,
+}
+
+/// Partial response for the `optimism_outputAtBlock` RPC.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MinL1BlockRef {
+    pub(crate) number: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, CliSubcommand};
+    use clap::Parser;
+
+    #[test]
+    fn refuse_existing_fixtures() {
+        let tests_dir = tempfile::tempdir().unwrap();
+        let parse = |args: &[&str]| {
+            let tests_dir = tests_dir.path().to_str().unwrap();
+            let base = ["fpt", "generate", "--devnet", "--l2-block", "1"];
+            match Cli::parse_from(base.iter().chain(args).chain(&["--tests-dir", tests_dir]))
+                .subcommand
+            {
+                Some(CliSubcommand::Generate(cfg)) => cfg,
+                _ => unreachable!(),
+            }
+        };
+
+        let cfg = parse(&["--name", "block-1"]);
+        let generator = TestCaseGenerator::new(&cfg).unwrap();
+        let fixture_path = tests_dir.path().join("devnet/block-1");
+        assert_eq!(generator.fixture_path(), fixture_path);
+
+        // The checkpoint of an interrupted generation is not an existing fixture.
+        fs::create_dir_all(fixture_path.join(CHECKPOINT_DIR)).unwrap();
+        assert!(generator.existing_fixture_files().unwrap().is_empty());
+        fs::write(fixture_path.join("fixture.toml"), "").unwrap();
+        assert_eq!(
+            generator.existing_fixture_files().unwrap(),
+            vec![fixture_path.join("fixture.toml")]
+        );
+
+        let out_dir = tests_dir.path().join("elsewhere");
+        let cfg = parse(&["--name", "block-1", "--out-dir", out_dir.to_str().unwrap()]);
+        let generator = TestCaseGenerator::new(&cfg).unwrap();
+        assert_eq!(generator.fixture_path(), out_dir);
+        assert!(generator.existing_fixture_files().unwrap().is_empty());
+    }
+}
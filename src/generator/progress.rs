@@ -0,0 +1,117 @@
+//! Contains the [CaptureProgress] tracker, which follows the reference program's log output during
+//! a witness capture.
+
+use alloy_primitives::B256;
+use std::collections::HashSet;
+
+/// The progress of a witness capture, as observed from the reference program's logs.
+#[derive(Debug, Default)]
+pub(crate) struct CaptureProgress {
+    /// The L1 blocks that derivation has traversed.
+    l1_blocks: HashSet<u64>,
+    /// The highest L1 block that derivation has traversed.
+    l1_head: Option<u64>,
+    /// The number of preimages fetched from the remotes.
+    preimages: usize,
+    /// The message of the most recent log line, describing the current stage of the program.
+    stage: Option<String>,
+    /// The output root that the program derived, once it validates the claim.
+    output_root: Option<B256>,
+}
+
+impl CaptureProgress {
+    /// Updates the progress with a line of the program's `logfmt` output.
+    pub(crate) fn observe(&mut self, line: &str) {
+        if let Some(number) = logfmt_field(line, "origin")
+            .or_else(|| logfmt_field(line, "l1_origin"))
+            .and_then(|origin| origin.rsplit_once(':'))
+            .and_then(|(_, number)| number.parse::<u64>().ok())
+        {
+            self.l1_blocks.insert(number);
+            self.l1_head = self.l1_head.max(Some(number));
+        }
+
+        if let Some(msg) = logfmt_field(line, "msg") {
+            if msg.to_ascii_lowercase().contains("fetch") {
+                self.preimages += 1;
+            }
+            if let Some(output_root) = reported_output_root(line) {
+                self.output_root = Some(output_root);
+            }
+            self.stage = Some(msg.to_string());
+        }
+    }
+
+    /// Returns the number of L1 blocks that derivation has traversed.
+    pub(crate) fn l1_blocks(&self) -> u64 {
+        self.l1_blocks.len() as u64
+    }
+
+    /// Returns the output root that the program derived, if it has validated the claim.
+    pub(crate) fn output_root(&self) -> Option<B256> {
+        self.output_root
+    }
+
+    /// Renders the progress message.
+    pub(crate) fn render(&self) -> String {
+        format!(
+            "L1 blocks traversed: {}{} | Preimages fetched: {} | Stage: {}",
+            self.l1_blocks.len(),
+            self.l1_head
+                .map_or(String::new(), |h| format!(" (at #{h})")),
+            self.preimages,
+            self.stage.as_deref().unwrap_or("starting")
+        )
+    }
+}
+
+/// Returns the output root that the program reports in a line of its `logfmt` output, if the line
+/// is the one that it logs when it validates the claim.
+pub(crate) fn reported_output_root(line: &str) -> Option<B256> {
+    logfmt_field(line, "msg")
+        .filter(|msg| *msg == "Validating claim")
+        .and_then(|_| logfmt_field(line, "output"))
+        .and_then(|output| output.parse().ok())
+}
+
+/// Extracts the value of a field from a `logfmt` line, e.g. `msg` from `lvl=info msg="hello"`.
+fn logfmt_field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let start = line
+        .match_indices(&format!("{key}="))
+        .find(|(i, _)| *i == 0 || line.as_bytes()[i - 1] == b' ')
+        .map(|(i, m)| i + m.len())?;
+    let rest = &line[start..];
+    match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next(),
+        None => rest.split(' ').next(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observe_logfmt_progress() {
+        let mut progress = CaptureProgress::default();
+        for line in [
+            r#"t=2024-09-01T00:00:00+0000 lvl=info msg="Advancing bq origin" origin=0xabc:100"#,
+            r#"t=2024-09-01T00:00:01+0000 lvl=info msg="Fetching preimage" key=0x02ab"#,
+            r#"t=2024-09-01T00:00:02+0000 lvl=info msg="Advancing bq origin" origin=0xdef:101"#,
+            r#"t=2024-09-01T00:00:03+0000 lvl=info msg="Validating claim" head=0x01 output=0x0000000000000000000000000000000000000000000000000000000000000abc claim=0x02"#,
+            r#"t=2024-09-01T00:00:03+0000 lvl=info msg="Derivation complete: reached L2 block""#,
+        ] {
+            progress.observe(line);
+        }
+        assert_eq!(progress.l1_blocks(), 2);
+        assert_eq!(
+            progress.output_root(),
+            Some(B256::left_padding_from(&[0x0a, 0xbc]))
+        );
+
+        assert_eq!(
+            progress.render(),
+            "L1 blocks traversed: 2 (at #101) | Preimages fetched: 1 | Stage: Derivation complete: reached L2 block"
+        );
+    }
+}
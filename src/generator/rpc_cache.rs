@@ -0,0 +1,407 @@
+//! Contains the [RpcCache], a caching proxy that the RPC traffic of test case generation flows
+//! through, to be kinder to shared archive nodes. Identical requests for immutable chain data are
+//! answered once per `fpt generate` invocation, across every test case of a batch and the hosts
+//! that capture their witnesses, and the requests that reach the RPCs are paced by a global
+//! requests-per-second budget.
+//!
+//! JSON-RPC requests are cached by their method and parameters, unless they refer to a moving block
+//! tag (e.g. `latest`) or ask for state that changes (e.g. `eth_blockNumber`). Beacon API requests
+//! are cached by their path, unless they refer to the head or a checkpoint. Batched JSON-RPC
+//! requests and error responses are never cached.
+
+use crate::{
+    cli::GenerateConfig,
+    pipeline::{
+        chaos::{ProxiedRequest, ProxiedResponse},
+        rate_limit::TokenBucket,
+    },
+};
+use alloy_transport_http::reqwest::Client;
+use color_eyre::Result;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::OnceCell,
+    task::JoinHandle,
+};
+use tracing::{debug, info};
+
+/// The block tags that refer to a moving block.
+const MOVING_BLOCK_TAGS: [&str; 4] = ["latest", "pending", "safe", "finalized"];
+
+/// The JSON-RPC methods whose responses change over time, or that have side effects.
+const UNCACHEABLE_METHODS: [&str; 10] = [
+    "eth_blockNumber",
+    "eth_syncing",
+    "eth_gasPrice",
+    "eth_maxPriorityFeePerGas",
+    "eth_feeHistory",
+    "eth_estimateGas",
+    "eth_getTransactionCount",
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "optimism_syncStatus",
+];
+
+/// The segments of a beacon API path that refer to a moving block, or to the node's status.
+const MOVING_BEACON_SEGMENTS: [&str; 4] = ["head", "finalized", "justified", "node"];
+
+/// A cached response, shared by the concurrent requests for it.
+type CachedResponse = Arc<OnceCell<ProxiedResponse>>;
+
+/// The cache of the responses of the RPCs, shared by the proxies to each of them.
+#[derive(Debug)]
+pub(crate) struct RpcCache {
+    /// The responses, by upstream and request.
+    responses: Mutex<HashMap<String, CachedResponse>>,
+    /// The budget of the requests that reach the RPCs, if any.
+    limiter: Option<TokenBucket>,
+    /// The client that requests are forwarded with.
+    client: Client,
+    /// The number of requests proxied.
+    requests: AtomicU64,
+    /// The number of requests forwarded to the RPCs.
+    forwarded: AtomicU64,
+}
+
+/// A local proxy to an upstream RPC, answering requests from the [RpcCache]. The proxy is shut down
+/// when dropped.
+#[derive(Debug)]
+pub(crate) struct RpcCacheProxy {
+    /// The local address that the proxy listens on.
+    addr: SocketAddr,
+    /// The task accepting connections to the proxy.
+    task: JoinHandle<()>,
+}
+
+impl RpcCache {
+    /// Create a new, empty [RpcCache], forwarding up to the given number of requests per second to
+    /// the RPCs, if any.
+    pub(crate) fn new(rps: Option<f64>) -> Self {
+        Self {
+            responses: Mutex::default(),
+            limiter: rps.map(TokenBucket::new),
+            client: Client::new(),
+            requests: AtomicU64::new(0),
+            forwarded: AtomicU64::new(0),
+        }
+    }
+
+    /// Routes the RPCs of the configuration through proxies to the cache.
+    ///
+    /// ## Takes
+    /// - `cfg` - The generation configuration, whose RPC endpoints are replaced by the proxies.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<RpcCacheProxy>>` - The running proxies, which must outlive the generation.
+    pub(crate) async fn proxy(
+        self: &Arc<Self>,
+        cfg: &mut GenerateConfig,
+    ) -> Result<Vec<RpcCacheProxy>> {
+        let mut proxies = Vec::new();
+        for url in [
+            &mut cfg.l1_rpc,
+            &mut cfg.l1_beacon_rpc,
+            &mut cfg.l2_node_rpc,
+            &mut cfg.l2_rpc,
+        ] {
+            if url.is_empty() {
+                continue;
+            }
+            let proxy = RpcCacheProxy::spawn(url, self.clone()).await?;
+            *url = proxy.url();
+            proxies.push(proxy);
+        }
+        Ok(proxies)
+    }
+
+    /// Returns the number of requests proxied, and the number of them that were forwarded to the
+    /// RPCs rather than answered from the cache.
+    pub(crate) fn stats(&self) -> (u64, u64) {
+        (
+            self.requests.load(Ordering::Relaxed),
+            self.forwarded.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Logs the number of requests that the cache answered, once the generation completes.
+    pub(crate) fn log_stats(&self) {
+        let (requests, forwarded) = self.stats();
+        info!(
+            target: "rpc-cache",
+            "Proxied {requests} RPC requests, answering {} from the cache",
+            requests - forwarded
+        );
+    }
+
+    /// Answers a request to the upstream, from the cache if the request is cacheable.
+    async fn answer(&self, upstream: &str, request: &ProxiedRequest) -> Result<ProxiedResponse> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let Some(key) = cache_key(request) else {
+            return self.forward(upstream, request).await;
+        };
+
+        let cell = self
+            .responses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(format!("{upstream} {key}"))
+            .or_default()
+            .clone();
+        // Concurrent requests for the same response wait for the first to forward it. Responses
+        // that are not cacheable are returned without filling the cell.
+        let mut uncached = None;
+        let uncached_ref = &mut uncached;
+        let cached = cell
+            .get_or_try_init(|| async move {
+                let response = self.forward(upstream, request).await.map_err(Some)?;
+                if cacheable_response(&response) {
+                    Ok(response)
+                } else {
+                    *uncached_ref = Some(response);
+                    Err(None)
+                }
+            })
+            .await;
+        match (cached, uncached) {
+            (Ok(response), _) => Ok(with_request_id(response, request)),
+            (Err(_), Some(response)) => Ok(response),
+            (Err(Some(e)), None) => Err(e),
+            (Err(None), None) => unreachable!("uncacheable responses are returned"),
+        }
+    }
+
+    /// Forwards a request to the upstream, once the budget admits it.
+    async fn forward(&self, upstream: &str, request: &ProxiedRequest) -> Result<ProxiedResponse> {
+        if let Some(limiter) = self.limiter.as_ref() {
+            limiter.acquire().await;
+        }
+        self.forwarded.fetch_add(1, Ordering::Relaxed);
+        request.forward(upstream, &self.client).await
+    }
+}
+
+impl RpcCacheProxy {
+    /// Starts a proxy to the upstream RPC on an ephemeral local port.
+    ///
+    /// ## Takes
+    /// - `upstream` - The URL of the upstream RPC.
+    /// - `cache` - The cache that answers the requests.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The running proxy.
+    pub(crate) async fn spawn(upstream: &str, cache: Arc<RpcCache>) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let upstream = upstream.trim_end_matches('/').to_string();
+
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (upstream, cache) = (upstream.clone(), cache.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = proxy_connection(stream, &upstream, &cache).await {
+                        debug!(target: "rpc-cache", "Connection to {upstream} closed: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr, task })
+    }
+
+    /// Returns the URL of the proxy.
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for RpcCacheProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answers the HTTP/1.1 requests on a connection from the cache, until the client closes it.
+async fn proxy_connection(stream: TcpStream, upstream: &str, cache: &RpcCache) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    while let Some(request) = ProxiedRequest::read(&mut stream).await? {
+        let response = cache.answer(upstream, &request).await?;
+        let stream = stream.get_mut();
+        stream.write_all(response.head().as_bytes()).await?;
+        stream.write_all(&response.body).await?;
+    }
+    Ok(())
+}
+
+/// Returns the key that the response to the request is cached by, or `None` if the response may
+/// change over time.
+fn cache_key(request: &ProxiedRequest) -> Option<String> {
+    match request.method.as_str() {
+        "GET" => request
+            .path
+            .split(['/', '?', '&', '='])
+            .all(|segment| !MOVING_BEACON_SEGMENTS.contains(&segment))
+            .then(|| format!("GET {}", request.path)),
+        "POST" => {
+            let body = serde_json::from_slice::<serde_json::Value>(&request.body).ok()?;
+            let method = body.get("method")?.as_str()?;
+            let params = body.get("params").cloned().unwrap_or_default();
+            let moving = UNCACHEABLE_METHODS.contains(&method)
+                || refers_to_moving_block(&params)
+                || method.contains("send");
+            (!moving).then(|| format!("POST {} {method} {params}", request.path))
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether or not the parameters of a JSON-RPC request refer to a moving block tag.
+fn refers_to_moving_block(params: &serde_json::Value) -> bool {
+    match params {
+        serde_json::Value::String(s) => MOVING_BLOCK_TAGS.contains(&s.as_str()),
+        serde_json::Value::Array(values) => values.iter().any(refers_to_moving_block),
+        serde_json::Value::Object(values) => values.values().any(refers_to_moving_block),
+        _ => false,
+    }
+}
+
+/// Returns whether or not the response may be cached: it succeeded, and is not a JSON-RPC error.
+fn cacheable_response(response: &ProxiedResponse) -> bool {
+    response.status.is_success()
+        && !serde_json::from_slice::<serde_json::Value>(&response.body)
+            .is_ok_and(|body| body.get("error").is_some())
+}
+
+/// Returns the cached response to a JSON-RPC request, with the `id` of the request.
+fn with_request_id(response: &ProxiedResponse, request: &ProxiedRequest) -> ProxiedResponse {
+    let id = serde_json::from_slice::<serde_json::Value>(&request.body)
+        .ok()
+        .and_then(|body| body.get("id").cloned());
+    let body = serde_json::from_slice::<serde_json::Value>(&response.body).ok();
+    match (id, body) {
+        (Some(id), Some(mut body)) if body.get("id").is_some() => {
+            body["id"] = id;
+            ProxiedResponse {
+                body: serde_json::to_vec(&body).unwrap_or_else(|_| response.body.clone()),
+                ..response.clone()
+            }
+        }
+        _ => response.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    /// Starts an upstream that answers every JSON-RPC request with the number of requests that it
+    /// received, counting them.
+    async fn spawn_upstream(count: Arc<AtomicU64>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let count = count.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    loop {
+                        let mut content_length = 0;
+                        loop {
+                            let mut line = String::new();
+                            if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                                return;
+                            }
+                            if let Some(len) =
+                                line.to_ascii_lowercase().strip_prefix("content-length:")
+                            {
+                                content_length = len.trim().parse().unwrap();
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                        }
+                        let mut body = vec![0; content_length];
+                        stream.read_exact(&mut body).await.unwrap();
+                        let request = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+                        let n = count.fetch_add(1, Ordering::SeqCst) + 1;
+                        let response = serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": n }).to_string();
+                        let head = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n", response.len());
+                        let stream = stream.get_mut();
+                        stream.write_all(head.as_bytes()).await.unwrap();
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn cache_rpc_requests() {
+        let count = Arc::new(AtomicU64::new(0));
+        let upstream = spawn_upstream(count.clone()).await;
+        let cache = Arc::new(RpcCache::new(Some(1000.0)));
+        let proxy = RpcCacheProxy::spawn(&upstream, cache.clone())
+            .await
+            .unwrap();
+
+        let call = |id: u64, method: &str, params: serde_json::Value| {
+            let (url, body) = (
+                proxy.url(),
+                serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+            );
+            async move {
+                Client::new()
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .unwrap()
+                    .json::<serde_json::Value>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Identical requests are forwarded once, and answered with their own ids.
+        let header = serde_json::json!(["0x1234", false]);
+        let (a, b) = tokio::join!(
+            call(1, "eth_getBlockByHash", header.clone()),
+            call(2, "eth_getBlockByHash", header.clone())
+        );
+        assert_eq!((a["id"].clone(), a["result"].clone()), (1.into(), 1.into()));
+        assert_eq!((b["id"].clone(), b["result"].clone()), (2.into(), 1.into()));
+        assert_eq!(call(3, "eth_getBlockByHash", header).await["result"], 1);
+
+        // Requests for moving data are always forwarded.
+        assert_eq!(
+            call(
+                4,
+                "eth_getBlockByNumber",
+                serde_json::json!(["latest", false])
+            )
+            .await["result"],
+            2
+        );
+        assert_eq!(
+            call(5, "eth_blockNumber", serde_json::json!([])).await["result"],
+            3
+        );
+        assert_eq!(
+            call(6, "eth_blockNumber", serde_json::json!([])).await["result"],
+            4
+        );
+
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+        assert_eq!(cache.stats(), (6, 4));
+    }
+}
@@ -0,0 +1,174 @@
+//! Contains the interactive generation wizard, which prompts for the [GenerateConfig] of a test
+//! case.
+
+use super::TestCaseGenerator;
+use crate::{cli::GenerateConfig, devnet::sync_status, fixture::FixtureInputs};
+use alloy_provider::{network::Ethereum, Provider, ReqwestProvider};
+use alloy_rpc_types::BlockTransactionsKind;
+use alloy_transport_http::reqwest::{Client, Url};
+use color_eyre::{eyre::ensure, owo_colors::OwoColorize, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::future::Future;
+
+/// The number of recent finalized blocks offered for selection.
+const RECENT_BLOCKS: u64 = 10;
+
+impl GenerateConfig {
+    /// Prompts for the fields of the configuration, using the values passed on the command line
+    /// (if any) as defaults.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The completed configuration.
+    pub(crate) async fn prompt(mut self) -> Result<Self> {
+        let theme = ColorfulTheme::default();
+
+        let tests_dir = self.tests_dir.path.join(&self.chain);
+        let overwrite = self.force || self.out_dir.is_some();
+        self.name = Input::<String>::with_theme(&theme)
+            .with_prompt("Test case name")
+            .with_initial_text(self.name.clone())
+            .validate_with(|name: &String| {
+                if name.trim().is_empty() {
+                    Err("The name must not be empty")
+                } else if !overwrite && tests_dir.join(name).join("fixture.toml").exists() {
+                    Err("A test case with this name already exists")
+                } else {
+                    Ok(())
+                }
+            })
+            .interact_text()?;
+
+        self.l1_rpc = prompt_endpoint(&theme, "L1 RPC", &self.l1_rpc, |url| async move {
+            ReqwestProvider::<Ethereum>::new_http(url)
+                .get_chain_id()
+                .await?;
+            Ok(())
+        })
+        .await?;
+        self.l1_beacon_rpc = prompt_endpoint(
+            &theme,
+            "L1 beacon RPC",
+            &self.l1_beacon_rpc,
+            |url| async move {
+                let response = Client::new()
+                    .get(url.join("eth/v1/node/version")?)
+                    .send()
+                    .await?;
+                ensure!(
+                    response.status().is_success(),
+                    "Beacon node responded with {}",
+                    response.status()
+                );
+                Ok(())
+            },
+        )
+        .await?;
+        self.l2_node_rpc = prompt_endpoint(
+            &theme,
+            "L2 rollup node RPC",
+            &self.l2_node_rpc,
+            |url| async move {
+                sync_status(url).await?;
+                Ok(())
+            },
+        )
+        .await?;
+        self.l2_rpc = prompt_endpoint(&theme, "L2 RPC", &self.l2_rpc, |url| async move {
+            ReqwestProvider::<Ethereum>::new_http(url)
+                .get_chain_id()
+                .await?;
+            Ok(())
+        })
+        .await?;
+
+        self.l2_block = self.prompt_block(&theme).await?;
+        Ok(self)
+    }
+
+    /// Prompts for the L2 block of the test case from a list of recent finalized blocks.
+    async fn prompt_block(&self, theme: &ColorfulTheme) -> Result<u64> {
+        let finalized = sync_status(Url::parse(&self.l2_node_rpc)?)
+            .await?
+            .finalized_l2
+            .number;
+        let l2_rpc = ReqwestProvider::<Ethereum>::new_http(Url::parse(&self.l2_rpc)?);
+
+        let mut blocks = Vec::new();
+        for number in (finalized.saturating_sub(RECENT_BLOCKS - 1).max(1)..=finalized).rev() {
+            let Some(block) = l2_rpc
+                .get_block(number.into(), BlockTransactionsKind::Hashes)
+                .await?
+            else {
+                continue;
+            };
+            blocks.push((
+                number,
+                format!(
+                    "#{number} {} ({} transactions)",
+                    block.header.hash,
+                    block.transactions.len()
+                ),
+            ));
+        }
+        ensure!(!blocks.is_empty(), "No finalized L2 blocks available");
+
+        let selection = Select::with_theme(theme)
+            .with_prompt("L2 block (recent finalized blocks)")
+            .items(&blocks.iter().map(|(_, label)| label).collect::<Vec<_>>())
+            .default(0)
+            .interact()?;
+        Ok(blocks[selection].0)
+    }
+}
+
+impl TestCaseGenerator<'_> {
+    /// Previews the derived inputs of the test case, and asks for confirmation before the witness
+    /// is captured.
+    ///
+    /// ## Returns
+    /// - `Result<bool>` - Whether or not the user confirmed the generation.
+    pub(crate) fn confirm_inputs(&self, inputs: &FixtureInputs) -> Result<bool> {
+        println!("\n{}", "Derived inputs".bold());
+        println!("  {:<18} {}", "name", self.cfg.name.blue());
+        println!("  {:<18} {}", "l1-head", inputs.l1_head);
+        println!("  {:<18} {}", "l2-head", inputs.l2_head);
+        println!("  {:<18} {}", "l2-output-root", inputs.l2_output_root);
+        println!("  {:<18} {}", "l2-claim", inputs.l2_claim);
+        println!("  {:<18} {}", "l2-block-number", inputs.l2_block_number);
+        println!("  {:<18} {}\n", "l2-chain-id", inputs.l2_chain_id);
+
+        Ok(Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Run the reference program and capture the witness?")
+            .default(true)
+            .interact()?)
+    }
+}
+
+/// Prompts for an endpoint until one is given that passes the connectivity check.
+async fn prompt_endpoint<F, Fut>(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    default: &str,
+    check: F,
+) -> Result<String>
+where
+    F: Fn(Url) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut initial = default.to_string();
+    loop {
+        let endpoint = Input::<String>::with_theme(theme)
+            .with_prompt(prompt)
+            .with_initial_text(initial.clone())
+            .validate_with(|s: &String| Url::parse(s).map(|_| ()).map_err(|e| e.to_string()))
+            .interact_text()?;
+
+        match check(Url::parse(&endpoint)?).await {
+            Ok(()) => return Ok(endpoint),
+            Err(e) => {
+                println!("{} Failed to reach {endpoint}: {e}", "error:".red().bold());
+                initial = endpoint;
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Contains the [GenesisInfo] of a fixture, the essential fields of its `genesis.json`. The genesis
+//! files of big devnets are hundreds of megabytes, nearly all of it the state allocation, so they
+//! are memory-mapped and parsed once per fixture, skipping over the allocation, rather than read
+//! into memory by every test.
+
+use alloy_primitives::{hex, keccak256};
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use memmap2::Mmap;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+/// The essential fields of a `genesis.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GenesisInfo {
+    /// The chain ID of the L2 chain.
+    pub(crate) chain_id: u64,
+    /// The activation times of the forks that are scheduled by timestamp, by their name in the
+    /// chain configuration (e.g. `ecotoneTime`).
+    pub(crate) fork_times: BTreeMap<String, u64>,
+    /// The JSON encoded chain configuration.
+    pub(crate) chain_config: Vec<u8>,
+}
+
+/// The fields of a `genesis.json` that are parsed; the rest, including the state allocation, are
+/// skipped.
+#[derive(Deserialize)]
+struct GenesisFile {
+    /// The chain configuration.
+    config: Map<String, Value>,
+}
+
+impl GenesisInfo {
+    /// Parses the essential fields of a `genesis.json`, memory-mapping the file rather than reading
+    /// it into memory.
+    ///
+    /// ## Takes
+    /// - `path` - The path of the `genesis.json`.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The essential fields, or Err if the file is not a valid genesis.
+    pub(crate) fn parse(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).map_err(|e| eyre!("Failed to open `{}`: {e}", path.display()))?;
+        // SAFETY: The genesis must not be modified while it is mapped. `fpt` never writes to it
+        // during a run: the decompressed copies are made read-only, and the genesis files of
+        // uncompressed fixtures are mapped straight from the tests directory, which must be left
+        // alone during a run, as for every other file of a fixture.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::from_slice(&mmap).map_err(|e| eyre!("Invalid genesis `{}`: {e}", path.display()))
+    }
+
+    /// Parses the essential fields of a JSON encoded genesis.
+    fn from_slice(genesis: &[u8]) -> Result<Self> {
+        let GenesisFile { config } = serde_json::from_slice(genesis)?;
+        let chain_id = config
+            .get("chainId")
+            .and_then(Value::as_u64)
+            .ok_or(eyre!("The chain configuration is missing its `chainId`"))?;
+        let fork_times = config
+            .iter()
+            .filter(|(name, _)| name.ends_with("Time"))
+            .filter_map(|(name, time)| Some((name.clone(), time.as_u64()?)))
+            .collect();
+
+        Ok(Self {
+            chain_id,
+            fork_times,
+            chain_config: serde_json::to_vec(&config)?,
+        })
+    }
+
+    /// Writes the chain configuration into the directory, named by its hash, so that processes can
+    /// read it rather than parsing the genesis again. Genesis files with the same chain
+    /// configuration share the file.
+    ///
+    /// ## Takes
+    /// - `dir` - The directory to write the chain configuration into.
+    ///
+    /// ## Returns
+    /// - `Result<PathBuf>` - The path of the chain configuration.
+    pub(crate) fn write_chain_config(&self, dir: &Path) -> Result<PathBuf> {
+        let hash = keccak256(&self.chain_config);
+        let path = dir.join(format!("chain-config-{}.json", hex::encode(&hash[..8])));
+        fs::write(&path, &self.chain_config)?;
+        Ok(path)
+    }
+
+    /// Checks that the fork times of the genesis agree with those of the rollup configuration,
+    /// which names them in snake case (e.g. `ecotoneTime` and `ecotone_time`). Forks that only
+    /// one of them schedules are not compared.
+    ///
+    /// ## Takes
+    /// - `rollup_config` - The JSON encoded rollup configuration.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors naming the first fork whose times disagree.
+    pub(crate) fn check_rollup_config(&self, rollup_config: &[u8]) -> Result<()> {
+        let rollup = serde_json::from_slice::<Map<String, Value>>(rollup_config)?;
+        for (name, time) in self.fork_times.iter() {
+            let fork = name.trim_end_matches("Time");
+            let Some(rollup_time) = rollup
+                .get(&format!("{}_time", fork.to_lowercase()))
+                .and_then(Value::as_u64)
+            else {
+                continue;
+            };
+            ensure!(
+                rollup_time == *time,
+                "The genesis activates {fork} at {time}, but the rollup configuration at {rollup_time}"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: &str = r#"{
+        "config": { "chainId": 901, "shanghaiTime": 0, "ecotoneTime": 6, "terminalTotalDifficulty": 0 },
+        "alloc": { "0x4200000000000000000000000000000000000000": { "balance": "0x1" } }
+    }"#;
+
+    #[test]
+    fn parse_genesis() {
+        let genesis = GenesisInfo::from_slice(GENESIS.as_bytes()).unwrap();
+        assert_eq!(genesis.chain_id, 901);
+        assert_eq!(
+            genesis.fork_times,
+            BTreeMap::from([
+                ("ecotoneTime".to_string(), 6),
+                ("shanghaiTime".to_string(), 0)
+            ])
+        );
+        assert_eq!(
+            serde_json::from_slice::<Value>(&genesis.chain_config).unwrap()["ecotoneTime"],
+            6
+        );
+        assert!(GenesisInfo::from_slice(br#"{"config":{}}"#).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = genesis.write_chain_config(dir.path()).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), genesis.chain_config);
+        assert_eq!(genesis.write_chain_config(dir.path()).unwrap(), path);
+
+        genesis
+            .check_rollup_config(br#"{"ecotone_time": 6, "fjord_time": 12}"#)
+            .unwrap();
+        assert!(genesis
+            .check_rollup_config(br#"{"ecotone_time": 8}"#)
+            .is_err());
+    }
+}
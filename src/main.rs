@@ -6,11 +6,26 @@
 use clap::Parser;
 use color_eyre::Result;
 
+mod archive;
+mod bisect;
+mod clean;
 mod cli;
+mod devnet;
+mod executor;
 mod fixture;
 mod generator;
+mod genesis;
+mod onchain;
+mod output;
+mod perf;
 mod pipeline;
+mod preimage;
+mod process;
 mod registry;
+mod report;
+mod rpc;
+mod state;
+mod units;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
@@ -0,0 +1,267 @@
+//! Contains `fpt verify-onchain`, which checks whether the claim of a fixture was actually proposed
+//! on L1, in an `L2OutputOracle` or a game of a `DisputeGameFactory`, telling the fixtures built
+//! from real proposals apart from those with synthetic claims.
+
+use crate::{cli::VerifyOnchainConfig, fixture::FixtureDir};
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_provider::{network::Ethereum, Provider, ReqwestProvider};
+use alloy_transport_http::reqwest::Url;
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tracing::info;
+
+/// The contract that output roots are proposed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ProposalSource {
+    /// The `L2OutputOracle`, of chains without fault proofs.
+    L2OutputOracle,
+    /// The `DisputeGameFactory`, whose games each propose a root claim.
+    DisputeGameFactory,
+}
+
+/// Whether or not the claim of a fixture was proposed on L1, as recorded in its `[onchain]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct OnchainFinding {
+    /// The kind of contract that was checked.
+    pub(crate) source: ProposalSource,
+    /// The address of the contract that was checked.
+    pub(crate) contract: Address,
+    /// The L1 block that the contract was checked at.
+    pub(crate) l1_block: u64,
+    /// Whether or not the fixture's claim was proposed for its L2 block.
+    pub(crate) proposed: bool,
+    /// The dispute game that proposed the claim, if it was proposed to a `DisputeGameFactory`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) game: Option<Address>,
+    /// Another root that was proposed for the fixture's L2 block, if its claim was not.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) conflicting_root: Option<B256>,
+}
+
+/// Checks whether the claim of the configured fixture was proposed on L1, and records the finding
+/// in its `fixture.toml`, unless `--dry-run` is set.
+///
+/// ## Returns
+/// - `Result<OnchainFinding>` - The finding, or Err if the contract could not be queried.
+pub(crate) async fn verify(cfg: &VerifyOnchainConfig) -> Result<OnchainFinding> {
+    let fixture_dir = FixtureDir::find(&cfg.tests_dir.path, &cfg.fixture)?;
+    let mut fixture = fixture_dir.load()?;
+    let (block, claim) = (fixture.inputs.l2_block_number, fixture.inputs.l2_claim);
+
+    let l1 = L1Caller::new(&cfg.l1_rpc).await?;
+    let finding = match (cfg.l2_output_oracle, cfg.dispute_game_factory) {
+        (Some(oracle), _) => l1.find_output(oracle, block, claim).await?,
+        (None, Some(factory)) => l1.find_game(factory, block, claim, cfg.max_games).await?,
+        (None, None) => {
+            return Err(eyre!(
+                "Either `--l2-output-oracle` or `--dispute-game-factory` is required"
+            ))
+        }
+    };
+
+    if !cfg.dry_run {
+        fixture.metadata.onchain = Some(finding.clone());
+        fs::write(
+            fixture_dir.path.join("fixture.toml"),
+            toml::to_string(&fixture)?,
+        )?;
+    }
+    Ok(finding)
+}
+
+/// Calls the view functions of L1 contracts at a fixed block.
+struct L1Caller {
+    /// The L1 RPC.
+    provider: ReqwestProvider<Ethereum>,
+    /// The L1 block that the contracts are called at.
+    block: u64,
+}
+
+impl L1Caller {
+    /// Create a new [L1Caller], which calls the contracts at the L1 RPC's latest block.
+    async fn new(l1_rpc: &str) -> Result<Self> {
+        let provider = ReqwestProvider::<Ethereum>::new_http(Url::parse(l1_rpc)?);
+        let block = provider.get_block_number().await?;
+        Ok(Self { provider, block })
+    }
+
+    /// Calls a view function of a contract.
+    ///
+    /// ## Takes
+    /// - `to` - The address of the contract.
+    /// - `signature` - The signature of the function, e.g. `getL2Output(uint256)`.
+    /// - `args` - The static arguments of the function, as words.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<B256>>` - The words that the function returned.
+    async fn call(&self, to: Address, signature: &str, args: &[U256]) -> Result<Vec<B256>> {
+        let request = serde_json::json!({ "to": to, "data": calldata(signature, args) });
+        let ret = self
+            .provider
+            .raw_request::<_, Bytes>("eth_call".into(), (request, format!("{:#x}", self.block)))
+            .await
+            .map_err(|e| eyre!("Failed to call `{signature}` on {to}: {e}"))?;
+        ensure!(
+            ret.len() % 32 == 0 && !ret.is_empty(),
+            "`{signature}` on {to} returned malformed data; is it the right contract?"
+        );
+        Ok(ret.chunks(32).map(B256::from_slice).collect())
+    }
+
+    /// Checks whether the claim was proposed for the L2 block to an `L2OutputOracle`.
+    async fn find_output(
+        &self,
+        oracle: Address,
+        block: u64,
+        claim: B256,
+    ) -> Result<OnchainFinding> {
+        let mut finding = OnchainFinding {
+            source: ProposalSource::L2OutputOracle,
+            contract: oracle,
+            l1_block: self.block,
+            proposed: false,
+            game: None,
+            conflicting_root: None,
+        };
+
+        // The oracle reverts when asked for an output after its latest one.
+        let latest = word_u64(&self.call(oracle, "latestBlockNumber()", &[]).await?, 0)?;
+        if latest < block {
+            info!(target: "verify-onchain", "The latest output proposed to {oracle} is for block #{latest}");
+            return Ok(finding);
+        }
+
+        let index = self
+            .call(
+                oracle,
+                "getL2OutputIndexAfter(uint256)",
+                &[U256::from(block)],
+            )
+            .await?;
+        let output = self
+            .call(
+                oracle,
+                "getL2Output(uint256)",
+                &[U256::from_be_bytes(index[0].0)],
+            )
+            .await?;
+        // The output is returned as `(bytes32 outputRoot, uint128 timestamp, uint128 l2BlockNumber)`.
+        let (root, output_block) = (output[0], word_u64(&output, 2)?);
+        if output_block != block {
+            info!(target: "verify-onchain", "No output was proposed to {oracle} for block #{block}; the next is for block #{output_block}");
+        } else if root == claim {
+            finding.proposed = true;
+        } else {
+            finding.conflicting_root = Some(root);
+        }
+        Ok(finding)
+    }
+
+    /// Checks whether the claim was proposed for the L2 block by one of the latest `max_games`
+    /// games of a `DisputeGameFactory`.
+    async fn find_game(
+        &self,
+        factory: Address,
+        block: u64,
+        claim: B256,
+        max_games: u64,
+    ) -> Result<OnchainFinding> {
+        let mut finding = OnchainFinding {
+            source: ProposalSource::DisputeGameFactory,
+            contract: factory,
+            l1_block: self.block,
+            proposed: false,
+            game: None,
+            conflicting_root: None,
+        };
+
+        let count = word_u64(&self.call(factory, "gameCount()", &[]).await?, 0)?;
+        info!(target: "verify-onchain", "Searching the latest {} of {count} games of {factory}", count.min(max_games));
+        for index in (count.saturating_sub(max_games)..count).rev() {
+            // The game is returned as `(uint32 gameType, uint64 timestamp, address proxy)`.
+            let game = self
+                .call(factory, "gameAtIndex(uint256)", &[U256::from(index)])
+                .await?;
+            let proxy = Address::from_word(*game.get(2).ok_or(eyre!("Malformed game"))?);
+            let game_block = word_u64(&self.call(proxy, "l2BlockNumber()", &[]).await?, 0)?;
+            if game_block != block {
+                continue;
+            }
+
+            let root = self.call(proxy, "rootClaim()", &[]).await?[0];
+            if root == claim {
+                finding.proposed = true;
+                finding.game = Some(proxy);
+                finding.conflicting_root = None;
+                break;
+            }
+            finding.conflicting_root.get_or_insert(root);
+        }
+        Ok(finding)
+    }
+}
+
+/// Returns the calldata of a call to the function with the given signature and static arguments.
+fn calldata(signature: &str, args: &[U256]) -> Bytes {
+    let mut data = keccak256(signature)[..4].to_vec();
+    for arg in args {
+        data.extend_from_slice(&arg.to_be_bytes::<32>());
+    }
+    data.into()
+}
+
+/// Returns the word at the index of the returned words as a `u64`.
+fn word_u64(words: &[B256], index: usize) -> Result<u64> {
+    let word = words
+        .get(index)
+        .ok_or_else(|| eyre!("Missing return value {index}"))?;
+    u64::try_from(U256::from_be_bytes(word.0)).map_err(|_| eyre!("Return value {index} overflows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{FixtureMetadata, TestFixture};
+    use alloy_primitives::{address, b256, hex};
+
+    #[test]
+    fn encode_calldata() {
+        assert_eq!(
+            hex::encode(calldata(
+                "transfer(address,uint256)",
+                &[U256::from(1), U256::from(2)]
+            )),
+            format!("a9059cbb{:064x}{:064x}", 1, 2)
+        );
+    }
+
+    #[test]
+    fn record_finding() {
+        let fixture = TestFixture {
+            metadata: FixtureMetadata {
+                name: "proposed".to_string(),
+                onchain: Some(OnchainFinding {
+                    source: ProposalSource::DisputeGameFactory,
+                    contract: address!("e5965ab5962edc7477c8520243a95517cd252fa9"),
+                    l1_block: 100,
+                    proposed: false,
+                    game: None,
+                    conflicting_root: Some(b256!(
+                        "0101010101010101010101010101010101010101010101010101010101010101"
+                    )),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let encoded = toml::to_string(&fixture).unwrap();
+        assert!(encoded.contains("source = \"dispute-game-factory\""));
+        assert_eq!(toml::from_str::<TestFixture>(&encoded).unwrap(), fixture);
+    }
+}
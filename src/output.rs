@@ -0,0 +1,106 @@
+//! Contains the [OutputMode], which decides whether `fpt` draws progress bars and colors, or
+//! plain lines suitable for CI logs. Progress bars and colors are disabled with `--no-progress`
+//! and `--quiet`, and whenever the output is not a terminal.
+
+use color_eyre::{eyre::ensure, Result};
+use indicatif::{ProgressBar, ProgressDrawTarget};
+use once_cell::sync::OnceCell;
+use std::io::{self, IsTerminal};
+
+/// The output mode of the run, if it was set with [OutputMode::set].
+static OUTPUT_MODE: OnceCell<OutputMode> = OnceCell::new();
+
+/// How `fpt` renders its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OutputMode {
+    /// Whether or not progress bars and spinners are drawn.
+    pub(crate) progress: bool,
+    /// Whether or not the output is colored.
+    pub(crate) color: bool,
+    /// Whether or not only warnings, errors, and the results of tests are printed.
+    pub(crate) quiet: bool,
+}
+
+impl OutputMode {
+    /// Detects the output mode from the flags and the terminal: progress bars are drawn if
+    /// stderr is a terminal, and colors are used if stdout is a terminal and `NO_COLOR` is not
+    /// set, unless either is disabled by the flags.
+    ///
+    /// ## Takes
+    /// - `no_progress` - Whether or not `--no-progress` is set.
+    /// - `quiet` - Whether or not `--quiet` is set, which implies `--no-progress`.
+    pub(crate) fn detect(no_progress: bool, quiet: bool) -> Self {
+        let plain = no_progress || quiet;
+        Self {
+            progress: !plain && io::stderr().is_terminal(),
+            color: !plain && io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            quiet,
+        }
+    }
+
+    /// Returns the output mode of the run, detected from the terminal if it was not set.
+    pub(crate) fn get() -> Self {
+        *OUTPUT_MODE.get_or_init(|| Self::detect(false, false))
+    }
+
+    /// Sets the output mode of the run. Must be called before the output mode is first accessed.
+    pub(crate) fn set(mode: Self) -> Result<()> {
+        ensure!(
+            OUTPUT_MODE.set(mode).is_ok(),
+            "The output mode was already in use"
+        );
+        Ok(())
+    }
+
+    /// Renders a message for the output, stripping its colors if the output is not colored.
+    pub(crate) fn render(&self, msg: String) -> String {
+        if self.color {
+            msg
+        } else {
+            strip_ansi(&msg)
+        }
+    }
+}
+
+/// Returns the progress bar, hidden if progress bars are not drawn.
+pub(crate) fn progress_bar(bar: ProgressBar) -> ProgressBar {
+    if !OutputMode::get().progress {
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    bar
+}
+
+/// Strips the ANSI escape sequences (e.g. colors) from the text.
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        // Skip the control sequence, up to and including its final byte.
+        if chars.next_if_eq(&'[').is_some() {
+            for c in chars.by_ref() {
+                if ('\x40'..='\x7e').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color_eyre::owo_colors::OwoColorize;
+
+    #[test]
+    fn strip_colors() {
+        let colored = format!("{} {} done", "PASS".green().bold(), "a::b::c".magenta());
+        assert_ne!(colored, "PASS a::b::c done");
+        assert_eq!(strip_ansi(&colored), "PASS a::b::c done");
+        assert_eq!(strip_ansi("no colors"), "no colors");
+    }
+}
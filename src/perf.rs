@@ -0,0 +1,559 @@
+//! Contains `fpt perf compare`, which compares the wall time of a program's host at two revisions,
+//! running the selected fixtures natively with both, so that performance regression checks are a
+//! one-liner, and `fpt bench`, which compares the instruction counts and wall times of a program at
+//! two revisions on an FPVM, flagging the regressions.
+
+use crate::{
+    cli::{BenchConfig, PerfCompareConfig},
+    executor::{ComponentBuild, ExecutionOutcome, Executor},
+    fixture::FixtureDir,
+    process::ProcessOptions,
+    registry::{platform::PlatformKind, program::ProgramKind, FPPDefinition, FP_REGISTRY},
+    state::StateDir,
+    units::Elapsed,
+};
+use cli_table::{format::Justify, Cell, Style, Table, TableStruct};
+use color_eyre::{
+    eyre::{ensure, eyre},
+    owo_colors::OwoColorize,
+    Result,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tempfile::TempDir;
+use tracing::info;
+
+/// The absolute Welch t-statistic above which a difference in wall time is reported as
+/// significant rather than noise. A rough threshold, close to the 95% confidence level for the
+/// repeat counts that are practical.
+const SIGNIFICANT_T: f64 = 2.0;
+
+/// The wall times of the repeated runs of a fixture at one revision.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Samples(pub(crate) Vec<f64>);
+
+impl Samples {
+    /// Returns the wall times of the executions.
+    pub(crate) fn of_wall_times(outcomes: &[ExecutionOutcome]) -> Self {
+        Self(outcomes.iter().map(|o| o.duration.as_secs_f64()).collect())
+    }
+
+    /// Returns the mean wall time, in seconds.
+    pub(crate) fn mean(&self) -> f64 {
+        self.0.iter().sum::<f64>() / self.0.len().max(1) as f64
+    }
+
+    /// Returns the median wall time, in seconds.
+    pub(crate) fn median(&self) -> f64 {
+        let mut sorted = self.0.clone();
+        sorted.sort_by(f64::total_cmp);
+        match sorted.len() {
+            0 => 0.0,
+            n if n % 2 == 1 => sorted[n / 2],
+            n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+        }
+    }
+
+    /// Returns the sample variance of the wall times.
+    fn variance(&self) -> f64 {
+        if self.0.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        self.0.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (self.0.len() - 1) as f64
+    }
+
+    /// Returns the sample standard deviation of the wall times, in seconds.
+    pub(crate) fn stddev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Returns Welch's t-statistic of the difference between the means of the samples, or `None`
+    /// if neither varies.
+    pub(crate) fn welch_t(&self, other: &Self) -> Option<f64> {
+        let se = (self.variance() / self.0.len() as f64 + other.variance() / other.0.len() as f64)
+            .sqrt();
+        (se > 0.0).then(|| (other.mean() - self.mean()) / se)
+    }
+}
+
+/// The comparison of the wall times of a fixture at both revisions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FixtureComparison {
+    /// The name of the fixture.
+    pub(crate) fixture: String,
+    /// The wall times at the first revision.
+    pub(crate) a: Samples,
+    /// The wall times at the second revision.
+    pub(crate) b: Samples,
+}
+
+impl FixtureComparison {
+    /// Returns the change of the mean wall time from the first revision to the second, in percent.
+    pub(crate) fn delta_pct(&self) -> f64 {
+        (self.b.mean() / self.a.mean() - 1.0) * 100.0
+    }
+
+    /// Returns whether or not the change of the mean wall time is larger than the noise.
+    pub(crate) fn significant(&self) -> bool {
+        self.a
+            .welch_t(&self.b)
+            .is_some_and(|t| t.abs() >= SIGNIFICANT_T)
+    }
+
+    /// Renders the comparisons as a table.
+    pub(crate) fn table(comparisons: &[Self], rev_a: &str, rev_b: &str) -> TableStruct {
+        let summary = |samples: &Samples| {
+            let secs = |s: f64| Elapsed(Duration::from_secs_f64(s));
+            format!(
+                "{} ± {} (median {})",
+                secs(samples.mean()),
+                secs(samples.stddev()),
+                secs(samples.median())
+            )
+        };
+        comparisons
+            .iter()
+            .map(|c| {
+                vec![
+                    c.fixture.clone().cell(),
+                    summary(&c.a).cell(),
+                    summary(&c.b).cell(),
+                    format_delta(c.delta_pct(), !c.significant())
+                        .cell()
+                        .justify(Justify::Right),
+                    c.a.welch_t(&c.b)
+                        .map_or("-".to_string(), |t| format!("{t:.2}"))
+                        .cell()
+                        .justify(Justify::Right),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Fixture".cell(),
+                rev_a.cell(),
+                rev_b.cell(),
+                "Delta".cell(),
+                "t".cell(),
+            ])
+            .bold(true)
+    }
+}
+
+/// The instruction counts and wall times of a fixture at the baseline and candidate revisions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FixtureBench {
+    /// The number of instructions that the baseline executed, if the platform reports them.
+    pub(crate) baseline_instructions: Option<u64>,
+    /// The number of instructions that the candidate executed, if the platform reports them.
+    pub(crate) candidate_instructions: Option<u64>,
+    /// The wall times at both revisions, the baseline's first.
+    pub(crate) wall_times: FixtureComparison,
+}
+
+impl FixtureBench {
+    /// Returns the change of the instruction count from the baseline to the candidate, in percent,
+    /// if both were reported.
+    pub(crate) fn instructions_delta_pct(&self) -> Option<f64> {
+        match (self.baseline_instructions, self.candidate_instructions) {
+            (Some(baseline), Some(candidate)) if baseline > 0 => {
+                Some((candidate as f64 / baseline as f64 - 1.0) * 100.0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether or not the candidate regressed by more than the threshold: it executed
+    /// more instructions, or took more wall time beyond the noise.
+    ///
+    /// ## Takes
+    /// - `threshold_pct` - The largest increase that is not a regression, in percent.
+    pub(crate) fn regressed(&self, threshold_pct: f64) -> bool {
+        self.instructions_delta_pct()
+            .is_some_and(|delta| delta > threshold_pct)
+            || (self.wall_times.significant() && self.wall_times.delta_pct() > threshold_pct)
+    }
+
+    /// Renders the benchmarks as a table, flagging the regressions beyond the threshold.
+    pub(crate) fn table(benches: &[Self], threshold_pct: f64) -> TableStruct {
+        let instructions = |count: Option<u64>| count.map_or("-".to_string(), |c| c.to_string());
+        let mean = |samples: &Samples| Elapsed(Duration::from_secs_f64(samples.mean()));
+        benches
+            .iter()
+            .map(|b| {
+                vec![
+                    b.wall_times.fixture.clone().cell(),
+                    instructions(b.baseline_instructions)
+                        .cell()
+                        .justify(Justify::Right),
+                    instructions(b.candidate_instructions)
+                        .cell()
+                        .justify(Justify::Right),
+                    b.instructions_delta_pct()
+                        .map_or("-".to_string(), |delta| format_delta(delta, false))
+                        .cell()
+                        .justify(Justify::Right),
+                    mean(&b.wall_times.a).cell().justify(Justify::Right),
+                    mean(&b.wall_times.b).cell().justify(Justify::Right),
+                    format_delta(b.wall_times.delta_pct(), !b.wall_times.significant())
+                        .cell()
+                        .justify(Justify::Right),
+                    if b.regressed(threshold_pct) {
+                        "regressed".red().bold().to_string()
+                    } else {
+                        "ok".green().to_string()
+                    }
+                    .cell(),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Fixture".cell(),
+                "Baseline Instructions".cell(),
+                "Candidate Instructions".cell(),
+                "Delta".cell(),
+                "Baseline Wall Time".cell(),
+                "Candidate Wall Time".cell(),
+                "Delta".cell(),
+                "Result".cell(),
+            ])
+            .bold(true)
+    }
+}
+
+/// Formats the change in percent, coloring increases red and decreases green, unless the change
+/// is noise.
+fn format_delta(delta_pct: f64, noise: bool) -> String {
+    let delta = format!("{delta_pct:+.1}%");
+    match (noise, delta_pct > 0.0) {
+        (true, _) => format!("{delta} (noise)").dimmed().to_string(),
+        (false, true) => delta.red().to_string(),
+        (false, false) => delta.green().to_string(),
+    }
+}
+
+/// Builds the program at both revisions, and runs each selected fixture natively with both,
+/// alternating between the revisions so that drift in the host's load affects both alike.
+///
+/// ## Takes
+/// - `cfg` - The configuration of `fpt perf compare`.
+///
+/// ## Returns
+/// - `Result<Vec<FixtureComparison>>` - The wall times of each fixture at both revisions. Errors
+///   if a build fails, or if a run does not exit with the fixture's expected status.
+pub(crate) async fn compare(cfg: &PerfCompareConfig) -> Result<Vec<FixtureComparison>> {
+    let revisions = Revisions::build(
+        cfg.program,
+        PlatformKind::Native,
+        [&cfg.rev_a, &cfg.rev_b],
+        cfg.test.as_deref(),
+        &cfg.tests_dir.path,
+        cfg.repeat,
+    )
+    .await?;
+    revisions
+        .run_all(|fixture, [a, b]| FixtureComparison {
+            fixture,
+            a: Samples::of_wall_times(&a),
+            b: Samples::of_wall_times(&b),
+        })
+        .await
+}
+
+/// Builds the program at the baseline and candidate revisions, and runs each selected fixture on
+/// the platform with both, alternating between the revisions so that drift in the host's load
+/// affects both alike.
+///
+/// ## Takes
+/// - `cfg` - The configuration of `fpt bench`.
+///
+/// ## Returns
+/// - `Result<Vec<FixtureBench>>` - The instruction counts and wall times of each fixture at both
+///   revisions. Errors if a build fails, or if a run does not exit with the fixture's expected
+///   status.
+pub(crate) async fn bench(cfg: &BenchConfig) -> Result<Vec<FixtureBench>> {
+    let definition = FP_REGISTRY
+        .program
+        .get(&cfg.program)
+        .ok_or_else(|| eyre!("Program `{}` is not in the registry", cfg.program))?;
+    let platform = match cfg.platform {
+        Some(platform) => platform,
+        None => *definition
+            .platform_compat
+            .first()
+            .ok_or_else(|| eyre!("Program `{}` runs on no platform", cfg.program))?,
+    };
+    let revisions = Revisions::build(
+        cfg.program,
+        platform,
+        [&cfg.baseline, &cfg.candidate],
+        cfg.test.as_deref(),
+        &cfg.tests_dir.path,
+        cfg.repeat,
+    )
+    .await?;
+    revisions
+        .run_all(|fixture, [baseline, candidate]| {
+            // The instructions that a revision executes are deterministic, so the first run's
+            // count stands for all of them.
+            let instructions =
+                |outcomes: &[ExecutionOutcome]| outcomes.first().and_then(|o| o.instructions);
+            FixtureBench {
+                baseline_instructions: instructions(&baseline),
+                candidate_instructions: instructions(&candidate),
+                wall_times: FixtureComparison {
+                    fixture,
+                    a: Samples::of_wall_times(&baseline),
+                    b: Samples::of_wall_times(&candidate),
+                },
+            }
+        })
+        .await
+}
+
+/// A program built at two revisions, and the fixtures that it is ran with at both.
+struct Revisions<'a> {
+    /// The program.
+    program: ProgramKind,
+    /// The revisions of the program.
+    revs: [&'a str; 2],
+    /// The executors of the program at each revision.
+    executors: [Executor; 2],
+    /// The selected fixtures.
+    fixture_dirs: Vec<FixtureDir>,
+    /// The number of times to run each fixture with each revision.
+    repeat: usize,
+    /// The directory holding the copied artifacts of each revision, removed on drop.
+    _artifacts: TempDir,
+}
+
+impl<'a> Revisions<'a> {
+    /// Selects the fixtures, and builds the platform and the program at both revisions.
+    ///
+    /// ## Takes
+    /// - `program` - The program to build.
+    /// - `platform` - The platform to run the program on.
+    /// - `revs` - The revisions of the program to build.
+    /// - `test` - The glob pattern that selects the fixtures, if any.
+    /// - `tests_dir` - The directory containing the fixtures.
+    /// - `repeat` - The number of times to run each fixture with each revision.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - Errors if the program cannot run on the platform, if no fixture is
+    ///   selected, or if a build fails.
+    async fn build(
+        program: ProgramKind,
+        platform: PlatformKind,
+        revs: [&'a String; 2],
+        test: Option<&str>,
+        tests_dir: &Path,
+        repeat: usize,
+    ) -> Result<Self> {
+        ensure!(repeat > 0, "`--repeat` must be at least 1");
+        let definition = FP_REGISTRY
+            .program
+            .get(&program)
+            .ok_or_else(|| eyre!("Program `{program}` is not in the registry"))?;
+        ensure!(
+            definition.platform_compat.contains(&platform),
+            "Program `{program}` does not run on `{platform}`; it runs on {:?}",
+            definition.platform_compat
+        );
+        let platform_def = FP_REGISTRY
+            .platform
+            .get(&platform)
+            .ok_or_else(|| eyre!("Platform `{platform}` is not in the registry"))?;
+
+        // Select the fixtures before spending time on the builds.
+        let glob = glob::Pattern::new(test.unwrap_or("*"))?;
+        let fixture_dirs = FixtureDir::find_all(tests_dir)?
+            .into_iter()
+            .filter(|dir| {
+                dir.path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| glob.matches(name))
+            })
+            .collect::<Vec<_>>();
+        ensure!(
+            !fixture_dirs.is_empty(),
+            "No fixtures in `{}` match `{glob}`",
+            tests_dir.display()
+        );
+
+        if let Some(build) = ComponentBuild::of_platform(platform, platform_def)? {
+            info!(target: "perf", "Building platform: {platform}");
+            build.run(false).await?;
+        }
+
+        // Both revisions are built in the same checkout, so the artifacts of each are copied out
+        // of it once built.
+        let artifacts = StateDir::get().tempdir()?;
+        let mut executors = Vec::new();
+        for rev in revs {
+            let mut definition = definition.clone();
+            definition.build.rev = rev.clone();
+            info!(target: "perf", "Building {program} at {rev}");
+            ComponentBuild::of_program(&definition).run(false).await?;
+            let (host, client) = copy_artifacts(&definition, &artifacts, executors.len())?;
+            executors.push(Executor::with_artifacts(
+                platform,
+                platform_def,
+                program,
+                &definition,
+                host,
+                client,
+            )?);
+        }
+
+        Ok(Self {
+            program,
+            revs: revs.map(String::as_str),
+            executors: executors
+                .try_into()
+                .map_err(|_| eyre!("Expected an executor per revision"))?,
+            fixture_dirs,
+            repeat,
+            _artifacts: artifacts,
+        })
+    }
+
+    /// Runs each selected fixture `repeat` times with both revisions, and summarizes the outcomes
+    /// of each fixture.
+    ///
+    /// ## Takes
+    /// - `summarize` - Summarizes the name of a fixture and the outcomes of its runs with each
+    ///   revision.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<T>>` - The summary of each fixture. Errors if a run does not exit with the
+    ///   fixture's expected status.
+    async fn run_all<T>(
+        &self,
+        summarize: impl Fn(String, [Vec<ExecutionOutcome>; 2]) -> T,
+    ) -> Result<Vec<T>> {
+        let scratch_dir = StateDir::get().scratch_dir();
+        fs::create_dir_all(&scratch_dir)?;
+        let scratch = tempfile::Builder::new()
+            .prefix("perf-")
+            .tempdir_in(scratch_dir)?;
+
+        let mut summaries = Vec::with_capacity(self.fixture_dirs.len());
+        for fixture_dir in self.fixture_dirs.iter() {
+            let (fixture, inputs) = fixture_dir.host_inputs(scratch.path()).await?;
+
+            let mut outcomes = [Vec::new(), Vec::new()];
+            for i in 0..self.repeat {
+                let order = if i % 2 == 0 { [0, 1] } else { [1, 0] };
+                for rev in order {
+                    let workdir = StateDir::get().tempdir()?;
+                    let outcome = self.executors[rev]
+                        .run(&inputs, workdir.path(), &ProcessOptions::default())
+                        .await?;
+                    ensure!(
+                        outcome.exit_status == Some(fixture.metadata.expected_status),
+                        "{} at {} exited with status {:?} on `{}`, but {} was expected",
+                        self.program,
+                        self.revs[rev],
+                        outcome.exit_status,
+                        fixture.metadata.name,
+                        fixture.metadata.expected_status
+                    );
+                    outcomes[rev].push(outcome);
+                }
+                info!(
+                    target: "perf",
+                    "{}: run {}/{} of both revisions complete",
+                    fixture.metadata.name,
+                    i + 1,
+                    self.repeat
+                );
+            }
+            summaries.push(summarize(fixture.metadata.name, outcomes));
+        }
+        Ok(summaries)
+    }
+}
+
+/// Copies the host and client artifacts of the built program out of its checkout, into the
+/// `index`th subdirectory of the artifacts directory.
+fn copy_artifacts(
+    definition: &FPPDefinition,
+    artifacts: &TempDir,
+    index: usize,
+) -> Result<(PathBuf, PathBuf)> {
+    let dir = artifacts.path().join(index.to_string());
+    fs::create_dir_all(&dir)?;
+    let copy = |name: &str| -> Result<PathBuf> {
+        let artifact = definition
+            .build
+            .get_artifact(name)
+            .ok_or_else(|| eyre!("No {name} artifact"))?;
+        let dst = dir.join(name);
+        fs::copy(&artifact, &dst)?;
+        Ok(dst)
+    };
+    Ok((copy("host")?, copy("client")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_wall_times() {
+        let comparison = FixtureComparison {
+            fixture: "basic".to_string(),
+            a: Samples(vec![10.0, 10.2, 9.8, 10.0]),
+            b: Samples(vec![12.0, 12.2, 11.8, 12.0]),
+        };
+        assert_eq!(comparison.a.mean(), 10.0);
+        assert_eq!(comparison.b.median(), 12.0);
+        assert!((comparison.a.stddev() - 0.163).abs() < 1e-3);
+        assert!((comparison.delta_pct() - 20.0).abs() < 1e-9);
+        assert!(comparison.significant());
+
+        let noisy = FixtureComparison {
+            b: Samples(vec![10.4, 9.6, 10.1, 10.1]),
+            ..comparison
+        };
+        assert!(!noisy.significant());
+        assert_eq!(Samples(vec![1.0]).welch_t(&Samples(vec![1.0])), None);
+    }
+
+    #[test]
+    fn flag_regressions() {
+        let bench = FixtureBench {
+            baseline_instructions: Some(1_000_000),
+            candidate_instructions: Some(1_030_000),
+            wall_times: FixtureComparison {
+                fixture: "basic".to_string(),
+                a: Samples(vec![10.0, 10.2, 9.8]),
+                b: Samples(vec![10.4, 9.6, 10.1]),
+            },
+        };
+        assert!((bench.instructions_delta_pct().unwrap() - 3.0).abs() < 1e-9);
+        assert!(!bench.regressed(5.0));
+        assert!(bench.regressed(2.0));
+
+        // Wall time regressions are flagged beyond the noise alone.
+        let slower = FixtureBench {
+            candidate_instructions: Some(1_000_000),
+            wall_times: FixtureComparison {
+                b: Samples(vec![12.0, 12.2, 11.8]),
+                ..bench.wall_times.clone()
+            },
+            ..bench.clone()
+        };
+        assert!(slower.regressed(5.0));
+        let unreported = FixtureBench {
+            baseline_instructions: None,
+            ..bench
+        };
+        assert_eq!(unreported.instructions_delta_pct(), None);
+        assert!(!unreported.regressed(0.0));
+    }
+}
@@ -0,0 +1,114 @@
+//! Contains the [CpuPinning] scheduler, which assigns the workers of the pool to CPU cores.
+
+use color_eyre::{eyre::eyre, Result};
+use std::{fs, path::Path, sync::Mutex};
+
+/// The directory containing the NUMA node topology.
+const NUMA_NODE_DIR: &str = "/sys/devices/system/node";
+
+/// Assigns the workers of the pool to CPU cores, round-robin, interleaving the cores of each NUMA
+/// node so that concurrently running VMs are spread across the memory controllers.
+#[derive(Debug)]
+pub(crate) struct CpuPinning {
+    /// The cores to assign workers to, in assignment order.
+    cores: Vec<usize>,
+    /// The worker slots that are not currently running a test.
+    free_slots: Mutex<Vec<usize>>,
+}
+
+impl CpuPinning {
+    /// Create a new [CpuPinning] for a pool of `workers` workers.
+    pub(crate) fn new(workers: usize) -> Result<Self> {
+        let nodes = numa_nodes().unwrap_or_else(|_| {
+            let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+            vec![(0..cores).collect()]
+        });
+
+        // Interleave the cores of each node: node0[0], node1[0], node0[1], node1[1], ...
+        let max_cores = nodes.iter().map(Vec::len).max().unwrap_or_default();
+        let cores = (0..max_cores)
+            .flat_map(|i| nodes.iter().filter_map(move |node| node.get(i).copied()))
+            .collect::<Vec<_>>();
+        if cores.is_empty() {
+            return Err(eyre!("No CPU cores available for pinning"));
+        }
+
+        Ok(Self {
+            cores,
+            free_slots: Mutex::new((0..workers).rev().collect()),
+        })
+    }
+
+    /// Claims a free worker slot, returning the core that it is pinned to.
+    pub(crate) fn acquire(&self) -> (usize, usize) {
+        let slot = self
+            .free_slots
+            .lock()
+            .expect("Poisoned lock")
+            .pop()
+            .expect("More workers than permits");
+        (slot, self.cores[slot % self.cores.len()])
+    }
+
+    /// Releases a worker slot.
+    pub(crate) fn release(&self, slot: usize) {
+        self.free_slots.lock().expect("Poisoned lock").push(slot);
+    }
+}
+
+/// Reads the cores of each NUMA node on the machine.
+fn numa_nodes() -> Result<Vec<Vec<usize>>> {
+    let mut nodes = fs::read_dir(NUMA_NODE_DIR)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let index = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("node")?
+                .parse::<usize>()
+                .ok()?;
+            Some((index, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    nodes.sort();
+
+    let nodes = nodes
+        .into_iter()
+        .map(|(_, path)| read_cpulist(&path.join("cpulist")))
+        .collect::<Result<Vec<_>>>()?;
+    if nodes.iter().all(Vec::is_empty) {
+        return Err(eyre!("No NUMA nodes with CPUs"));
+    }
+    Ok(nodes)
+}
+
+/// Reads a cpulist file.
+fn read_cpulist(path: &Path) -> Result<Vec<usize>> {
+    parse_cpulist(fs::read_to_string(path)?.trim())
+}
+
+/// Parses a kernel cpulist, e.g. `0-3,8,10-11`.
+fn parse_cpulist(list: &str) -> Result<Vec<usize>> {
+    let mut cores = Vec::new();
+    for range in list.split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => cores.extend(start.parse::<usize>()?..=end.parse::<usize>()?),
+            None => cores.push(range.parse()?),
+        }
+    }
+    Ok(cores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kernel_cpulist() {
+        assert_eq!(
+            parse_cpulist("0-3,8,10-11").unwrap(),
+            vec![0, 1, 2, 3, 8, 10, 11]
+        );
+        assert_eq!(parse_cpulist("").unwrap(), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,311 @@
+//! Contains the chaos proxy, which sits between a program host and its RPCs during RPC-backed test
+//! runs, injecting latency, errors, and truncated responses, and pacing the requests to the RPC if
+//! it is rate limited.
+
+use super::rate_limit::TokenBucket;
+use alloy_transport_http::reqwest::{Client, Method, StatusCode};
+use color_eyre::{eyre::eyre, Result};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tracing::debug;
+
+/// The faults injected by a [ChaosProxy].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct ChaosConfig {
+    /// The latency added to every response.
+    pub(crate) latency: Duration,
+    /// The fraction of requests that are answered with `503 Service Unavailable`.
+    pub(crate) error_rate: f64,
+    /// The fraction of responses whose body is cut off halfway.
+    pub(crate) truncate_rate: f64,
+}
+
+/// Parses a fraction of requests, within `[0, 1]`.
+pub(crate) fn parse_rate(s: &str) -> Result<f64, String> {
+    let rate = s.parse::<f64>().map_err(|e| e.to_string())?;
+    if (0.0..=1.0).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(format!("`{s}` is not within [0, 1]"))
+    }
+}
+
+/// A local HTTP proxy to an upstream RPC, which injects faults into the proxied responses. The
+/// proxy is shut down when dropped.
+#[derive(Debug)]
+pub(crate) struct ChaosProxy {
+    /// The local address that the proxy listens on.
+    addr: SocketAddr,
+    /// The task accepting connections to the proxy.
+    task: JoinHandle<()>,
+}
+
+impl ChaosProxy {
+    /// Starts a proxy to the upstream RPC on an ephemeral local port.
+    ///
+    /// ## Takes
+    /// - `upstream` - The URL of the upstream RPC.
+    /// - `cfg` - The faults to inject.
+    /// - `limiter` - The token bucket that paces the requests to the upstream RPC, if any.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The running proxy.
+    pub(crate) async fn spawn(
+        upstream: &str,
+        cfg: ChaosConfig,
+        limiter: Option<Arc<TokenBucket>>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let upstream = upstream.trim_end_matches('/').to_string();
+        let client = Client::new();
+
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (upstream, client, limiter) =
+                    (upstream.clone(), client.clone(), limiter.clone());
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        proxy_connection(stream, &upstream, &client, cfg, limiter.as_deref()).await
+                    {
+                        debug!(target: "chaos-proxy", "Connection to {upstream} closed: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr, task })
+    }
+
+    /// Returns the URL of the proxy.
+    pub(crate) fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for ChaosProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// An HTTP/1.1 request read from a proxied connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProxiedRequest {
+    /// The method of the request.
+    pub(crate) method: String,
+    /// The path and query of the request.
+    pub(crate) path: String,
+    /// The content type of the body, if given.
+    pub(crate) content_type: Option<String>,
+    /// The body of the request.
+    pub(crate) body: Vec<u8>,
+}
+
+/// The response of an upstream to a [ProxiedRequest].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProxiedResponse {
+    /// The status code of the response.
+    pub(crate) status: StatusCode,
+    /// The content type of the body.
+    pub(crate) content_type: String,
+    /// The body of the response.
+    pub(crate) body: Vec<u8>,
+}
+
+impl ProxiedRequest {
+    /// Reads the next request on the connection, or `None` if the client closed it.
+    pub(crate) async fn read(stream: &mut BufReader<TcpStream>) -> Result<Option<Self>> {
+        // Read the request line and headers.
+        let mut request_line = String::new();
+        if stream.read_line(&mut request_line).await? == 0 {
+            return Ok(None);
+        }
+        let mut parts = request_line.split_whitespace();
+        let (method, path) = (
+            parts.next().ok_or(eyre!("Missing request method"))?,
+            parts.next().ok_or(eyre!("Missing request path"))?,
+        );
+
+        let mut content_length = 0;
+        let mut content_type = None;
+        loop {
+            let mut header = String::new();
+            stream.read_line(&mut header).await?;
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "content-length" => content_length = value.trim().parse()?,
+                    "content-type" => content_type = Some(value.trim().to_string()),
+                    _ => {}
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        stream.read_exact(&mut body).await?;
+
+        Ok(Some(Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            content_type,
+            body,
+        }))
+    }
+
+    /// Forwards the request to the upstream.
+    pub(crate) async fn forward(&self, upstream: &str, client: &Client) -> Result<ProxiedResponse> {
+        let mut request = client
+            .request(
+                Method::from_bytes(self.method.as_bytes())?,
+                format!("{upstream}{}", self.path),
+            )
+            .body(self.body.clone());
+        if let Some(content_type) = self.content_type.as_ref() {
+            request = request.header("content-type", content_type);
+        }
+        let response = request.send().await?;
+        Ok(ProxiedResponse {
+            status: response.status(),
+            content_type: response
+                .headers()
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("application/json")
+                .to_string(),
+            body: response.bytes().await?.to_vec(),
+        })
+    }
+}
+
+impl ProxiedResponse {
+    /// Returns the status line and headers of the response.
+    pub(crate) fn head(&self) -> String {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            self.status.as_u16(),
+            self.status.canonical_reason().unwrap_or_default(),
+            self.content_type,
+            self.body.len()
+        )
+    }
+}
+
+/// Proxies the HTTP/1.1 requests on a connection to the upstream RPC, until the client closes the
+/// connection or a response is truncated.
+async fn proxy_connection(
+    stream: TcpStream,
+    upstream: &str,
+    client: &Client,
+    cfg: ChaosConfig,
+    limiter: Option<&TokenBucket>,
+) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    while let Some(request) = ProxiedRequest::read(&mut stream).await? {
+        // Forward the request to the upstream RPC, once the rate limit admits it.
+        if let Some(limiter) = limiter {
+            limiter.acquire().await;
+        }
+        let response = request.forward(upstream, client).await?;
+
+        tokio::time::sleep(cfg.latency).await;
+
+        let stream = stream.get_mut();
+        if rand::random::<f64>() < cfg.error_rate {
+            stream
+                .write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n")
+                .await?;
+            continue;
+        }
+
+        stream.write_all(response.head().as_bytes()).await?;
+        if rand::random::<f64>() < cfg.truncate_rate {
+            // Cut the body off halfway, and close the connection.
+            stream
+                .write_all(&response.body[..response.body.len() / 2])
+                .await?;
+            stream.shutdown().await?;
+            return Ok(());
+        }
+        stream.write_all(&response.body).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Starts an upstream that answers every request on a connection with `pong`.
+    async fn spawn_upstream() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    loop {
+                        let mut line = String::new();
+                        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        if line == "\r\n" {
+                            stream
+                                .get_mut()
+                                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\npong")
+                                .await
+                                .unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn injects_faults() {
+        let upstream = format!("http://{}", spawn_upstream().await);
+
+        let proxy = ChaosProxy::spawn(&upstream, ChaosConfig::default(), None)
+            .await
+            .unwrap();
+        let response = reqwest_get(&proxy).await.unwrap();
+        assert_eq!(response, (200, "pong".to_string()));
+
+        let proxy = ChaosProxy::spawn(
+            &upstream,
+            ChaosConfig {
+                error_rate: 1.0,
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(reqwest_get(&proxy).await.unwrap().0, 503);
+
+        let proxy = ChaosProxy::spawn(
+            &upstream,
+            ChaosConfig {
+                truncate_rate: 1.0,
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(reqwest_get(&proxy).await.is_err());
+    }
+
+    async fn reqwest_get(proxy: &ChaosProxy) -> Result<(u16, String)> {
+        let response = Client::new().get(proxy.url()).send().await?;
+        Ok((response.status().as_u16(), response.text().await?))
+    }
+}
@@ -0,0 +1,530 @@
+//! Contains the [Dashboard], the interactive terminal UI of `fpt test --tui`, which shows the
+//! tests of a long run, the activity of each worker, and the output of the selected test.
+
+use super::status::WorkerStatus;
+use crate::{
+    report::{TestId, TestStatus},
+    units::Elapsed,
+};
+use color_eyre::{eyre::ensure, Result};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+use std::{
+    collections::VecDeque,
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+/// The interval at which the dashboard is redrawn, and input is polled.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The number of output lines that are kept per test.
+const TEST_LOG_LINES: usize = 1000;
+
+/// The number of log events of the run that are kept.
+const EVENT_LINES: usize = 200;
+
+/// The log events of the run, captured rather than written to the terminal while a dashboard is
+/// shown.
+static EVENTS: Mutex<Option<VecDeque<String>>> = Mutex::new(None);
+
+/// The state of a test run, as shown by the dashboard.
+#[derive(Debug)]
+pub(crate) struct Dashboard {
+    /// The utilization of the worker pool, rendered as the counters of the run.
+    status: Arc<WorkerStatus>,
+    /// The tests that have been scheduled, and the workers running them.
+    state: Mutex<DashboardState>,
+    /// Whether or not the dashboard has been stopped.
+    stopped: AtomicBool,
+    /// The token that cancels the run, on Ctrl-C.
+    cancel: CancellationToken,
+}
+
+/// The tests of a [Dashboard], and the workers running them.
+#[derive(Debug)]
+struct DashboardState {
+    /// The tests that have been scheduled, in the order they were scheduled in.
+    tests: Vec<DashboardTest>,
+    /// The index of the test that each worker is running, if any.
+    workers: Vec<Option<usize>>,
+}
+
+/// A test of a [Dashboard].
+#[derive(Debug)]
+struct DashboardTest {
+    /// The test case.
+    id: TestId,
+    /// The phase that the test is in.
+    phase: TestPhase,
+    /// The last lines of the test's output.
+    logs: VecDeque<String>,
+}
+
+/// The phase of a test in a [Dashboard].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TestPhase {
+    /// The test is waiting for a worker.
+    Queued,
+    /// The test is running on a worker.
+    Running { worker: usize, since: Instant },
+    /// The test has completed.
+    Done {
+        status: TestStatus,
+        duration: Duration,
+    },
+}
+
+/// A shown [Dashboard], which is redrawn until it is stopped.
+#[derive(Debug)]
+pub(crate) struct DashboardHandle {
+    /// The dashboard.
+    dashboard: Arc<Dashboard>,
+    /// The thread that draws the dashboard and handles input, until it is stopped.
+    renderer: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Dashboard {
+    /// Create a new [Dashboard] of the run whose worker pool is tracked by `status`, and that is
+    /// cancelled with `cancel`.
+    pub(crate) fn new(status: Arc<WorkerStatus>, cancel: CancellationToken) -> Self {
+        Self {
+            state: Mutex::new(DashboardState {
+                tests: Vec::new(),
+                workers: vec![None; status.workers()],
+            }),
+            status,
+            stopped: AtomicBool::new(false),
+            cancel,
+        }
+    }
+
+    /// Shows the dashboard on the terminal, capturing the log events of the run until it is
+    /// stopped.
+    ///
+    /// ## Returns
+    /// - `Result<DashboardHandle>` - The shown dashboard, or Err if stdout is not a terminal.
+    pub(crate) fn show(self: Arc<Self>) -> Result<DashboardHandle> {
+        ensure!(
+            io::stdout().is_terminal(),
+            "`--tui` requires stdout to be a terminal"
+        );
+        *EVENTS.lock().expect("Poisoned lock") = Some(VecDeque::new());
+        let terminal = ratatui::init();
+        let dashboard = self.clone();
+        let renderer = tokio::task::spawn_blocking(move || dashboard.render_loop(terminal));
+        Ok(DashboardHandle {
+            dashboard: self,
+            renderer: Some(renderer),
+        })
+    }
+
+    /// Adds a scheduled test to the dashboard, as queued.
+    ///
+    /// ## Returns
+    /// - `usize` - The index of the test within the dashboard.
+    pub(crate) fn schedule(&self, id: TestId) -> usize {
+        let mut state = self.state.lock().expect("Poisoned lock");
+        state.tests.push(DashboardTest {
+            id,
+            phase: TestPhase::Queued,
+            logs: VecDeque::new(),
+        });
+        state.tests.len() - 1
+    }
+
+    /// Records that a test has started on the first idle worker.
+    pub(crate) fn start(&self, test: usize) {
+        let mut state = self.state.lock().expect("Poisoned lock");
+        let worker = match state.workers.iter().position(Option::is_none) {
+            Some(worker) => worker,
+            None => {
+                state.workers.push(None);
+                state.workers.len() - 1
+            }
+        };
+        state.workers[worker] = Some(test);
+        state.tests[test].phase = TestPhase::Running {
+            worker,
+            since: Instant::now(),
+        };
+    }
+
+    /// Returns a channel that appends the lines sent through it to the output of a test.
+    pub(crate) fn output(self: &Arc<Self>, test: usize) -> mpsc::UnboundedSender<String> {
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel::<String>();
+        let dashboard = self.clone();
+        tokio::spawn(async move {
+            while let Some(line) = lines_rx.recv().await {
+                let mut state = dashboard.state.lock().expect("Poisoned lock");
+                push_bounded(&mut state.tests[test].logs, line, TEST_LOG_LINES);
+            }
+        });
+        lines_tx
+    }
+
+    /// Records that a test has completed with the status after the duration, freeing its worker.
+    pub(crate) fn finish(&self, test: usize, status: TestStatus, duration: Duration) {
+        let mut state = self.state.lock().expect("Poisoned lock");
+        if let TestPhase::Running { worker, .. } = state.tests[test].phase {
+            state.workers[worker] = None;
+        }
+        state.tests[test].phase = TestPhase::Done { status, duration };
+    }
+
+    /// Draws the dashboard and handles input until the dashboard is stopped, then restores the
+    /// terminal.
+    fn render_loop(&self, mut terminal: DefaultTerminal) -> io::Result<()> {
+        let mut view = DashboardView::default();
+        let result = (|| -> io::Result<()> {
+            while !self.stopped.load(Ordering::Relaxed) {
+                terminal.draw(|frame| view.draw(frame, self))?;
+                if !event::poll(REFRESH_INTERVAL)? {
+                    continue;
+                }
+                if let Event::Key(key) = event::read()? {
+                    // Raw mode swallows Ctrl-C, which cancels the run as it would without the
+                    // dashboard, and exits on the second press.
+                    if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
+                        if self.cancel.is_cancelled() {
+                            ratatui::restore();
+                            std::process::exit(130);
+                        }
+                        self.cancel.cancel();
+                        continue;
+                    }
+                    view.handle(key);
+                }
+            }
+            Ok(())
+        })();
+        ratatui::restore();
+        result
+    }
+}
+
+impl DashboardHandle {
+    /// Stops redrawing the dashboard and restores the terminal, replaying the log events that were
+    /// captured while it was shown.
+    pub(crate) async fn stop(mut self) -> Result<()> {
+        self.dashboard.stopped.store(true, Ordering::Relaxed);
+        if let Some(renderer) = self.renderer.take() {
+            renderer.await??;
+        }
+        let events = EVENTS.lock().expect("Poisoned lock").take();
+        let mut stdout = io::stdout();
+        for event in events.into_iter().flatten() {
+            writeln!(stdout, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DashboardHandle {
+    /// Stops the dashboard if the run is aborted before it is stopped, so that the terminal is
+    /// restored.
+    fn drop(&mut self) {
+        self.dashboard.stopped.store(true, Ordering::Relaxed);
+        EVENTS.lock().expect("Poisoned lock").take();
+    }
+}
+
+/// The navigation state of the dashboard.
+#[derive(Debug, Default)]
+struct DashboardView {
+    /// The selection within the table of tests.
+    table: TableState,
+    /// The first line of the selected test's output that is shown, or `None` to follow its end.
+    log_top: Option<usize>,
+    /// The first line of the output shown when it was last drawn, were it following its end.
+    log_max_top: usize,
+    /// The number of output lines that fit in the log pane when it was last drawn.
+    log_height: usize,
+}
+
+impl DashboardView {
+    /// Handles a key press.
+    fn handle(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        let page = self.log_height.max(1);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.table.select_previous();
+                self.log_top = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.table.select_next();
+                self.log_top = None;
+            }
+            KeyCode::PageUp => {
+                let top = self.log_top.unwrap_or(self.log_max_top);
+                self.log_top = Some(top.min(self.log_max_top).saturating_sub(page));
+            }
+            KeyCode::PageDown => {
+                self.log_top = self
+                    .log_top
+                    .map(|top| top + page)
+                    .filter(|top| *top < self.log_max_top);
+            }
+            KeyCode::Home => self.log_top = Some(0),
+            KeyCode::End => self.log_top = None,
+            _ => {}
+        }
+    }
+
+    /// Draws the dashboard.
+    fn draw(&mut self, frame: &mut Frame<'_>, dashboard: &Dashboard) {
+        let state = dashboard.state.lock().expect("Poisoned lock");
+        let [header, body, logs, events, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Percentage(45),
+            Constraint::Min(6),
+            Constraint::Length(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [tests, workers] =
+            Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .areas(body);
+
+        frame.render_widget(
+            Paragraph::new(dashboard.status.render()).block(Block::bordered().title(" fpt test ")),
+            header,
+        );
+        self.draw_tests(frame, tests, &state);
+        draw_workers(frame, workers, &state);
+        self.draw_logs(frame, logs, &state);
+        draw_events(frame, events);
+        frame.render_widget(
+            Line::from("↑/↓ select test · PgUp/PgDn scroll output · Home/End jump · Ctrl-C cancel")
+                .dim(),
+            footer,
+        );
+    }
+
+    /// Draws the table of scheduled tests.
+    fn draw_tests(&mut self, frame: &mut Frame<'_>, area: Rect, state: &DashboardState) {
+        if self.table.selected().is_none() && !state.tests.is_empty() {
+            self.table.select(Some(0));
+        }
+        let rows = state.tests.iter().map(|test| {
+            let (phase, worker, time) = match test.phase {
+                TestPhase::Queued => ("QUEUED".dim(), String::new(), String::new()),
+                TestPhase::Running { worker, since } => (
+                    "RUNNING".cyan(),
+                    worker.to_string(),
+                    Elapsed(since.elapsed()).to_string(),
+                ),
+                TestPhase::Done { status, duration } => (
+                    status_span(status),
+                    String::new(),
+                    Elapsed(duration).to_string(),
+                ),
+            };
+            Row::new(vec![
+                Line::from(test.id.to_string()),
+                Line::from(phase),
+                Line::from(worker),
+                Line::from(time),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(6),
+                Constraint::Length(12),
+            ],
+        )
+        .header(Row::new(["Test", "State", "Worker", "Time"]).bold())
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(" Tests "));
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    /// Draws the output of the selected test, following its end unless it is scrolled.
+    fn draw_logs(&mut self, frame: &mut Frame<'_>, area: Rect, state: &DashboardState) {
+        let selected = self.table.selected().and_then(|i| state.tests.get(i));
+        let title = selected.map_or(" Output ".to_string(), |t| format!(" Output of {} ", t.id));
+        let lines = selected.map(|t| &t.logs);
+        let len = lines.map_or(0, VecDeque::len);
+
+        self.log_height = area.height.saturating_sub(2) as usize;
+        self.log_max_top = len.saturating_sub(self.log_height);
+        let top = self
+            .log_top
+            .map_or(self.log_max_top, |top| top.min(self.log_max_top));
+        let text = lines
+            .into_iter()
+            .flatten()
+            .skip(top)
+            .take(self.log_height)
+            .map(|line| Line::from(line.as_str()))
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title(title)),
+            area,
+        );
+    }
+}
+
+/// Draws the activity of each worker.
+fn draw_workers(frame: &mut Frame<'_>, area: Rect, state: &DashboardState) {
+    let lines = state
+        .workers
+        .iter()
+        .enumerate()
+        .map(|(worker, test)| match test.map(|i| &state.tests[i]) {
+            Some(test) => {
+                let elapsed = match test.phase {
+                    TestPhase::Running { since, .. } => Elapsed(since.elapsed()).to_string(),
+                    _ => String::new(),
+                };
+                Line::from(format!("{worker}: {} ({elapsed})", test.id.fixture))
+            }
+            None => Line::from(format!("{worker}: idle")).dim(),
+        })
+        .collect::<Vec<_>>();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Workers ")),
+        area,
+    );
+}
+
+/// Draws the most recent log events of the run.
+fn draw_events(frame: &mut Frame<'_>, area: Rect) {
+    let height = area.height.saturating_sub(2) as usize;
+    let events = EVENTS.lock().expect("Poisoned lock");
+    let lines = events
+        .iter()
+        .flatten()
+        .rev()
+        .take(height)
+        .rev()
+        .map(|event| Line::from(event.clone()))
+        .collect::<Vec<_>>();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" Events ")),
+        area,
+    );
+}
+
+/// Returns the styled label of a test's status.
+fn status_span(status: TestStatus) -> Span<'static> {
+    match status {
+        TestStatus::Pass => "PASS".green().bold(),
+        TestStatus::Fail => "FAIL".red().bold(),
+        TestStatus::Stalled => "STALLED".fg(Color::Yellow).bold(),
+        TestStatus::Timeout => "TIMEOUT".fg(Color::Yellow).bold(),
+    }
+}
+
+/// Appends a line to a buffer, dropping its oldest lines beyond `capacity`.
+fn push_bounded(lines: &mut VecDeque<String>, line: String, capacity: usize) {
+    if lines.len() == capacity {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// Returns whether or not the log events of the run are captured by a shown dashboard.
+pub(crate) fn capturing_events() -> bool {
+    EVENTS.lock().expect("Poisoned lock").is_some()
+}
+
+/// A writer of a log event, which is captured by the shown dashboard, or written to stderr if the
+/// dashboard was stopped in the meantime.
+#[derive(Debug, Default)]
+pub(crate) struct EventWriter {
+    /// The bytes of the event being written.
+    buf: Vec<u8>,
+}
+
+impl Write for EventWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventWriter {
+    fn drop(&mut self) {
+        let mut events = EVENTS.lock().expect("Poisoned lock");
+        match events.as_mut() {
+            Some(events) => {
+                for line in String::from_utf8_lossy(&self.buf).lines() {
+                    push_bounded(events, line.to_string(), EVENT_LINES);
+                }
+            }
+            None => {
+                let _ = io::stderr().write_all(&self.buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{platform::PlatformKind, program::ProgramKind};
+
+    fn test_id(fixture: &str) -> TestId {
+        TestId {
+            platform: PlatformKind::Mock,
+            program: ProgramKind::Mock,
+            fixture: fixture.to_string(),
+        }
+    }
+
+    #[test]
+    fn tracks_workers() {
+        let dashboard = Dashboard::new(Arc::new(WorkerStatus::new(2, 3)), CancellationToken::new());
+        let tests = ["a", "b", "c"].map(|f| dashboard.schedule(test_id(f)));
+        dashboard.start(tests[0]);
+        dashboard.start(tests[1]);
+        dashboard.finish(tests[0], TestStatus::Fail, Duration::from_millis(1500));
+        dashboard.start(tests[2]);
+
+        let state = dashboard.state.lock().unwrap();
+        assert_eq!(state.workers, vec![Some(tests[2]), Some(tests[1])]);
+        assert_eq!(
+            state.tests[tests[0]].phase,
+            TestPhase::Done {
+                status: TestStatus::Fail,
+                duration: Duration::from_millis(1500)
+            }
+        );
+        assert!(matches!(
+            state.tests[tests[2]].phase,
+            TestPhase::Running { worker: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn bounds_logs() {
+        let mut lines = VecDeque::new();
+        for i in 0..5 {
+            push_bounded(&mut lines, i.to_string(), 3);
+        }
+        assert_eq!(lines, ["2", "3", "4"]);
+    }
+}
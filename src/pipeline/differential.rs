@@ -0,0 +1,162 @@
+//! Contains the differential comparison of a test run, which checks that two fault proof programs
+//! agree on every fixture that they ran on the same platform: that they exit with the same status,
+//! and report the same output root. This is the cross-client equivalence of fault proofs.
+
+use crate::{
+    registry::{platform::PlatformKind, program::ProgramKind},
+    report::TestResult,
+};
+use alloy_primitives::B256;
+use cli_table::{Cell, Style, Table, TableStruct};
+use color_eyre::owo_colors::OwoColorize;
+use std::collections::BTreeMap;
+
+/// The outcome of a program on a fixture, as compared with that of the other program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ProgramOutcome {
+    /// The program.
+    pub(crate) program: ProgramKind,
+    /// The exit status of the program.
+    pub(crate) exit_status: u8,
+    /// The output root that the program reported, if it logged one.
+    pub(crate) output_root: Option<B256>,
+}
+
+/// A fixture on which two programs disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Disagreement {
+    /// The platform that both programs ran on.
+    pub(crate) platform: PlatformKind,
+    /// The name of the fixture.
+    pub(crate) fixture: String,
+    /// The outcomes of both programs.
+    pub(crate) outcomes: [ProgramOutcome; 2],
+}
+
+impl Disagreement {
+    /// Compares the outcomes of the programs that ran each fixture on each platform.
+    ///
+    /// ## Takes
+    /// - `results` - The results of the test run.
+    ///
+    /// ## Returns
+    /// - `(Vec<Self>, usize)` - The fixtures on which the programs disagree, and the number of
+    ///   fixtures that could not be compared because a program did not exit (e.g. it stalled or
+    ///   timed out).
+    pub(crate) fn find(results: &[TestResult]) -> (Vec<Self>, usize) {
+        let mut by_fixture = BTreeMap::<(String, String), Vec<&TestResult>>::new();
+        for result in results {
+            by_fixture
+                .entry((result.platform.to_string(), result.fixture.clone()))
+                .or_default()
+                .push(result);
+        }
+
+        let mut disagreements = Vec::new();
+        let mut inconclusive = 0;
+        for mut results in by_fixture.into_values() {
+            results.sort_by_cached_key(|r| r.program.to_string());
+            let [a, b] = results.as_slice() else {
+                continue;
+            };
+            let outcome = |r: &TestResult| {
+                Some(ProgramOutcome {
+                    program: r.program,
+                    exit_status: r.exit_status?,
+                    output_root: r.output_root,
+                })
+            };
+            let (Some(outcome_a), Some(outcome_b)) = (outcome(a), outcome(b)) else {
+                inconclusive += 1;
+                continue;
+            };
+
+            // Output roots are only compared if both programs reported one.
+            let roots_differ = outcome_a
+                .output_root
+                .zip(outcome_b.output_root)
+                .is_some_and(|(a, b)| a != b);
+            if outcome_a.exit_status != outcome_b.exit_status || roots_differ {
+                disagreements.push(Self {
+                    platform: a.platform,
+                    fixture: a.fixture.clone(),
+                    outcomes: [outcome_a, outcome_b],
+                });
+            }
+        }
+        (disagreements, inconclusive)
+    }
+
+    /// Renders the disagreements as a table.
+    pub(crate) fn table(disagreements: &[Self]) -> TableStruct {
+        let outcome = |o: &ProgramOutcome| {
+            let root = o.output_root.map_or("-".to_string(), |r| r.to_string());
+            format!("{}: exit {}, root {root}", o.program.cyan(), o.exit_status)
+        };
+        disagreements
+            .iter()
+            .map(|d| {
+                vec![
+                    d.fixture.clone().cell(),
+                    d.platform.magenta().to_string().cell(),
+                    outcome(&d.outcomes[0]).cell(),
+                    outcome(&d.outcomes[1]).cell(),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Fixture".cell(),
+                "Platform".cell(),
+                "Program A".cell(),
+                "Program B".cell(),
+            ])
+            .bold(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_disagreements() {
+        let result = |program, fixture: &str, exit_status, output_root| TestResult {
+            platform: PlatformKind::Native,
+            program,
+            exit_status,
+            output_root,
+            ..TestResult::mock(fixture)
+        };
+        let (op, kona) = (ProgramKind::OpProgramNative, ProgramKind::KonaNative);
+        let root = |byte| Some(B256::repeat_byte(byte));
+        let results = [
+            result(op, "agree", Some(0), root(1)),
+            result(kona, "agree", Some(0), root(1)),
+            result(op, "status", Some(0), None),
+            result(kona, "status", Some(1), None),
+            result(op, "root", Some(0), root(1)),
+            result(kona, "root", Some(0), root(2)),
+            // Output roots are only compared if both programs reported one.
+            result(op, "unreported", Some(0), root(1)),
+            result(kona, "unreported", Some(0), None),
+            result(op, "stalled", Some(0), None),
+            result(kona, "stalled", None, None),
+        ];
+
+        let (disagreements, inconclusive) = Disagreement::find(&results);
+        assert_eq!(
+            disagreements
+                .iter()
+                .map(|d| d.fixture.as_str())
+                .collect::<Vec<_>>(),
+            ["root", "status"]
+        );
+        assert_eq!(
+            disagreements[1]
+                .outcomes
+                .map(|o| (o.program, o.exit_status)),
+            [(kona, 1), (op, 0)]
+        );
+        assert_eq!(inconclusive, 1);
+    }
+}
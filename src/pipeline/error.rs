@@ -0,0 +1,181 @@
+//! Contains the [TestError] taxonomy, which classifies why a test run or a test failed, so that
+//! failures can be filtered and triaged programmatically from the summary and the JSON report.
+
+use crate::report::{TestResult, TestStatus};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display};
+
+/// The patterns that a host's output starts a panic with: Rust's, and Go's.
+const PANIC_PATTERNS: [&str; 2] = ["panicked at", "panic: "];
+
+/// The cause of a failed test, or of a test run that could not start.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case"
+)]
+pub(crate) enum TestError {
+    /// A platform or program of the matrix failed to build.
+    BuildFailure {
+        /// The repository or image of the component.
+        component: String,
+        /// The error of the build.
+        message: String,
+    },
+    /// A fixture's artifacts are missing or corrupted.
+    FixtureCorrupt {
+        /// The name of the fixture.
+        fixture: String,
+        /// The problem with the fixture, naming it.
+        message: String,
+    },
+    /// The platform failed to load or run the program, e.g. because the VM crashed.
+    VmCrash {
+        /// The error of the platform.
+        message: String,
+    },
+    /// The host program panicked, and the program did not exit with the expected status.
+    HostPanic {
+        /// The line of the host's output that reports the panic.
+        message: String,
+    },
+    /// The program exited with a status other than the fixture's expected status.
+    WrongStatus {
+        /// The expected exit status.
+        expected: u8,
+        /// The exit status of the program.
+        actual: u8,
+    },
+    /// The program exited as expected, but its output or final state is wrong, or a validator
+    /// vetoed the pass.
+    InvalidOutput {
+        /// The problem with the output.
+        message: String,
+    },
+    /// The program stopped producing output for the stall timeout, and was killed if
+    /// `--kill-stalled` is set.
+    Stalled,
+    /// The test did not complete within the `--timeout`, and was killed.
+    Timeout {
+        /// The timeout, in milliseconds.
+        after_ms: u64,
+    },
+}
+
+impl TestError {
+    /// Returns the kind of the error, as it is serialized.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::BuildFailure { .. } => "build-failure",
+            Self::FixtureCorrupt { .. } => "fixture-corrupt",
+            Self::VmCrash { .. } => "vm-crash",
+            Self::HostPanic { .. } => "host-panic",
+            Self::WrongStatus { .. } => "wrong-status",
+            Self::InvalidOutput { .. } => "invalid-output",
+            Self::Stalled => "stalled",
+            Self::Timeout { .. } => "timeout",
+        }
+    }
+
+    /// Returns the status of a test that failed with the error.
+    pub(crate) fn status(&self) -> TestStatus {
+        match self {
+            Self::Stalled => TestStatus::Stalled,
+            Self::Timeout { .. } => TestStatus::Timeout,
+            _ => TestStatus::Fail,
+        }
+    }
+
+    /// Counts the failed tests of a run by the kind of their error.
+    pub(crate) fn tally(results: &[TestResult]) -> BTreeMap<&'static str, usize> {
+        let mut tally = BTreeMap::new();
+        for error in results.iter().filter_map(|r| r.error.as_ref()) {
+            *tally.entry(error.kind()).or_default() += 1;
+        }
+        tally
+    }
+}
+
+impl Display for TestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BuildFailure { component, message } => {
+                write!(f, "Failed to build `{component}`: {message}")
+            }
+            Self::FixtureCorrupt { message, .. } => write!(f, "{message}"),
+            Self::VmCrash { message } => {
+                write!(f, "The platform failed to run the program: {message}")
+            }
+            Self::HostPanic { message } => write!(f, "The host panicked: {message}"),
+            Self::WrongStatus { expected, actual } => {
+                write!(f, "Expected exit status {expected}, got {actual}")
+            }
+            Self::InvalidOutput { message } => write!(f, "{message}"),
+            Self::Stalled => write!(
+                f,
+                "Program stalled, producing no output for the stall timeout"
+            ),
+            Self::Timeout { .. } => write!(f, "Test timed out and was killed"),
+        }
+    }
+}
+
+impl std::error::Error for TestError {}
+
+/// Returns the panic that a line of a host's output reports, if any.
+pub(crate) fn host_panic(line: &str) -> Option<String> {
+    PANIC_PATTERNS
+        .iter()
+        .any(|pattern| line.contains(pattern))
+        .then(|| line.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_failures() {
+        assert_eq!(
+            host_panic("thread 'main' panicked at bin/host/src/main.rs:10:5:"),
+            Some("thread 'main' panicked at bin/host/src/main.rs:10:5:".to_string())
+        );
+        assert_eq!(
+            host_panic("panic: runtime error: index out of range"),
+            Some("panic: runtime error: index out of range".to_string())
+        );
+        assert_eq!(host_panic("lvl=info msg=\"Validating claim\""), None);
+
+        let error = TestError::WrongStatus {
+            expected: 0,
+            actual: 1,
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "kind": "wrong-status", "expected": 0, "actual": 1 })
+        );
+        assert_eq!(
+            serde_json::to_value(TestError::Timeout { after_ms: 1000 }).unwrap(),
+            serde_json::json!({ "kind": "timeout", "after-ms": 1000 })
+        );
+        assert_eq!(error.status(), TestStatus::Fail);
+        assert_eq!(TestError::Stalled.status(), TestStatus::Stalled);
+
+        let result = |fixture: &str, error: Option<TestError>| TestResult {
+            status: error.as_ref().map_or(TestStatus::Pass, TestError::status),
+            error,
+            ..TestResult::mock(fixture)
+        };
+        let results = [
+            result("pass", None),
+            result("status", Some(error.clone())),
+            result("other-status", Some(error)),
+            result("stalled", Some(TestError::Stalled)),
+        ];
+        assert_eq!(
+            TestError::tally(&results).into_iter().collect::<Vec<_>>(),
+            [("stalled", 1), ("wrong-status", 2)]
+        );
+    }
+}
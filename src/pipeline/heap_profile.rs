@@ -0,0 +1,130 @@
+//! Contains the [HeapProfile] of a test, which profiles the allocations of a Rust-based host that
+//! is ran natively, either with jemalloc's built-in profiler or with `heaptrack`, for hunting
+//! memory regressions.
+
+use clap::ValueEnum;
+use color_eyre::{eyre::ensure, Result};
+use std::{fs, path::PathBuf, process::Command};
+
+/// The environment variables that jemalloc reads its options from: the unprefixed one, and the
+/// one read by `tikv-jemallocator`, which prefixes jemalloc's symbols.
+const JEMALLOC_CONF_VARS: [&str; 2] = ["MALLOC_CONF", "_RJEM_MALLOC_CONF"];
+
+/// The profilers that the heap of a host can be profiled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum HeapProfiler {
+    /// jemalloc's built-in profiler, enabled through `MALLOC_CONF`, which dumps a profile when the
+    /// host exits. The host must be built with jemalloc's profiling enabled.
+    Jemalloc,
+    /// `heaptrack`, which wraps the host and traces all of its allocations.
+    Heaptrack,
+}
+
+impl HeapProfiler {
+    /// Checks that the profiler is installed, before any test is profiled with it.
+    pub(crate) fn check_installed(&self) -> Result<()> {
+        if *self == Self::Heaptrack {
+            let installed = Command::new("heaptrack")
+                .arg("--version")
+                .output()
+                .is_ok_and(|output| output.status.success());
+            ensure!(
+                installed,
+                "`heaptrack` is required to profile the heap with it; install it, or use `--heap-profile jemalloc`"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The profiling of a host's heap, writing its profiles to a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HeapProfile {
+    /// The profiler.
+    pub(crate) profiler: HeapProfiler,
+    /// The directory that the profiles are written to.
+    pub(crate) dir: PathBuf,
+}
+
+impl HeapProfile {
+    /// Returns the environment variables that enable jemalloc's profiler, dumping the profile of
+    /// each process to `<dir>/jeprof.<pid>.<seq>.f.heap` when it exits.
+    pub(crate) fn jemalloc_env(&self) -> Vec<(String, String)> {
+        let conf = format!(
+            "prof:true,prof_active:true,prof_final:true,prof_prefix:{}",
+            self.dir.join("jeprof").display()
+        );
+        JEMALLOC_CONF_VARS
+            .iter()
+            .map(|var| (var.to_string(), conf.clone()))
+            .collect()
+    }
+
+    /// Returns the arguments that wrap a command with `heaptrack`, writing its profile to
+    /// `<dir>/heaptrack.*`.
+    pub(crate) fn heaptrack_args(&self) -> Vec<String> {
+        vec![
+            "--output".to_string(),
+            self.dir.join("heaptrack").display().to_string(),
+        ]
+    }
+
+    /// Prepares the profile directory, removing the profiles of an earlier attempt.
+    pub(crate) fn prepare(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        Ok(())
+    }
+
+    /// Returns the profiles that were written to the profile directory.
+    pub(crate) fn profiles(&self) -> Result<Vec<PathBuf>> {
+        let mut profiles = fs::read_dir(&self.dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        profiles.sort();
+        Ok(profiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{self, OutputLogs, ProcessOptions};
+
+    #[tokio::test]
+    async fn inject_jemalloc_profiling() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = HeapProfile {
+            profiler: HeapProfiler::Jemalloc,
+            dir: dir.path().join("heap-profile"),
+        };
+        profile.prepare().unwrap();
+        fs::write(profile.dir.join("jeprof.1.0.f.heap"), "").unwrap();
+        // The profiles of an earlier attempt are removed.
+        profile.prepare().unwrap();
+        assert!(profile.profiles().unwrap().is_empty());
+
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c")
+            .arg("echo \"$_RJEM_MALLOC_CONF\" > \"${MALLOC_CONF##*prof_prefix:}.1.0.f.heap\"");
+        let opts = ProcessOptions {
+            heap_profile: Some(profile.clone()),
+            ..Default::default()
+        };
+        process::run_supervised(cmd, &opts, &OutputLogs::new(dir.path(), ""))
+            .await
+            .unwrap();
+
+        let profiles = profile.profiles().unwrap();
+        assert_eq!(profiles, vec![profile.dir.join("jeprof.1.0.f.heap")]);
+        assert_eq!(
+            fs::read_to_string(&profiles[0]).unwrap().trim(),
+            format!(
+                "prof:true,prof_active:true,prof_final:true,prof_prefix:{}/jeprof",
+                profile.dir.display()
+            )
+        );
+    }
+}
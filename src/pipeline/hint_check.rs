@@ -0,0 +1,137 @@
+//! Contains the hint check of a test run, which checks that fault proof programs do not depend on
+//! the hint channel for correctness. Each disk-backed test on an FPVM is ran again with the
+//! builtin preimage server, which acknowledges hints without acting on them and serves every
+//! preimage from the witness database, and its outcome is compared with that of the test, whose
+//! hints were routed to the program's host.
+
+use crate::{registry::program::ProgramKind, report::TestResult};
+use alloy_primitives::B256;
+use cli_table::{Cell, Style, Table, TableStruct};
+use color_eyre::owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The outcome of a test's execution with its hints ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct HintlessOutcome {
+    /// The exit status of the program, or `None` if it stalled.
+    pub(crate) exit_status: Option<u8>,
+    /// The output root that the program reported, if it logged one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) output_root: Option<B256>,
+}
+
+impl HintlessOutcome {
+    /// Returns whether or not the outcome diverges from that of the test with its hints acted
+    /// upon. Output roots are only compared if both executions reported one.
+    pub(crate) fn diverges(&self, result: &TestResult) -> bool {
+        let roots_differ = self
+            .output_root
+            .zip(result.output_root)
+            .is_some_and(|(a, b)| a != b);
+        self.exit_status != result.exit_status || roots_differ
+    }
+
+    /// Renders the hint check of each program as a table, with the fixtures on which the program
+    /// diverged when its hints were ignored.
+    ///
+    /// ## Takes
+    /// - `results` - The results of the test run.
+    ///
+    /// ## Returns
+    /// - `(TableStruct, usize)` - The table, and the number of tests that diverged.
+    pub(crate) fn table(results: &[TestResult]) -> (TableStruct, usize) {
+        let mut by_program = BTreeMap::<String, (ProgramKind, usize, Vec<&TestResult>)>::new();
+        for result in results {
+            let Some(hintless) = result.hintless.as_ref() else {
+                continue;
+            };
+            let (_, checked, diverged) = by_program.entry(result.program.to_string()).or_insert((
+                result.program,
+                0,
+                Vec::new(),
+            ));
+            *checked += 1;
+            if hintless.diverges(result) {
+                diverged.push(result);
+            }
+        }
+
+        let divergences = by_program.values().map(|(_, _, d)| d.len()).sum();
+        let table = by_program
+            .into_values()
+            .map(|(program, checked, diverged)| {
+                let fixtures = diverged
+                    .iter()
+                    .map(|r| format!("{} ({})", r.fixture, r.platform))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                vec![
+                    program.cyan().to_string().cell(),
+                    checked.cell(),
+                    if diverged.is_empty() {
+                        "0".green().to_string().cell()
+                    } else {
+                        diverged.len().red().to_string().cell()
+                    },
+                    fixtures.cell(),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Program".cell(),
+                "Checked".cell(),
+                "Diverged".cell(),
+                "Fixtures".cell(),
+            ])
+            .bold(true);
+        (table, divergences)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_hint_divergences() {
+        let result = |program, fixture: &str, exit_status, output_root, hintless| TestResult {
+            program,
+            exit_status,
+            output_root,
+            hintless,
+            ..TestResult::mock(fixture)
+        };
+        let hintless = |exit_status, output_root| {
+            Some(HintlessOutcome {
+                exit_status,
+                output_root,
+            })
+        };
+        let (op, kona) = (ProgramKind::OpProgramMips, ProgramKind::KonaRiscv);
+        let root = |byte| Some(B256::repeat_byte(byte));
+        let results = [
+            result(op, "agree", Some(0), root(1), hintless(Some(0), root(1))),
+            result(op, "status", Some(0), None, hintless(Some(1), None)),
+            // Output roots are only compared if both executions reported one.
+            result(
+                kona,
+                "unreported",
+                Some(0),
+                root(1),
+                hintless(Some(0), None),
+            ),
+            result(kona, "root", Some(0), root(1), hintless(Some(0), root(2))),
+            result(kona, "stalled", Some(0), None, hintless(None, None)),
+            // Tests that were not checked are not counted.
+            result(kona, "unchecked", Some(1), None, None),
+        ];
+
+        assert!(!results[0].hintless.unwrap().diverges(&results[0]));
+        assert!(results[1].hintless.unwrap().diverges(&results[1]));
+        assert!(!results[2].hintless.unwrap().diverges(&results[2]));
+        let (_, divergences) = HintlessOutcome::table(&results);
+        assert_eq!(divergences, 3);
+    }
+}
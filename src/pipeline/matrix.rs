@@ -0,0 +1,146 @@
+//! Contains the [TestMatrix], the table of definitions that a test run is constructed from. The
+//! [RunnableTest]s of the run share a single [TestMatrix], and refer to their platform, program,
+//! and fixture within it, rather than holding their own copies of the definitions.
+
+use super::{queue::TestJob, rate_limit::TokenBucket, runnable::RunnableTest, FixtureGenesis};
+use crate::{
+    cli::TestConfig,
+    fixture::{FixtureMetadata, TestFixture},
+    registry::{
+        platform::PlatformKind,
+        program::{ProgramHostInputs, ProgramHostSource},
+        PlatformAndPrograms,
+    },
+};
+use color_eyre::{eyre::eyre, Result};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+/// The definitions of a test run.
+#[derive(Debug)]
+pub(crate) struct TestMatrix {
+    /// The test configuration.
+    pub(crate) cfg: TestConfig,
+    /// The platforms of the run, and their programs.
+    pub(crate) platforms: Vec<PlatformAndPrograms>,
+    /// The fixtures of the run.
+    pub(crate) fixtures: Vec<MatrixFixture>,
+    /// The index of each platform within [Self::platforms].
+    platform_index: HashMap<PlatformKind, usize>,
+    /// The index of each fixture directory within [Self::fixtures].
+    fixture_index: HashMap<PathBuf, usize>,
+    /// The token buckets that pace the requests to each RPC across all workers, by URL.
+    rpc_limiters: HashMap<String, Arc<TokenBucket>>,
+}
+
+/// A fixture of a [TestMatrix].
+#[derive(Debug)]
+pub(crate) struct MatrixFixture {
+    /// The test fixture metadata.
+    pub(crate) metadata: FixtureMetadata,
+    /// The inputs for the fixture's test cases.
+    pub(crate) inputs: ProgramHostInputs,
+}
+
+impl TestMatrix {
+    /// Creates a new [TestMatrix].
+    ///
+    /// ## Takes
+    /// - `cfg` - The test configuration.
+    /// - `platforms` - The platforms of the run, and their programs.
+    /// - `fixtures` - The fixtures of the run, and their directories.
+    /// - `decompressed` - The scratch directories that the compressed fixtures were decompressed
+    ///   into, by fixture directory.
+    /// - `genesis` - The parsed genesis files of the fixtures, by fixture directory.
+    pub(crate) fn new<'a>(
+        cfg: TestConfig,
+        platforms: Vec<PlatformAndPrograms>,
+        fixtures: impl IntoIterator<Item = (&'a PathBuf, &'a TestFixture)>,
+        decompressed: &HashMap<PathBuf, PathBuf>,
+        genesis: &HashMap<PathBuf, FixtureGenesis>,
+    ) -> Self {
+        let platform_index = platforms
+            .iter()
+            .enumerate()
+            .map(|(i, platform)| (platform.vm_kind, i))
+            .collect();
+
+        let mut fixture_index = HashMap::new();
+        let fixtures = fixtures
+            .into_iter()
+            .enumerate()
+            .map(|(i, (fixture_dir, fixture))| {
+                fixture_index.insert(fixture_dir.clone(), i);
+                let data_dir = decompressed.get(fixture_dir).unwrap_or(fixture_dir);
+                MatrixFixture {
+                    metadata: fixture.metadata.clone(),
+                    inputs: ProgramHostInputs {
+                        fixture_inputs: fixture.inputs.clone(),
+                        rollup_cfg_path: fixture_dir.join("rollup.json"),
+                        genesis_path: data_dir.join("genesis.json"),
+                        chain_config_path: genesis
+                            .get(fixture_dir)
+                            .map(|genesis| genesis.chain_config_path.clone()),
+                        source: cfg.rpc_source().unwrap_or(ProgramHostSource::Disk {
+                            path: data_dir.join("witness-db"),
+                        }),
+                    },
+                }
+            })
+            .collect();
+
+        // RPCs that are shared by multiple sources share their rate limit.
+        let rpc_limiters = cfg
+            .rpc_rate_limit
+            .map(|rps| {
+                [&cfg.l1_rpc, &cfg.l1_beacon_rpc, &cfg.l2_rpc]
+                    .into_iter()
+                    .flatten()
+                    .map(|url| (url.clone(), Arc::new(TokenBucket::new(rps))))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            cfg,
+            platforms,
+            fixtures,
+            platform_index,
+            fixture_index,
+            rpc_limiters,
+        }
+    }
+
+    /// Returns the token bucket that paces the requests to the RPC, if `--rpc-rate-limit` is set.
+    pub(crate) fn rpc_limiter(&self, url: &str) -> Option<Arc<TokenBucket>> {
+        self.rpc_limiters.get(url).cloned()
+    }
+
+    /// Constructs the [RunnableTest] of a queued [TestJob].
+    ///
+    /// ## Takes
+    /// - `job` - The queued test case.
+    ///
+    /// ## Returns
+    /// - `Result<RunnableTest>` - Errors if the job's platform, program, or fixture is not within
+    ///   the matrix.
+    pub(crate) fn runnable_test(self: &Arc<Self>, job: &TestJob) -> Result<RunnableTest> {
+        let platform = *self
+            .platform_index
+            .get(&job.platform)
+            .ok_or_else(|| eyre!("Unknown platform `{}`", job.platform))?;
+        if !self.platforms[platform].programs.contains_key(&job.program) {
+            return Err(eyre!("Unknown program `{}`", job.program));
+        }
+        let fixture = *self
+            .fixture_index
+            .get(&job.fixture)
+            .ok_or_else(|| eyre!("Unknown fixture {}", job.fixture.display()))?;
+
+        Ok(RunnableTest::new(
+            self.clone(),
+            platform,
+            job.program,
+            fixture,
+        ))
+    }
+}
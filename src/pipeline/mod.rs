@@ -2,28 +2,170 @@
 
 use crate::{
     cli::TestConfig,
-    fixture::TestFixture,
-    registry::{
-        program::{ProgramHostInputs, ProgramHostSource},
-        PlatformAndPrograms,
+    executor::ComponentBuild,
+    fixture::{FixtureDir, TestFixture},
+    genesis::GenesisInfo,
+    output::{self, OutputMode},
+    registry::{hooks::PlatformHook, PlatformAndPrograms, FP_REGISTRY},
+    report::{
+        history::{self, TestHistory},
+        metrics, signing,
+        stream::ReportStream,
+        summary::{MatrixBreakdown, QueueStats, RunSummary},
+        TestId, TestResult, TestStatus,
     },
+    state::StateDir,
+    units::Elapsed,
 };
-use color_eyre::{eyre::eyre, owo_colors::OwoColorize, Result};
-use indicatif::{HumanDuration, MultiProgress, ProgressBar, ProgressStyle};
-use itertools::Itertools;
-use runnable::RunnableTest;
+use affinity::CpuPinning;
+use alloy_primitives::hex;
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    owo_colors::OwoColorize,
+    Result,
+};
+use dashboard::Dashboard;
+use differential::Disagreement;
+use error::TestError;
+use hint_check::HintlessOutcome;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use matrix::TestMatrix;
+use quarantine::Quarantine;
+use queue::{TestJob, TestQueue};
+use status::WorkerStatus;
 use std::{
+    collections::{HashMap, HashSet},
     fs,
+    path::{Path, PathBuf},
     sync::Arc,
-    time::{self, Duration},
+    time::{Duration, Instant},
 };
+use tempfile::TempDir;
 use tokio::{
     sync::{Mutex, Semaphore},
     task::JoinSet,
 };
-use tracing::info;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use witness_reads::WitnessReads;
+
+/// The `--json` destination that writes the structured results to stdout.
+pub(crate) const JSON_STDOUT: &str = "-";
+
+/// The number of tests per worker that are constructed and scheduled ahead of time, while the
+/// rest of the tests wait in the [TestQueue].
+const SCHEDULING_WINDOW_FACTOR: usize = 4;
+
+/// The directory within the scratch directory that decompressed fixtures are kept in between runs.
+const DECOMPRESSED_CACHE_DIR: &str = "decompressed";
 
+mod affinity;
+pub(crate) mod chaos;
+pub(crate) mod dashboard;
+mod differential;
+pub(crate) mod error;
+pub(crate) mod heap_profile;
+pub(crate) mod hint_check;
+mod matrix;
+pub(crate) mod partition;
+pub(crate) mod quarantine;
+pub(crate) mod queue;
+pub(crate) mod rate_limit;
+pub(crate) mod retention;
 mod runnable;
+mod schedule;
+mod status;
+pub(crate) mod upload;
+mod validate;
+pub(crate) mod witness_reads;
+
+/// Runs the tests selected by the configuration against its matrix, from the setup of the
+/// [TestPipeline] to its teardown.
+///
+/// ## Takes
+/// - `cfg` - The test configuration.
+/// - `cancel` - The token that cancels the run. A run cancelled during its setup fails; otherwise
+///   the completed tests are reported, and the pipeline is torn down.
+///
+/// ## Returns
+/// - `Result<usize>` - The number of tests that did not pass, excluding quarantined tests, and
+///   including the tests that were not run if the run was cancelled.
+pub(crate) async fn run_tests(cfg: &TestConfig, cancel: CancellationToken) -> Result<usize> {
+    let matrix = FP_REGISTRY.resolve_matrix(Some(cfg))?;
+    let mut pipeline = TestPipeline::new(cfg, matrix);
+    pipeline.cancel_on(cancel.clone());
+    let pipeline = tokio::select! {
+        pipeline = pipeline.setup() => pipeline?,
+        _ = cancel.cancelled() => bail!("The run was cancelled during its setup"),
+    };
+    let failures = pipeline.run().await?;
+    pipeline.teardown().await?;
+    Ok(failures)
+}
+
+/// Runs a single test case, from the setup of its [TestPipeline] to its teardown, for embedding the
+/// test runner in other tooling (e.g. `fpt rpc`). Unlike [run_tests], the result is returned rather
+/// than printed or reported, and the setup only draws progress bars if the [OutputMode] allows it.
+///
+/// ## Takes
+/// - `cfg` - The test configuration, whose matrix and test selection are narrowed to the test case.
+/// - `id` - The test case to run.
+/// - `cancel` - The token that cancels the test case, killing its processes.
+///
+/// ## Returns
+/// - `Result<TestResult>` - The result of the test case. Errors if the test case is not within the
+///   tests directory, or if it was cancelled.
+pub(crate) async fn run_single(
+    cfg: &TestConfig,
+    id: &TestId,
+    cancel: CancellationToken,
+) -> Result<TestResult> {
+    let cfg = TestConfig {
+        test: Some(glob::Pattern::escape(&id.to_string())),
+        vm: Some(vec![id.platform]),
+        program: Some(vec![id.program]),
+        all_vms: false,
+        all_programs: false,
+        all: false,
+        partition: None,
+        quarantine_flaky: None,
+        differential: false,
+        ..cfg.clone()
+    };
+    let matrix = FP_REGISTRY.resolve_matrix(Some(&cfg))?;
+    let mut pipeline = TestPipeline::new(&cfg, matrix);
+    pipeline.cancel_on(cancel.clone());
+    let pipeline = tokio::select! {
+        pipeline = pipeline.setup() => pipeline?,
+        _ = cancel.cancelled() => bail!("{id} was cancelled during its setup"),
+    };
+    let result = pipeline.run_single(id).await;
+    pipeline.teardown().await?;
+    result
+}
+
+/// Cancels the token on the first Ctrl-C, so that the run winds down and reports the tests that
+/// completed, and exits on the second.
+pub(crate) async fn cancel_on_ctrl_c(cancel: CancellationToken) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    warn!(target: "test-runner", "Interrupted; cancelling the run. Press Ctrl-C again to exit immediately.");
+    cancel.cancel();
+    if tokio::signal::ctrl_c().await.is_ok() {
+        std::process::exit(130);
+    }
+}
+
+/// The genesis of a fixture, parsed once per run rather than by each of its tests.
+#[derive(Debug)]
+pub(crate) struct FixtureGenesis {
+    /// The essential fields of the genesis.
+    pub(crate) info: GenesisInfo,
+    /// The file in the scratch directory of the run that the chain configuration was written to,
+    /// for the processes of the tests to read.
+    pub(crate) chain_config_path: PathBuf,
+}
 
 /// The [TestPipeline] is a pipelined test runner, with [Self::setup], [Self::run], and [Self::teardown] stages.
 pub(crate) struct TestPipeline<'a> {
@@ -31,8 +173,19 @@ pub(crate) struct TestPipeline<'a> {
     cfg: &'a TestConfig,
     /// The matrix of platforms and programs to run tests on.
     matrix: Vec<PlatformAndPrograms>,
-    /// The tests to run.
-    tests: Option<Vec<RunnableTest>>,
+    /// The tests to run, spooled to disk.
+    tests: Option<TestQueue>,
+    /// The fixtures of the tests to run, by directory.
+    fixtures: HashMap<PathBuf, TestFixture>,
+    /// The scratch directories that the compressed fixtures are decompressed into, by fixture
+    /// directory.
+    decompressed: HashMap<PathBuf, PathBuf>,
+    /// The genesis files of the fixtures, parsed once per run, by fixture directory.
+    genesis: HashMap<PathBuf, FixtureGenesis>,
+    /// The scratch directory of the run, removed once the run is torn down.
+    scratch: Option<TempDir>,
+    /// The token that cancels the run, and with it every running test and its processes.
+    cancel: CancellationToken,
 }
 
 impl<'a> TestPipeline<'a> {
@@ -41,125 +194,555 @@ impl<'a> TestPipeline<'a> {
             cfg,
             matrix,
             tests: None,
+            fixtures: HashMap::new(),
+            decompressed: HashMap::new(),
+            genesis: HashMap::new(),
+            scratch: None,
+            cancel: CancellationToken::new(),
         }
     }
 
+    /// Cancels the run once the token is cancelled. The tests that are running are killed, the
+    /// queued tests are not started, and the completed tests are reported.
+    pub(crate) fn cancel_on(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
+    }
+
     /// Sets up the test pipeline.
     ///
     /// ## Tasks
     /// 1. Build the active platforms and programs.
-    /// 2. Gather the tests that will be ran from the active matrix.
-    /// 3. Decompress the compressed artifacts within the active fixture folders.
+    /// 2. Run the setup hooks of the active platforms.
+    /// 3. Gather the tests that will be ran from the active matrix.
+    /// 4. Decompress the compressed artifacts of the active fixtures into the scratch directory.
+    /// 5. Parse the genesis files of the active fixtures once, checking them against the fixtures'
+    ///    inputs and rollup configurations, and keeping them for the tests.
+    /// 6. Check the witness databases of the active fixtures, unless preimages are served from
+    ///    RPCs.
     pub(crate) async fn setup(mut self) -> Result<Self> {
+        // Check the tests directory before spending time on the builds.
+        self.fixture_dirs()?;
+        if self.cfg.trace_witness_reads {
+            WitnessReads::check_strace()?;
+        }
+        if let Some(profiler) = self.cfg.heap_profile {
+            profiler.check_installed()?;
+        }
+        if self.cfg.differential {
+            ensure!(
+                self.matrix.iter().all(|p| p.programs.len() == 2),
+                "`--differential` compares two programs on each platform; select them with `--program <a>,<b>`"
+            );
+        }
+
         // Attempt to build all platforms and programs in the matrix.
         self.try_build_matrix().await?;
 
+        // Prepare the environments of the platforms.
+        for platform in self.matrix.iter() {
+            platform.run_hook(PlatformHook::Setup).await?;
+        }
+
         // Gather the tests that will be ran from the active matrix.
-        self.tests = Some(self.gather_tests()?);
+        self.gather_tests()?;
 
-        // Decompress the artifacts within the active fixture folders.
+        // Decompress the artifacts of the active fixtures.
         self.decompress_fixtures().await?;
 
+        // Parse the genesis files once, before every test touches them.
+        self.parse_genesis().await?;
+
+        // Check that the witness databases are intact, before the programs fail on them.
+        if self.cfg.l1_rpc.is_none() {
+            for (fixture_dir, fixture) in self.fixtures.iter() {
+                fixture
+                    .check_witness_db(self.decompressed.get(fixture_dir).unwrap_or(fixture_dir))
+                    .map_err(|e| TestError::FixtureCorrupt {
+                        fixture: fixture.metadata.name.clone(),
+                        message: e.to_string(),
+                    })?;
+            }
+        }
+
         Ok(self)
     }
 
+    /// Lists the tests that the pipeline would run, without building or running anything.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<TestId>>` - The tests, in the order that they would be scheduled in.
+    pub(crate) fn list_tests(mut self) -> Result<Vec<TestId>> {
+        self.gather_tests()?;
+        let tests = self.tests.as_ref().ok_or(eyre!("No tests to run"))?;
+        tests
+            .jobs()?
+            .map(|job| {
+                let job = job?;
+                let fixture = self
+                    .fixtures
+                    .get(&job.fixture)
+                    .ok_or_else(|| eyre!("Unknown fixture {}", job.fixture.display()))?;
+                Ok(TestId {
+                    platform: job.platform,
+                    program: job.program,
+                    fixture: fixture.metadata.name.clone(),
+                })
+            })
+            .collect()
+    }
+
     /// Runs the tests against the active matrix.
     ///
     /// ## Tasks
     /// 1. Schedule the tests to run in parallel in a worker pool.
-    pub(crate) async fn run(self) -> Result<Self> {
-        let tests = self.tests.clone().ok_or(eyre!("No tests to run"))?;
+    ///
+    /// ## Returns
+    /// - `Result<usize>` - The number of tests that did not pass, excluding quarantined tests, and
+    ///   including the tests that were not run if the run was cancelled.
+    pub(crate) async fn run(&self) -> Result<usize> {
+        let tests = self.tests.as_ref().ok_or(eyre!("No tests to run"))?;
         let num_tests = tests.len();
+        let quarantine = Quarantine::load(&self.cfg.tests_dir.path)?;
 
         // Inform the cli of the number of tests to run.
-        println!(
+        self.print_human(format!(
             "\n\nRunning {} tests across {} platforms...",
             num_tests.blue(),
             self.matrix.len().blue()
-        );
+        ));
 
+        let start_time = Instant::now();
         let multi_progress = Arc::new(Mutex::new(MultiProgress::new()));
         let semaphore = Arc::new(Semaphore::new(self.cfg.workers));
         let mut join_set = JoinSet::new();
 
-        // Execute the tests in a parallel worker pool.
-        for case in tests {
-            let semaphore = semaphore.clone();
-            let multi_progress = multi_progress.clone();
+        // Set up the worker status line, which stays below the test progress bars.
+        let worker_status = Arc::new(WorkerStatus::new(self.cfg.workers, num_tests));
+        let status_bar = multi_progress.lock().await.add(ProgressBar::new_spinner());
+        status_bar.set_style(ProgressStyle::with_template("{wide_msg:.dim}")?);
+        let status_updater = {
+            let worker_status = worker_status.clone();
+            let status_bar = status_bar.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_millis(500));
+                loop {
+                    ticker.tick().await;
+                    status_bar.set_message(worker_status.render());
+                }
+            })
+        };
 
-            join_set.spawn(async move {
-                // Aquire a permit on the semaphore. Once the permit is aquired, we can begin
-                // running the test case.
-                let _permit = semaphore.acquire().await?;
+        // Replace the progress bars with the dashboard, if requested.
+        let dashboard = self
+            .cfg
+            .tui
+            .then(|| Arc::new(Dashboard::new(worker_status.clone(), self.cancel.clone())));
+        let mode = OutputMode::get();
+        ensure!(
+            !self.cfg.tui || mode.progress,
+            "`--tui` cannot be combined with `--no-progress` or `--quiet`, or used outside of a terminal"
+        );
+        if self.cfg.tui || !mode.progress {
+            multi_progress
+                .lock()
+                .await
+                .set_draw_target(ProgressDrawTarget::hidden());
+        }
+        let dashboard_handle = dashboard
+            .as_ref()
+            .map(|dashboard| dashboard.clone().show())
+            .transpose()?;
 
-                // Set up the progress bar.
-                let pb = multi_progress.lock().await.add(ProgressBar::new_spinner());
-                pb.set_style(
-                    ProgressStyle::with_template("{prefix:.bold} {spinner} {wide_msg}")?
-                        .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ "),
-                );
-                pb.set_prefix(format!(
-                    "{}::{}::{}",
-                    case.platform_kind.magenta(),
-                    case.program_kind.cyan(),
-                    case.fixture_meta.name.blue()
-                ));
-                pb.enable_steady_tick(Duration::from_millis(50));
-                pb.set_message("Executing test...");
-
-                let start_time = time::Instant::now();
-                let pass = case.run().await?;
-
-                // Notify the user that the test has completed.
-                pb.finish_with_message(format!(
-                    "{} {} Test took {} {} Status: {}",
-                    "Done".green().bold(),
-                    "|".black(),
-                    HumanDuration(start_time.elapsed()).magenta(),
-                    "|".black(),
-                    if pass {
-                        "PASS".green().bold().to_string()
+        // Without progress bars, each test's start and finish are printed as plain lines instead.
+        let plain_lines = !mode.progress;
+        let human_to_stderr = self.human_to_stderr();
+
+        // Set up the CPU pinning of the workers, if requested.
+        let pinning = self
+            .cfg
+            .pin_cpus
+            .then(|| CpuPinning::new(self.cfg.workers))
+            .transpose()?
+            .map(Arc::new);
+
+        // Limit the concurrency of fixtures that declare a maximum parallelism.
+        let fixture_semaphores = self
+            .fixtures
+            .values()
+            .filter_map(|f| {
+                let max_parallel = f.metadata.max_parallel?;
+                Some((
+                    f.metadata.name.clone(),
+                    Arc::new(Semaphore::new(max_parallel.max(1))),
+                ))
+            })
+            .collect::<HashMap<_, _>>();
+        let num_workers = self.cfg.workers as u32;
+
+        // Execute the tests in a parallel worker pool, constructing the queued tests as the
+        // scheduling window frees up. Failing fast cancels the remaining tests after the first
+        // failure, without cancelling the run itself.
+        let matrix = Arc::new(TestMatrix::new(
+            self.cfg.clone(),
+            self.matrix.clone(),
+            self.fixtures.iter(),
+            &self.decompressed,
+            &self.genesis,
+        ));
+        let mut jobs = tests.jobs()?;
+        let window = self.cfg.workers * SCHEDULING_WINDOW_FACTOR;
+        let signing_key = self
+            .cfg
+            .signing_key
+            .as_deref()
+            .map(signing::read_signing_key)
+            .transpose()?;
+        let mut results = ReportStream::create(&self.cfg.report, self.cfg.partition)?;
+        let tests_cancel = self.cancel.child_token();
+        loop {
+            while !tests_cancel.is_cancelled() && join_set.len() < window {
+                let Some(job) = jobs.next() else {
+                    break;
+                };
+                let mut case = matrix.runnable_test(&job?)?;
+                let dashboard_test = dashboard.as_ref().map(|dashboard| {
+                    let test = dashboard.schedule(case.id());
+                    case.forward_output(dashboard.output(test));
+                    (dashboard.clone(), test)
+                });
+                let cancel = tests_cancel.child_token();
+                case.cancel_on(cancel.clone());
+                let semaphore = semaphore.clone();
+                let fixture_semaphore = fixture_semaphores.get(&case.fixture_meta().name).cloned();
+                let pinning = pinning.clone();
+                let multi_progress = multi_progress.clone();
+                let worker_status = worker_status.clone();
+                let status_bar = status_bar.clone();
+                let scheduled = Instant::now();
+
+                join_set.spawn(async move {
+                    let acquire = async {
+                        // Aquire a permit on the fixture's semaphore, if its parallelism is
+                        // limited, before occupying a worker.
+                        let fixture_permit = match fixture_semaphore.as_ref() {
+                            Some(s) => Some(s.acquire().await?),
+                            None => None,
+                        };
+
+                        // Aquire a permit on the semaphore. Once the permit is aquired, we can
+                        // begin running the test case. Exclusive fixtures occupy every worker in
+                        // the pool.
+                        let permit = if case.fixture_meta().exclusive {
+                            semaphore.acquire_many(num_workers).await?
+                        } else {
+                            semaphore.acquire().await?
+                        };
+                        Ok::<_, color_eyre::Report>((fixture_permit, permit))
+                    };
+                    // A cancelled test that is still queued is not started.
+                    let _permits = tokio::select! {
+                        permits = acquire => permits?,
+                        _ = cancel.cancelled() => return Ok(None),
+                    };
+                    let queued = scheduled.elapsed();
+                    worker_status.start();
+                    if let Some((dashboard, test)) = dashboard_test.as_ref() {
+                        dashboard.start(*test);
+                    }
+                    let id = case.id();
+                    if plain_lines && !mode.quiet {
+                        print_line(human_to_stderr, format!("START {id}"));
+                    }
+
+                    // Set up the progress bar.
+                    let pb = multi_progress
+                        .lock()
+                        .await
+                        .insert_before(&status_bar, ProgressBar::new_spinner());
+                    pb.set_style(
+                        ProgressStyle::with_template("{prefix:.bold} {spinner} {wide_msg}")?
+                            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ "),
+                    );
+                    pb.set_prefix(id.colored());
+                    pb.enable_steady_tick(Duration::from_millis(50));
+                    pb.set_message("Executing test...");
+
+                    let slot = pinning.as_ref().map(|p| p.acquire());
+                    let result = case.run(slot.map(|(_, cpu)| cpu)).await;
+                    if let (Some(pinning), Some((slot, _))) = (pinning.as_ref(), slot) {
+                        pinning.release(slot);
+                    }
+                    // A cancelled test has no result.
+                    if cancel.is_cancelled() {
+                        worker_status.abandon();
+                        pb.finish_and_clear();
+                        return Ok(None);
+                    }
+                    let mut result = result?;
+                    result.queue_ms = queued.as_millis() as u64;
+                    worker_status.finish(result.status == TestStatus::Pass);
+                    if let Some((dashboard, test)) = dashboard_test.as_ref() {
+                        dashboard.finish(
+                            *test,
+                            result.status,
+                            Duration::from_millis(result.duration_ms),
+                        );
+                    }
+
+                    // Notify the user that the test has completed, pointing to the logs of failures.
+                    let logs = match case.log_path() {
+                        Some(path) if result.status != TestStatus::Pass => {
+                            format!(" {} Logs: {}", "|".black(), path.display())
+                        }
+                        _ => String::new(),
+                    };
+                    let artifacts = if case.cfg().retention().retains(result.status) {
+                        format!(
+                            " {} Artifacts: {}",
+                            "|".black(),
+                            case.artifacts_path().display()
+                        )
                     } else {
-                        "FAIL".red().bold().italic().to_string()
+                        String::new()
+                    };
+                    pb.finish_with_message(format!(
+                        "{} {} Test took {} {} Status: {}{logs}{artifacts}",
+                        "Done".green().bold(),
+                        "|".black(),
+                        Elapsed(Duration::from_millis(result.duration_ms)).magenta(),
+                        "|".black(),
+                        status_label(result.status)
+                    ));
+                    if plain_lines {
+                        let (completed, total) = worker_status.progress();
+                        print_line(
+                            human_to_stderr,
+                            mode.render(format!(
+                                "{} {id} in {} ({completed}/{total}){logs}{artifacts}",
+                                status_label(result.status),
+                                Elapsed(Duration::from_millis(result.duration_ms))
+                            )),
+                        );
                     }
+
+                    Ok::<_, color_eyre::Report>(Some(result))
+                });
+            }
+
+            let Some(result) = join_set.join_next().await else {
+                break;
+            };
+            let Some(mut result) = result?? else {
+                continue;
+            };
+            result.quarantined = quarantine.contains(&result.id());
+            let failed = result.status != TestStatus::Pass && !result.quarantined;
+            results.push(result)?;
+
+            let completed = results.results().len();
+            if failed && self.cfg.fail_fast && !tests_cancel.is_cancelled() && completed < num_tests
+            {
+                warn!(target: "test-runner", "Test failed; cancelling the {} remaining tests", num_tests - completed);
+                tests_cancel.cancel();
+            }
+        }
+        let not_run = if self.cancel.is_cancelled() {
+            num_tests - results.results().len()
+        } else {
+            0
+        };
+        if not_run > 0 {
+            warn!(target: "test-runner", "The run was cancelled; {not_run} tests were not run");
+        }
+        status_updater.abort();
+        status_bar.finish_with_message(worker_status.render());
+        if let Some(handle) = dashboard_handle {
+            handle.stop().await?;
+        }
+
+        let elapsed = start_time.elapsed();
+        self.print_human(format!(
+            "\n{}\n{}\n",
+            MatrixBreakdown::table(&MatrixBreakdown::new(results.results())).display()?,
+            RunSummary::new(results.results(), elapsed)
+        ));
+        if let Some(stats) = QueueStats::new(results.results(), self.cfg.workers, elapsed) {
+            self.print_human(format!("{stats}\n"));
+        }
+        let causes = TestError::tally(results.results());
+        if !causes.is_empty() {
+            self.print_human(format!(
+                "Failures by cause: {}\n",
+                causes
+                    .iter()
+                    .map(|(kind, count)| format!("{count} {kind}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if self.cfg.trace_witness_reads {
+            self.print_human(format!(
+                "Witness database reads:\n{}\n",
+                WitnessReads::table(results.results()).display()?
+            ));
+        }
+
+        // Compare the outcomes of the programs, if requested.
+        let disagreements = if self.cfg.differential {
+            let (disagreements, inconclusive) = Disagreement::find(results.results());
+            if inconclusive > 0 {
+                warn!(target: "test-runner", "{inconclusive} fixtures could not be compared, as a program did not exit");
+            }
+            if disagreements.is_empty() {
+                self.print_human(format!(
+                    "{}\n",
+                    "The programs agree on every fixture.".green()
                 ));
+            } else {
+                self.print_human(format!(
+                    "{}\n{}\n",
+                    format!("The programs disagree on {} fixtures:", disagreements.len()).red(),
+                    Disagreement::table(&disagreements).display()?
+                ));
+            }
+            disagreements.len()
+        } else {
+            0
+        };
 
-                Ok::<_, color_eyre::Report>(pass)
-            });
+        // Report the programs that diverged with their hints ignored, which failed those tests.
+        if self.cfg.hint_check {
+            let (table, divergences) = HintlessOutcome::table(results.results());
+            let summary = if divergences == 0 {
+                "No program diverged with its hints ignored."
+                    .green()
+                    .to_string()
+            } else {
+                format!("Programs diverged with their hints ignored on {divergences} tests:")
+                    .red()
+                    .to_string()
+            };
+            self.print_human(format!("{summary}\n{}\n", table.display()?));
         }
 
-        // Join all test tasks.
-        let mut num_passed = 0;
-        while let Some(result) = join_set.join_next().await {
-            num_passed += result?? as usize;
-        }
-        println!(
-            "{} - {} tests {}, {} tests {}.\n",
-            "Completed".bold(),
-            num_passed.to_string().blue().bold(),
-            "passed".green().bold(),
-            (num_tests - num_passed).to_string().blue().bold(),
-            "failed".red().bold()
-        );
+        // Complete the reports, if requested.
+        let report = results.finish()?;
+        for target in self.cfg.report.iter() {
+            info!(target: "test-runner", "Wrote test report to {}", target.path.display());
+        }
 
-        Ok(self)
+        // Emit the structured results, if requested.
+        match self.cfg.json.as_deref() {
+            Some(path) if path == Path::new(JSON_STDOUT) => {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            Some(path) => report.write(path)?,
+            None => {}
+        }
+
+        // Write the metrics of the tests, for tracking their performance across revisions.
+        if let Some(path) = self.cfg.metrics.as_deref() {
+            metrics::write_metrics(path, &report.results)?;
+            info!(target: "test-runner", "Wrote test metrics to {}", path.display());
+        }
+
+        // Sign the written reports, if requested.
+        if let Some(key) = signing_key.as_ref() {
+            let json = self
+                .cfg
+                .json
+                .as_deref()
+                .filter(|path| *path != Path::new(JSON_STDOUT));
+            for path in self.cfg.report.iter().map(|t| t.path.as_path()).chain(json) {
+                let signature = signing::sign(path, key)?;
+                info!(
+                    target: "test-runner",
+                    "Signed test report with key {}: {}",
+                    hex::encode(key.verifying_key().as_bytes()),
+                    signature.display()
+                );
+            }
+        }
+
+        // Record the run in the history, for flakiness scoring.
+        if !self.cfg.no_history {
+            history::record(&report)?;
+        }
+
+        // Quarantined tests are ran, but do not fail the run.
+        for result in report.results.iter() {
+            if result.quarantined && result.status != TestStatus::Pass {
+                warn!(target: "test-runner", "Ignoring the failure of quarantined test {}", result.id());
+            }
+        }
+
+        // Disagreements between the programs fail the run, even if both exited as expected, as
+        // do the tests that a cancelled run did not run.
+        Ok(report
+            .results
+            .iter()
+            .filter(|r| r.status != TestStatus::Pass && !r.quarantined)
+            .count()
+            + disagreements
+            + not_run)
+    }
+
+    /// Runs the only test of the pipeline, as [run_single] narrows it to.
+    ///
+    /// ## Returns
+    /// - `Result<TestResult>` - The result of the test case, or Err if it is not the pipeline's
+    ///   only test, or was cancelled.
+    async fn run_single(&self, id: &TestId) -> Result<TestResult> {
+        let tests = self.tests.as_ref().ok_or(eyre!("No tests to run"))?;
+        ensure!(tests.len() == 1, "No test {id} in the tests directory");
+        let job = tests.jobs()?.next().ok_or(eyre!("No tests to run"))??;
+
+        let matrix = Arc::new(TestMatrix::new(
+            self.cfg.clone(),
+            self.matrix.clone(),
+            self.fixtures.iter(),
+            &self.decompressed,
+            &self.genesis,
+        ));
+        let mut case = matrix.runnable_test(&job)?;
+        case.cancel_on(self.cancel.clone());
+        let mut result = case.run(None).await?;
+        result.quarantined = Quarantine::load(&self.cfg.tests_dir.path)?.contains(&result.id());
+        Ok(result)
+    }
+
+    /// Prints human-readable output, which is moved to stderr when the structured results are
+    /// written to stdout.
+    fn print_human(&self, msg: String) {
+        print_line(self.human_to_stderr(), OutputMode::get().render(msg));
+    }
+
+    /// Returns whether or not human-readable output goes to stderr, as the JSON report is written
+    /// to stdout.
+    fn human_to_stderr(&self) -> bool {
+        self.cfg
+            .json
+            .as_deref()
+            .is_some_and(|p| p == Path::new(JSON_STDOUT))
     }
 
     /// Cleans up the artifacts created during the test run.
     ///
     /// ## Tasks
-    /// 1. Remove all uncompressed artifacts from the active fixture folders.
+    /// 1. Remove the decompressed artifacts of the active fixtures, unless they are kept for the
+    ///    next run.
+    /// 2. Run the teardown hooks of the active platforms.
     pub(crate) async fn teardown(mut self) -> Result<()> {
-        let tests = self.tests.take().ok_or(eyre!("No tests to run"))?;
-
-        let unique_fixtures = tests
-            .iter()
-            .unique_by(|t| t.inputs.genesis_path.as_path())
-            .cloned()
+        self.tests.take().ok_or(eyre!("No tests to run"))?;
+        let scratch_dirs = self
+            .decompressed
+            .drain()
+            .map(|(_, dir)| dir)
+            .filter(|_| !self.cfg.keep_decompressed)
             .collect::<Vec<_>>();
 
         let progress_bar = {
-            let bar = ProgressBar::new(unique_fixtures.len() as u64);
+            let bar = output::progress_bar(ProgressBar::new(scratch_dirs.len() as u64));
             bar.enable_steady_tick(Duration::from_millis(50));
             bar.set_message("Cleaning up decompressed fixture artifacts...");
             bar.set_style(ProgressStyle::default_bar().template("{msg} {wide_bar} {pos}/{len}")?);
@@ -169,7 +752,7 @@ impl<'a> TestPipeline<'a> {
         let semaphore = Arc::new(Semaphore::new(self.cfg.workers));
         let mut join_set = JoinSet::new();
 
-        for test in unique_fixtures.into_iter() {
+        for scratch_dir in scratch_dirs.into_iter() {
             let semaphore = semaphore.clone();
             let progress_bar = progress_bar.clone();
 
@@ -178,10 +761,10 @@ impl<'a> TestPipeline<'a> {
                 // deleting the test fixture artifacts.
                 let _permit = semaphore.acquire().await?;
 
-                // Decompress the fixture.
-                test.teardown().await?;
+                // Delete the decompressed fixture.
+                tokio::fs::remove_dir_all(&scratch_dir).await?;
 
-                // Notify the cli that the fixture has been decompressed.
+                // Notify the cli that the fixture has been deleted.
                 progress_bar.lock().await.inc(1);
 
                 Ok::<_, color_eyre::Report>(())
@@ -196,40 +779,122 @@ impl<'a> TestPipeline<'a> {
             .lock()
             .await
             .finish_with_message("Deleted decompressed fixture artifacts");
+        if let Some(scratch) = self.scratch.take() {
+            scratch.close()?;
+        }
+
+        // Restore the environments of the platforms, running every hook even if one fails.
+        let mut failed_hooks = Vec::new();
+        for platform in self.matrix.iter() {
+            if let Err(e) = platform.run_hook(PlatformHook::Teardown).await {
+                failed_hooks.push(e.to_string());
+            }
+        }
+        ensure!(failed_hooks.is_empty(), "{}", failed_hooks.join("\n"));
 
         Ok(())
     }
 
-    /// Attempts to build all platforms and programs in the matrix.
+    /// Attempts to build all platforms and programs in the matrix. Components that are shared
+    /// between several platforms and programs are only built once, and up to `workers` components
+    /// are built in parallel.
     async fn try_build_matrix(&self) -> Result<()> {
+        let mut builds = Vec::new();
         for platform in self.matrix.iter() {
-            for (program_name, program) in platform.programs.iter() {
-                info!(target: "test-runner", "Building program: {}", program_name);
-                program.build.try_build().await?;
-            }
+            builds.extend(platform.programs.values().map(ComponentBuild::of_program));
+            builds.extend(ComponentBuild::of_platform(platform.vm_kind, &platform.vm)?);
+        }
 
-            if let Some(vm_build) = platform.vm.build.as_ref() {
-                info!(target: "test-runner", "Building platform: {}", platform.vm_kind);
-                vm_build.try_build().await?;
-            }
+        let semaphore = Arc::new(Semaphore::new(self.cfg.workers));
+        let mut join_set = JoinSet::new();
+        for group in ComponentBuild::schedule(builds) {
+            let semaphore = semaphore.clone();
+            let force = self.cfg.force_rebuild;
+
+            join_set.spawn(async move {
+                let _permit = semaphore.acquire().await?;
+
+                // Builds from the same repository share its checkout, and are ran in sequence.
+                for build in group {
+                    info!(target: "test-runner", "Building component: {}", build.key());
+                    build
+                        .run(force)
+                        .await
+                        .map_err(|e| TestError::BuildFailure {
+                            component: build.key().to_string(),
+                            message: format!("{e:#}"),
+                        })?;
+                }
+                Ok::<_, color_eyre::Report>(())
+            });
+        }
+
+        while let Some(result) = join_set.join_next().await {
+            result??;
         }
         Ok(())
     }
 
-    /// Gathers the [RunnableTest]s to execute.
-    fn gather_tests(&self) -> Result<Vec<RunnableTest>> {
-        // TODO: Custom tests dir.
-        let test_files = fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests"))?;
-        let glob = glob::Pattern::new(self.cfg.test.as_ref().unwrap_or(&"*".to_string()).as_str())?;
+    /// Returns the fixture directories within the tests directory, limited to the selected chains
+    /// if any are given.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<PathBuf>>` - The fixture directories, or Err if the tests directory does not
+    ///   exist or contains no fixtures.
+    fn fixture_dirs(&self) -> Result<Vec<PathBuf>> {
+        let tests_dir = &self.cfg.tests_dir.path;
+        ensure!(
+            tests_dir.is_dir(),
+            "Tests directory `{}` does not exist",
+            tests_dir.display()
+        );
 
-        let enabled_fixtures = test_files
+        let fixture_dirs = FixtureDir::find_all(tests_dir)?
             .into_iter()
-            .filter_map(|entry| {
-                let entry = glob
-                    .matches(entry.as_ref().ok()?.file_name().to_str()?)
-                    .then_some(entry)?;
+            .filter(|fixture_dir| {
+                self.cfg.chain.as_ref().is_none_or(|chains| {
+                    fixture_dir
+                        .chain
+                        .as_ref()
+                        .is_some_and(|chain| chains.contains(chain))
+                })
+            })
+            .map(|fixture_dir| fixture_dir.path)
+            .collect::<Vec<_>>();
+        match self.cfg.chain.as_ref() {
+            Some(chains) => ensure!(
+                !fixture_dirs.is_empty(),
+                "Tests directory `{}` contains no fixtures for the chains {} (`<chain>/<name>/fixture.toml`)",
+                tests_dir.display(),
+                chains.join(", ")
+            ),
+            None => ensure!(
+                !fixture_dirs.is_empty(),
+                "Tests directory `{}` contains no fixtures (`<name>/fixture.toml`, or `<chain>/<name>/fixture.toml`)",
+                tests_dir.display()
+            ),
+        }
+        Ok(fixture_dirs)
+    }
+
+    /// Gathers the tests to execute from the active matrix, spooling them to a [TestQueue].
+    fn gather_tests(&mut self) -> Result<()> {
+        // A pattern containing a `/` selects the test cases by their ids, and any other selects them
+        // by the names of their fixtures.
+        let pattern = self.cfg.test.as_deref().unwrap_or("*");
+        let glob = glob::Pattern::new(pattern)?;
+        let by_id = pattern.contains(TestId::SEPARATOR);
 
-                let fixture_path = entry.ok()?.path();
+        let enabled_fixtures = self
+            .fixture_dirs()?
+            .into_iter()
+            .filter(|fixture_path| {
+                fixture_path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| by_id || glob.matches(name))
+            })
+            .filter_map(|fixture_path| {
                 let fixture = toml::from_str::<TestFixture>(
                     &fs::read_to_string(fixture_path.join("fixture.toml")).ok()?,
                 )
@@ -238,47 +903,124 @@ impl<'a> TestPipeline<'a> {
             })
             .collect::<Vec<_>>();
 
-        // Create the test case runners for enabled tests.
-        let mut tests = Vec::new();
-        for platform in self.matrix.iter() {
-            for (program_kind, program_def) in platform.programs.iter() {
-                for (fixture_path, fixture) in enabled_fixtures.iter() {
-                    let inputs = ProgramHostInputs {
-                        fixture_inputs: fixture.inputs.clone(),
-                        rollup_cfg_path: fixture_path.join("rollup.json"),
-                        genesis_path: fixture_path.join("genesis.json"),
-                        source: ProgramHostSource::Disk {
-                            path: fixture_path.join("witness-db"),
-                        },
-                    };
-
-                    // TODO: Lift the arc's, terrible code I wrote at 2am.
-                    tests.push(RunnableTest::new(
-                        Arc::new(fixture.metadata.clone()),
-                        Arc::new(inputs),
-                        platform.vm_kind,
-                        Arc::new(platform.clone()),
-                        *program_kind,
-                        Arc::new(program_def.clone()),
-                    ));
-                }
+        // Test cases are identified by the names of their fixtures, which must be unique across
+        // chains.
+        let mut names = HashMap::new();
+        for (fixture_path, fixture) in enabled_fixtures.iter() {
+            if let Some(other) = names.insert(&fixture.metadata.name, fixture_path) {
+                bail!(
+                    "Fixture `{}` is defined in both `{}` and `{}`",
+                    fixture.metadata.name,
+                    other.display(),
+                    fixture_path.display()
+                );
             }
         }
 
-        Ok(tests)
+        // Quarantine the test cases that have been flaky across recent runs, if requested.
+        let flaky = match self.cfg.quarantine_flaky {
+            Some(threshold) => TestHistory::from_reports(&history::load()?)
+                .into_iter()
+                .filter(|h| h.flakiness() > threshold)
+                .map(|h| h.id)
+                .collect::<HashSet<_>>(),
+            None => HashSet::new(),
+        };
+
+        // Spool the enabled test cases to disk, interleaving the platforms by the resources that
+        // their tests are bound by.
+        let jobs = schedule::interleave(self.matrix.iter().map(|platform| {
+            let tests = platform
+                .programs
+                .iter()
+                .flat_map(|(program_kind, program)| {
+                    enabled_fixtures
+                    .iter()
+                    .filter(|(_, fixture)| {
+                        // Skip the test cases outside of the shard of the matrix being ran, if any.
+                        self.cfg.partition.is_none_or(|partition| {
+                            partition.contains(
+                                &fixture.metadata.name,
+                                platform.vm_kind,
+                                *program_kind,
+                            )
+                        })
+                    })
+                    .filter(|(_, fixture)| {
+                        let id = TestId {
+                            platform: platform.vm_kind,
+                            program: *program_kind,
+                            fixture: fixture.metadata.name.clone(),
+                        };
+                        if by_id && !glob.matches(&id.to_string()) {
+                            return false;
+                        }
+                        if flaky.contains(&id) {
+                            warn!(target: "test-runner", "Skipping flaky test {id}");
+                            return false;
+                        }
+
+                        // Skip the test cases whose fixture is incompatible with the program's
+                        // revision.
+                        if let Err(e) = fixture
+                            .metadata
+                            .check_program_rev(*program_kind, &program.build.rev)
+                        {
+                            warn!(target: "test-runner", "Skipping incompatible test {id}: {e}");
+                            return false;
+                        }
+                        true
+                    })
+                    .map(|(fixture_path, _)| TestJob {
+                        fixture: fixture_path.clone(),
+                        platform: platform.vm_kind,
+                        program: *program_kind,
+                    })
+                });
+            (platform.vm.resource, tests)
+        }));
+        let queue = TestQueue::spool(jobs)?;
+
+        self.fixtures = enabled_fixtures
+            .into_iter()
+            .filter(|(fixture_path, _)| queue.fixtures().contains(fixture_path))
+            .collect();
+        self.tests = Some(queue);
+        Ok(())
     }
 
-    /// Decompresses the fixtures within the test directory.
-    async fn decompress_fixtures(&self) -> Result<()> {
-        let tests = self.tests.as_ref().ok_or(eyre!("No tests to run"))?;
-        let unique_fixtures = tests
-            .iter()
-            .unique_by(|t| t.inputs.genesis_path.as_path())
-            .cloned()
+    /// Decompresses the compressed fixtures of the run into the scratch directory, leaving the
+    /// tests directory untouched. Each run decompresses into its own directory, unless the
+    /// decompressed fixtures are kept, in which case they are shared between runs.
+    async fn decompress_fixtures(&mut self) -> Result<()> {
+        let compressed = self
+            .fixtures
+            .keys()
+            .filter(|fixture_dir| TestFixture::is_compressed(fixture_dir))
             .collect::<Vec<_>>();
+        if compressed.is_empty() {
+            return Ok(());
+        }
+
+        let run_dir = if self.cfg.keep_decompressed {
+            let scratch_dir = StateDir::get().scratch_dir();
+            fs::create_dir_all(&scratch_dir)?;
+            scratch_dir.join(DECOMPRESSED_CACHE_DIR)
+        } else {
+            self.run_dir()?
+        };
+        self.decompressed = compressed
+            .into_iter()
+            .map(|fixture_dir| {
+                let name = fixture_dir
+                    .strip_prefix(&self.cfg.tests_dir.path)
+                    .unwrap_or_else(|_| Path::new(fixture_dir.file_name().unwrap_or_default()));
+                (fixture_dir.clone(), run_dir.join(name))
+            })
+            .collect();
 
         let progress_bar = {
-            let bar = ProgressBar::new(unique_fixtures.len() as u64);
+            let bar = output::progress_bar(ProgressBar::new(self.decompressed.len() as u64));
             bar.enable_steady_tick(Duration::from_millis(50));
             bar.set_message("Decompressing active fixtures...");
             bar.set_style(ProgressStyle::default_bar().template("{msg} {wide_bar} {pos}/{len}")?);
@@ -288,7 +1030,9 @@ impl<'a> TestPipeline<'a> {
         let semaphore = Arc::new(Semaphore::new(self.cfg.workers));
         let mut join_set = JoinSet::new();
 
-        for test in unique_fixtures.into_iter() {
+        let reuse = self.cfg.keep_decompressed;
+        for (fixture_dir, scratch_dir) in self.decompressed.iter() {
+            let (fixture_dir, scratch_dir) = (fixture_dir.clone(), scratch_dir.clone());
             let semaphore = semaphore.clone();
             let progress_bar = progress_bar.clone();
 
@@ -298,7 +1042,7 @@ impl<'a> TestPipeline<'a> {
                 let _permit = semaphore.acquire().await?;
 
                 // Decompress the fixture.
-                test.decompress_fixture().await?;
+                TestFixture::decompress(&fixture_dir, &scratch_dir, reuse).await?;
 
                 // Notify the cli that the fixture has been decompressed.
                 progress_bar.lock().await.inc(1);
@@ -317,4 +1061,234 @@ impl<'a> TestPipeline<'a> {
             .finish_with_message("Decompressed fixtures");
         Ok(())
     }
+
+    /// Returns the scratch directory of the run, creating it if it does not exist yet.
+    fn run_dir(&mut self) -> Result<PathBuf> {
+        if let Some(scratch) = self.scratch.as_ref() {
+            return Ok(scratch.path().to_path_buf());
+        }
+        let scratch_dir = StateDir::get().scratch_dir();
+        fs::create_dir_all(&scratch_dir)?;
+        let run_dir = tempfile::Builder::new()
+            .prefix("run-")
+            .tempdir_in(&scratch_dir)?;
+        let path = run_dir.path().to_path_buf();
+        self.scratch = Some(run_dir);
+        Ok(path)
+    }
+
+    /// Parses the genesis files of the active fixtures once, and checks that they agree with the
+    /// chain IDs of the fixtures' inputs, and with the fork times of their rollup configurations.
+    /// The parsed genesis of each fixture is kept for its tests, with its chain configuration
+    /// written to the scratch directory of the run.
+    async fn parse_genesis(&mut self) -> Result<()> {
+        let run_dir = self.run_dir()?;
+        let mut join_set = JoinSet::new();
+        for (fixture_dir, fixture) in self.fixtures.iter() {
+            let genesis_path = self
+                .decompressed
+                .get(fixture_dir)
+                .unwrap_or(fixture_dir)
+                .join("genesis.json");
+            let rollup_path = fixture_dir.join("rollup.json");
+            let fixture_dir = fixture_dir.clone();
+            let fixture = fixture.clone();
+            let run_dir = run_dir.clone();
+            join_set.spawn_blocking(move || {
+                let check = || {
+                    let genesis = GenesisInfo::parse(&genesis_path)?;
+                    ensure!(
+                        genesis.chain_id == fixture.inputs.l2_chain_id,
+                        "The genesis is for chain {}, but the fixture's inputs are for chain {}",
+                        genesis.chain_id,
+                        fixture.inputs.l2_chain_id
+                    );
+                    genesis.check_rollup_config(&fs::read(&rollup_path)?)?;
+                    if let Some(interop) = fixture.inputs.interop.as_ref() {
+                        interop.check(fixture.inputs.l2_chain_id)?;
+                    }
+                    Ok(genesis)
+                };
+                let genesis =
+                    check().map_err(|e: color_eyre::Report| TestError::FixtureCorrupt {
+                        fixture: fixture.metadata.name.clone(),
+                        message: e.to_string(),
+                    })?;
+                let chain_config_path = genesis.write_chain_config(&run_dir)?;
+                Ok::<_, color_eyre::Report>((
+                    fixture_dir,
+                    FixtureGenesis {
+                        info: genesis,
+                        chain_config_path,
+                    },
+                ))
+            });
+        }
+
+        while let Some(result) = join_set.join_next().await {
+            let (fixture_dir, genesis) = result??;
+            debug!(target: "test-runner", "Fixture {} is on chain {}, and schedules the forks {:?}", fixture_dir.display(), genesis.info.chain_id, genesis.info.fork_times);
+            self.genesis.insert(fixture_dir, genesis);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the colored label of a test status.
+fn status_label(status: TestStatus) -> String {
+    match status {
+        TestStatus::Pass => "PASS".green().bold().to_string(),
+        TestStatus::Fail => "FAIL".red().bold().italic().to_string(),
+        TestStatus::Stalled => "STALLED".yellow().bold().italic().to_string(),
+        TestStatus::Timeout => "TIMEOUT".yellow().bold().italic().to_string(),
+    }
+}
+
+/// Prints a line of human-readable output to stdout, or stderr if `to_stderr` is set.
+fn print_line(to_stderr: bool, msg: String) {
+    if to_stderr {
+        eprintln!("{msg}");
+    } else {
+        println!("{msg}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::{Cli, CliSubcommand},
+        fixture::{FixtureInputs, FixtureMetadata},
+        registry::{
+            platform::PlatformKind, program::ProgramKind, BuildInstructions, BuildStep,
+            FPPDefinition, PlatformDefinition,
+        },
+        report::TestReport,
+    };
+    use clap::Parser;
+    use std::path::Path;
+
+    /// Writes a synthetic fixture whose mock program exits with `exit_status`.
+    fn write_fixture(dir: &Path, name: &str, exit_status: u8, expected_status: u8) {
+        let fixture = TestFixture {
+            metadata: FixtureMetadata {
+                name: name.to_string(),
+                expected_status,
+                exclusive: name == "exclusive",
+                ..Default::default()
+            },
+            inputs: FixtureInputs {
+                l2_block_number: exit_status as u64,
+                ..Default::default()
+            },
+            reference: None,
+        };
+        let fixture_dir = dir.join(name);
+        fs::create_dir_all(fixture_dir.join("witness-db")).unwrap();
+        fs::write(fixture_dir.join("genesis.json"), "{}").unwrap();
+        fs::write(
+            fixture_dir.join("fixture.toml"),
+            toml::to_string(&fixture).unwrap(),
+        )
+        .unwrap();
+    }
+
+    /// Returns a matrix of the mock platform and program.
+    fn mock_matrix() -> Vec<PlatformAndPrograms> {
+        let build = BuildInstructions {
+            repo: "mock/mock".to_string(),
+            step: Some(BuildStep {
+                artifacts: [("client", "client"), ("host", "host")]
+                    .into_iter()
+                    .map(|(name, path)| (name.to_string(), PathBuf::from(path)))
+                    .collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        vec![PlatformAndPrograms {
+            vm: PlatformDefinition::default(),
+            vm_kind: PlatformKind::Mock,
+            programs: [(
+                ProgramKind::Mock,
+                FPPDefinition {
+                    build,
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+        }]
+    }
+
+    #[tokio::test]
+    async fn run_mock_pipeline() {
+        let fixtures = tempfile::tempdir().unwrap();
+        write_fixture(fixtures.path(), "passing", 0, 0);
+        write_fixture(fixtures.path(), "failing", 1, 0);
+        write_fixture(fixtures.path(), "exclusive", 1, 1);
+
+        let report = fixtures.path().join("report.json");
+        let cfg = match Cli::parse_from([
+            "fpt",
+            "test",
+            "--workers",
+            "2",
+            "--report",
+            report.to_str().unwrap(),
+            "--tests-dir",
+            fixtures.path().to_str().unwrap(),
+            "--no-history",
+            "--log-dir",
+            fixtures.path().join("logs").to_str().unwrap(),
+            "--keep",
+            "failures",
+            "--artifacts-dir",
+            fixtures.path().join("artifacts").to_str().unwrap(),
+        ])
+        .subcommand
+        {
+            Some(CliSubcommand::Test(cfg)) => cfg,
+            _ => unreachable!(),
+        };
+
+        // Skip building the matrix, which would clone the mock's repository.
+        let mut pipeline = TestPipeline::new(&cfg, mock_matrix());
+        pipeline.gather_tests().unwrap();
+        pipeline.decompress_fixtures().await.unwrap();
+        assert_eq!(pipeline.run().await.unwrap(), 1);
+        pipeline.teardown().await.unwrap();
+
+        let mut results = TestReport::read(&report)
+            .unwrap()
+            .results
+            .into_iter()
+            .map(|r| (r.fixture, r.status, r.exit_status))
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            results,
+            vec![
+                ("exclusive".to_string(), TestStatus::Pass, Some(1)),
+                ("failing".to_string(), TestStatus::Fail, Some(1)),
+                ("passing".to_string(), TestStatus::Pass, Some(0)),
+            ]
+        );
+
+        // The output of each test is persisted to its log file.
+        assert!(
+            fs::read_to_string(fixtures.path().join("logs/mock/mock/failing.log"))
+                .unwrap()
+                .starts_with("mock-host ")
+        );
+
+        // Only the artifacts of the failing test are kept, along with its output.
+        let artifacts = fixtures.path().join("artifacts/mock/mock");
+        assert!(artifacts.join("failing/output.log").is_file());
+        assert!(!artifacts.join("passing").exists());
+        assert_eq!(fs::read_dir(&artifacts).unwrap().count(), 1);
+
+        // Uncompressed fixtures are left intact by the teardown.
+        assert!(fixtures.path().join("passing/genesis.json").exists());
+    }
 }
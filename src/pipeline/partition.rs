@@ -0,0 +1,99 @@
+//! Contains the [Partition] type, which describes a shard of the test matrix.
+
+use crate::registry::{platform::PlatformKind, program::ProgramKind};
+use alloy_primitives::keccak256;
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Report, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// A 1-indexed shard of the test matrix, e.g. `2/4`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct Partition {
+    /// The index of the shard, in `[1, total]`.
+    pub(crate) index: usize,
+    /// The total number of shards.
+    pub(crate) total: usize,
+}
+
+impl Partition {
+    /// Returns whether or not a test case belongs to the shard. Test cases are assigned to shards
+    /// by the keccak256 hash of their fixture, platform, and program, so that the assignment is
+    /// stable across runs, machines, and changes to the rest of the matrix.
+    ///
+    /// ## Takes
+    /// - `fixture` - The name of the test fixture.
+    /// - `platform` - The platform that the test case runs on.
+    /// - `program` - The program that the test case runs.
+    ///
+    /// ## Returns
+    /// - `bool` - Whether or not the test case belongs to the shard.
+    pub(crate) fn contains(
+        &self,
+        fixture: &str,
+        platform: PlatformKind,
+        program: ProgramKind,
+    ) -> bool {
+        let hash = keccak256(format!("{fixture}/{platform}/{program}"));
+        let bucket = u64::from_be_bytes(hash[..8].try_into().expect("hash is 32 bytes"));
+        bucket % self.total as u64 == self.index as u64 - 1
+    }
+}
+
+impl FromStr for Partition {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, total) = s
+            .split_once('/')
+            .ok_or(eyre!("Invalid partition `{s}`, expected `<index>/<total>`"))?;
+        let (index, total) = (index.trim().parse()?, total.trim().parse()?);
+        ensure!(
+            (1..=total).contains(&index),
+            "Invalid partition `{s}`, index must be within [1, {total}]"
+        );
+        Ok(Self { index, total })
+    }
+}
+
+impl TryFrom<String> for Partition {
+    type Error = Report;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Partition> for String {
+    fn from(partition: Partition) -> Self {
+        partition.to_string()
+    }
+}
+
+impl Display for Partition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards_are_disjoint_and_complete() {
+        let shards = (1..=4)
+            .map(|index| Partition { index, total: 4 })
+            .collect::<Vec<_>>();
+        for fixture in (0..64).map(|i| format!("fixture-{i}")) {
+            let owners = shards
+                .iter()
+                .filter(|p| p.contains(&fixture, PlatformKind::Cannon, ProgramKind::OpProgramMips))
+                .count();
+            assert_eq!(owners, 1, "{fixture} belongs to {owners} shards");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Contains the [Quarantine], the list of known-bad test cases that are ran but excluded from the
+//! pass / fail gate of a test run.
+
+use crate::report::TestId;
+use color_eyre::{eyre::ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the quarantine file, within the tests directory.
+pub(crate) const QUARANTINE_FILE: &str = "quarantine.toml";
+
+/// The quarantined test cases of a tests directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Quarantine {
+    /// The quarantined test cases.
+    #[serde(default, rename = "test")]
+    pub(crate) tests: Vec<QuarantinedTest>,
+}
+
+/// A quarantined test case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct QuarantinedTest {
+    /// The test case.
+    pub(crate) id: TestId,
+    /// Why the test case is quarantined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reason: Option<String>,
+}
+
+impl Quarantine {
+    /// Returns the path to the quarantine file of the tests directory.
+    pub(crate) fn path(tests_dir: &Path) -> PathBuf {
+        tests_dir.join(QUARANTINE_FILE)
+    }
+
+    /// Loads the quarantine of the tests directory. A missing quarantine file is empty.
+    pub(crate) fn load(tests_dir: &Path) -> Result<Self> {
+        let path = Self::path(tests_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the quarantine to the tests directory.
+    pub(crate) fn write(&self, tests_dir: &Path) -> Result<()> {
+        fs::write(Self::path(tests_dir), toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns whether or not the test case is quarantined.
+    pub(crate) fn contains(&self, id: &TestId) -> bool {
+        self.tests.iter().any(|t| t.id == *id)
+    }
+
+    /// Quarantines a test case.
+    ///
+    /// ## Takes
+    /// - `id` - The test case.
+    /// - `reason` - Why the test case is quarantined.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors if the test case is already quarantined.
+    pub(crate) fn add(&mut self, id: TestId, reason: Option<String>) -> Result<()> {
+        ensure!(!self.contains(&id), "{id} is already quarantined");
+        self.tests.push(QuarantinedTest { id, reason });
+        Ok(())
+    }
+
+    /// Releases a test case from quarantine.
+    ///
+    /// ## Takes
+    /// - `id` - The test case.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors if the test case is not quarantined.
+    pub(crate) fn remove(&mut self, id: &TestId) -> Result<()> {
+        ensure!(self.contains(id), "{id} is not quarantined");
+        self.tests.retain(|t| t.id != *id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_quarantine() {
+        let tests_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            Quarantine::load(tests_dir.path()).unwrap(),
+            Quarantine::default()
+        );
+
+        let id = "cannon/op-program-mips/fixture".parse::<TestId>().unwrap();
+        assert_eq!(id.to_string(), "cannon/op-program-mips/fixture");
+        // Quarantine files that predate the canonical ids are still understood.
+        assert_eq!(
+            "cannon::op-program-mips::fixture"
+                .parse::<TestId>()
+                .unwrap(),
+            id
+        );
+        let mut quarantine = Quarantine::default();
+        quarantine
+            .add(id.clone(), Some("Known-bad".to_string()))
+            .unwrap();
+        assert!(quarantine.add(id.clone(), None).is_err());
+        quarantine.write(tests_dir.path()).unwrap();
+
+        let mut loaded = Quarantine::load(tests_dir.path()).unwrap();
+        assert_eq!(loaded, quarantine);
+        assert!(loaded.contains(&id));
+
+        loaded.remove(&id).unwrap();
+        assert!(!loaded.contains(&id));
+        assert!(loaded.remove(&id).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Contains the [TestQueue], which spools the test cases of a run to disk, so that their
+//! [RunnableTest]s are constructed lazily as workers free up, rather than all up front.
+//!
+//! [RunnableTest]: super::runnable::RunnableTest
+
+use crate::{
+    registry::{platform::PlatformKind, program::ProgramKind},
+    state::StateDir,
+};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+use tempfile::NamedTempFile;
+
+/// A test case within the [TestQueue].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct TestJob {
+    /// The directory of the test fixture.
+    pub(crate) fixture: PathBuf,
+    /// The platform to run the test on.
+    pub(crate) platform: PlatformKind,
+    /// The program to run the test with.
+    pub(crate) program: ProgramKind,
+}
+
+/// An on-disk queue of [TestJob]s, stored as JSON lines in a scratch file that is removed when the
+/// queue is dropped.
+#[derive(Debug)]
+pub(crate) struct TestQueue {
+    /// The file that the jobs are spooled to.
+    file: NamedTempFile,
+    /// The number of jobs in the queue.
+    len: usize,
+    /// The fixture directories that the jobs belong to.
+    fixtures: BTreeSet<PathBuf>,
+}
+
+impl TestQueue {
+    /// Spools the jobs to a new queue on disk.
+    pub(crate) fn spool(jobs: impl IntoIterator<Item = TestJob>) -> Result<Self> {
+        let file = StateDir::get().tempfile()?;
+        let mut writer = BufWriter::new(file.as_file());
+        let mut len = 0;
+        let mut fixtures = BTreeSet::new();
+        for job in jobs {
+            serde_json::to_writer(&mut writer, &job)?;
+            writer.write_all(b"\n")?;
+            fixtures.insert(job.fixture);
+            len += 1;
+        }
+        writer.flush()?;
+        drop(writer);
+
+        Ok(Self {
+            file,
+            len,
+            fixtures,
+        })
+    }
+
+    /// Returns the number of jobs in the queue.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the fixture directories that the jobs belong to.
+    pub(crate) fn fixtures(&self) -> &BTreeSet<PathBuf> {
+        &self.fixtures
+    }
+
+    /// Returns an iterator that reads the jobs back from disk, in the order they were spooled.
+    pub(crate) fn jobs(&self) -> Result<impl Iterator<Item = Result<TestJob>>> {
+        let reader = BufReader::new(File::open(self.file.path())?);
+        Ok(reader
+            .lines()
+            .map(|line| Ok(serde_json::from_str::<TestJob>(&line?)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spool_jobs() {
+        let jobs = (0..1000)
+            .map(|i| TestJob {
+                fixture: PathBuf::from(format!("tests/fixture-{}", i % 10)),
+                platform: PlatformKind::Cannon,
+                program: ProgramKind::OpProgramMips,
+            })
+            .collect::<Vec<_>>();
+
+        let queue = TestQueue::spool(jobs.clone()).unwrap();
+        assert_eq!(queue.len(), 1000);
+        assert_eq!(queue.fixtures().len(), 10);
+        assert_eq!(
+            queue.jobs().unwrap().collect::<Result<Vec<_>>>().unwrap(),
+            jobs
+        );
+    }
+}
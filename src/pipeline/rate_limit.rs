@@ -0,0 +1,93 @@
+//! Contains the [TokenBucket] that paces the requests of RPC-backed test runs to each upstream RPC,
+//! shared by every worker of the run, so that concurrent hosts do not exhaust the RPC's rate limit.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Parses a rate of requests per second, which must be positive.
+pub(crate) fn parse_rps(s: &str) -> Result<f64, String> {
+    let rps = s.parse::<f64>().map_err(|e| e.to_string())?;
+    if rps > 0.0 && rps.is_finite() {
+        Ok(rps)
+    } else {
+        Err(format!("`{s}` is not a positive rate"))
+    }
+}
+
+/// A token bucket, which admits requests at a steady rate, with bursts of up to a second's worth
+/// of requests after a pause.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    /// The rate that tokens are added at, per second.
+    rate: f64,
+    /// The number of tokens that the bucket holds when full.
+    burst: f64,
+    /// The tokens in the bucket, which is negative while requests wait for them, and the time at
+    /// which they were counted.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    /// Create a new, full [TokenBucket] admitting the given number of requests per second.
+    pub(crate) fn new(rate: f64) -> Self {
+        let burst = rate.max(1.0);
+        Self {
+            rate,
+            burst,
+            state: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    /// Waits until a request is admitted.
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token from the bucket, refilled as of `now`, and returns how long the request must
+    /// wait for it. Requests are admitted in the order that they reserve their tokens.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (tokens, counted) = *state;
+        let elapsed = now.saturating_duration_since(counted).as_secs_f64();
+        let tokens = (tokens + elapsed * self.rate).min(self.burst) - 1.0;
+        *state = (tokens, now.max(counted));
+        if tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pace_requests() {
+        let bucket = TokenBucket::new(10.0);
+        let start = Instant::now();
+        bucket.state.lock().unwrap().1 = start;
+
+        // A second's worth of requests is admitted at once, and the rest are paced.
+        let waits = (0..12).map(|_| bucket.reserve(start)).collect::<Vec<_>>();
+        assert!(waits[..10].iter().all(Duration::is_zero));
+        assert_eq!(waits[10], Duration::from_millis(100));
+        assert_eq!(waits[11], Duration::from_millis(200));
+
+        // The bucket refills over time, up to a second's worth of requests.
+        assert_eq!(
+            bucket.reserve(start + Duration::from_secs(10)),
+            Duration::ZERO
+        );
+        assert_eq!(bucket.state.lock().unwrap().0, 9.0);
+
+        assert_eq!(parse_rps("2.5"), Ok(2.5));
+        assert!(parse_rps("0").is_err());
+    }
+}
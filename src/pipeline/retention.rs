@@ -0,0 +1,71 @@
+//! Contains the [RetentionPolicy], which decides what is kept of each test once it completes: its
+//! working directory (including the VM's state) and its output.
+
+use crate::report::TestStatus;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The environment variable that CI providers set, under which failures are kept by default.
+const CI_ENV: &str = "CI";
+
+/// The artifacts of the tests to keep once they complete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RetentionPolicy {
+    /// Keep nothing.
+    None,
+    /// Keep the artifacts of the tests that did not pass.
+    Failures,
+    /// Keep the artifacts of every test.
+    All,
+}
+
+impl RetentionPolicy {
+    /// Returns the given policy, or the default: [Self::Failures] in CI, and [Self::None]
+    /// otherwise.
+    pub(crate) fn or_default(policy: Option<Self>) -> Self {
+        policy.unwrap_or_else(|| {
+            let ci = std::env::var(CI_ENV).is_ok_and(|v| !v.is_empty() && v != "false");
+            if ci {
+                Self::Failures
+            } else {
+                Self::None
+            }
+        })
+    }
+
+    /// Returns whether or not the artifacts of a test with the given status are kept.
+    pub(crate) fn retains(&self, status: TestStatus) -> bool {
+        match self {
+            Self::None => false,
+            Self::Failures => status != TestStatus::Pass,
+            Self::All => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_by_status() {
+        let statuses = [
+            TestStatus::Pass,
+            TestStatus::Fail,
+            TestStatus::Stalled,
+            TestStatus::Timeout,
+        ];
+        let retained = |policy: RetentionPolicy| statuses.map(|status| policy.retains(status));
+        assert_eq!(retained(RetentionPolicy::None), [false; 4]);
+        assert_eq!(
+            retained(RetentionPolicy::Failures),
+            [false, true, true, true]
+        );
+        assert_eq!(retained(RetentionPolicy::All), [true; 4]);
+        assert_eq!(
+            RetentionPolicy::or_default(Some(RetentionPolicy::All)),
+            RetentionPolicy::All
+        );
+    }
+}
@@ -1,147 +1,659 @@
 //! Contains the test runner for `fpt`.
 
 use crate::{
+    cli::TestConfig,
+    executor::{ExecutionOutcome, Executor},
     fixture::FixtureMetadata,
+    generator::progress::reported_output_root,
+    pipeline::{
+        chaos::ChaosProxy,
+        error::{host_panic, TestError},
+        heap_profile::HeapProfile,
+        hint_check::HintlessOutcome,
+        matrix::TestMatrix,
+        retention::RetentionPolicy,
+        validate::RunArtifacts,
+        witness_reads::WitnessReads,
+    },
+    process::{ProcessCancelled, ProcessOptions},
     registry::{
-        platform::PlatformKind,
-        program::{ProgramHostInputs, ProgramKind},
-        FPPDefinition, PlatformAndPrograms,
+        platform::{PlatformKind, PlatformRuntime},
+        program::{builtin::BuiltinHost, ProgramHostInputs, ProgramHostSource, ProgramKind},
+        validators::ValidatorInputs,
+        FPPDefinition, PlatformDefinition, FP_REGISTRY,
     },
+    report::{metrics::VmMetrics, TestId, TestResult, TestStatus},
+    state::StateDir,
 };
-use color_eyre::{
-    eyre::{ensure, eyre},
-    Result,
+use alloy_primitives::B256;
+use color_eyre::{eyre::eyre, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::{fs, sync::Arc};
-use tempfile::tempdir;
-use tokio::process::Command;
+use tempfile::TempDir;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::mpsc, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+/// The file within the kept artifacts of a test case that its output is written to.
+const OUTPUT_LOG: &str = "output.log";
+
+/// The file within the working directory of an attempt that the `strace` of its witness database
+/// reads is written to.
+const WITNESS_TRACE: &str = "witness-reads.strace";
+
+/// The directory within the working directory of an attempt that the test case is ran in again
+/// with its hints ignored, if it is hint-checked.
+const HINTLESS_DIR: &str = "hintless";
+
+/// The time that a timed out attempt is given to wind down once cancelled, before it is abandoned.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(5);
 
 /// An individual test case runner.
 #[derive(Clone)]
 pub(crate) struct RunnableTest {
-    /// The test fixture metadata.
-    pub(crate) fixture_meta: Arc<FixtureMetadata>,
-    /// The inputs for the test case.
-    pub(crate) inputs: Arc<ProgramHostInputs>,
-    /// The platform to run the test on.
-    pub(crate) platform_kind: PlatformKind,
-    /// The platform definition.
-    pub(crate) platform_definition: Arc<PlatformAndPrograms>,
-    /// The program to run.
-    pub(crate) program_kind: ProgramKind,
-    /// The program definition.
-    pub(crate) program_definition: Arc<FPPDefinition>,
+    /// The matrix that the test case belongs to.
+    matrix: Arc<TestMatrix>,
+    /// The index of the platform within the matrix.
+    platform: usize,
+    /// The program to run, within the platform's programs.
+    program: ProgramKind,
+    /// The index of the fixture within the matrix.
+    fixture: usize,
+    /// The channel that the output of the platform and the host program is forwarded to, if any.
+    output: Option<mpsc::UnboundedSender<String>>,
+    /// The token that cancels the test case, killing its processes.
+    cancel: CancellationToken,
 }
 
 impl RunnableTest {
-    /// Create a new [RunnableTest].
+    /// Create a new [RunnableTest]. The program must be one of the platform's.
     pub(crate) fn new(
-        fixture_meta: Arc<FixtureMetadata>,
-        inputs: Arc<ProgramHostInputs>,
-        platform: PlatformKind,
-        platform_definition: Arc<PlatformAndPrograms>,
+        matrix: Arc<TestMatrix>,
+        platform: usize,
         program: ProgramKind,
-        program_definition: Arc<FPPDefinition>,
+        fixture: usize,
     ) -> Self {
         Self {
-            fixture_meta,
-            inputs,
-            platform_kind: platform,
-            platform_definition,
-            program_kind: program,
-            program_definition,
-        }
-    }
-
-    /// Run the test case and return whether or not it passed.
-    pub(crate) async fn run(&self) -> Result<bool> {
-        // Create a temporary directory for the test case.
-        let workdir = tempdir()?;
-
-        // Grab the client artifact for the program.
-        let client_artifact = self
-            .program_definition
-            .build
-            .get_artifact("client")
-            .ok_or(eyre!("Failed to get client artifact"))?;
-
-        let platform = self.platform_definition.vm_kind.get_platform(
-            self.platform_definition
-                .vm
-                .build
-                .as_ref()
-                .and_then(|b| b.get_artifact("vm")),
-        )?;
-        let program = self.program_kind.get_program(
-            self.program_definition
-                .build
-                .get_artifact("host")
-                .ok_or(eyre!("No host artifact"))?,
-        );
+            matrix,
+            platform,
+            program,
+            fixture,
+            output: None,
+            cancel: CancellationToken::new(),
+        }
+    }
 
-        // Load the binary into the platform's state format.
-        platform
-            .load_elf(client_artifact.as_path(), workdir.path())
-            .await?;
+    /// Forwards the output of the platform and the host program to the channel, line by line.
+    pub(crate) fn forward_output(&mut self, lines: mpsc::UnboundedSender<String>) {
+        self.output = Some(lines);
+    }
 
-        // Run the program on the platform.
-        let result = platform
-            .run(self.inputs.as_ref(), program, workdir.path())
-            .await?;
+    /// Cancels the test case once the token is cancelled, killing the processes of its running
+    /// attempt.
+    pub(crate) fn cancel_on(&mut self, cancel: CancellationToken) {
+        self.cancel = cancel;
+    }
 
-        Ok(result == self.fixture_meta.expected_status)
+    /// Returns the test configuration.
+    pub(crate) fn cfg(&self) -> &TestConfig {
+        &self.matrix.cfg
     }
 
-    /// Decompresses the files within the test fixture.
-    pub(crate) async fn decompress_fixture(&self) -> Result<()> {
-        // Grab the fixture directory.
-        let fixture_dir = self
-            .inputs
-            .genesis_path
-            .parent()
-            .ok_or(eyre!("Fixture at top-level directory"))?;
+    /// Returns the test fixture metadata.
+    pub(crate) fn fixture_meta(&self) -> &FixtureMetadata {
+        &self.matrix.fixtures[self.fixture].metadata
+    }
 
-        // Decompress the genesis file
-        let decompress_status = Command::new("zstd")
-            .arg("-d")
-            .arg(fixture_dir.join("genesis.json.zst"))
-            .current_dir(fixture_dir)
-            .output()
-            .await?;
-        ensure!(
-            decompress_status.status.success(),
-            "Failed to decompress genesis file"
-        );
-
-        // Decompress witness database
-        let decompress_status = Command::new("tar")
-            .arg("--zstd")
-            .arg("-xvf")
-            .arg(fixture_dir.join("witness-db.tar.zst"))
-            .current_dir(fixture_dir)
-            .output()
-            .await?;
-        ensure!(
-            decompress_status.status.success(),
-            "Failed to decompress witness database"
-        );
+    /// Returns the inputs for the test case.
+    pub(crate) fn inputs(&self) -> &ProgramHostInputs {
+        &self.matrix.fixtures[self.fixture].inputs
+    }
 
-        Ok(())
+    /// Returns the platform to run the test on.
+    pub(crate) fn platform_kind(&self) -> PlatformKind {
+        self.matrix.platforms[self.platform].vm_kind
+    }
+
+    /// Returns the platform definition.
+    pub(crate) fn platform_definition(&self) -> &PlatformDefinition {
+        &self.matrix.platforms[self.platform].vm
+    }
+
+    /// Returns the program to run.
+    pub(crate) fn program_kind(&self) -> ProgramKind {
+        self.program
+    }
+
+    /// Returns the [TestId] of the test case.
+    pub(crate) fn id(&self) -> TestId {
+        TestId {
+            platform: self.platform_kind(),
+            program: self.program_kind(),
+            fixture: self.fixture_meta().name.clone(),
+        }
+    }
+
+    /// Returns the program definition.
+    pub(crate) fn program_definition(&self) -> &FPPDefinition {
+        &self.matrix.platforms[self.platform].programs[&self.program]
     }
 
-    /// Clean up the decompressed fixture files.
-    pub(crate) async fn teardown(&self) -> Result<()> {
-        // Grab the fixture directory.
+    /// Run the test case and return its [TestResult], retrying it up to `--retries` times if it
+    /// fails, stalls, or times out.
+    ///
+    /// ## Takes
+    /// - `cpu` - The CPU core to pin the VM to, if any. Ignored on the native platform.
+    ///
+    /// ## Returns
+    /// - `Result<TestResult>` - The result of the test case. Errors with [ProcessCancelled] if the
+    ///   test case was cancelled.
+    pub(crate) async fn run(&self, cpu: Option<usize>) -> Result<TestResult> {
+        // Each attempt appends to the log file, which starts out empty.
+        if let Some(log_path) = self.log_path() {
+            fs::create_dir_all(log_path.parent().ok_or(eyre!("Invalid log path"))?)?;
+            fs::write(&log_path, "")?;
+        }
+        // The artifacts kept by an earlier run are replaced.
+        let artifacts_path = self.artifacts_path();
+        if artifacts_path.exists() {
+            fs::remove_dir_all(&artifacts_path)?;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let last_attempt = attempt == self.cfg().retries;
+            let workdir = self.attempt_workdir()?;
+            // The attempt is cancelled with the test case, or once it times out, which kills its
+            // process tree.
+            let attempt_cancel = self.cancel.child_token();
+            let attempt_run = self.run_attempt(workdir.path(), cpu, last_attempt, &attempt_cancel);
+            let mut result = match self.cfg().timeout.map(Duration::from_secs) {
+                Some(timeout) => {
+                    tokio::pin!(attempt_run);
+                    match tokio::time::timeout(timeout, &mut attempt_run).await {
+                        Ok(result) => result?,
+                        Err(_) => {
+                            // Let the cancelled attempt wind down, flushing its output.
+                            attempt_cancel.cancel();
+                            let _ = tokio::time::timeout(CANCEL_GRACE_PERIOD, attempt_run).await;
+                            self.failed(
+                                TestError::Timeout {
+                                    after_ms: timeout.as_millis() as u64,
+                                },
+                                timeout,
+                            )
+                        }
+                    }
+                }
+                None => attempt_run.await?,
+            };
+            if self.cfg().retention().retains(result.status) {
+                self.keep_artifacts(workdir)?;
+            }
+            if result.status == TestStatus::Pass || last_attempt {
+                result.retries = attempt;
+                return Ok(result);
+            }
+
+            attempt += 1;
+            warn!(
+                target: "test-runner",
+                "{} is {:?}; retrying (attempt {}/{})",
+                result.id(),
+                result.status,
+                attempt,
+                self.cfg().retries
+            );
+        }
+    }
+
+    /// Run a single attempt of the test case and return its [TestResult].
+    ///
+    /// ## Takes
+    /// - `workdir` - The working directory of the attempt.
+    /// - `cpu` - The CPU core to pin the VM to, if any. Ignored on the native platform.
+    /// - `last_attempt` - Whether or not the attempt is the last, whose reproduction bundle is
+    ///   uploaded if it fails.
+    /// - `cancel` - The token that cancels the attempt, killing its processes.
+    ///
+    /// ## Returns
+    /// - `Result<TestResult>` - The result of the attempt. Errors with [ProcessCancelled] if the
+    ///   attempt was cancelled.
+    async fn run_attempt(
+        &self,
+        workdir: &Path,
+        cpu: Option<usize>,
+        last_attempt: bool,
+        cancel: &CancellationToken,
+    ) -> Result<TestResult> {
+        let executor = Executor::new(
+            self.platform_kind(),
+            self.platform_definition(),
+            self.program_kind(),
+            self.program_definition(),
+            self.cfg().builtin_host.then_some(BuiltinHost {
+                fault: self.cfg().preimage_fault,
+            }),
+        )?;
+
+        // Route the host's RPC requests through chaos proxies, if faults are injected.
+        let (inputs, _proxies) = self.chaos_inputs().await?;
+
+        // Persist the output of the platform and the host program, if requested, or if the
+        // artifacts of the attempt may be kept or validated. The output is also tapped for the
+        // output root that the program reports, and for a panic of its host.
+        let log_path = self.log_path().or_else(|| {
+            (self.cfg().retention() != RetentionPolicy::None || !FP_REGISTRY.validator.is_empty())
+                .then(|| workdir.join(OUTPUT_LOG))
+        });
+        let (lines_tx, lines_rx) = mpsc::unbounded_channel::<String>();
+        let output_tap = tap_output(log_path.clone(), lines_rx, self.output.clone());
+
+        // Run the program on the platform, tracing its reads of the witness database and profiling
+        // its heap if requested.
+        let witness_trace = self.witness_dir().map(|_| workdir.join(WITNESS_TRACE));
+        let heap_profile = self.heap_profile();
+        if let Some(heap_profile) = heap_profile.as_ref() {
+            heap_profile.prepare()?;
+        }
+        let opts = ProcessOptions {
+            cpu,
+            output_lines: Some(lines_tx),
+            trace_reads: witness_trace.clone(),
+            heap_profile: heap_profile.clone(),
+            cancel: Some(cancel.clone()),
+            ..self.cfg().process_options()
+        };
+        let start_time = Instant::now();
+        let outcome = executor.run(&inputs, workdir, &opts).await;
+        drop(opts);
+        let TappedOutput {
+            output_root,
+            host_panic,
+        } = output_tap.await??;
+        // A platform that fails to run the program fails the test, rather than the run.
+        let ExecutionOutcome {
+            exit_status: result,
+            stalled,
+            duration,
+            vm_duration,
+            instructions,
+            peak_rss,
+        } = match outcome {
+            Ok(outcome) => outcome,
+            // A cancelled attempt has no result.
+            Err(e) if e.downcast_ref::<ProcessCancelled>().is_some() => return Err(e),
+            Err(e) => {
+                let result = self.failed(
+                    TestError::VmCrash {
+                        message: format!("{e:#}"),
+                    },
+                    start_time.elapsed(),
+                );
+                warn!(target: "test-runner", "{} crashed: {e:#}", result.id());
+                return Ok(result);
+            }
+        };
+
+        let expected_status = self.fixture_meta().expected_status;
+        let error = match result {
+            // A stalled program is flagged, even if it was left to run to completion.
+            _ if stalled => Some(TestError::Stalled),
+            // Under a corrupting fault, the client passes if it does not accept the claim.
+            Some(0) if self.corrupting_fault() => Some(TestError::InvalidOutput {
+                message: "The client accepted the claim from corrupted preimages".to_string(),
+            }),
+            Some(_) if self.corrupting_fault() => None,
+            Some(status) if status == expected_status => None,
+            Some(status) => Some(match host_panic {
+                Some(message) => TestError::HostPanic { message },
+                None => TestError::WrongStatus {
+                    expected: expected_status,
+                    actual: status,
+                },
+            }),
+            None => Some(TestError::Stalled),
+        };
+
+        let status = error.as_ref().map_or(TestStatus::Pass, TestError::status);
+        let mut test_result = TestResult {
+            duration_ms: duration.as_millis() as u64,
+            exit_status: result,
+            output_root,
+            metrics: Some(VmMetrics {
+                instructions,
+                vm_duration_ms: vm_duration.as_millis() as u64,
+                peak_rss,
+            }),
+            error,
+            ..TestResult::new(self.id(), status, expected_status)
+        };
+        if let (Some(trace), Some(witness_dir)) = (witness_trace, self.witness_dir()) {
+            match WitnessReads::read(&trace, witness_dir) {
+                Ok(reads) => test_result.witness_reads = Some(reads),
+                Err(e) => {
+                    warn!(target: "test-runner", "Failed to read the witness reads of {}: {e}", test_result.id())
+                }
+            }
+        }
+
+        if let Some(heap_profile) = heap_profile {
+            match heap_profile.profiles() {
+                Ok(profiles) if profiles.is_empty() => warn!(
+                    target: "test-runner",
+                    "No heap profile of {} was written; with jemalloc, the host must be built with its profiling enabled",
+                    test_result.id()
+                ),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(target: "test-runner", "Failed to collect the heap profile of {}: {e}", test_result.id())
+                }
+            }
+        }
+
+        // Catch the executions that exit with the expected status, but derive the wrong output
+        // or end in the wrong state.
+        if test_result.status == TestStatus::Pass && !self.corrupting_fault() {
+            let artifacts = RunArtifacts {
+                output_root,
+                final_state: executor.final_state(workdir),
+            };
+            if let Err(e) = artifacts.validate(self.fixture_meta()) {
+                warn!(target: "test-runner", "{} failed validation: {e}", test_result.id());
+                test_result.veto(TestError::InvalidOutput {
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        // Run the test case again with its hints ignored, which must not change its outcome.
+        if self.hint_checked() {
+            let hintless = self.run_hintless(&inputs, workdir, cpu, cancel).await?;
+            if hintless.diverges(&test_result) && test_result.status == TestStatus::Pass {
+                let message = format!(
+                    "Diverged with its hints ignored: exit status {:?}, output root {:?}",
+                    hintless.exit_status, hintless.output_root
+                );
+                warn!(target: "test-runner", "{}: {message}", test_result.id());
+                test_result.veto(TestError::InvalidOutput { message });
+            }
+            test_result.hintless = Some(hintless);
+        }
+
+        // Run the validators of the registry, any of which may veto the pass.
         let fixture_dir = self
-            .inputs
-            .genesis_path
+            .inputs()
+            .rollup_cfg_path
             .parent()
             .ok_or(eyre!("Fixture at top-level directory"))?;
+        let mut verdicts = Vec::new();
+        for (name, validator) in FP_REGISTRY.validator.iter() {
+            if !validator.applies_to(self.program_kind()) {
+                continue;
+            }
+            let inputs = ValidatorInputs {
+                result: &test_result,
+                workdir,
+                fixture_dir,
+                output_log: log_path.as_deref(),
+            };
+            verdicts.push(validator.run(name, inputs).await);
+        }
+        for verdict in verdicts.iter().filter(|v| !v.passed) {
+            if test_result.status == TestStatus::Pass {
+                let message = format!(
+                    "Vetoed by validator `{}`: {}",
+                    verdict.validator,
+                    verdict.message.as_deref().unwrap_or("no message")
+                );
+                warn!(target: "test-runner", "{}: {message}", test_result.id());
+                test_result.veto(TestError::InvalidOutput { message });
+            }
+        }
+        test_result.validators = verdicts;
+
+        // Upload the reproduction bundle of failed tests, if requested.
+        if let Some(target) = self.cfg().upload_failures.as_ref() {
+            if test_result.status != TestStatus::Pass && last_attempt {
+                match target.upload_bundle(self, &test_result, workdir).await {
+                    Ok(url) => test_result.bundle_url = Some(url),
+                    Err(e) => warn!(target: "test-runner", "Failed to upload failure bundle: {e}"),
+                }
+            }
+        }
+
+        Ok(test_result)
+    }
+
+    /// Runs the test case with the builtin preimage server, which acknowledges the client's hints
+    /// without acting on them, in a subdirectory of the attempt's working directory. Its output is
+    /// written alongside its state.
+    ///
+    /// ## Takes
+    /// - `inputs` - The inputs of the test case.
+    /// - `workdir` - The working directory of the attempt.
+    /// - `cpu` - The CPU core to pin the VM to, if any.
+    /// - `cancel` - The token that cancels the execution, killing its processes.
+    ///
+    /// ## Returns
+    /// - `Result<HintlessOutcome>` - The outcome of the execution.
+    async fn run_hintless(
+        &self,
+        inputs: &ProgramHostInputs,
+        workdir: &Path,
+        cpu: Option<usize>,
+        cancel: &CancellationToken,
+    ) -> Result<HintlessOutcome> {
+        let executor = Executor::new(
+            self.platform_kind(),
+            self.platform_definition(),
+            self.program_kind(),
+            self.program_definition(),
+            Some(BuiltinHost::default()),
+        )?;
+        let workdir = workdir.join(HINTLESS_DIR);
+        fs::create_dir_all(&workdir)?;
 
-        // Remove the decompressed files.
-        fs::remove_file(fixture_dir.join("genesis.json"))?;
-        fs::remove_dir_all(fixture_dir.join("witness-db"))?;
+        let (lines_tx, lines_rx) = mpsc::unbounded_channel::<String>();
+        let output_tap = tap_output(Some(workdir.join(OUTPUT_LOG)), lines_rx, None);
+        let opts = ProcessOptions {
+            cpu,
+            output_lines: Some(lines_tx),
+            cancel: Some(cancel.clone()),
+            ..self.cfg().process_options()
+        };
+        let outcome = executor.run(inputs, &workdir, &opts).await;
+        drop(opts);
+        let TappedOutput { output_root, .. } = output_tap.await??;
+        Ok(HintlessOutcome {
+            exit_status: outcome?.exit_status,
+            output_root,
+        })
+    }
+
+    /// Returns the path that the output of the test case is written to, if `--log-dir` is set.
+    pub(crate) fn log_path(&self) -> Option<PathBuf> {
+        self.cfg().log_dir.as_ref().map(|dir| {
+            dir.join(self.platform_kind().to_string())
+                .join(self.program_kind().to_string())
+                .join(format!("{}.log", self.fixture_meta().name))
+        })
+    }
 
+    /// Returns the directory that the artifacts of the test case are moved to, if they are kept.
+    pub(crate) fn artifacts_path(&self) -> PathBuf {
+        self.cfg()
+            .artifacts_dir()
+            .join(self.platform_kind().to_string())
+            .join(self.program_kind().to_string())
+            .join(&self.fixture_meta().name)
+    }
+
+    /// Creates the working directory of an attempt. If the artifacts of the attempt may be kept,
+    /// it is created within the artifacts directory, so that keeping it is a rename.
+    fn attempt_workdir(&self) -> Result<TempDir> {
+        if self.cfg().retention() == RetentionPolicy::None {
+            return StateDir::get().tempdir();
+        }
+        let artifacts_dir = self.cfg().artifacts_dir();
+        fs::create_dir_all(&artifacts_dir)?;
+        Ok(tempfile::Builder::new()
+            .prefix(".attempt-")
+            .tempdir_in(artifacts_dir)?)
+    }
+
+    /// Keeps the working directory of an attempt, along with its output, as the artifacts of the
+    /// test case, replacing those of an earlier attempt.
+    fn keep_artifacts(&self, workdir: TempDir) -> Result<()> {
+        if let Some(log_path) = self.log_path() {
+            fs::copy(log_path, workdir.path().join(OUTPUT_LOG))?;
+        }
+
+        let artifacts_path = self.artifacts_path();
+        if artifacts_path.exists() {
+            fs::remove_dir_all(&artifacts_path)?;
+        }
+        fs::create_dir_all(
+            artifacts_path
+                .parent()
+                .ok_or(eyre!("Invalid artifacts path"))?,
+        )?;
+        fs::rename(workdir.into_path(), &artifacts_path)?;
         Ok(())
     }
+
+    /// Returns the [TestResult] of an attempt that failed with the error before the program
+    /// exited, e.g. that was abandoned after the `--timeout`.
+    fn failed(&self, error: TestError, duration: Duration) -> TestResult {
+        let status = error.status();
+        TestResult {
+            duration_ms: duration.as_millis() as u64,
+            error: Some(error),
+            ..TestResult::new(self.id(), status, self.fixture_meta().expected_status)
+        }
+    }
+
+    /// Returns the witness database whose reads are traced, if `--trace-witness-reads` is set and
+    /// the test reads it from the host. The programs that are ran inside of a container cannot be
+    /// traced.
+    fn witness_dir(&self) -> Option<&Path> {
+        let ProgramHostSource::Disk { path } = &self.inputs().source else {
+            return None;
+        };
+        let in_container = self.platform_definition().runtime == PlatformRuntime::Docker
+            && self.platform_kind() != PlatformKind::Native;
+        (self.cfg().trace_witness_reads && !in_container).then_some(path.as_path())
+    }
+
+    /// Returns the profiling of the host's heap, if `--heap-profile` is set and the test runs
+    /// kona's Rust-based host natively. The profiles are written to
+    /// `<artifacts>/heap-profiles/<platform>/<program>/<fixture>`.
+    fn heap_profile(&self) -> Option<HeapProfile> {
+        let profiler = self.cfg().heap_profile?;
+        let native_kona = self.program_kind() == ProgramKind::KonaNative
+            && self.platform_kind() == PlatformKind::Native;
+        native_kona.then(|| HeapProfile {
+            profiler,
+            dir: self
+                .cfg()
+                .artifacts_dir()
+                .join("heap-profiles")
+                .join(self.platform_kind().to_string())
+                .join(self.program_kind().to_string())
+                .join(&self.fixture_meta().name),
+        })
+    }
+
+    /// Returns whether or not the test case is ran again with its hints ignored: if `--hint-check`
+    /// is set, and the test runs a client on an FPVM from the fixture's witness database, which the
+    /// builtin preimage server can serve it from.
+    fn hint_checked(&self) -> bool {
+        self.cfg().hint_check
+            && self.platform_kind() != PlatformKind::Native
+            && matches!(self.inputs().source, ProgramHostSource::Disk { .. })
+    }
+
+    /// Returns whether or not a fault that corrupts the data served to the client is injected by
+    /// the builtin host. The native platform always runs the program's own host.
+    fn corrupting_fault(&self) -> bool {
+        self.cfg().builtin_host
+            && self.platform_kind() != PlatformKind::Native
+            && self.cfg().preimage_fault.is_some_and(|f| f.corrupts())
+    }
+
+    /// Returns the inputs of the test case, with the RPCs of an RPC-backed source replaced by
+    /// [ChaosProxy]s if faults are injected or the RPCs are rate limited. The proxies are shut
+    /// down when dropped.
+    async fn chaos_inputs(&self) -> Result<(ProgramHostInputs, Vec<ChaosProxy>)> {
+        let mut inputs = self.inputs().clone();
+        let mut proxies = Vec::new();
+        let chaos = self.cfg().chaos();
+        let proxied = chaos.is_some() || self.cfg().rpc_rate_limit.is_some();
+        if let (
+            true,
+            ProgramHostSource::Rpc {
+                l1, l1_beacon, l2, ..
+            },
+        ) = (proxied, &mut inputs.source)
+        {
+            for url in [l1, l1_beacon, l2] {
+                let limiter = self.matrix.rpc_limiter(url);
+                let proxy = ChaosProxy::spawn(url, chaos.unwrap_or_default(), limiter).await?;
+                *url = proxy.url();
+                proxies.push(proxy);
+            }
+        }
+        Ok((inputs, proxies))
+    }
+}
+
+/// What the output of an execution reported.
+#[derive(Debug, Default)]
+struct TappedOutput {
+    /// The last output root that the program reported.
+    output_root: Option<B256>,
+    /// The first panic that the host reported.
+    host_panic: Option<String>,
+}
+
+/// Spawns a task that appends each line of output to the log file, if any, and forwards it to the
+/// channel, if any, until the channel is closed, and returns what the output reported.
+fn tap_output(
+    path: Option<PathBuf>,
+    mut lines: mpsc::UnboundedReceiver<String>,
+    forward: Option<mpsc::UnboundedSender<String>>,
+) -> JoinHandle<std::io::Result<TappedOutput>> {
+    tokio::spawn(async move {
+        let mut file = match path {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?,
+            ),
+            None => None,
+        };
+        let mut tapped = TappedOutput::default();
+        while let Some(line) = lines.recv().await {
+            tapped.output_root = reported_output_root(&line).or(tapped.output_root);
+            if tapped.host_panic.is_none() {
+                tapped.host_panic = host_panic(&line);
+            }
+            if let Some(file) = file.as_mut() {
+                file.write_all(format!("{line}\n").as_bytes()).await?;
+            }
+            if let Some(forward) = forward.as_ref() {
+                let _ = forward.send(line);
+            }
+        }
+        if let Some(file) = file.as_mut() {
+            file.flush().await?;
+        }
+        Ok(tapped)
+    })
 }
@@ -0,0 +1,86 @@
+//! Contains the scheduling order of the test queue, which interleaves the tests of platforms with
+//! different [ResourceProfile]s, so that CPU-bound and I/O-bound tests run concurrently rather than
+//! in whatever order the matrix lists them in.
+
+use crate::registry::platform::ResourceProfile;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Interleaves the tests of each platform, alternating between the resource profiles and, within
+/// each profile, between its platforms. Once the tests of a profile run out, the rest are
+/// interleaved among the remaining profiles.
+///
+/// ## Takes
+/// - `platforms` - The resource profile and the tests of each platform, in matrix order.
+///
+/// ## Returns
+/// - `impl Iterator<Item = I::Item>` - The tests, in the order to schedule them in.
+pub(crate) fn interleave<I: Iterator>(
+    platforms: impl IntoIterator<Item = (ResourceProfile, I)>,
+) -> impl Iterator<Item = I::Item> {
+    let mut profiles = BTreeMap::<_, Vec<_>>::new();
+    for (profile, tests) in platforms {
+        profiles.entry(profile).or_default().push(tests);
+    }
+    RoundRobin::new(profiles.into_values().map(RoundRobin::new))
+}
+
+/// An iterator that takes one item from each of its iterators in turn, dropping those that are
+/// exhausted.
+struct RoundRobin<I> {
+    /// The iterators that are not yet exhausted, in turn order.
+    iters: VecDeque<I>,
+}
+
+impl<I> RoundRobin<I> {
+    fn new(iters: impl IntoIterator<Item = I>) -> Self {
+        Self {
+            iters: iters.into_iter().collect(),
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for RoundRobin<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(mut iter) = self.iters.pop_front() {
+            if let Some(item) = iter.next() {
+                self.iters.push_back(iter);
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleave_resource_profiles() {
+        let order = interleave([
+            (
+                ResourceProfile::Cpu,
+                vec!["cannon-1", "cannon-2", "cannon-3"].into_iter(),
+            ),
+            (ResourceProfile::Cpu, vec!["asterisc-1"].into_iter()),
+            (
+                ResourceProfile::Io,
+                vec!["native-1", "native-2"].into_iter(),
+            ),
+        ])
+        .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                "cannon-1",
+                "native-1",
+                "asterisc-1",
+                "native-2",
+                "cannon-2",
+                "cannon-3"
+            ]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Contains the [WorkerStatus] tracker, which renders the utilization of the worker pool.
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+/// Tracks the utilization of the worker pool over the course of a test run.
+#[derive(Debug)]
+pub(crate) struct WorkerStatus {
+    /// The number of workers in the pool.
+    workers: usize,
+    /// The total number of tests in the run.
+    total: usize,
+    /// The number of tests currently running.
+    active: AtomicUsize,
+    /// The number of tests that have completed.
+    completed: AtomicUsize,
+    /// The number of tests that have passed.
+    passed: AtomicUsize,
+    /// The start time of the run.
+    start_time: Instant,
+}
+
+impl WorkerStatus {
+    /// Create a new [WorkerStatus] for a run of `total` tests across `workers` workers.
+    pub(crate) fn new(workers: usize, total: usize) -> Self {
+        Self {
+            workers,
+            total,
+            active: AtomicUsize::new(0),
+            completed: AtomicUsize::new(0),
+            passed: AtomicUsize::new(0),
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Returns the number of workers in the pool.
+    pub(crate) fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Returns the number of tests that have completed, and the total number of tests in the run.
+    pub(crate) fn progress(&self) -> (usize, usize) {
+        (self.completed.load(Ordering::Relaxed), self.total)
+    }
+
+    /// Records that a worker has started a test.
+    pub(crate) fn start(&self) {
+        self.active.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a worker has finished a test.
+    pub(crate) fn finish(&self, passed: bool) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        if passed {
+            self.passed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Records that a worker has abandoned a cancelled test, which does not count as completed.
+    pub(crate) fn abandon(&self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Renders the status line.
+    pub(crate) fn render(&self) -> String {
+        let active = self.active.load(Ordering::Relaxed);
+        let completed = self.completed.load(Ordering::Relaxed);
+        let passed = self.passed.load(Ordering::Relaxed);
+        let queued = self.total.saturating_sub(active + completed);
+        let minutes = self.start_time.elapsed().as_secs_f64() / 60.0;
+        let throughput = if minutes > 0.0 {
+            completed as f64 / minutes
+        } else {
+            0.0
+        };
+
+        format!(
+            "Workers: {active}/{} active | Queued: {queued} | Completed: {completed}/{} ({passed} passed, {} failed) | Throughput: {throughput:.2} tests/min",
+            self.workers,
+            self.total,
+            completed - passed
+        )
+    }
+}
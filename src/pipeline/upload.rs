@@ -0,0 +1,124 @@
+//! Contains the [UploadTarget], which uploads the reproduction bundles of failed tests to object
+//! storage.
+
+use super::runnable::RunnableTest;
+use crate::{archive, report::TestResult, state::StateDir};
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Report, Result,
+};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::process::Command;
+use tracing::info;
+
+/// The name of the reproduction metadata file within a bundle.
+const REPRODUCTION_FILE: &str = "reproduction.toml";
+
+/// An object storage location that failure bundles are uploaded to, e.g. `s3://bucket/prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UploadTarget {
+    /// The URL of the location, without a trailing slash.
+    url: String,
+}
+
+impl UploadTarget {
+    /// Bundles the workdir of a failed test with its logs and a reference to its fixture, and
+    /// uploads it to the target.
+    ///
+    /// ## Takes
+    /// - `test` - The failed test.
+    /// - `result` - The result of the failed test.
+    /// - `workdir` - The working directory of the failed test.
+    ///
+    /// ## Returns
+    /// - `Result<String>` - The URL of the uploaded bundle.
+    pub(crate) async fn upload_bundle(
+        &self,
+        test: &RunnableTest,
+        result: &TestResult,
+        workdir: &Path,
+    ) -> Result<String> {
+        let fixture_dir = test
+            .inputs()
+            .rollup_cfg_path
+            .parent()
+            .ok_or(eyre!("Fixture at top-level directory"))?;
+
+        // Record a reference to the fixture alongside the workdir contents.
+        let reproduction = Reproduction {
+            fixture_dir: fixture_dir.to_path_buf(),
+            result: result.clone(),
+        };
+        fs::write(
+            workdir.join(REPRODUCTION_FILE),
+            toml::to_string_pretty(&reproduction)?,
+        )?;
+        fs::copy(
+            fixture_dir.join("fixture.toml"),
+            workdir.join("fixture.toml"),
+        )?;
+
+        // Compress the workdir into the bundle.
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let bundle_name = format!(
+            "{}-{}-{}-{timestamp}.tar.zst",
+            result.platform, result.program, result.fixture
+        );
+        let bundle_dir = StateDir::get().tempdir()?;
+        let bundle_path = bundle_dir.path().join(&bundle_name);
+        archive::pack_dir(workdir, ".", &bundle_path)
+            .await
+            .map_err(|e| eyre!("Failed to compress failure bundle: {e}"))?;
+
+        // Upload the bundle.
+        let url = format!("{}/{bundle_name}", self.url);
+        let output = Command::new("aws")
+            .arg("s3")
+            .arg("cp")
+            .arg(&bundle_path)
+            .arg(&url)
+            .output()
+            .await?;
+        ensure!(
+            output.status.success(),
+            "Failed to upload failure bundle to {url}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        info!(target: "test-runner", "Uploaded failure bundle to {url}");
+        Ok(url)
+    }
+}
+
+impl FromStr for UploadTarget {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s.strip_prefix("s3://").ok_or(eyre!(
+            "Unsupported upload target `{s}`, expected `s3://bucket/prefix`"
+        ))?;
+        ensure!(
+            !path.is_empty() && !path.starts_with('/'),
+            "Upload target `{s}` is missing a bucket"
+        );
+        Ok(Self {
+            url: s.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// The reproduction metadata of a failure bundle.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Reproduction {
+    /// The fixture directory that the test was ran from.
+    fixture_dir: PathBuf,
+    /// The result of the failed test.
+    result: TestResult,
+}
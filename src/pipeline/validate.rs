@@ -0,0 +1,100 @@
+//! Contains the post-run validation of a test case, which checks the artifacts of an execution
+//! against those that the fixture expects, so that executions that exit with the expected status
+//! but derive the wrong output, or end in the wrong state, are caught.
+
+use crate::fixture::FixtureMetadata;
+use alloy_primitives::{keccak256, B256};
+use color_eyre::{eyre::ensure, Result};
+use std::{fs, path::PathBuf};
+use tracing::warn;
+
+/// The artifacts of an execution that are validated against the fixture.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct RunArtifacts {
+    /// The output root that the program reported, if it logged one.
+    pub(crate) output_root: Option<B256>,
+    /// The final state that the FPVM wrote, if the platform writes one.
+    pub(crate) final_state: Option<PathBuf>,
+}
+
+impl RunArtifacts {
+    /// Validates the artifacts against those that the fixture expects. The programs that do not
+    /// report their output root in the reference program's format cannot be checked for it, so an
+    /// unreported output root is only warned about.
+    ///
+    /// ## Takes
+    /// - `meta` - The metadata of the fixture.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors with the mismatch if an artifact differs from the expected one, or
+    ///   if the expected final state was not written.
+    pub(crate) fn validate(&self, meta: &FixtureMetadata) -> Result<()> {
+        if let Some(expected) = meta.expected_output_root {
+            match self.output_root {
+                Some(output_root) => ensure!(
+                    output_root == expected,
+                    "Reported the output root {output_root}, but {expected} was expected"
+                ),
+                None => warn!(
+                    target: "test-runner",
+                    "{}: no output root was reported; expected {expected}", meta.name
+                ),
+            }
+        }
+
+        if let (Some(expected), Some(final_state)) = (meta.expected_state_hash, &self.final_state) {
+            ensure!(
+                final_state.is_file(),
+                "No final state was written to {}",
+                final_state.display()
+            );
+            let state_hash = keccak256(fs::read(final_state)?);
+            ensure!(
+                state_hash == expected,
+                "Ended in the state {state_hash}, but {expected} was expected"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_run_artifacts() {
+        let workdir = tempfile::tempdir().unwrap();
+        let final_state = workdir.path().join("out.json");
+        fs::write(&final_state, "{}").unwrap();
+
+        let output_root = B256::repeat_byte(0x01);
+        let meta = FixtureMetadata {
+            name: "artifacts".to_string(),
+            expected_output_root: Some(output_root),
+            expected_state_hash: Some(keccak256("{}")),
+            ..Default::default()
+        };
+        let artifacts = RunArtifacts {
+            output_root: Some(output_root),
+            final_state: Some(final_state.clone()),
+        };
+        artifacts.validate(&meta).unwrap();
+
+        // Platforms without a final state, and programs that do not report the output root, are
+        // only checked for what they produce.
+        RunArtifacts::default().validate(&meta).unwrap();
+
+        let wrong_root = RunArtifacts {
+            output_root: Some(B256::repeat_byte(0x02)),
+            ..artifacts.clone()
+        };
+        assert!(wrong_root.validate(&meta).is_err());
+
+        fs::write(&final_state, "{\"step\":1}").unwrap();
+        assert!(artifacts.validate(&meta).is_err());
+
+        fs::remove_file(&final_state).unwrap();
+        assert!(artifacts.validate(&meta).is_err());
+    }
+}
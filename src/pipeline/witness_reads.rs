@@ -0,0 +1,244 @@
+//! Contains the [WitnessReads] of a test, which measure how much of the witness database a program
+//! reads, and how often it reads the same data again, from an `strace` of the processes that the
+//! test spawned.
+
+use crate::{report::TestResult, units::Bytes};
+use cli_table::{format::Justify, Cell, Style, Table, TableStruct};
+use color_eyre::{eyre::ensure, owo_colors::OwoColorize, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The system calls that are traced: those that open the files of the witness database, and those
+/// that read from them.
+const TRACED_SYSCALLS: &str = "trace=open,openat,read,pread64";
+
+/// The reads of the witness database by the processes of a test.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct WitnessReads {
+    /// The number of times that a file of the witness database was opened.
+    pub(crate) key_reads: u64,
+    /// The number of distinct files of the witness database that were opened.
+    pub(crate) unique_keys: u64,
+    /// The number of bytes read from the witness database.
+    pub(crate) bytes_read: u64,
+    /// The total size of the distinct files of the witness database that were opened.
+    pub(crate) unique_bytes: u64,
+}
+
+impl WitnessReads {
+    /// Checks that `strace` is installed, before any test is traced with it.
+    pub(crate) fn check_strace() -> Result<()> {
+        let installed = Command::new("strace")
+            .arg("-V")
+            .output()
+            .is_ok_and(|output| output.status.success());
+        ensure!(
+            installed,
+            "`strace` is required to trace the witness database reads; install it, or drop `--trace-witness-reads`"
+        );
+        Ok(())
+    }
+
+    /// Returns the arguments that wrap a command with `strace`, appending the traced system calls
+    /// of the command and all of its children to the trace file.
+    pub(crate) fn strace_args(trace: &Path) -> Vec<String> {
+        let mut args = ["-f", "-y", "-qq", "-A", "-s", "0", "-e", TRACED_SYSCALLS]
+            .map(String::from)
+            .to_vec();
+        args.extend([
+            "-o".to_string(),
+            trace.display().to_string(),
+            "--".to_string(),
+        ]);
+        args
+    }
+
+    /// Reads the witness database reads from a trace written by `strace`.
+    ///
+    /// ## Takes
+    /// - `trace` - The path of the trace.
+    /// - `witness_dir` - The directory of the witness database.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The reads, or Err if the trace or the read files cannot be accessed.
+    pub(crate) fn read(trace: &Path, witness_dir: &Path) -> Result<Self> {
+        let witness_dir = witness_dir.canonicalize()?;
+        let (mut reads, files) = Self::parse(&fs::read_to_string(trace)?, &witness_dir);
+        for file in files {
+            reads.unique_bytes += fs::metadata(file)?.len();
+        }
+        Ok(reads)
+    }
+
+    /// Reads the distinct files of the witness database that were opened, from a trace written by
+    /// `strace`.
+    ///
+    /// ## Takes
+    /// - `trace` - The path of the trace.
+    /// - `witness_dir` - The directory of the witness database.
+    ///
+    /// ## Returns
+    /// - `Result<HashSet<PathBuf>>` - The canonical paths of the opened files.
+    pub(crate) fn opened_files(trace: &Path, witness_dir: &Path) -> Result<HashSet<PathBuf>> {
+        let witness_dir = witness_dir.canonicalize()?;
+        Ok(Self::parse(&fs::read_to_string(trace)?, &witness_dir).1)
+    }
+
+    /// Parses the reads of the files within the witness directory from a trace, returning the
+    /// distinct files that were opened alongside them.
+    fn parse(trace: &str, witness_dir: &Path) -> (Self, HashSet<PathBuf>) {
+        let mut reads = Self::default();
+        let mut files = HashSet::new();
+        // The calls that were interrupted by the calls of other processes, by PID.
+        let mut unfinished = HashMap::<&str, String>::new();
+        for line in trace.lines() {
+            let Some((pid, call)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let call = call.trim_start();
+            if let Some(call) = call.strip_suffix("<unfinished ...>") {
+                unfinished.insert(pid, call.to_string());
+                continue;
+            }
+            let call = match call.strip_prefix("<... ") {
+                Some(resumed) => match (unfinished.remove(pid), resumed.split_once(" resumed>")) {
+                    (Some(start), Some((_, end))) => format!("{start}{end}"),
+                    _ => continue,
+                },
+                None => call.to_string(),
+            };
+
+            let Some((name, rest)) = call.split_once('(') else {
+                continue;
+            };
+            let Some((args, ret)) = rest.rsplit_once(" = ") else {
+                continue;
+            };
+            let Some(count) = ret
+                .split(|c: char| !c.is_ascii_digit() && c != '-')
+                .next()
+                .and_then(|n| n.parse::<i64>().ok())
+                .filter(|n| *n >= 0)
+            else {
+                continue;
+            };
+            match name {
+                // The opened path is decorated on the returned file descriptor.
+                "open" | "openat" => {
+                    if let Some(path) = decorated_path(ret).filter(|p| p.starts_with(witness_dir)) {
+                        reads.key_reads += 1;
+                        files.insert(path);
+                    }
+                }
+                // The read path is decorated on the file descriptor argument.
+                "read" | "pread64"
+                    if decorated_path(args).is_some_and(|p| p.starts_with(witness_dir)) =>
+                {
+                    reads.bytes_read += count as u64;
+                }
+                _ => {}
+            }
+        }
+        reads.unique_keys = files.len() as u64;
+        (reads, files)
+    }
+
+    /// Returns the read amplification: the bytes read per byte of the distinct files read, or
+    /// `None` if nothing was read.
+    pub(crate) fn amplification(&self) -> Option<f64> {
+        (self.unique_bytes > 0).then(|| self.bytes_read as f64 / self.unique_bytes as f64)
+    }
+
+    /// Renders the witness database reads of the traced tests as a table, grouping the programs
+    /// that ran each fixture on each platform together.
+    pub(crate) fn table(results: &[TestResult]) -> TableStruct {
+        let mut traced = results
+            .iter()
+            .filter_map(|r| Some((r, r.witness_reads.as_ref()?)))
+            .collect::<Vec<_>>();
+        traced.sort_by_cached_key(|(r, _)| {
+            (
+                r.fixture.clone(),
+                r.platform.to_string(),
+                r.program.to_string(),
+            )
+        });
+        traced
+            .into_iter()
+            .map(|(r, reads)| {
+                vec![
+                    r.fixture.clone().cell(),
+                    r.platform.magenta().to_string().cell(),
+                    r.program.cyan().to_string().cell(),
+                    reads.key_reads.cell().justify(Justify::Right),
+                    reads.unique_keys.cell().justify(Justify::Right),
+                    Bytes(reads.bytes_read).cell().justify(Justify::Right),
+                    reads
+                        .amplification()
+                        .map_or("-".to_string(), |a| format!("{a:.2}x"))
+                        .cell()
+                        .justify(Justify::Right),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Fixture".cell(),
+                "Platform".cell(),
+                "Program".cell(),
+                "Key Reads".cell(),
+                "Unique Keys".cell(),
+                "Bytes Read".cell(),
+                "Amplification".cell(),
+            ])
+            .bold(true)
+    }
+}
+
+/// Returns the path that `strace -y` decorates a file descriptor with, e.g. `/a/b` in `3</a/b>`.
+fn decorated_path(s: &str) -> Option<PathBuf> {
+    let (_, rest) = s.split_once('<')?;
+    let (path, _) = rest.split_once('>')?;
+    Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_witness_reads() {
+        let trace = r#"
+101 openat(AT_FDCWD</w>, "/w/db/0x01.txt", O_RDONLY|O_CLOEXEC) = 3</w/db/0x01.txt>
+101 read(3</w/db/0x01.txt>, ""..., 512) = 100
+101 read(3</w/db/0x01.txt>, "", 412) = 0
+101 openat(AT_FDCWD</w>, "/w/db/0x02.txt", O_RDONLY|O_CLOEXEC) = 4</w/db/0x02.txt>
+102 openat(AT_FDCWD</w>, "/w/db/0x01.txt", O_RDONLY|O_CLOEXEC) = 5</w/db/0x01.txt>
+101 pread64(4</w/db/0x02.txt>,  <unfinished ...>
+102 read(5</w/db/0x01.txt>, ""..., 512) = 100
+101 <... pread64 resumed>""..., 4096, 0) = 60
+102 openat(AT_FDCWD</w>, "/w/db/0x03.txt", O_RDONLY) = -1 ENOENT (No such file or directory)
+102 openat(AT_FDCWD</w>, "/etc/hosts", O_RDONLY) = 6</etc/hosts>
+102 read(6</etc/hosts>, ""..., 4096) = 200
+"#;
+        let (reads, files) = WitnessReads::parse(trace, Path::new("/w/db"));
+        assert_eq!(
+            reads,
+            WitnessReads {
+                key_reads: 3,
+                unique_keys: 2,
+                bytes_read: 260,
+                unique_bytes: 0,
+            }
+        );
+        assert_eq!(
+            files,
+            HashSet::from(["/w/db/0x01.txt", "/w/db/0x02.txt"].map(PathBuf::from))
+        );
+    }
+}
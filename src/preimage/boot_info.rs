@@ -0,0 +1,152 @@
+//! Contains the [BootInfo] encoder, which maps the boot information of a fault proof program onto
+//! the local keys of the preimage oracle.
+
+use super::local_key;
+use crate::{fixture::FixtureInputs, genesis::GenesisInfo, registry::program::ProgramHostInputs};
+use alloy_primitives::B256;
+use color_eyre::Result;
+use std::{collections::HashMap, fs};
+
+/// The local index of the L1 head hash.
+pub(crate) const L1_HEAD_LOCAL_INDEX: u64 = 1;
+/// The local index of the starting L2 output root.
+pub(crate) const L2_OUTPUT_ROOT_LOCAL_INDEX: u64 = 2;
+/// The local index of the disputed L2 output root claim.
+pub(crate) const L2_CLAIM_LOCAL_INDEX: u64 = 3;
+/// The local index of the L2 block number of the claim.
+pub(crate) const L2_CLAIM_BLOCK_NUMBER_LOCAL_INDEX: u64 = 4;
+/// The local index of the L2 chain ID.
+pub(crate) const L2_CHAIN_ID_LOCAL_INDEX: u64 = 5;
+/// The local index of the L2 chain configuration.
+pub(crate) const L2_CHAIN_CONFIG_LOCAL_INDEX: u64 = 6;
+/// The local index of the rollup configuration.
+pub(crate) const ROLLUP_CONFIG_LOCAL_INDEX: u64 = 7;
+
+/// The chain ID that informs the client to read the chain configurations from the oracle rather
+/// than its builtin registry.
+pub(crate) const CUSTOM_CHAIN_ID_INDICATOR: u64 = u64::MAX;
+
+/// The boot information of a fault proof program, served through the local keys of the oracle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BootInfo {
+    /// The inputs of the fixture.
+    pub(crate) inputs: FixtureInputs,
+    /// The JSON encoded L2 chain configuration, if the chain is custom.
+    pub(crate) chain_config: Option<Vec<u8>>,
+    /// The JSON encoded rollup configuration, if the chain is custom.
+    pub(crate) rollup_config: Option<Vec<u8>>,
+}
+
+impl BootInfo {
+    /// Creates a new [BootInfo] for a custom chain from the given [ProgramHostInputs], reading the
+    /// chain configurations from disk. The L2 chain configuration is parsed out of the genesis,
+    /// unless it was already.
+    pub(crate) fn from_host_inputs(inputs: &ProgramHostInputs) -> Result<Self> {
+        let chain_config = match inputs.chain_config_path.as_ref() {
+            Some(path) => fs::read(path)?,
+            None => GenesisInfo::parse(&inputs.genesis_path)?.chain_config,
+        };
+
+        Ok(Self {
+            inputs: inputs.fixture_inputs.clone(),
+            chain_config: Some(chain_config),
+            rollup_config: Some(fs::read(&inputs.rollup_cfg_path)?),
+        })
+    }
+
+    /// Encodes the [BootInfo] into the local preimages of the oracle.
+    ///
+    /// ## Returns
+    /// - `HashMap<B256, Vec<u8>>` - The local preimages, keyed by their local preimage key.
+    pub(crate) fn encode(&self) -> HashMap<B256, Vec<u8>> {
+        let custom = self.chain_config.is_some() || self.rollup_config.is_some();
+        let chain_id = if custom {
+            CUSTOM_CHAIN_ID_INDICATOR
+        } else {
+            self.inputs.l2_chain_id
+        };
+
+        let mut local = HashMap::from([
+            (local_key(L1_HEAD_LOCAL_INDEX), self.inputs.l1_head.to_vec()),
+            (
+                local_key(L2_OUTPUT_ROOT_LOCAL_INDEX),
+                self.inputs.l2_output_root.to_vec(),
+            ),
+            (
+                local_key(L2_CLAIM_LOCAL_INDEX),
+                self.inputs.l2_claim.to_vec(),
+            ),
+            (
+                local_key(L2_CLAIM_BLOCK_NUMBER_LOCAL_INDEX),
+                self.inputs.l2_block_number.to_be_bytes().to_vec(),
+            ),
+            (
+                local_key(L2_CHAIN_ID_LOCAL_INDEX),
+                chain_id.to_be_bytes().to_vec(),
+            ),
+        ]);
+        if let Some(chain_config) = self.chain_config.as_ref() {
+            local.insert(local_key(L2_CHAIN_CONFIG_LOCAL_INDEX), chain_config.clone());
+        }
+        if let Some(rollup_config) = self.rollup_config.as_ref() {
+            local.insert(local_key(ROLLUP_CONFIG_LOCAL_INDEX), rollup_config.clone());
+        }
+        local
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::b256;
+
+    #[test]
+    fn encode_boot_info() {
+        let boot_info = BootInfo {
+            inputs: FixtureInputs {
+                l1_head: B256::repeat_byte(0x01),
+                l2_block_number: 20,
+                l2_claim: B256::repeat_byte(0x03),
+                l2_output_root: B256::repeat_byte(0x02),
+                l2_head: B256::repeat_byte(0x04),
+                l2_chain_id: 1337,
+                interop: None,
+            },
+            chain_config: None,
+            rollup_config: None,
+        };
+
+        let local = boot_info.encode();
+        assert_eq!(local.len(), 5);
+        assert_eq!(
+            local_key(L2_CLAIM_BLOCK_NUMBER_LOCAL_INDEX),
+            b256!("0100000000000000000000000000000000000000000000000000000000000004")
+        );
+        assert_eq!(
+            local[&local_key(L1_HEAD_LOCAL_INDEX)],
+            B256::repeat_byte(0x01).to_vec()
+        );
+        assert_eq!(
+            local[&local_key(L2_CLAIM_BLOCK_NUMBER_LOCAL_INDEX)],
+            20u64.to_be_bytes().to_vec()
+        );
+        assert_eq!(
+            local[&local_key(L2_CHAIN_ID_LOCAL_INDEX)],
+            1337u64.to_be_bytes().to_vec()
+        );
+
+        let custom = BootInfo {
+            rollup_config: Some(b"{}".to_vec()),
+            ..boot_info
+        }
+        .encode();
+        assert_eq!(
+            custom[&local_key(L2_CHAIN_ID_LOCAL_INDEX)],
+            CUSTOM_CHAIN_ID_INDICATOR.to_be_bytes().to_vec()
+        );
+        assert_eq!(
+            custom[&local_key(ROLLUP_CONFIG_LOCAL_INDEX)],
+            b"{}".to_vec()
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Contains the [PreimageFault]s that the builtin preimage server can inject into its channels, to
+//! check that fault proof program clients fail safe when their host misbehaves.
+
+use clap::ValueEnum;
+use std::{fmt::Display, time::Duration};
+
+/// The delay added to every preimage response by [PreimageFault::Delay].
+pub(crate) const FAULT_DELAY: Duration = Duration::from_millis(50);
+
+/// A fault injected into the channels of the builtin preimage server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub(crate) enum PreimageFault {
+    /// Delay every preimage response; the client must still produce the expected result.
+    Delay,
+    /// Append a byte to every global preimage; the client must fail rather than accept the claim.
+    WrongLength,
+    /// Close the hint channel instead of acknowledging the first hint; the client must fail rather
+    /// than accept the claim.
+    DroppedHints,
+}
+
+impl PreimageFault {
+    /// Returns whether or not the fault corrupts the data served to the client, such that the
+    /// client must fail safe rather than produce its expected result.
+    pub(crate) fn corrupts(&self) -> bool {
+        !matches!(self, Self::Delay)
+    }
+}
+
+impl Display for PreimageFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delay => write!(f, "delay"),
+            Self::WrongLength => write!(f, "wrong-length"),
+            Self::DroppedHints => write!(f, "dropped-hints"),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Contains `fpt`'s builtin preimage oracle server, used to drive fault proof program clients on a
+//! platform without the program's host binary.
+
+use alloy_primitives::B256;
+
+pub(crate) mod boot_info;
+pub(crate) mod faults;
+pub(crate) mod server;
+
+/// The type byte of a local preimage key.
+pub(crate) const LOCAL_KEY_TYPE: u8 = 1;
+
+/// The type byte of a keccak256 preimage key.
+pub(crate) const KECCAK256_KEY_TYPE: u8 = 2;
+
+/// Returns the local preimage key for the given local index.
+///
+/// ## Takes
+/// - `index` - The local index of the key.
+///
+/// ## Returns
+/// - `B256` - The preimage key, with the local key type byte prefixed and the big-endian index in
+///   the low 8 bytes.
+pub(crate) fn local_key(index: u64) -> B256 {
+    let mut key = B256::ZERO;
+    key[0] = LOCAL_KEY_TYPE;
+    key[24..].copy_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Returns the keccak256 preimage key for the given hash, e.g. the key of a block header by its
+/// hash.
+pub(crate) fn keccak256_key(hash: B256) -> B256 {
+    let mut key = hash;
+    key[0] = KECCAK256_KEY_TYPE;
+    key
+}
@@ -0,0 +1,202 @@
+//! Contains the [PreimageServer], which serves preimages from a fixture's witness database over the
+//! host side of the preimage oracle and hint channels.
+
+use super::{
+    boot_info::BootInfo,
+    faults::{PreimageFault, FAULT_DELAY},
+    LOCAL_KEY_TYPE,
+};
+use alloy_primitives::{hex, B256};
+use color_eyre::{eyre::eyre, Result};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read, Write},
+    os::fd::FromRawFd,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+};
+use tracing::{debug, trace};
+
+/// The file descriptor that the host reads hints from.
+const HINT_READ_FD: i32 = 3;
+/// The file descriptor that the host acknowledges hints on.
+const HINT_WRITE_FD: i32 = 4;
+/// The file descriptor that the host reads preimage requests from.
+const PREIMAGE_READ_FD: i32 = 5;
+/// The file descriptor that the host writes preimages to.
+const PREIMAGE_WRITE_FD: i32 = 6;
+
+/// A preimage server backed by a disk witness database and the encoded [BootInfo].
+#[derive(Debug)]
+pub(crate) struct PreimageServer {
+    /// The local preimages, containing the boot information.
+    local: HashMap<B256, Vec<u8>>,
+    /// The directory of the witness database.
+    kv_dir: PathBuf,
+    /// The fault to inject into the channels, if any.
+    fault: Option<PreimageFault>,
+}
+
+impl PreimageServer {
+    /// Create a new [PreimageServer] serving the given [BootInfo] and witness database.
+    pub(crate) fn new(boot_info: &BootInfo, kv_dir: PathBuf) -> Self {
+        Self {
+            local: boot_info.encode(),
+            kv_dir,
+            fault: None,
+        }
+    }
+
+    /// Injects the given fault into the channels served by the [PreimageServer].
+    pub(crate) fn with_fault(mut self, fault: Option<PreimageFault>) -> Self {
+        self.fault = fault;
+        self
+    }
+
+    /// Fetches the preimage for the given key.
+    ///
+    /// ## Takes
+    /// - `key` - The preimage key.
+    ///
+    /// ## Returns
+    /// - `Result<Vec<u8>>` - The preimage, or an error if it is not present.
+    pub(crate) fn get(&self, key: B256) -> Result<Vec<u8>> {
+        if key[0] == LOCAL_KEY_TYPE {
+            return self
+                .local
+                .get(&key)
+                .cloned()
+                .ok_or(eyre!("Unknown local preimage key: {key}"));
+        }
+
+        let path = self.kv_dir.join(format!("{key}.txt"));
+        let encoded =
+            fs::read_to_string(&path).map_err(|e| eyre!("Missing preimage for {key}: {e}"))?;
+        Ok(hex::decode(encoded.trim())?)
+    }
+
+    /// Serves the hint and preimage channels on the host file descriptors (3-6) until the client
+    /// closes them.
+    pub(crate) async fn serve_host_fds(self) -> Result<()> {
+        // SAFETY: The platform opens the hint and preimage pipes on file descriptors 3-6 when it
+        // spawns the host, and nothing else in this process owns them.
+        let (hint_read, hint_write, preimage_read, preimage_write) = unsafe {
+            (
+                File::from_raw_fd(HINT_READ_FD),
+                File::from_raw_fd(HINT_WRITE_FD),
+                File::from_raw_fd(PREIMAGE_READ_FD),
+                File::from_raw_fd(PREIMAGE_WRITE_FD),
+            )
+        };
+
+        let server = Arc::new(self);
+        let fault = server.fault;
+        let hints = thread::spawn(move || route_hints(hint_read, hint_write, fault));
+        let preimages = {
+            let server = server.clone();
+            thread::spawn(move || server.route_preimages(preimage_read, preimage_write))
+        };
+
+        tokio::task::spawn_blocking(move || {
+            hints.join().map_err(|_| eyre!("Hint router panicked"))??;
+            preimages
+                .join()
+                .map_err(|_| eyre!("Preimage router panicked"))?
+        })
+        .await?
+    }
+
+    /// Answers preimage requests on the preimage channel until it is closed.
+    fn route_preimages(&self, mut reader: impl Read, mut writer: impl Write) -> Result<()> {
+        let mut key = B256::ZERO;
+        loop {
+            if let Err(e) = reader.read_exact(key.as_mut_slice()) {
+                if e.kind() == io::ErrorKind::UnexpectedEof {
+                    debug!(target: "preimage-server", "Preimage channel closed");
+                    return Ok(());
+                }
+                return Err(e.into());
+            }
+
+            let mut preimage = self.get(key)?;
+            match self.fault {
+                Some(PreimageFault::Delay) => thread::sleep(FAULT_DELAY),
+                Some(PreimageFault::WrongLength) if key[0] != LOCAL_KEY_TYPE => preimage.push(0),
+                _ => {}
+            }
+            trace!(target: "preimage-server", "Serving preimage for {key} ({} bytes)", preimage.len());
+            writer.write_all(&(preimage.len() as u64).to_be_bytes())?;
+            writer.write_all(&preimage)?;
+            writer.flush()?;
+        }
+    }
+}
+
+/// Acknowledges hints on the hint channel until it is closed. The witness database is complete, so
+/// hints are not acted upon.
+fn route_hints(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    fault: Option<PreimageFault>,
+) -> Result<()> {
+    let mut len = [0u8; 4];
+    loop {
+        if let Err(e) = reader.read_exact(&mut len) {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                debug!(target: "preimage-server", "Hint channel closed");
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+
+        let mut hint = vec![0u8; u32::from_be_bytes(len) as usize];
+        reader.read_exact(&mut hint)?;
+        trace!(target: "preimage-server", "Received hint: {}", String::from_utf8_lossy(&hint));
+        if fault == Some(PreimageFault::DroppedHints) {
+            debug!(target: "preimage-server", "Dropping hint; closing the hint channel");
+            return Ok(());
+        }
+        writer.write_all(&[1])?;
+        writer.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::FixtureInputs;
+    use std::io::Cursor;
+
+    #[test]
+    fn injects_wrong_length_preimages() {
+        let kv_dir = tempfile::tempdir().unwrap();
+        let key = B256::repeat_byte(0x02);
+        fs::write(kv_dir.path().join(format!("{key}.txt")), "deadbeef").unwrap();
+        let boot_info = BootInfo {
+            inputs: FixtureInputs::default(),
+            chain_config: None,
+            rollup_config: None,
+        };
+
+        let serve = |fault| {
+            let server =
+                PreimageServer::new(&boot_info, kv_dir.path().to_path_buf()).with_fault(fault);
+            let mut response = Vec::new();
+            server
+                .route_preimages(Cursor::new(key.to_vec()), &mut response)
+                .unwrap();
+            response
+        };
+
+        assert_eq!(
+            serve(None),
+            [&4u64.to_be_bytes()[..], &hex!("deadbeef")].concat()
+        );
+        assert_eq!(
+            serve(Some(PreimageFault::WrongLength)),
+            [&5u64.to_be_bytes()[..], &hex!("deadbeef00")].concat()
+        );
+    }
+}
@@ -0,0 +1,545 @@
+//! Contains helpers for supervising the child processes spawned by platforms.
+
+use crate::pipeline::{
+    heap_profile::{HeapProfile, HeapProfiler},
+    witness_reads::WitnessReads,
+};
+use color_eyre::Result;
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+/// The maximum interval between checks for stalled processes.
+const MAX_STALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The interval between samples of the resident set size of a process tree.
+const RSS_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The number of bytes at the end of each output stream that are kept in memory for diagnostics.
+pub(crate) const OUTPUT_TAIL_SIZE: usize = 64 * 1024;
+
+/// Options for supervising a child process.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProcessOptions {
+    /// The duration without any output after which the process is considered stalled.
+    pub(crate) stall_timeout: Option<Duration>,
+    /// Whether or not to kill the process once it is considered stalled.
+    pub(crate) kill_stalled: bool,
+    /// The flag to raise once the process is considered stalled, whether or not it is killed.
+    pub(crate) stalled: Option<Arc<AtomicBool>>,
+    /// The CPU core to pin the process (and its children) to.
+    pub(crate) cpu: Option<usize>,
+    /// Whether or not to run the process in its own process group, shielding it from signals sent
+    /// to `fpt`'s (e.g. Ctrl-C in the terminal).
+    pub(crate) own_process_group: bool,
+    /// The channel to forward each line of the process' output to, as it is produced.
+    pub(crate) output_lines: Option<UnboundedSender<String>>,
+    /// The file to append an `strace` of the file reads of the process (and its children) to.
+    pub(crate) trace_reads: Option<PathBuf>,
+    /// The gauge to record the peak resident set size of the process (and its children) in, in
+    /// bytes.
+    pub(crate) peak_rss: Option<Arc<AtomicU64>>,
+    /// The profiling of the process' heap, if any.
+    pub(crate) heap_profile: Option<HeapProfile>,
+    /// The token that cancels the process, killing it (and its children) once cancelled.
+    pub(crate) cancel: Option<CancellationToken>,
+}
+
+/// The error returned when a supervised process was killed after stalling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ProcessStalled {
+    /// The duration that the process went without producing output.
+    pub(crate) idle: Duration,
+}
+
+impl Display for ProcessStalled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Process stalled with no output for {}s",
+            self.idle.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for ProcessStalled {}
+
+/// The error returned when a supervised process was killed after its [CancellationToken] was
+/// cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ProcessCancelled;
+
+impl Display for ProcessCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Process was cancelled")
+    }
+}
+
+impl std::error::Error for ProcessCancelled {}
+
+/// The paths that a supervised process' output streams are written to.
+#[derive(Debug, Clone)]
+pub(crate) struct OutputLogs {
+    /// The path to write stdout to.
+    pub(crate) stdout: PathBuf,
+    /// The path to write stderr to.
+    pub(crate) stderr: PathBuf,
+}
+
+impl OutputLogs {
+    /// Create a new [OutputLogs] writing `<prefix>stdout.log` and `<prefix>stderr.log` within the
+    /// given directory.
+    pub(crate) fn new(dir: &Path, prefix: &str) -> Self {
+        Self {
+            stdout: dir.join(format!("{prefix}stdout.log")),
+            stderr: dir.join(format!("{prefix}stderr.log")),
+        }
+    }
+}
+
+/// The output of a supervised process. The full output streams are written to its [OutputLogs];
+/// only their tails are kept in memory.
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessOutput {
+    /// The exit status of the process.
+    pub(crate) status: ExitStatus,
+    /// The last [OUTPUT_TAIL_SIZE] bytes of stdout.
+    pub(crate) stdout_tail: Vec<u8>,
+    /// The last [OUTPUT_TAIL_SIZE] bytes of stderr.
+    pub(crate) stderr_tail: Vec<u8>,
+}
+
+/// Runs the command to completion, appending its output to the log files while watching it for
+/// stalls.
+///
+/// ## Takes
+/// - `cmd` - The command to run.
+/// - `opts` - The supervision options.
+/// - `logs` - The paths to stream the output of the process to.
+///
+/// ## Returns
+/// - `Result<ProcessOutput>` - The output of the process. Errors with [ProcessStalled] if the
+///   process stalled and was killed, or with [ProcessCancelled] if it was cancelled.
+pub(crate) async fn run_supervised(
+    cmd: Command,
+    opts: &ProcessOptions,
+    logs: &OutputLogs,
+) -> Result<ProcessOutput> {
+    let cmd = match opts.heap_profile.as_ref() {
+        Some(profile) if profile.profiler == HeapProfiler::Heaptrack => {
+            wrapped(cmd, "heaptrack", profile.heaptrack_args())
+        }
+        Some(profile) => {
+            let mut cmd = cmd;
+            cmd.envs(profile.jemalloc_env());
+            cmd
+        }
+        None => cmd,
+    };
+    let cmd = match opts.trace_reads.as_deref() {
+        Some(trace) => wrapped(cmd, "strace", WitnessReads::strace_args(trace)),
+        None => cmd,
+    };
+    let mut cmd = match opts.cpu {
+        Some(cpu) => wrapped(cmd, "taskset", ["-c".to_string(), cpu.to_string()]),
+        None => cmd,
+    };
+    if opts.own_process_group {
+        cmd.as_std_mut().process_group(0);
+    }
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // Kill the process and any processes that it spawned if supervision is abandoned (e.g. when a
+    // test times out), or once it is cancelled or stalls and is killed.
+    let mut tree_guard = ProcessTreeGuard(child.id());
+
+    let last_activity = Arc::new(Mutex::new(Instant::now()));
+    let stdout = child.stdout.take().map(|s| {
+        collect(
+            s,
+            logs.stdout.clone(),
+            last_activity.clone(),
+            opts.output_lines.clone(),
+        )
+    });
+    let stderr = child.stderr.take().map(|s| {
+        collect(
+            s,
+            logs.stderr.clone(),
+            last_activity.clone(),
+            opts.output_lines.clone(),
+        )
+    });
+
+    let check_interval = opts.stall_timeout.map_or(MAX_STALL_CHECK_INTERVAL, |t| {
+        (t / 4).clamp(Duration::from_millis(100), MAX_STALL_CHECK_INTERVAL)
+    });
+    let mut ticker = tokio::time::interval(check_interval);
+    let mut rss_ticker = tokio::time::interval(RSS_SAMPLE_INTERVAL);
+    let mut warned = false;
+
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => {
+                tree_guard.0 = None;
+                break status?;
+            }
+            _ = ticker.tick(), if opts.stall_timeout.is_some() => {
+                let idle = last_activity.lock().expect("Poisoned lock").elapsed();
+                if opts.stall_timeout.is_some_and(|t| idle < t) {
+                    warned = false;
+                    continue;
+                }
+
+                if let Some(stalled) = opts.stalled.as_ref() {
+                    stalled.store(true, Ordering::Relaxed);
+                }
+                if opts.kill_stalled {
+                    tree_guard.kill();
+                    child.kill().await?;
+                    join(stdout).await?;
+                    join(stderr).await?;
+                    return Err(ProcessStalled { idle }.into());
+                }
+                if !warned {
+                    warn!(target: "process", "Process appears stalled; no output for {}s", idle.as_secs());
+                    warned = true;
+                }
+            }
+            _ = cancelled(opts.cancel.as_ref()) => {
+                // The tree is killed before the process is reaped; its descendants would be
+                // reparented, and no longer found, once it is.
+                tree_guard.kill();
+                child.kill().await?;
+                join(stdout).await?;
+                join(stderr).await?;
+                return Err(ProcessCancelled.into());
+            }
+            _ = rss_ticker.tick(), if opts.peak_rss.is_some() => {
+                if let (Some(gauge), Some(pid)) = (opts.peak_rss.as_ref(), tree_guard.0) {
+                    gauge.fetch_max(tree_rss(pid), Ordering::Relaxed);
+                }
+            }
+        }
+    };
+
+    Ok(ProcessOutput {
+        status,
+        stdout_tail: join(stdout).await?,
+        stderr_tail: join(stderr).await?,
+    })
+}
+
+/// Resolves once the token is cancelled, or never if there is no token.
+async fn cancelled(cancel: Option<&CancellationToken>) {
+    match cancel {
+        Some(cancel) => cancel.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// A guard that kills a process and all of its descendants when dropped, unless the process has
+/// already exited.
+#[derive(Debug)]
+struct ProcessTreeGuard(Option<u32>);
+
+impl ProcessTreeGuard {
+    /// Kills the process and all of its descendants now, if the process has not already exited.
+    /// Must be called before the process is reaped, while its PID still identifies it.
+    fn kill(&mut self) {
+        if let Some(pid) = self.0.take() {
+            kill_tree(pid);
+        }
+    }
+}
+
+impl Drop for ProcessTreeGuard {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Returns the process and all of its descendants, found by walking the parent PIDs in `/proc`.
+fn process_tree(pid: u32) -> Vec<u32> {
+    let parents = std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.ok()?.file_name().to_str()?.parse::<u32>().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            // The command name may contain spaces, so the fields are read after its last `)`.
+            let ppid = stat
+                .rsplit_once(')')?
+                .1
+                .split_whitespace()
+                .nth(1)?
+                .parse()
+                .ok()?;
+            Some((pid, ppid))
+        })
+        .collect::<Vec<(u32, u32)>>();
+
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(
+            parents
+                .iter()
+                .filter(|(_, ppid)| *ppid == parent)
+                .map(|(pid, _)| *pid),
+        );
+        i += 1;
+    }
+    tree
+}
+
+/// Returns the sum of the peak resident set sizes of the process and all of its descendants, in
+/// bytes, as read from their `VmHWM` in `/proc`.
+fn tree_rss(pid: u32) -> u64 {
+    process_tree(pid)
+        .into_iter()
+        .filter_map(|pid| {
+            let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+            let kib = status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmHWM:"))?
+                .trim()
+                .strip_suffix("kB")?
+                .trim()
+                .parse::<u64>()
+                .ok()?;
+            Some(kib * 1024)
+        })
+        .sum()
+}
+
+/// Sends `SIGKILL` to the process and all of its descendants. Descendants are stopped before being
+/// killed, so that none can spawn new processes or be reparented in the meantime.
+fn kill_tree(pid: u32) {
+    let tree = process_tree(pid);
+    for signal in [libc::SIGSTOP, libc::SIGKILL] {
+        for pid in tree.iter() {
+            // SAFETY: `kill` has no memory safety preconditions.
+            unsafe { libc::kill(*pid as libc::pid_t, signal) };
+        }
+    }
+}
+
+/// Wraps the command with a program that runs it, e.g. `taskset` to pin it and all of its children
+/// to a CPU core, keeping its working directory and environment.
+fn wrapped(cmd: Command, program: &str, args: impl IntoIterator<Item = String>) -> Command {
+    let cmd = cmd.as_std();
+    let mut wrapped = Command::new(program);
+    wrapped
+        .args(args)
+        .arg(cmd.get_program())
+        .args(cmd.get_args());
+    if let Some(dir) = cmd.get_current_dir() {
+        wrapped.current_dir(dir);
+    }
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => wrapped.env(key, value),
+            None => wrapped.env_remove(key),
+        };
+    }
+    wrapped
+}
+
+/// Spawns a task that appends the output to the log file until it is closed, recording the time
+/// of the last activity and returning the last [OUTPUT_TAIL_SIZE] bytes. If a line channel is
+/// given, each complete line of output is forwarded to it.
+fn collect(
+    mut stream: impl AsyncRead + Unpin + Send + 'static,
+    log: PathBuf,
+    last_activity: Arc<Mutex<Instant>>,
+    lines: Option<UnboundedSender<String>>,
+) -> JoinHandle<std::io::Result<Vec<u8>>> {
+    tokio::spawn(async move {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)
+            .await?;
+        let mut tail = VecDeque::with_capacity(OUTPUT_TAIL_SIZE);
+        let mut partial_line = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                file.flush().await?;
+                return Ok(tail.into());
+            }
+            file.write_all(&buf[..n]).await?;
+
+            if let Some(lines) = lines.as_ref() {
+                partial_line.extend_from_slice(&buf[..n]);
+                while let Some(end) = partial_line.iter().position(|b| *b == b'\n') {
+                    let line = partial_line.drain(..=end).collect::<Vec<_>>();
+                    let _ = lines.send(String::from_utf8_lossy(&line).trim_end().to_string());
+                }
+            }
+
+            let overflow = (tail.len() + n).saturating_sub(OUTPUT_TAIL_SIZE);
+            tail.drain(..overflow.min(tail.len()));
+            tail.extend(&buf[n.saturating_sub(OUTPUT_TAIL_SIZE)..n]);
+            *last_activity.lock().expect("Poisoned lock") = Instant::now();
+        }
+    })
+}
+
+/// Joins a stream collection task, if the stream was present.
+async fn join(handle: Option<JoinHandle<std::io::Result<Vec<u8>>>>) -> Result<Vec<u8>> {
+    match handle {
+        Some(handle) => Ok(handle.await??),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn kills_stalled_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = OutputLogs::new(dir.path(), "");
+        let opts = ProcessOptions {
+            stall_timeout: Some(Duration::from_millis(200)),
+            kill_stalled: true,
+            ..Default::default()
+        };
+
+        let pid_file = dir.path().join("grandchild.pid");
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(format!(
+            "echo started; sleep 10 & echo $! > {}; wait",
+            pid_file.display()
+        ));
+        let err = run_supervised(cmd, &opts, &logs).await.unwrap_err();
+        assert!(err.downcast_ref::<ProcessStalled>().is_some());
+        assert_killed(&pid_file).await;
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo done");
+        let output = run_supervised(cmd, &opts, &logs).await.unwrap();
+        assert_eq!(output.stdout_tail, b"done\n");
+        assert_eq!(std::fs::read(&logs.stdout).unwrap(), b"started\ndone\n");
+    }
+
+    #[tokio::test]
+    async fn flags_stalled_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = OutputLogs::new(dir.path(), "");
+        let stalled = Arc::new(AtomicBool::new(false));
+        let opts = ProcessOptions {
+            stall_timeout: Some(Duration::from_millis(200)),
+            stalled: Some(stalled.clone()),
+            ..Default::default()
+        };
+
+        // A stalled process that is not killed runs to completion, but is flagged.
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo started; sleep 1; echo done");
+        let output = run_supervised(cmd, &opts, &logs).await.unwrap();
+        assert!(output.status.success());
+        assert!(stalled.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn kills_cancelled_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = OutputLogs::new(dir.path(), "");
+        let cancel = CancellationToken::new();
+        let opts = ProcessOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+
+        let pid_file = dir.path().join("grandchild.pid");
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()));
+        let start = Instant::now();
+        let run = tokio::spawn(async move { run_supervised(cmd, &opts, &logs).await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        cancel.cancel();
+        let err = run.await.unwrap().unwrap_err();
+        assert!(err.downcast_ref::<ProcessCancelled>().is_some());
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_killed(&pid_file).await;
+    }
+
+    #[tokio::test]
+    async fn kills_process_tree_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = OutputLogs::new(dir.path(), "");
+        let pid_file = dir.path().join("grandchild.pid");
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()));
+        let opts = ProcessOptions::default();
+        let run = tokio::time::timeout(
+            Duration::from_millis(500),
+            run_supervised(cmd, &opts, &logs),
+        );
+        assert!(run.await.is_err());
+        assert_killed(&pid_file).await;
+    }
+
+    /// Asserts that the process whose PID was written to the file has been killed.
+    async fn assert_killed(pid_file: &Path) {
+        // Give the signals a moment to be delivered.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let pid = std::fs::read_to_string(pid_file).unwrap();
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        // The killed process is either reaped, or a zombie awaiting its reparented parent.
+        assert!(stat.map_or(true, |s| s
+            .rsplit_once(')')
+            .unwrap()
+            .1
+            .trim_start()
+            .starts_with('Z')));
+    }
+
+    #[tokio::test]
+    async fn bounds_output_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = OutputLogs::new(dir.path(), "");
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("head -c 200000 /dev/zero; printf end");
+        let output = run_supervised(cmd, &ProcessOptions::default(), &logs)
+            .await
+            .unwrap();
+        assert_eq!(output.stdout_tail.len(), OUTPUT_TAIL_SIZE);
+        assert!(output.stdout_tail.ends_with(b"end"));
+        assert_eq!(std::fs::metadata(&logs.stdout).unwrap().len(), 200_003);
+    }
+}
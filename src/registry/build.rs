@@ -1,125 +1,440 @@
 //! The builder for the [FPRegistry]'s contents.
 
-use super::{BuildInstructions, COMPONENTS_DIR};
+use super::{BuildInstructions, BuildStep};
+use crate::{
+    output,
+    process::{self, OutputLogs, ProcessOptions},
+    state::StateDir,
+};
+use alloy_primitives::{hex, keccak256, B256};
 use color_eyre::eyre::{ensure, eyre, Result};
+use git2::{build::CheckoutBuilder, build::RepoBuilder, FetchOptions, RemoteCallbacks, Repository};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
+    fs,
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 use tokio::process::Command;
 use tracing::debug;
 
 impl BuildInstructions {
+    /// Returns the build steps of the component, in the order that they are ran.
+    pub(crate) fn build_steps(&self) -> impl Iterator<Item = &BuildStep> {
+        self.step.iter().chain(self.steps.iter())
+    }
+
     /// Returns a specific artifact by name.
     pub(crate) fn get_artifact(&self, name: &str) -> Option<PathBuf> {
-        self.artifacts.get(name).map(|path| {
-            PathBuf::from(COMPONENTS_DIR)
-                .join(self.repo.clone())
-                .join(self.workdir.clone())
-                .join(path)
+        self.build_steps().find_map(|step| {
+            step.artifacts
+                .get(name)
+                .map(|path| self.repo_dir().join(&step.workdir).join(path))
         })
     }
 
-    /// Builds the binary artifact(s) from the cloned GitHub repository.
-    pub(crate) async fn try_build(&self) -> Result<()> {
+    /// Returns the directory that the GitHub repository is cloned into.
+    pub(crate) fn repo_dir(&self) -> PathBuf {
+        StateDir::get().components_dir().join(&self.repo)
+    }
+
+    /// Builds the binary artifact(s) from the cloned GitHub repository. Build steps are skipped
+    /// if their [BuildStamp] matches the checked out commit, the build command, and the artifacts
+    /// on disk.
+    ///
+    /// ## Takes
+    /// - `force` - Whether or not to rebuild every step, regardless of its stamp.
+    pub(crate) async fn try_build(&self, force: bool) -> Result<()> {
         // Clone the repository.
-        self.sync_repo().await?;
+        let commit = self.sync_repo().await?;
+
+        // Run each of the build steps whose artifacts are stale.
+        for step in self.build_steps() {
+            let stamp = BuildStamp::new(&commit, step, &self.repo_dir());
+            if !force
+                && stamp
+                    .as_ref()
+                    .is_some_and(|s| s.is_fresh(&self.repo_dir(), step))
+            {
+                debug!(
+                    target: "build",
+                    "Artifacts of {repo} (step: {step}) are up to date, skipping build.",
+                    repo = self.repo,
+                    step = step.stamp_name()
+                );
+                continue;
+            }
+
+            step.run(&self.repo_dir()).await.map_err(|e| {
+                eyre!(
+                    "Failed to build repository {repo} (step: {step}): {e}",
+                    repo = self.repo,
+                    step = step.stamp_name()
+                )
+            })?;
+
+            // Record the freshness of the freshly built artifacts.
+            BuildStamp::record(&commit, step, &self.repo_dir())?;
+        }
+
+        Ok(())
+    }
+
+    /// Clones the GitHub repository, or fetches it if it already exists, and checks out the
+    /// specified revision.
+    ///
+    /// ## Returns
+    /// - `Result<String>` - The checked out commit hash.
+    async fn sync_repo(&self) -> Result<String> {
+        let instructions = self.clone();
+        tokio::task::spawn_blocking(move || instructions.sync_repo_blocking()).await?
+    }
+
+    /// Blocking implementation of [Self::sync_repo].
+    fn sync_repo_blocking(&self) -> Result<String> {
+        let repo_dir = self.repo_dir();
+
+        // Render the transfer progress of the clone / fetch.
+        let progress = output::progress_bar(ProgressBar::new(0));
+        progress.set_style(
+            ProgressStyle::default_bar().template("{msg} {wide_bar} {pos}/{len} objects")?,
+        );
+        progress.set_message(format!("Syncing {}", self.repo));
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.transfer_progress(|stats| {
+            progress.set_length(stats.total_objects() as u64);
+            progress.set_position(stats.received_objects() as u64);
+            true
+        });
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+
+        let repo = if repo_dir.exists() {
+            debug!(
+                target: "build",
+                "Repository {repo} already exists, fetching upstream.",
+                repo = self.repo
+            );
+
+            let repo = Repository::open(&repo_dir)?;
+            repo.find_remote("origin")?
+                .fetch(
+                    &[
+                        "+refs/heads/*:refs/remotes/origin/*",
+                        "+refs/tags/*:refs/tags/*",
+                    ],
+                    Some(&mut fetch_options),
+                    None,
+                )
+                .map_err(|e| eyre!("Failed to fetch upstream {repo}: {e}", repo = self.repo))?;
+            repo
+        } else {
+            debug!(target: "build", "Cloning repository: {}", self.repo);
+
+            RepoBuilder::new()
+                .fetch_options(fetch_options)
+                .clone(&format!("https://github.com/{}", self.repo), &repo_dir)
+                .map_err(|e| eyre!("Failed to clone repository {repo}: {e}", repo = self.repo))?
+        };
+        progress.finish_and_clear();
+
+        // Resolve the revision as a tag, remote branch, or commit, and check it out.
+        let object = repo
+            .revparse_single(&format!("refs/tags/{}", self.rev))
+            .or_else(|_| repo.revparse_single(&format!("origin/{}", self.rev)))
+            .or_else(|_| repo.revparse_single(&self.rev))
+            .map_err(|e| eyre!("Failed to resolve revision {rev}: {e}", rev = self.rev))?;
+        let commit = object.peel_to_commit()?;
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))
+            .map_err(|e| eyre!("Failed to checkout revision {rev}: {e}", rev = self.rev))?;
+        repo.set_head_detached(commit.id())?;
+
+        debug!(
+            target: "build",
+            "Synced {repo} at {rev} ({commit}) successfully.",
+            repo = self.repo,
+            rev = self.rev,
+            commit = commit.id()
+        );
+
+        Ok(commit.id().to_string())
+    }
+}
+
+impl BuildStep {
+    /// Returns the hash of the step's build command and workdir, which invalidates its build stamp
+    /// when either changes.
+    pub(crate) fn cmd_hash(&self) -> B256 {
+        keccak256(format!("{}\0{}", self.workdir.display(), self.cmd))
+    }
+
+    /// Returns the name of the step, as shown in the build's logs.
+    pub(crate) fn stamp_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+
+    /// Returns the key that identifies the step's build stamp and logs within the repository:
+    /// the step's name, qualified by the hash of its build command and workdir. Components that
+    /// build from the same repository each keep their own stamp, while identical steps share one.
+    pub(crate) fn stamp_key(&self) -> String {
+        format!(
+            "{}-{}",
+            self.stamp_name(),
+            hex::encode(&self.cmd_hash()[..8])
+        )
+    }
+
+    /// Runs the build command of the step within the given repository directory.
+    ///
+    /// The output of the build is written to `<key>.stdout.log` and `<key>.stderr.log` within
+    /// the repository's [BUILD_STAMP_DIR], where `<key>` is the step's [Self::stamp_key].
+    async fn run(&self, repo_dir: &Path) -> Result<()> {
+        let log_dir = repo_dir.join(BUILD_STAMP_DIR);
+        fs::create_dir_all(&log_dir)?;
+        let logs = OutputLogs::new(&log_dir, &format!("{}.", self.stamp_key()));
+        for log in [&logs.stdout, &logs.stderr] {
+            if log.exists() {
+                fs::remove_file(log)?;
+            }
+        }
 
         // Navigate to the work directory and build the binaries.
         let commands = self.cmd.split(" && ").collect::<Vec<_>>();
         for command_str in commands {
             let args = command_str.split_whitespace().collect::<Vec<_>>();
-            let build_output = Command::new(args.first().ok_or(eyre!("Command is empty"))?)
-                .args(args.get(1..).ok_or(eyre!("No arguments"))?.iter())
-                .current_dir(
-                    PathBuf::from(COMPONENTS_DIR)
-                        .join(self.repo.clone())
-                        .join(self.workdir.clone()),
-                )
-                .output()
-                .await?;
+            let mut cmd = Command::new(args.first().ok_or(eyre!("Command is empty"))?);
+            cmd.args(args.get(1..).ok_or(eyre!("No arguments"))?.iter())
+                .current_dir(repo_dir.join(&self.workdir));
+            let build_output =
+                process::run_supervised(cmd, &ProcessOptions::default(), &logs).await?;
 
             if !build_output.status.success() {
-                io::stdout().write_all(&build_output.stdout).unwrap();
-                io::stderr().write_all(&build_output.stderr).unwrap();
+                io::stdout().write_all(&build_output.stdout_tail).unwrap();
+                io::stderr().write_all(&build_output.stderr_tail).unwrap();
             }
 
             // Check if the build was successful.
             ensure!(
                 build_output.status.success(),
-                "Failed to build repository: {repo}",
-                repo = self.repo
+                "Command `{command_str}` failed"
             );
         }
 
         Ok(())
     }
+}
 
-    /// Clones the GitHub repository for the specified revision.
-    async fn sync_repo(&self) -> Result<()> {
-        if PathBuf::from(COMPONENTS_DIR)
-            .join(self.repo.clone())
-            .exists()
-        {
-            debug!(
-                target: "build",
-                "Repository {repo} already exists, skipping clone.",
-                repo = self.repo
-            );
+/// The directory within a repository's git directory that build stamps are stored in.
+pub(crate) const BUILD_STAMP_DIR: &str = ".git/fpt-build";
 
-            let fetch_output = Command::new("git")
-                .arg("fetch")
-                .arg("origin")
-                .current_dir(PathBuf::from(COMPONENTS_DIR).join(self.repo.clone()))
-                .output()
-                .await?;
-            ensure!(
-                fetch_output.status.success(),
-                "Failed to fetch upstream: {repo}",
-                repo = self.repo
-            );
+/// A record of the artifacts produced by a build step at a given commit and with a given build
+/// command, used to skip rebuilding artifacts that are unchanged since they were built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildStamp {
+    /// The commit that the artifacts were built from.
+    commit: String,
+    /// The hash of the build command that the artifacts were built with.
+    cmd_hash: B256,
+    /// The modification times of the artifacts, in nanoseconds since the unix epoch.
+    artifacts: HashMap<String, u64>,
+}
 
-            let checkout_output = Command::new("git")
-                .arg("checkout")
-                .arg(self.rev.clone())
-                .current_dir(PathBuf::from(COMPONENTS_DIR).join(self.repo.clone()))
-                .output()
-                .await?;
-            ensure!(
-                checkout_output.status.success(),
-                "Failed to checkout revision: {rev}",
-                rev = self.rev
-            );
+impl BuildStamp {
+    /// Reads the stamp of the build step, if it was last built from the given commit with its
+    /// current build command.
+    fn new(commit: &str, step: &BuildStep, repo_dir: &Path) -> Option<Self> {
+        let stamp =
+            toml::from_str::<Self>(&fs::read_to_string(Self::path(step, repo_dir)).ok()?).ok()?;
+        (stamp.commit == commit && stamp.cmd_hash == step.cmd_hash()).then_some(stamp)
+    }
 
-            return Ok(());
-        }
+    /// Returns whether or not every artifact of the step is unchanged since it was stamped.
+    fn is_fresh(&self, repo_dir: &Path, step: &BuildStep) -> bool {
+        step.artifacts.iter().all(|(name, path)| {
+            mtime(&repo_dir.join(&step.workdir).join(path))
+                .is_some_and(|mtime| self.artifacts.get(name) == Some(&mtime))
+        })
+    }
 
-        debug!(target: "build", "Cloning repository: {}", self.repo);
+    /// Records the stamp of the build step's artifacts at the given commit.
+    fn record(commit: &str, step: &BuildStep, repo_dir: &Path) -> Result<()> {
+        let artifacts = step
+            .artifacts
+            .iter()
+            .filter_map(|(name, path)| {
+                Some((
+                    name.clone(),
+                    mtime(&repo_dir.join(&step.workdir).join(path))?,
+                ))
+            })
+            .collect();
+        let stamp = Self {
+            commit: commit.to_string(),
+            cmd_hash: step.cmd_hash(),
+            artifacts,
+        };
 
-        // Clone the repository.
-        let clone_output = Command::new("git")
-            .arg("clone")
-            .arg("-b")
-            .arg(self.rev.clone())
-            .arg(format!("https://github.com/{}", self.repo))
-            .arg(PathBuf::from(COMPONENTS_DIR).join(self.repo.clone()))
-            .output()
-            .await?;
-
-        // Check if the clone was successful.
-        ensure!(
-            clone_output.status.success(),
-            "Failed to clone repository: {repo}",
-            repo = self.repo
-        );
+        let path = Self::path(step, repo_dir);
+        fs::create_dir_all(path.parent().ok_or(eyre!("Invalid stamp path"))?)?;
+        fs::write(path, toml::to_string(&stamp)?)?;
+        Ok(())
+    }
 
-        debug!(
-            target: "build",
-            "Cloned {repo} at {rev} successfully.",
-            repo = self.repo,
-            rev = self.rev
+    /// Returns the path of the stamp of the build step.
+    fn path(step: &BuildStep, repo_dir: &Path) -> PathBuf {
+        repo_dir
+            .join(BUILD_STAMP_DIR)
+            .join(format!("{}.toml", step.stamp_key()))
+    }
+}
+
+/// Returns the files within the repository's [BUILD_STAMP_DIR] that no longer describe a build of
+/// any of the given steps: the stamps that were recorded at another commit or with another build
+/// command, and every file of the steps that were removed from the registry.
+///
+/// ## Takes
+/// - `repo_dir` - The directory of the repository.
+/// - `commit` - The currently checked out commit, if the repository is intact.
+/// - `steps` - The build steps of the repository within the registry.
+///
+/// ## Returns
+/// - `Vec<PathBuf>` - The stale files, sorted by path.
+pub(crate) fn stale_stamps<'a>(
+    repo_dir: &Path,
+    commit: Option<&str>,
+    steps: impl IntoIterator<Item = &'a BuildStep>,
+) -> Vec<PathBuf> {
+    let steps = steps
+        .into_iter()
+        .map(|step| (step.stamp_key(), step))
+        .collect::<HashMap<_, _>>();
+    let Ok(entries) = fs::read_dir(repo_dir.join(BUILD_STAMP_DIR)) else {
+        return Vec::new();
+    };
+
+    let mut stale = entries
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|path| {
+            // Stamps are named `<key>.toml`, and build logs `<key>.std{out,err}.log`.
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                return false;
+            };
+            let key = file_name.split('.').next().unwrap_or_default();
+            match steps.get(key) {
+                None => true,
+                Some(step) if file_name.ends_with(".toml") => {
+                    commit.is_none_or(|commit| BuildStamp::new(commit, step, repo_dir).is_none())
+                }
+                Some(_) => false,
+            }
+        })
+        .collect::<Vec<_>>();
+    stale.sort();
+    stale
+}
+
+/// Returns the modification time of the file, in nanoseconds since the unix epoch. TOML integers
+/// are 64-bit, which holds nanosecond timestamps until 2262.
+fn mtime(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos()
+        .try_into()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_stamp_round_trip() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let step = BuildStep {
+            workdir: PathBuf::from("."),
+            cmd: "make".to_string(),
+            artifacts: HashMap::from([("vm".to_string(), PathBuf::from("vm"))]),
+            ..Default::default()
+        };
+        fs::write(repo_dir.path().join("vm"), "v1").unwrap();
+        BuildStamp::record("abc", &step, repo_dir.path()).unwrap();
+
+        // The nanosecond modification times survive the round trip through TOML.
+        let stamp = BuildStamp::new("abc", &step, repo_dir.path()).unwrap();
+        assert_eq!(
+            stamp.artifacts["vm"],
+            mtime(&repo_dir.path().join("vm")).unwrap()
+        );
+        assert_eq!(
+            toml::from_str::<BuildStamp>(&toml::to_string(&stamp).unwrap()).unwrap(),
+            stamp
         );
+    }
 
-        Ok(())
+    #[test]
+    fn invalidate_build_stamps() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let mut step = BuildStep {
+            workdir: PathBuf::from("."),
+            cmd: "make".to_string(),
+            artifacts: HashMap::from([("vm".to_string(), PathBuf::from("bin/vm"))]),
+            ..Default::default()
+        };
+        fs::create_dir_all(repo_dir.path().join("bin")).unwrap();
+        fs::write(repo_dir.path().join("bin/vm"), "v1").unwrap();
+        BuildStamp::record("abc", &step, repo_dir.path()).unwrap();
+
+        let stamp = BuildStamp::new("abc", &step, repo_dir.path()).unwrap();
+        assert!(stamp.is_fresh(repo_dir.path(), &step));
+
+        // A different commit or build command invalidates the stamp.
+        assert!(BuildStamp::new("def", &step, repo_dir.path()).is_none());
+        step.cmd = "make release".to_string();
+        assert!(BuildStamp::new("abc", &step, repo_dir.path()).is_none());
+
+        // A missing artifact is stale.
+        fs::remove_file(repo_dir.path().join("bin/vm")).unwrap();
+        assert!(!stamp.is_fresh(repo_dir.path(), &step));
+    }
+
+    #[test]
+    fn stamps_shared_repository() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let build_step = |workdir: &str, cmd: &str, artifact: &str| BuildStep {
+            workdir: PathBuf::from(workdir),
+            cmd: cmd.to_string(),
+            artifacts: HashMap::from([("bin".to_string(), PathBuf::from(artifact))]),
+            ..Default::default()
+        };
+        let cannon = build_step("cannon", "make", "cannon");
+        let program = build_step("op-program", "make", "op-program");
+        for step in [&cannon, &program] {
+            let dir = repo_dir.path().join(&step.workdir);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(&step.artifacts["bin"]), "bin").unwrap();
+            BuildStamp::record("abc", step, repo_dir.path()).unwrap();
+        }
+
+        // Unnamed steps of components sharing a repository keep their own stamps.
+        assert_ne!(cannon.stamp_key(), program.stamp_key());
+        for step in [&cannon, &program] {
+            let stamp = BuildStamp::new("abc", step, repo_dir.path()).unwrap();
+            assert!(stamp.is_fresh(repo_dir.path(), step));
+        }
+        assert!(stale_stamps(repo_dir.path(), Some("abc"), [&cannon, &program]).is_empty());
+
+        // The stamps of a removed step are stale.
+        assert_eq!(
+            stale_stamps(repo_dir.path(), Some("abc"), [&cannon]),
+            vec![BuildStamp::path(&program, repo_dir.path())]
+        );
     }
 }
@@ -0,0 +1,212 @@
+//! Rendering and checking of the command lines that `fpt` constructs for the programs and
+//! platforms in the registry.
+//!
+//! The rendered commands are snapshotted in `snapshots/commands.txt`, so that changes to the
+//! flags passed to host binaries are reviewed explicitly. `fpt cmd --check` additionally checks the
+//! flags against the `--help` output of the built host binaries, catching regressions against
+//! upstream CLIs before an expensive run fails.
+
+use super::{
+    platform::cannon::Cannon,
+    program::{ProgramHostInputs, ProgramHostSource, ProgramKind},
+    FP_REGISTRY,
+};
+use crate::fixture::{FixtureInputs, InteropChain, InteropInputs};
+use alloy_primitives::B256;
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+use std::{collections::BTreeSet, fmt::Write, path::PathBuf};
+use tokio::process::Command;
+
+/// The programs with host command implementations.
+const PROGRAMS: [ProgramKind; 5] = [
+    ProgramKind::OpProgramNative,
+    ProgramKind::OpProgramMips,
+    ProgramKind::OpProgramRiscv,
+    ProgramKind::KonaNative,
+    ProgramKind::KonaRiscv,
+];
+
+/// Returns the fixed inputs that commands are rendered with, for each data source.
+fn canonical_inputs() -> Vec<(&'static str, ProgramHostInputs)> {
+    let fixture_inputs = FixtureInputs {
+        l1_head: B256::repeat_byte(0x11),
+        l2_block_number: 20,
+        l2_claim: B256::repeat_byte(0x22),
+        l2_output_root: B256::repeat_byte(0x33),
+        l2_head: B256::repeat_byte(0x44),
+        l2_chain_id: 1337,
+        interop: None,
+    };
+    let interop_inputs = FixtureInputs {
+        interop: Some(InteropInputs {
+            agreed_super_root: B256::repeat_byte(0x55),
+            agreed_timestamp: 10,
+            claimed_timestamp: 12,
+            chains: vec![
+                InteropChain {
+                    chain_id: 1337,
+                    l2_head: B256::repeat_byte(0x44),
+                    l2_output_root: B256::repeat_byte(0x33),
+                },
+                InteropChain {
+                    chain_id: 1338,
+                    l2_head: B256::repeat_byte(0x77),
+                    l2_output_root: B256::repeat_byte(0x66),
+                },
+            ],
+        }),
+        ..fixture_inputs.clone()
+    };
+    let inputs = |fixture_inputs: &FixtureInputs, source| ProgramHostInputs {
+        fixture_inputs: fixture_inputs.clone(),
+        rollup_cfg_path: PathBuf::from("fixture/rollup.json"),
+        genesis_path: PathBuf::from("fixture/genesis.json"),
+        chain_config_path: None,
+        source,
+    };
+
+    vec![
+        (
+            "disk",
+            inputs(
+                &fixture_inputs,
+                ProgramHostSource::Disk {
+                    path: PathBuf::from("fixture/witness-db"),
+                },
+            ),
+        ),
+        (
+            "rpc",
+            inputs(
+                &fixture_inputs,
+                ProgramHostSource::Rpc {
+                    l1: "http://l1".to_string(),
+                    l1_beacon: "http://l1-beacon".to_string(),
+                    l2: "http://l2".to_string(),
+                    path: PathBuf::from("fixture/witness-db"),
+                },
+            ),
+        ),
+        (
+            "interop",
+            inputs(
+                &interop_inputs,
+                ProgramHostSource::Disk {
+                    path: PathBuf::from("fixture/witness-db"),
+                },
+            ),
+        ),
+    ]
+}
+
+/// Returns the host commands of each program with the canonical inputs, labelled by program and
+/// data source.
+fn host_cmds() -> Result<Vec<(ProgramKind, &'static str, Vec<String>)>> {
+    let mut cmds = Vec::new();
+    for kind in PROGRAMS {
+        let program = kind.get_program(
+            PathBuf::from(kind.to_string()),
+            PathBuf::from(format!("{kind}-client")),
+        );
+        for (source, inputs) in canonical_inputs() {
+            cmds.push((kind, source, program.host_cmd(&inputs)?));
+        }
+    }
+    Ok(cmds)
+}
+
+/// Renders the command lines constructed for each program and platform.
+///
+/// ## Returns
+/// - `Result<String>` - The rendered commands, in the format of `snapshots/commands.txt`.
+pub(crate) fn render() -> Result<String> {
+    let mut out = String::new();
+    for (kind, source, cmd) in host_cmds()? {
+        writeln!(out, "# {kind} ({source})")?;
+        writeln!(out, "{}", cmd.join(" "))?;
+    }
+
+    let (_, _, host_cmd) = host_cmds()?
+        .into_iter()
+        .find(|(kind, _, _)| *kind == ProgramKind::OpProgramMips)
+        .ok_or(eyre!("Missing op-program-mips host command"))?;
+    writeln!(out, "# cannon (op-program-mips, disk)")?;
+    writeln!(
+        out,
+        "cannon {}",
+        Cannon::run_args(&Cannon::run_flags(None), host_cmd).join(" ")
+    )?;
+
+    Ok(out)
+}
+
+/// Checks that every flag passed to the built host binaries is accepted by them, according to
+/// their `--help` output.
+///
+/// ## Returns
+/// - `Result<()>` - Ok if every flag is documented by its binary, Err otherwise.
+pub(crate) async fn check() -> Result<()> {
+    let mut missing = Vec::new();
+
+    for kind in PROGRAMS {
+        let Some(definition) = FP_REGISTRY.program.get(&kind) else {
+            continue;
+        };
+        let host = definition
+            .build
+            .get_artifact("host")
+            .ok_or(eyre!("No host artifact for {kind}"))?;
+        ensure!(
+            host.exists(),
+            "The host binary of {kind} has not been built; build it with `fpt test -p {kind}`"
+        );
+
+        let help = help_text(Command::new(&host).arg("--help")).await?;
+        let flags = host_cmds()?
+            .into_iter()
+            .filter(|(k, _, _)| *k == kind)
+            .flat_map(|(_, _, cmd)| cmd)
+            .filter(|arg| arg.starts_with("--"))
+            .collect::<BTreeSet<_>>();
+        missing.extend(
+            flags
+                .into_iter()
+                .filter(|flag| !help.contains(flag.as_str()))
+                .map(|flag| format!("{kind}: {flag}")),
+        );
+    }
+
+    ensure!(
+        missing.is_empty(),
+        "Flags not accepted by the built host binaries:\n  - {}",
+        missing.join("\n  - ")
+    );
+    Ok(())
+}
+
+/// Runs the help command, returning its combined output.
+async fn help_text(cmd: &mut Command) -> Result<String> {
+    let output = cmd.output().await?;
+    Ok(format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_match_snapshot() {
+        assert_eq!(
+            render().unwrap(),
+            include_str!("snapshots/commands.txt"),
+            "Rendered commands changed; review and update the snapshot with `fpt cmd > src/registry/snapshots/commands.txt`"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Inspection and pruning of the components installed under [StateDir::components_dir].
+
+use super::{
+    build::{self, BUILD_STAMP_DIR},
+    BuildInstructions, FP_REGISTRY,
+};
+use crate::state::StateDir;
+use alloy_primitives::{keccak256, B256};
+use color_eyre::Result;
+use git2::Repository;
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// A component repository installed under [StateDir::components_dir].
+#[derive(Debug, Clone)]
+pub(crate) struct InstalledComponent {
+    /// The org/reponame of the component's GitHub repository.
+    pub(crate) repo: String,
+    /// The directory that the repository is cloned into.
+    pub(crate) dir: PathBuf,
+    /// The currently checked out commit, if the repository is intact.
+    pub(crate) commit: Option<String>,
+    /// The artifacts of the component that are present on disk.
+    pub(crate) artifacts: Vec<InstalledArtifact>,
+    /// The time of the last successful build step, if the component has been built.
+    pub(crate) last_build: Option<SystemTime>,
+    /// The disk usage of the component, in bytes.
+    pub(crate) disk_usage: u64,
+    /// Whether or not the component is referenced by the registry.
+    pub(crate) referenced: bool,
+}
+
+/// A built artifact of an [InstalledComponent].
+#[derive(Debug, Clone)]
+pub(crate) struct InstalledArtifact {
+    /// The name of the artifact.
+    pub(crate) name: String,
+    /// The size of the artifact, in bytes.
+    pub(crate) size: u64,
+    /// The keccak256 hash of the artifact.
+    pub(crate) hash: B256,
+}
+
+impl InstalledComponent {
+    /// Lists the components installed under [StateDir::components_dir].
+    ///
+    /// ## Returns
+    /// - `Result<Vec<Self>>` - The installed components, sorted by repository.
+    pub(crate) fn list() -> Result<Vec<Self>> {
+        let components_dir = StateDir::get().components_dir();
+        if !components_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        // Components are cloned into `<org>/<repo>`.
+        let mut components = Vec::new();
+        for org in read_dirs(&components_dir)? {
+            for dir in read_dirs(&org)? {
+                let repo = dir
+                    .strip_prefix(&components_dir)?
+                    .to_string_lossy()
+                    .to_string();
+                components.push(Self::inspect(repo, dir)?);
+            }
+        }
+        components.sort_by(|a, b| a.repo.cmp(&b.repo));
+        Ok(components)
+    }
+
+    /// Removes the component from disk.
+    pub(crate) fn remove(&self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)?;
+
+        // Clean up the org directory if this was its last component.
+        if let Some(org) = self.dir.parent() {
+            if fs::read_dir(org)?.next().is_none() {
+                fs::remove_dir(org)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the build stamps of the component that no longer describe a build of any of its
+    /// build steps within the registry.
+    pub(crate) fn stale_stamps(&self) -> Vec<PathBuf> {
+        build::stale_stamps(
+            &self.dir,
+            self.commit.as_deref(),
+            referencing_builds(&self.repo)
+                .into_iter()
+                .flat_map(|b| b.build_steps()),
+        )
+    }
+
+    /// Inspects a single installed component.
+    fn inspect(repo: String, dir: PathBuf) -> Result<Self> {
+        let commit = Repository::open(&dir)
+            .ok()
+            .and_then(|r| Some(r.head().ok()?.peel_to_commit().ok()?.id().to_string()));
+
+        let instructions = referencing_builds(&repo);
+        let mut artifacts = Vec::new();
+        let mut seen = BTreeSet::new();
+        for build in instructions.iter() {
+            for step in build.build_steps() {
+                for (name, path) in step.artifacts.iter() {
+                    let path = dir.join(&step.workdir).join(path);
+                    if !seen.insert(path.clone()) || !path.is_file() {
+                        continue;
+                    }
+                    let contents = fs::read(&path)?;
+                    artifacts.push(InstalledArtifact {
+                        name: name.clone(),
+                        size: contents.len() as u64,
+                        hash: keccak256(&contents),
+                    });
+                }
+            }
+        }
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let last_build = fs::read_dir(dir.join(BUILD_STAMP_DIR))
+            .ok()
+            .and_then(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "toml"))
+                    .filter_map(|e| e.metadata().ok()?.modified().ok())
+                    .max()
+            });
+
+        Ok(Self {
+            disk_usage: disk_usage(&dir),
+            referenced: !instructions.is_empty(),
+            repo,
+            dir,
+            commit,
+            artifacts,
+            last_build,
+        })
+    }
+}
+
+/// Returns the build instructions within the registry that build from the given repository.
+fn referencing_builds(repo: &str) -> Vec<&'static BuildInstructions> {
+    FP_REGISTRY
+        .platform
+        .values()
+        .filter_map(|p| p.build.as_ref())
+        .chain(FP_REGISTRY.program.values().map(|p| &p.build))
+        .filter(|b| b.repo == repo)
+        .collect()
+}
+
+/// Returns the subdirectories of the given directory.
+fn read_dirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect())
+}
+
+/// Returns the total size of the files within the given directory, in bytes.
+pub(crate) fn disk_usage(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| match e.file_type() {
+            Ok(t) if t.is_dir() => disk_usage(&e.path()),
+            Ok(t) if t.is_file() => e.metadata().map_or(0, |m| m.len()),
+            _ => 0,
+        })
+        .sum()
+}
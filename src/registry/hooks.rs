@@ -0,0 +1,100 @@
+//! Contains the [PlatformHook]s, the commands that prepare and restore the environment of a
+//! platform once per test run (e.g. pulling a docker image, or raising `vm.max_map_count`).
+
+use super::{platform::PlatformRuntime, PlatformAndPrograms};
+use color_eyre::{eyre::ensure, Result};
+use std::fmt::Display;
+use tokio::process::Command;
+use tracing::info;
+
+/// The environment variable that holds the path to the platform's binary, if it is built.
+pub(crate) const PLATFORM_BIN_ENV: &str = "FPT_PLATFORM_BIN";
+
+/// A lifecycle hook of a platform, ran once per test run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PlatformHook {
+    /// Ran after the matrix is built, before any test runs.
+    Setup,
+    /// Ran once every test has completed.
+    Teardown,
+}
+
+impl Display for PlatformHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Setup => write!(f, "setup"),
+            Self::Teardown => write!(f, "teardown"),
+        }
+    }
+}
+
+impl PlatformAndPrograms {
+    /// Runs a hook of the platform with `sh -c`, if it is defined in the registry. The path to
+    /// the platform's binary is passed in [PLATFORM_BIN_ENV], if it is built locally.
+    ///
+    /// ## Takes
+    /// - `hook` - The hook to run.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Ok if the hook is not defined or succeeded, Err with its output otherwise.
+    pub(crate) async fn run_hook(&self, hook: PlatformHook) -> Result<()> {
+        let cmd = match hook {
+            PlatformHook::Setup => self.vm.setup.as_ref(),
+            PlatformHook::Teardown => self.vm.teardown.as_ref(),
+        };
+        let Some(cmd) = cmd else {
+            return Ok(());
+        };
+
+        info!(target: "registry", "Running the {hook} hook of platform `{}`: {cmd}", self.vm_kind);
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        if let Some(bin) = self
+            .vm
+            .build
+            .as_ref()
+            .filter(|_| self.vm.runtime == PlatformRuntime::Host)
+            .and_then(|b| b.get_artifact("vm"))
+        {
+            command.env(PLATFORM_BIN_ENV, bin);
+        }
+        let output = command.output().await?;
+
+        ensure!(
+            output.status.success(),
+            "The {hook} hook of platform `{}` failed ({}): {}",
+            self.vm_kind,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{platform::PlatformKind, PlatformDefinition};
+
+    #[tokio::test]
+    async fn run_platform_hooks() {
+        let platform = PlatformAndPrograms {
+            vm: PlatformDefinition {
+                setup: Some("true".to_string()),
+                teardown: Some("echo cleanup failed >&2; exit 3".to_string()),
+                ..Default::default()
+            },
+            vm_kind: PlatformKind::Mock,
+            programs: Default::default(),
+        };
+
+        platform.run_hook(PlatformHook::Setup).await.unwrap();
+        let err = platform
+            .run_hook(PlatformHook::Teardown)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("The teardown hook of platform `mock` failed"));
+        assert!(err.ends_with("cleanup failed"));
+    }
+}
@@ -0,0 +1,52 @@
+//! Contains the [MockPlatform] and [MockProgram], which stand in for real FPVMs and FPPs when
+//! testing the crate itself.
+
+use super::{
+    platform::{ClientArtifactKind, Platform, STDERR_LOG, STDOUT_LOG},
+    program::{Program, ProgramHostInputs},
+};
+use crate::process::ProcessOptions;
+use async_trait::async_trait;
+use color_eyre::Result;
+use std::{fs, path::Path, sync::Arc};
+
+/// A platform that runs no binaries, outputting the host command and exiting with the low byte of
+/// the fixture's L2 block number.
+#[derive(Debug)]
+pub(crate) struct MockPlatform;
+
+#[async_trait]
+impl Platform for MockPlatform {
+    async fn prepare_client(&self, _: &Path, _: ClientArtifactKind, _: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        inputs: &ProgramHostInputs,
+        program: Arc<dyn Program + Send + Sync>,
+        workdir: &Path,
+        opts: &ProcessOptions,
+    ) -> Result<u8> {
+        let host_cmd = program.host_cmd(inputs)?.join(" ");
+        if let Some(lines) = opts.output_lines.as_ref() {
+            let _ = lines.send(host_cmd.clone());
+        }
+        fs::write(workdir.join(STDOUT_LOG), host_cmd)?;
+        fs::write(workdir.join(STDERR_LOG), [])?;
+        Ok(inputs.fixture_inputs.l2_block_number as u8)
+    }
+}
+
+/// A program whose host command only names the fixture's claim.
+#[derive(Debug)]
+pub(crate) struct MockProgram;
+
+impl Program for MockProgram {
+    fn host_cmd(&self, inputs: &ProgramHostInputs) -> Result<Vec<String>> {
+        Ok(vec![
+            "mock-host".to_string(),
+            inputs.fixture_inputs.l2_claim.to_string(),
+        ])
+    }
+}
@@ -1,25 +1,94 @@
 //! Contains the registry type, which holds metadata about the available FPVMs and FPPs.
 
-use crate::cli::TestConfig;
-use once_cell::sync::Lazy;
-use platform::PlatformKind;
+use crate::{cli::TestConfig, state::StateDir};
+use alloy_primitives::{keccak256, B256};
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    Result,
+};
+use once_cell::sync::{Lazy, OnceCell};
+use platform::{ClientArtifactKind, PlatformKind, PlatformRuntime, ResourceProfile};
+use profile::RunProfile;
 use program::ProgramKind;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::warn;
+use validators::ValidatorDefinition;
 
 pub(crate) mod build;
+pub(crate) mod commands;
+pub(crate) mod components;
+pub(crate) mod hooks;
+#[cfg(test)]
+pub(crate) mod mock;
+pub(crate) mod overlay;
 pub(crate) mod platform;
+pub(crate) mod profile;
 pub(crate) mod program;
+pub(crate) mod validators;
+pub(crate) mod version;
+
+/// The registry embedded into `fpt` at build time.
+const REGISTRY_SER: &str = include_str!("../../registry.toml");
 
-/// The directory containing the components.
-pub(crate) const COMPONENTS_DIR: &str = concat!(env!("HOME"), "/.fpt/components");
+/// Returns the keccak256 hash of the registry embedded into `fpt` at build time.
+pub(crate) fn embedded_registry_hash() -> B256 {
+    keccak256(REGISTRY_SER)
+}
 
-/// The FP Registry.
+/// The FP Registry. The registry loaded with [load_registry] takes the place of the embedded
+/// `registry.toml`, if any.
 pub(crate) static FP_REGISTRY: Lazy<FPRegistry> = Lazy::new(|| {
-    const REGISTRY_SER: &str = include_str!("../../registry.toml");
-    toml::from_str(REGISTRY_SER).expect("Failed to parse registry")
+    REGISTRY_OVERRIDE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| toml::from_str(REGISTRY_SER).expect("Failed to parse registry"))
 });
 
+/// The registry loaded with [load_registry], if any.
+static REGISTRY_OVERRIDE: OnceCell<FPRegistry> = OnceCell::new();
+
+/// Loads the registry at runtime as the [FP_REGISTRY], from disk or from the embedded
+/// `registry.toml`, and deep-merges the registry overlay of the [StateDir] into it if it exists.
+/// Must be called before the registry is first accessed.
+///
+/// ## Takes
+/// - `path` - The path to the registry TOML, if it replaces the embedded registry.
+///
+/// ## Returns
+/// - `Result<()>` - Errors if the registry or its overlay cannot be read or parsed, or if the
+///   embedded registry is already in use.
+pub(crate) fn load_registry(path: Option<&Path>) -> Result<()> {
+    let read = |path: &Path| {
+        fs::read_to_string(path)
+            .map_err(|e| eyre!("Failed to read registry {}: {e}", path.display()))?
+            .parse::<toml::Value>()
+            .map_err(|e| eyre!("Failed to parse registry {}: {e}", path.display()))
+    };
+
+    let mut registry = match path {
+        Some(path) => read(path)?,
+        None => REGISTRY_SER.parse::<toml::Value>()?,
+    };
+    let overlay_path = StateDir::get().registry_overlay();
+    if overlay_path.exists() {
+        overlay::merge(&mut registry, read(&overlay_path)?);
+    }
+    let registry = registry
+        .try_into::<FPRegistry>()
+        .map_err(|e| eyre!("Invalid registry: {e}"))?;
+
+    ensure!(
+        Lazy::get(&FP_REGISTRY).is_none() && REGISTRY_OVERRIDE.set(registry).is_ok(),
+        "The registry was already loaded"
+    );
+    Ok(())
+}
+
 /// The FP Registry holds metadata about the available FPVMs and FPPs.
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -28,20 +97,35 @@ pub(crate) struct FPRegistry {
     pub(crate) platform: HashMap<PlatformKind, PlatformDefinition>,
     /// The fault proof programs available in the registry.
     pub(crate) program: HashMap<ProgramKind, FPPDefinition>,
+    /// The named run profiles, selected with `--profile`.
+    #[serde(default)]
+    pub(crate) profile: HashMap<String, RunProfile>,
+    /// The validators that are ran after each test, by name, in the order that they are ran.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) validator: BTreeMap<String, ValidatorDefinition>,
 }
 
 impl FPRegistry {
     /// Returns the matrix of compatibility between the available FPVMs and FPPs.
     ///
+    /// When programs are explicitly selected, only the selected programs are included; otherwise,
+    /// the default programs compatible with each selected platform are included. `--all-vms` and
+    /// `--all-programs` override the defaults with every platform or program in the registry.
+    ///
     /// ## Takes
     /// - `cfg` - The test configuration. If `None`, all possible configurations are returned.
     ///
     /// ## Returns
-    /// - `Vec<DefPair>` - The matrix of FPVMs and FPPs compatible with the [TestConfig].
-    pub(crate) fn resolve_matrix(&self, cfg: Option<&TestConfig>) -> Vec<PlatformAndPrograms> {
+    /// - `Result<Vec<DefPair>>` - The matrix of FPVMs and FPPs compatible with the [TestConfig].
+    ///   Errors if the selection produces an empty matrix, detailing which constraint eliminated
+    ///   each selected platform and program.
+    pub(crate) fn resolve_matrix(
+        &self,
+        cfg: Option<&TestConfig>,
+    ) -> Result<Vec<PlatformAndPrograms>> {
         let mut matrix = Vec::new();
 
-        let selected_platforms = if let Some(cfg) = cfg {
+        let selected_platforms = if let Some(cfg) = cfg.filter(|cfg| !cfg.all_vms()) {
             if let Some(vm) = cfg.vm.as_ref() {
                 self.platform
                     .iter()
@@ -57,34 +141,99 @@ impl FPRegistry {
             self.platform.iter().collect::<HashMap<_, _>>()
         };
 
-        for (vm_kind, vm_def) in selected_platforms {
+        for (vm_kind, vm_def) in selected_platforms.iter() {
             let compat = self
                 .program
                 .iter()
                 .filter_map(|(prog_kind, prog_def)| {
                     let platform_compat = prog_def.platform_compat.contains(vm_kind);
 
-                    if let Some(cfg) = cfg {
-                        let is_default = prog_def.default;
-                        let is_selected = cfg
-                            .program
-                            .as_ref()
-                            .map_or(false, |p| p.contains(prog_kind));
-                        (platform_compat && (is_default || is_selected))
-                            .then(|| (*prog_kind, prog_def.clone()))
+                    if let Some(cfg) = cfg.filter(|cfg| !cfg.all_programs()) {
+                        let is_enabled = match cfg.program.as_ref() {
+                            Some(selected) => selected.contains(prog_kind),
+                            None => prog_def.default,
+                        };
+                        (platform_compat && is_enabled).then(|| (*prog_kind, prog_def.clone()))
                     } else {
                         platform_compat.then(|| (*prog_kind, prog_def.clone()))
                     }
                 })
                 .collect::<HashMap<_, _>>();
 
+            // The runtime given on the command line takes precedence over the registry's.
+            let mut vm = (*vm_def).clone();
+            if let Some(runtime) = cfg.and_then(|cfg| cfg.platform_runtime) {
+                vm.runtime = runtime;
+            }
+
             matrix.push(PlatformAndPrograms {
-                vm: vm_def.clone(),
-                vm_kind: *vm_kind,
+                vm,
+                vm_kind: **vm_kind,
                 programs: compat,
             });
         }
-        matrix
+
+        if let Some(cfg) = cfg {
+            self.check_selection(cfg, &matrix)?;
+        }
+        Ok(matrix)
+    }
+
+    /// Checks that an explicit selection of platforms and programs produced a non-empty matrix,
+    /// warning about any selected platform or program that was eliminated.
+    fn check_selection(&self, cfg: &TestConfig, matrix: &[PlatformAndPrograms]) -> Result<()> {
+        let mut eliminated = Vec::new();
+
+        for platform in matrix.iter().filter(|p| p.programs.is_empty()) {
+            let reason = if cfg.program.is_some() {
+                format!(
+                    "platform `{}` is not compatible with any of the selected programs",
+                    platform.vm_kind
+                )
+            } else {
+                format!(
+                    "platform `{}` is not compatible with any default program; select programs with `--program`",
+                    platform.vm_kind
+                )
+            };
+            eliminated.push(reason);
+        }
+
+        for program in cfg.program.iter().flatten() {
+            if matrix.iter().any(|p| p.programs.contains_key(program)) {
+                continue;
+            }
+            let compat = self
+                .program
+                .get(program)
+                .map(|def| {
+                    def.platform_compat
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            let selected = matrix
+                .iter()
+                .map(|p| p.vm_kind.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            eliminated.push(format!(
+                "program `{program}` is only compatible with [{compat}], but the selected platforms are [{selected}]"
+            ));
+        }
+
+        if matrix.iter().all(|p| p.programs.is_empty()) {
+            bail!(
+                "The selected platforms and programs produce an empty test matrix:\n  - {}",
+                eliminated.join("\n  - ")
+            );
+        }
+        for reason in eliminated {
+            warn!(target: "registry", "Selection excludes tests: {reason}");
+        }
+        Ok(())
     }
 }
 
@@ -97,6 +246,40 @@ pub(crate) struct PlatformDefinition {
     pub(crate) default: bool,
     /// The instructions to build the platform locally.
     pub(crate) build: Option<BuildInstructions>,
+    /// The runtime that the platform's virtual machine is ran with.
+    #[serde(default)]
+    pub(crate) runtime: PlatformRuntime,
+    /// The resource that the platform's test runs are bound by, which the tests of the platforms
+    /// are interleaved by.
+    #[serde(default)]
+    pub(crate) resource: ResourceProfile,
+    /// The container image that the virtual machine is ran in with the docker runtime, whose
+    /// entrypoint is the virtual machine's binary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) image: Option<String>,
+    /// The command that prepares the environment of the platform, ran once per test run before
+    /// any test.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) setup: Option<String>,
+    /// The command that restores the environment of the platform, ran once per test run after
+    /// every test.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) teardown: Option<String>,
+    /// The flags that the virtual machine is ran with, in place of its defaults (e.g. cannon's
+    /// `--info-at %10000000 --proof-at never`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) run_flags: Option<Vec<String>>,
+}
+
+impl PlatformDefinition {
+    /// Returns the flags that the virtual machine is ran with for the program: the program's
+    /// `vm-run-flags`, or the platform's `run-flags`, if either is set.
+    pub(crate) fn run_flags_for<'a>(&'a self, program: &'a FPPDefinition) -> Option<&'a [String]> {
+        program
+            .vm_run_flags
+            .as_deref()
+            .or(self.run_flags.as_deref())
+    }
 }
 
 /// The FPP definition holds metadata about a fault proof program.
@@ -110,6 +293,13 @@ pub(crate) struct FPPDefinition {
     pub(crate) platform_compat: Vec<PlatformKind>,
     /// The instructions to build the FPP locally.
     pub(crate) build: BuildInstructions,
+    /// The kind of the FPP's `client` artifact.
+    #[serde(default)]
+    pub(crate) client_kind: ClientArtifactKind,
+    /// The flags that the virtual machine is ran with for the FPP, in place of the platform's
+    /// `run-flags`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) vm_run_flags: Option<Vec<String>>,
 }
 
 /// Build instructions for a binary within a GitHub repository.
@@ -120,6 +310,22 @@ pub(crate) struct BuildInstructions {
     pub(crate) repo: String,
     /// The revision or tag to build.
     pub(crate) rev: String,
+    /// The build step, for components built with a single command.
+    #[serde(flatten)]
+    pub(crate) step: Option<BuildStep>,
+    /// The named build steps, ran in order, for components whose artifacts are built with separate
+    /// commands.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) steps: Vec<BuildStep>,
+}
+
+/// A single build command within a GitHub repository, and the artifacts it produces.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct BuildStep {
+    /// The name of the build step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
     /// The workdir of the build.
     pub(crate) workdir: PathBuf,
     /// The build command to run.
@@ -142,6 +348,8 @@ pub(crate) struct PlatformAndPrograms {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::{Cli, CliSubcommand};
+    use clap::Parser;
 
     #[test]
     fn serde_round_trip_fp_registry() {
@@ -149,4 +357,97 @@ mod tests {
         let de: FPRegistry = toml::from_str(&ser).unwrap();
         assert_eq!(*FP_REGISTRY, de);
     }
+
+    #[test]
+    fn resolve_build_steps() {
+        let op_program = &FP_REGISTRY.program[&ProgramKind::OpProgramMips].build;
+        assert_eq!(op_program.build_steps().count(), 1);
+        assert!(op_program.get_artifact("client").is_some());
+
+        let kona = &FP_REGISTRY.program[&ProgramKind::KonaRiscv].build;
+        assert!(kona.step.is_none());
+        assert_eq!(kona.build_steps().count(), 2);
+        assert!(kona
+            .get_artifact("host")
+            .unwrap()
+            .ends_with("target/release/kona-host"));
+    }
+
+    #[test]
+    fn resolve_run_flags() {
+        let registry = toml::from_str::<FPRegistry>(
+            r#"
+            [platform.asterisc]
+            run-flags = ['--info-at', '%1000', '--proof-at', 'never']
+
+            [program.op-program-riscv]
+            platform-compat = ['asterisc']
+            build = { repo = 'ethereum-optimism/optimism', rev = 'v1' }
+
+            [program.kona-riscv]
+            platform-compat = ['asterisc']
+            build = { repo = 'ethereum-optimism/kona', rev = 'v1' }
+            vm-run-flags = ['--debug']
+            "#,
+        )
+        .unwrap();
+        let asterisc = &registry.platform[&PlatformKind::Asterisc];
+        let run_flags = |program| {
+            asterisc
+                .run_flags_for(&registry.program[&program])
+                .map(|flags| flags.join(" "))
+        };
+        assert_eq!(
+            run_flags(ProgramKind::OpProgramRiscv).as_deref(),
+            Some("--info-at %1000 --proof-at never")
+        );
+        assert_eq!(
+            run_flags(ProgramKind::KonaRiscv).as_deref(),
+            Some("--debug")
+        );
+        assert_eq!(
+            FP_REGISTRY.platform[&PlatformKind::Cannon]
+                .run_flags_for(&FP_REGISTRY.program[&ProgramKind::OpProgramMips]),
+            None
+        );
+    }
+
+    #[test]
+    fn reject_empty_selection() {
+        let cfg =
+            |args: &[&str]| match Cli::parse_from([&["fpt", "test"], args].concat()).subcommand {
+                Some(CliSubcommand::Test(cfg)) => cfg,
+                _ => unreachable!(),
+            };
+
+        let err = FP_REGISTRY
+            .resolve_matrix(Some(&cfg(&["--program", "kona-riscv", "--vm", "native"])))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("program `kona-riscv` is only compatible with [asterisc]"));
+
+        let matrix = FP_REGISTRY
+            .resolve_matrix(Some(&cfg(&["--program", "kona-native"])))
+            .unwrap();
+        assert!(matrix
+            .iter()
+            .flat_map(|p| p.programs.keys())
+            .all(|p| *p == ProgramKind::KonaNative));
+
+        let mut mixed = cfg(&["--vm", "cannon,native", "--vm", "native"]);
+        mixed.normalize().unwrap();
+        assert_eq!(
+            mixed.vm,
+            Some(vec![PlatformKind::Cannon, PlatformKind::Native])
+        );
+        assert_eq!(FP_REGISTRY.resolve_matrix(Some(&mixed)).unwrap().len(), 2);
+
+        let all = FP_REGISTRY.resolve_matrix(Some(&cfg(&["--all"]))).unwrap();
+        assert_eq!(all.len(), FP_REGISTRY.platform.len());
+        assert_eq!(
+            all.iter().map(|p| p.programs.len()).sum::<usize>(),
+            FP_REGISTRY.program.len()
+        );
+    }
 }
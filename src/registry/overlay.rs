@@ -0,0 +1,57 @@
+//! Contains the registry overlay, a partial registry that is deep-merged into the base registry,
+//! so that single entries (e.g. the `rev` of a program) can be overridden, or added, without
+//! duplicating the whole registry.
+
+use toml::Value;
+
+/// Deep-merges the overlay into the base registry. Tables are merged key by key, while any other
+/// value in the overlay replaces the base's.
+///
+/// ## Takes
+/// - `base` - The base registry.
+/// - `overlay` - The overlay to merge into the base registry.
+pub(crate) fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{program::ProgramKind, FPRegistry};
+
+    #[test]
+    fn merge_overlay() {
+        let mut base = include_str!("../../registry.toml")
+            .parse::<Value>()
+            .unwrap();
+        let overlay = r#"
+            [program.op-program-mips.build]
+            rev = "deadbeef"
+        "#
+        .parse::<Value>()
+        .unwrap();
+        merge(&mut base, overlay);
+
+        let registry = base.try_into::<FPRegistry>().unwrap();
+        let program = &registry.program[&ProgramKind::OpProgramMips];
+        assert_eq!(program.build.rev, "deadbeef");
+
+        // The rest of the entry is left intact.
+        let embedded = toml::from_str::<FPRegistry>(include_str!("../../registry.toml")).unwrap();
+        let original = &embedded.program[&ProgramKind::OpProgramMips];
+        assert_eq!(program.platform_compat, original.platform_compat);
+        assert_eq!(program.build.repo, original.build.repo);
+    }
+}
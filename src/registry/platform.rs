@@ -1,33 +1,49 @@
 //! Contains the [Platform] trait, which defines the interface for a fault proof virtual machine.
 
-use super::program::{Program, ProgramHostInputs};
-use async_trait::async_trait;
-use color_eyre::{
-    eyre::{bail, eyre},
-    Result,
+use super::{
+    program::{Program, ProgramHostInputs},
+    PlatformDefinition,
 };
+use crate::process::ProcessOptions;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::Display,
     path::{Path, PathBuf},
-    str::FromStr,
     sync::Arc,
 };
 
 pub(crate) mod cannon;
+pub(crate) mod docker;
 pub(crate) mod native;
 
+/// The name of the file within the workdir that a platform writes the run's stdout to.
+pub(crate) const STDOUT_LOG: &str = "stdout.log";
+
+/// The name of the file within the workdir that a platform writes the run's stderr to.
+pub(crate) const STDERR_LOG: &str = "stderr.log";
+
 /// The minimal interface for a fault proof virtual machine binary.
 #[async_trait]
 pub(crate) trait Platform {
-    /// Load a program into the FPVM's state format.
+    /// Prepares the program's client artifact in the workdir, in the FPVM's state format.
     ///
     /// ## Takes
-    /// - `elf_path` - The path to the ELF file to load.
+    /// - `artifact` - The path to the client artifact.
+    /// - `kind` - The kind of the client artifact.
+    /// - `workdir` - The working directory that the program is ran in.
     ///
     /// ## Returns
-    /// - `Result<()>` - Ok if successful, Err otherwise.
-    async fn load_elf(&self, elf_path: &Path, out: &Path) -> Result<()>;
+    /// - `Result<()>` - Ok if successful, Err if the FPVM does not support the kind of artifact, or
+    ///   failed to load it.
+    async fn prepare_client(
+        &self,
+        artifact: &Path,
+        kind: ClientArtifactKind,
+        workdir: &Path,
+    ) -> Result<()>;
 
     /// Runs the loaded program on the FPVM.
     ///
@@ -35,6 +51,7 @@ pub(crate) trait Platform {
     /// - `program_inputs` - The inputs to the program.
     /// - `program` - The program command specification.
     /// - `workdir` - The working directory to run the program in.
+    /// - `opts` - The options for supervising the spawned process.
     ///
     /// ## Returns
     /// - `Result<StatusCode>` - Ok if successful, Err otherwise.
@@ -43,12 +60,49 @@ pub(crate) trait Platform {
         program_inputs: &ProgramHostInputs,
         program: Arc<dyn Program + Send + Sync>,
         workdir: &Path,
+        opts: &ProcessOptions,
     ) -> Result<u8>;
+
+    /// Returns the path of the final state that the FPVM writes to the workdir once the program
+    /// has ran, if it writes one.
+    fn final_state(&self, _workdir: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the number of instructions that the FPVM executed, as reported in the workdir once
+    /// the program has ran, if it reports them.
+    fn executed_steps(&self, _workdir: &Path) -> Option<u64> {
+        None
+    }
 }
 
-/// Supported platform kinds.
+/// The kinds of client artifacts that a platform can be prepared with.
 #[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
+pub(crate) enum ClientArtifactKind {
+    /// An ELF binary, loaded into the FPVM's state format.
+    #[default]
+    Elf,
+    /// A precompiled FPVM state.
+    State,
+    /// A flat binary image.
+    Image,
+}
+
+impl Display for ClientArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Elf => write!(f, "elf"),
+            Self::State => write!(f, "state"),
+            Self::Image => write!(f, "image"),
+        }
+    }
+}
+
+/// Supported platform kinds.
+#[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
 pub(crate) enum PlatformKind {
     /// Native platform
     #[default]
@@ -57,47 +111,85 @@ pub(crate) enum PlatformKind {
     Cannon,
     /// `asterisc`
     Asterisc,
+    /// A mock platform, for testing the crate itself.
+    #[cfg(test)]
+    Mock,
+}
+
+/// The runtimes that a platform's virtual machine can be ran with.
+#[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+pub(crate) enum PlatformRuntime {
+    /// The virtual machine is built locally, and ran on the host.
+    #[default]
+    Host,
+    /// The virtual machine is ran inside of a container of the platform's `image`.
+    Docker,
+}
+
+/// The resource that a platform's test runs are bound by, which the scheduler balances the mix of
+/// concurrently running tests over.
+#[derive(
+    Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ResourceProfile {
+    /// Bound by the CPU, e.g. emulating the program within an FPVM.
+    #[default]
+    Cpu,
+    /// Bound by I/O, e.g. natively executed programs reading the witness database.
+    Io,
 }
 
 impl PlatformKind {
+    /// Returns the [Platform] that runs the virtual machine, with the definition's runtime.
+    ///
+    /// ## Takes
+    /// - `definition` - The definition of the platform.
+    /// - `run_flags` - The flags to run the virtual machine with, in place of its defaults, if any.
+    ///
+    /// ## Returns
+    /// - `Result<Arc<dyn Platform + Send + Sync>>` - The platform. Errors if the platform's
+    ///   binary or image is missing.
     pub(crate) fn get_platform(
         &self,
-        binary: Option<PathBuf>,
+        definition: &PlatformDefinition,
+        run_flags: Option<&[String]>,
     ) -> Result<Arc<dyn Platform + Send + Sync>> {
+        if definition.runtime == PlatformRuntime::Docker && *self != Self::Native {
+            let image = definition
+                .image
+                .clone()
+                .ok_or_else(|| eyre!("Platform `{self}` has no image for the docker runtime"))?;
+            return Ok(Arc::new(docker::DockerPlatform::new(
+                *self,
+                image,
+                cannon::Cannon::run_flags(run_flags),
+            )));
+        }
+
+        let binary = definition.build.as_ref().and_then(|b| b.get_artifact("vm"));
         match self {
             Self::Native => Ok(Arc::new(native::Native)),
             Self::Cannon => Ok(Arc::new(cannon::Cannon::new(
                 binary.ok_or_else(|| eyre!("Missing Cannon binary"))?,
+                cannon::Cannon::run_flags(run_flags),
             ))),
+            #[cfg(test)]
+            Self::Mock => Ok(Arc::new(super::mock::MockPlatform)),
             _ => todo!(),
         }
     }
 }
-impl FromStr for PlatformKind {
-    type Err = color_eyre::Report;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "native" => Ok(Self::Native),
-            "cannon" => Ok(Self::Cannon),
-            "asterisc" => Ok(Self::Asterisc),
-            _ => bail!("Unknown program kind: {}", s),
-        }
-    }
-}
-
-impl From<String> for PlatformKind {
-    fn from(s: String) -> Self {
-        s.parse().unwrap_or_else(|e| panic!("{e}"))
-    }
-}
-
 impl Display for PlatformKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Native => write!(f, "native"),
             Self::Cannon => write!(f, "cannon"),
             Self::Asterisc => write!(f, "asterisc"),
+            #[cfg(test)]
+            Self::Mock => write!(f, "mock"),
         }
     }
 }
@@ -1,7 +1,10 @@
 //! Contains the implementation of the [Platform] trait for the Cannon virtual machine.
 
-use super::Platform;
-use crate::registry::program::{Program, ProgramHostInputs};
+use super::{ClientArtifactKind, Platform, STDERR_LOG, STDOUT_LOG};
+use crate::{
+    process::{self, OutputLogs, ProcessOptions},
+    registry::program::{Program, ProgramHostInputs},
+};
 use async_trait::async_trait;
 use color_eyre::{eyre::ensure, Result};
 use serde::{Deserialize, Serialize};
@@ -12,30 +15,186 @@ use std::{
 };
 use tokio::process::Command;
 
+/// The flags that `cannon run` is ran with, unless the registry overrides them.
+const DEFAULT_RUN_FLAGS: [&str; 4] = ["--info-at", "%10000000", "--proof-at", "never"];
+
 /// The Cannon virtual machine.
 pub(crate) struct Cannon {
     /// The path to the Cannon binary.
     binary: PathBuf,
+    /// The flags that `cannon run` is ran with.
+    run_flags: Vec<String>,
 }
 
 impl Cannon {
     /// Create a new `Cannon` instance.
-    pub(crate) fn new(binary: PathBuf) -> Self {
-        Self { binary }
+    pub(crate) fn new(binary: PathBuf, run_flags: Vec<String>) -> Self {
+        Self { binary, run_flags }
+    }
+
+    /// Returns the flags that `cannon run` is ran with: the given flags, or the defaults.
+    pub(crate) fn run_flags(flags: Option<&[String]>) -> Vec<String> {
+        flags.map_or_else(
+            || DEFAULT_RUN_FLAGS.map(String::from).to_vec(),
+            |flags| flags.to_vec(),
+        )
+    }
+
+    /// Returns the arguments to `cannon` for loading the ELF file into the workdir.
+    pub(crate) fn load_elf_args(elf_path: &Path, workdir: &Path) -> Vec<String> {
+        vec![
+            "load-elf".to_string(),
+            "--path".to_string(),
+            elf_path.display().to_string(),
+            "--out".to_string(),
+            workdir.join("state.json").display().to_string(),
+            "--meta".to_string(),
+            workdir.join("meta.json").display().to_string(),
+        ]
+    }
+
+    /// Copies a precompiled state into the workdir, in place of loading an ELF file. Flat binary
+    /// images are not supported.
+    pub(crate) fn copy_state(
+        artifact: &Path,
+        kind: ClientArtifactKind,
+        workdir: &Path,
+    ) -> Result<()> {
+        ensure!(
+            kind == ClientArtifactKind::State,
+            "Cannon does not support `{kind}` client artifacts"
+        );
+        fs::copy(artifact, workdir.join("state.json"))?;
+        Ok(())
+    }
+
+    /// Returns the path of the final state that `cannon` writes to the workdir.
+    pub(crate) fn final_state_path(workdir: &Path) -> PathBuf {
+        workdir.join("out.json")
+    }
+
+    /// Reads the `out.json` that `cannon` wrote to the workdir.
+    fn read_output(workdir: &Path) -> Result<PartialCannonOutput> {
+        Ok(serde_json::from_slice(
+            fs::read(Self::final_state_path(workdir))?.as_slice(),
+        )?)
+    }
+
+    /// Reads the exit code of the program from the `out.json` that `cannon` wrote to the workdir.
+    pub(crate) fn read_exit(workdir: &Path) -> Result<u8> {
+        let output = Self::read_output(workdir)?;
+        ensure!(output.exited, "Program did not exit");
+
+        Ok(output.exit)
+    }
+
+    /// Reads the number of steps that `cannon` executed from the `out.json` that it wrote to the
+    /// workdir.
+    pub(crate) fn read_steps(workdir: &Path) -> Result<u64> {
+        Ok(Self::read_output(workdir)?.step)
+    }
+
+    /// Returns the arguments to `cannon` for running the program from the input state until it
+    /// reaches the step or exits, writing the state that it stops in to the output.
+    pub(crate) fn run_to_step_args(
+        input: &Path,
+        output: &Path,
+        step: u64,
+        host_cmd: Vec<String>,
+    ) -> Vec<String> {
+        [
+            "run".to_string(),
+            "--input".to_string(),
+            input.display().to_string(),
+            "--output".to_string(),
+            output.display().to_string(),
+            "--stop-at".to_string(),
+            format!("={step}"),
+        ]
+        .into_iter()
+        .chain(
+            [
+                "--info-at",
+                "never",
+                "--proof-at",
+                "never",
+                "--snapshot-at",
+                "never",
+                "--",
+            ]
+            .map(String::from),
+        )
+        .chain(host_cmd)
+        .collect()
+    }
+
+    /// Runs the program from the input state until it reaches the step or exits, writing the
+    /// state that it stops in to the output. The output of each run is logged to the workdir.
+    ///
+    /// ## Takes
+    /// - `inputs` - The inputs to the program.
+    /// - `program` - The program command specification.
+    /// - `workdir` - The working directory to run the program in.
+    /// - `input` - The state to run the program from.
+    /// - `output` - The path to write the state at the step to.
+    /// - `step` - The step to stop at.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors if `cannon` did not write the state.
+    pub(crate) async fn run_to_step(
+        &self,
+        inputs: &ProgramHostInputs,
+        program: Arc<dyn Program + Send + Sync>,
+        workdir: &Path,
+        input: &Path,
+        output: &Path,
+        step: u64,
+    ) -> Result<()> {
+        let mut cmd = Command::new(self.binary.display().to_string());
+        cmd.args(Self::run_to_step_args(
+            input,
+            output,
+            step,
+            program.host_cmd(inputs)?,
+        ))
+        .current_dir(workdir);
+        let logs = OutputLogs::new(workdir, &format!("step-{step}-"));
+        // `cannon` may exit with the program's status, so its state is checked for instead.
+        let result = process::run_supervised(cmd, &ProcessOptions::default(), &logs).await?;
+        ensure!(
+            output.is_file(),
+            "Cannon did not write the state at step {step} ({}); see {}",
+            result.status,
+            logs.stderr.display()
+        );
+        Ok(())
+    }
+
+    /// Returns the arguments to `cannon` for running the loaded program with the given flags and
+    /// host command.
+    pub(crate) fn run_args(run_flags: &[String], host_cmd: Vec<String>) -> Vec<String> {
+        std::iter::once("run".to_string())
+            .chain(run_flags.iter().cloned())
+            .chain(["--input", "state.json", "--"].map(String::from))
+            .chain(host_cmd)
+            .collect()
     }
 }
 
 #[async_trait]
 impl Platform for Cannon {
-    async fn load_elf(&self, elf_path: &Path, workdir: &Path) -> Result<()> {
+    async fn prepare_client(
+        &self,
+        artifact: &Path,
+        kind: ClientArtifactKind,
+        workdir: &Path,
+    ) -> Result<()> {
+        if kind != ClientArtifactKind::Elf {
+            return Self::copy_state(artifact, kind, workdir);
+        }
+
         let result = Command::new(self.binary.display().to_string())
-            .arg("load-elf")
-            .arg("--path")
-            .arg(elf_path)
-            .arg("--out")
-            .arg(workdir.join("state.json"))
-            .arg("--meta")
-            .arg(workdir.join("meta.json"))
+            .args(Self::load_elf_args(artifact, workdir))
             .output()
             .await?;
 
@@ -53,29 +212,26 @@ impl Platform for Cannon {
         inputs: &ProgramHostInputs,
         program: Arc<dyn Program + Send + Sync>,
         workdir: &Path,
+        opts: &ProcessOptions,
     ) -> Result<u8> {
-        let host_args = program.host_cmd(inputs)?;
-        Command::new(self.binary.display().to_string())
-            .arg("run")
-            .arg("--info-at")
-            .arg("%10000000")
-            .arg("--proof-at")
-            .arg("never")
-            .arg("--input")
-            .arg("state.json")
-            .arg("--")
-            .args(host_args)
-            .current_dir(workdir)
-            .output()
-            .await?;
+        let mut cmd = Command::new(self.binary.display().to_string());
+        cmd.args(Self::run_args(&self.run_flags, program.host_cmd(inputs)?))
+            .current_dir(workdir);
+        let logs = OutputLogs {
+            stdout: workdir.join(STDOUT_LOG),
+            stderr: workdir.join(STDERR_LOG),
+        };
+        process::run_supervised(cmd, opts, &logs).await?;
 
-        // Read `out.json`
-        let output = serde_json::from_slice::<PartialCannonOutput>(
-            fs::read(workdir.join("out.json"))?.as_slice(),
-        )?;
-        ensure!(output.exited, "Program did not exit");
+        Self::read_exit(workdir)
+    }
 
-        Ok(output.exit)
+    fn final_state(&self, workdir: &Path) -> Option<PathBuf> {
+        Some(Self::final_state_path(workdir))
+    }
+
+    fn executed_steps(&self, workdir: &Path) -> Option<u64> {
+        Self::read_steps(workdir).ok()
     }
 }
 
@@ -86,4 +242,7 @@ struct PartialCannonOutput {
     exited: bool,
     /// The exit code of the program.
     exit: u8,
+    /// The number of steps that were executed.
+    #[serde(default)]
+    step: u64,
 }
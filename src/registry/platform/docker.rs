@@ -0,0 +1,280 @@
+//! Contains the implementation of the [Platform] trait for virtual machines that are ran inside of
+//! a docker container, so that they do not need to be built locally.
+
+use super::{cannon::Cannon, ClientArtifactKind, Platform, PlatformKind, STDERR_LOG, STDOUT_LOG};
+use crate::{
+    process::{self, OutputLogs, ProcessOptions},
+    registry::program::{Program, ProgramHostInputs},
+    state::StateDir,
+};
+use async_trait::async_trait;
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    Result,
+};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+use tokio::process::Command;
+use tracing::info;
+
+/// A virtual machine ran inside of a docker container. The entrypoint of the image must be the
+/// VM's binary.
+pub(crate) struct DockerPlatform {
+    /// The kind of the virtual machine.
+    kind: PlatformKind,
+    /// The container image that the virtual machine is ran in.
+    image: String,
+    /// The flags that the virtual machine is ran with.
+    run_flags: Vec<String>,
+}
+
+impl DockerPlatform {
+    /// Create a new `DockerPlatform` instance.
+    pub(crate) fn new(kind: PlatformKind, image: String, run_flags: Vec<String>) -> Self {
+        Self {
+            kind,
+            image,
+            run_flags,
+        }
+    }
+
+    /// Pulls the container image, in place of building the virtual machine locally.
+    pub(crate) async fn pull(image: &str) -> Result<()> {
+        info!(target: "docker-platform", "Pulling platform image: {image}");
+        let output = docker().arg("pull").arg(image).output().await?;
+        ensure!(
+            output.status.success(),
+            "Failed to pull image {image}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(())
+    }
+
+    /// Returns the `docker run` command that runs the virtual machine with the given arguments.
+    /// The workdir is mounted into the container read-write, and the other paths read-only, each
+    /// at the same path as on the host, so that the arguments need no translation.
+    ///
+    /// ## Takes
+    /// - `workdir` - The working directory of the virtual machine.
+    /// - `mounts` - The paths that the virtual machine reads from.
+    /// - `cpu` - The CPU core to pin the container to, if any.
+    /// - `args` - The arguments to the virtual machine.
+    ///
+    /// ## Returns
+    /// - `(Command, ContainerGuard)` - The command, and the guard that removes the container.
+    fn command(
+        &self,
+        workdir: &Path,
+        mounts: BTreeSet<PathBuf>,
+        cpu: Option<usize>,
+        args: Vec<String>,
+    ) -> (Command, ContainerGuard) {
+        let name = format!("fpt-{}-{:016x}", self.kind, rand::random::<u64>());
+
+        // SAFETY: `getuid` and `getgid` have no memory safety preconditions.
+        let user = unsafe { format!("{}:{}", libc::getuid(), libc::getgid()) };
+        let mut cmd = docker();
+        cmd.arg("run")
+            .arg("--rm")
+            .arg("--init")
+            .arg("--name")
+            .arg(&name)
+            .arg("--network")
+            .arg("host")
+            // Files written to the workdir stay owned by the user.
+            .arg("--user")
+            .arg(user)
+            .arg("--volume")
+            .arg(format!("{0}:{0}", workdir.display()))
+            .arg("--workdir")
+            .arg(workdir);
+        for mount in mounts.iter().filter(|m| !workdir.starts_with(m)) {
+            cmd.arg("--volume")
+                .arg(format!("{0}:{0}:ro", mount.display()));
+        }
+        if let Some(cpu) = cpu {
+            cmd.arg("--cpuset-cpus").arg(cpu.to_string());
+        }
+        cmd.arg(&self.image).args(args).current_dir(workdir);
+
+        (cmd, ContainerGuard(name))
+    }
+
+    /// Ensures that the virtual machine can be ran inside of a container.
+    fn check_kind(&self) -> Result<()> {
+        match self.kind {
+            PlatformKind::Cannon => Ok(()),
+            kind => bail!("Platform `{kind}` does not support the docker runtime"),
+        }
+    }
+}
+
+#[async_trait]
+impl Platform for DockerPlatform {
+    async fn prepare_client(
+        &self,
+        artifact: &Path,
+        kind: ClientArtifactKind,
+        workdir: &Path,
+    ) -> Result<()> {
+        self.check_kind()?;
+        if kind != ClientArtifactKind::Elf {
+            return Cannon::copy_state(artifact, kind, workdir);
+        }
+
+        let elf_dir = artifact
+            .parent()
+            .ok_or(eyre!("ELF file at top-level directory"))?;
+        let (mut cmd, _container) = self.command(
+            workdir,
+            BTreeSet::from([elf_dir.to_path_buf()]),
+            None,
+            Cannon::load_elf_args(artifact, workdir),
+        );
+        let result = cmd.output().await?;
+
+        ensure!(
+            result.status.success(),
+            "Failed to load ELF file into {} (image: {}): {}",
+            self.kind,
+            self.image,
+            String::from_utf8_lossy(&result.stderr).trim()
+        );
+
+        Ok(())
+    }
+
+    async fn run(
+        &self,
+        inputs: &ProgramHostInputs,
+        program: Arc<dyn Program + Send + Sync>,
+        workdir: &Path,
+        opts: &ProcessOptions,
+    ) -> Result<u8> {
+        self.check_kind()?;
+
+        // The host program runs inside of the container, alongside the virtual machine.
+        let host_cmd = program.host_cmd(inputs)?;
+        let host_dir = host_cmd
+            .first()
+            .and_then(|bin| Path::new(bin).parent())
+            .ok_or(eyre!("Missing host binary"))?;
+        // Compressed fixtures are decompressed outside of the fixture directory.
+        let fixture_dir = inputs
+            .rollup_cfg_path
+            .parent()
+            .ok_or(eyre!("Fixture at top-level directory"))?;
+        let decompressed_dir = inputs
+            .genesis_path
+            .parent()
+            .ok_or(eyre!("Fixture at top-level directory"))?;
+        let components_dir = StateDir::get().components_dir();
+        let mounts = [
+            host_dir,
+            fixture_dir,
+            decompressed_dir,
+            components_dir.as_path(),
+        ]
+        .into_iter()
+        .filter(|path| path.exists())
+        .map(std::path::absolute)
+        .collect::<Result<_, _>>()?;
+
+        // The container is pinned with `--cpuset-cpus`, rather than the docker client.
+        let (cmd, _container) = self.command(
+            workdir,
+            mounts,
+            opts.cpu,
+            Cannon::run_args(&self.run_flags, host_cmd),
+        );
+        let opts = ProcessOptions {
+            cpu: None,
+            // The resident set size of the docker client is not that of the VM.
+            peak_rss: None,
+            ..opts.clone()
+        };
+        let logs = OutputLogs {
+            stdout: workdir.join(STDOUT_LOG),
+            stderr: workdir.join(STDERR_LOG),
+        };
+        process::run_supervised(cmd, &opts, &logs).await?;
+
+        Cannon::read_exit(workdir)
+    }
+
+    fn final_state(&self, workdir: &Path) -> Option<PathBuf> {
+        Some(Cannon::final_state_path(workdir))
+    }
+
+    fn executed_steps(&self, workdir: &Path) -> Option<u64> {
+        Cannon::read_steps(workdir).ok()
+    }
+}
+
+/// Returns a command that runs the docker client, with the configuration of the [StateDir].
+fn docker() -> Command {
+    let mut cmd = Command::new("docker");
+    if let Some(config) = StateDir::get().docker_config() {
+        cmd.env("DOCKER_CONFIG", config);
+    }
+    cmd
+}
+
+/// Force-removes the container when dropped. Killing the docker client (e.g. when a test times
+/// out or stalls) leaves its container running otherwise.
+pub(crate) struct ContainerGuard(String);
+
+impl Drop for ContainerGuard {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new("docker")
+            .arg("rm")
+            .arg("--force")
+            .arg(&self.0)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docker_run_command() {
+        let platform = DockerPlatform::new(
+            PlatformKind::Cannon,
+            "cannon:latest".to_string(),
+            Cannon::run_flags(None),
+        );
+        let (cmd, container) = platform.command(
+            Path::new("/tmp/workdir"),
+            BTreeSet::from([PathBuf::from("/tmp"), PathBuf::from("/fixtures/a")]),
+            Some(3),
+            vec!["run".to_string()],
+        );
+        let args = cmd
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["--name", container.0.as_str()]));
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["--volume", "/tmp/workdir:/tmp/workdir"]));
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["--volume", "/fixtures/a:/fixtures/a:ro"]));
+        assert!(args.windows(2).any(|w| w == ["--cpuset-cpus", "3"]));
+        // Mounts that contain the workdir would shadow it.
+        assert!(!args.iter().any(|a| a == "/tmp:/tmp:ro"));
+        assert_eq!(args[args.len() - 2..], ["cannon:latest", "run"]);
+    }
+}
@@ -1,7 +1,10 @@
 //! Contains the implementation of the [Platform] trait for the Cannon virtual machine.
 
-use super::Platform;
-use crate::registry::program::{Program, ProgramHostInputs};
+use super::{ClientArtifactKind, Platform, STDERR_LOG, STDOUT_LOG};
+use crate::{
+    process::{self, OutputLogs, ProcessOptions},
+    registry::program::{Program, ProgramHostInputs},
+};
 use async_trait::async_trait;
 use color_eyre::{eyre::eyre, Result};
 use std::{path::Path, sync::Arc};
@@ -13,8 +16,8 @@ pub(crate) struct Native;
 
 #[async_trait]
 impl Platform for Native {
-    async fn load_elf(&self, _: &Path, _: &Path) -> Result<()> {
-        debug!(target: "native-platform", "Native platform; No need to load ELF file");
+    async fn prepare_client(&self, _: &Path, _: ClientArtifactKind, _: &Path) -> Result<()> {
+        debug!(target: "native-platform", "Native platform; No need to prepare the client");
         Ok(())
     }
 
@@ -23,19 +26,23 @@ impl Platform for Native {
         inputs: &ProgramHostInputs,
         program: Arc<dyn Program + Send + Sync>,
         workdir: &Path,
+        opts: &ProcessOptions,
     ) -> Result<u8> {
         let host_cmd = program.host_cmd(inputs)?;
 
         // On the native platform, the host program is ran verbatim.
-        let result = Command::new(host_cmd.first().ok_or(eyre!("Missing host binary"))?)
-            .args(
-                host_cmd
-                    .get(1..)
-                    .ok_or(eyre!("Missing host binary arguments"))?,
-            )
-            .current_dir(workdir)
-            .output()
-            .await?;
+        let mut cmd = Command::new(host_cmd.first().ok_or(eyre!("Missing host binary"))?);
+        cmd.args(
+            host_cmd
+                .get(1..)
+                .ok_or(eyre!("Missing host binary arguments"))?,
+        )
+        .current_dir(workdir);
+        let logs = OutputLogs {
+            stdout: workdir.join(STDOUT_LOG),
+            stderr: workdir.join(STDERR_LOG),
+        };
+        let result = process::run_supervised(cmd, opts, &logs).await?;
 
         Ok(result.status.code().ok_or(eyre!("Missing exit code"))? as u8)
     }
@@ -0,0 +1,144 @@
+//! Contains the [RunProfile]s, named bundles of test run options defined in the registry, so that
+//! CI pipelines select a run with `--profile <name>` rather than replicating long command lines.
+
+use super::{platform::PlatformKind, program::ProgramKind, FPRegistry};
+use crate::{cli::TestConfig, pipeline::retention::RetentionPolicy};
+use color_eyre::{eyre::eyre, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// A named bundle of test run options. Options that are given explicitly on the command line take
+/// precedence over the profile's.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct RunProfile {
+    /// The test to run (glob pattern supported).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) test: Option<String>,
+    /// The chains whose fixtures to run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) chain: Option<Vec<String>>,
+    /// The FPVMs to run the tests on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) vm: Option<Vec<PlatformKind>>,
+    /// The FPPs to run the tests on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) program: Option<Vec<ProgramKind>>,
+    /// Whether or not to run the complete matrix.
+    #[serde(default)]
+    pub(crate) all: bool,
+    /// Whether or not to cancel the remaining tests after the first failure.
+    #[serde(default)]
+    pub(crate) fail_fast: bool,
+    /// The number of times that a failed test is retried.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) retries: Option<u32>,
+    /// The number of seconds after which a test times out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) timeout: Option<u64>,
+    /// The number of seconds without output after which a test is considered stalled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) stall_timeout: Option<u64>,
+    /// What to keep of each test once it completes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) keep: Option<RetentionPolicy>,
+    /// The reports to write, as `[format:]path`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) report: Vec<String>,
+}
+
+impl FPRegistry {
+    /// Returns the [RunProfile] with the given name.
+    pub(crate) fn get_profile(&self, name: &str) -> Result<&RunProfile> {
+        self.profile.get(name).ok_or_else(|| {
+            eyre!(
+                "Unknown profile `{name}`, expected one of: {}",
+                self.profile.keys().sorted().join(", ")
+            )
+        })
+    }
+}
+
+impl RunProfile {
+    /// Applies the profile to the test configuration, filling in the options that were not given
+    /// explicitly. The profile's matrix selection only applies if the configuration selects
+    /// neither platforms nor programs.
+    pub(crate) fn apply(&self, cfg: &mut TestConfig) -> Result<()> {
+        let selects_matrix =
+            cfg.vm.is_some() || cfg.program.is_some() || cfg.all || cfg.all_vms || cfg.all_programs;
+        if !selects_matrix {
+            cfg.vm = self.vm.clone();
+            cfg.program = self.program.clone();
+            cfg.all = self.all;
+        }
+
+        cfg.test = cfg.test.take().or_else(|| self.test.clone());
+        cfg.chain = cfg.chain.take().or_else(|| self.chain.clone());
+        cfg.fail_fast |= self.fail_fast;
+        if cfg.retries == 0 {
+            cfg.retries = self.retries.unwrap_or_default();
+        }
+        cfg.timeout = cfg.timeout.or(self.timeout);
+        cfg.keep = cfg.keep.or(self.keep);
+        if cfg.stall_timeout.is_none() && self.stall_timeout.is_some() {
+            cfg.stall_timeout = self.stall_timeout;
+            cfg.kill_stalled = true;
+        }
+        if cfg.report.is_empty() {
+            cfg.report = self
+                .report
+                .iter()
+                .map(|r| r.parse())
+                .collect::<Result<_>>()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::{Cli, CliSubcommand},
+        registry::FP_REGISTRY,
+    };
+    use clap::Parser;
+
+    #[test]
+    fn apply_profiles() {
+        let cfg =
+            |args: &[&str]| match Cli::parse_from([&["fpt", "test"], args].concat()).subcommand {
+                Some(CliSubcommand::Test(cfg)) => cfg,
+                _ => unreachable!(),
+            };
+        let profile = RunProfile {
+            all: true,
+            retries: Some(2),
+            timeout: Some(600),
+            report: vec!["junit:results.xml".to_string()],
+            ..Default::default()
+        };
+
+        let mut bare = cfg(&[]);
+        profile.apply(&mut bare).unwrap();
+        assert!(bare.all);
+        assert_eq!((bare.retries, bare.timeout), (2, Some(600)));
+        assert_eq!(bare.report, vec!["junit:results.xml".parse().unwrap()]);
+
+        // Explicit options take precedence over the profile's.
+        let mut explicit = cfg(&["--vm", "cannon", "--timeout", "60"]);
+        profile.apply(&mut explicit).unwrap();
+        assert!(!explicit.all);
+        assert_eq!((explicit.retries, explicit.timeout), (2, Some(60)));
+
+        // The registry's profiles are valid.
+        for name in ["smoke", "pr", "nightly", "release"] {
+            FP_REGISTRY
+                .get_profile(name)
+                .unwrap()
+                .apply(&mut cfg(&[]))
+                .unwrap();
+        }
+        assert!(FP_REGISTRY.get_profile("unknown").is_err());
+    }
+}
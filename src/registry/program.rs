@@ -1,10 +1,13 @@
 //! Contains the [Program] trait, which defines the interface for a fault proof program.
 
-use crate::fixture::FixtureInputs;
-use color_eyre::{eyre::bail, Result};
+use crate::fixture::{FixtureInputs, InteropInputs};
+use clap::ValueEnum;
+use color_eyre::Result;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, path::PathBuf, str::FromStr, sync::Arc};
+use std::{fmt::Display, path::PathBuf, sync::Arc};
 
+pub(crate) mod builtin;
+pub(crate) mod kona;
 pub(crate) mod op_program;
 
 /// The minimal interface for a fault proof program host binary.
@@ -20,8 +23,9 @@ pub(crate) trait Program {
 }
 
 /// Supported program kinds.
-#[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
 pub(crate) enum ProgramKind {
     /// `op-program` (native)
     #[default]
@@ -34,41 +38,40 @@ pub(crate) enum ProgramKind {
     KonaNative,
     /// `kona` (riscv / asterisc)
     KonaRiscv,
+    /// A mock program, for testing the crate itself.
+    #[cfg(test)]
+    Mock,
 }
 
 impl ProgramKind {
-    pub(crate) fn get_program(&self, bin_path: PathBuf) -> Arc<dyn Program + Send + Sync> {
+    /// Returns the [Program] for the kind.
+    ///
+    /// ## Takes
+    /// - `bin_path` - The path to the host binary.
+    /// - `client_path` - The path to the client binary, which native hosts run as a child process.
+    pub(crate) fn get_program(
+        &self,
+        bin_path: PathBuf,
+        client_path: PathBuf,
+    ) -> Arc<dyn Program + Send + Sync> {
         match self {
             Self::OpProgramNative => Arc::new(op_program::OpProgram::new(bin_path, false)),
             Self::OpProgramMips | Self::OpProgramRiscv => {
                 Arc::new(op_program::OpProgram::new(bin_path, true))
             }
-            _ => todo!(),
-        }
-    }
-}
-
-impl FromStr for ProgramKind {
-    type Err = color_eyre::Report;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "op-program-native" => Ok(Self::OpProgramNative),
-            "op-program-mips" => Ok(Self::OpProgramMips),
-            "op-program-riscv" => Ok(Self::OpProgramRiscv),
-            "kona-native" => Ok(Self::KonaNative),
-            "kona-riscv" => Ok(Self::KonaNative),
-            _ => bail!("Unknown program kind: {}", s),
+            Self::KonaNative => Arc::new(kona::Kona::new(
+                bin_path,
+                kona::KonaHostMode::Native {
+                    client: client_path,
+                },
+            )),
+            Self::KonaRiscv => Arc::new(kona::Kona::new(bin_path, kona::KonaHostMode::Server)),
+            #[cfg(test)]
+            Self::Mock => Arc::new(super::mock::MockProgram),
         }
     }
 }
 
-impl From<String> for ProgramKind {
-    fn from(s: String) -> Self {
-        s.parse().unwrap_or_else(|e| panic!("{e}"))
-    }
-}
-
 impl Display for ProgramKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -77,6 +80,8 @@ impl Display for ProgramKind {
             Self::OpProgramRiscv => write!(f, "op-program-riscv"),
             Self::KonaNative => write!(f, "kona-native"),
             Self::KonaRiscv => write!(f, "kona-riscv"),
+            #[cfg(test)]
+            Self::Mock => write!(f, "mock"),
         }
     }
 }
@@ -90,10 +95,38 @@ pub(crate) struct ProgramHostInputs {
     pub(crate) rollup_cfg_path: PathBuf,
     /// The path to the `genesis.json` file.
     pub(crate) genesis_path: PathBuf,
+    /// The path to the JSON encoded L2 chain configuration, if it was already parsed out of the
+    /// genesis.
+    pub(crate) chain_config_path: Option<PathBuf>,
     /// The data sources for the fixture.
     pub(crate) source: ProgramHostSource,
 }
 
+impl ProgramHostInputs {
+    /// Returns the paths to the rollup configuration and genesis of each chain of an interop
+    /// fixture, in the order of its chains. The configuration of the fixture's own chain is its
+    /// `rollup.json` and `genesis.json`; those of the other chains lie beside them, suffixed with
+    /// their chain IDs (e.g. `rollup-902.json` and `genesis-902.json`).
+    pub(crate) fn interop_chain_configs(&self, interop: &InteropInputs) -> Vec<(PathBuf, PathBuf)> {
+        interop
+            .chains
+            .iter()
+            .map(|chain| {
+                if chain.chain_id == self.fixture_inputs.l2_chain_id {
+                    return (self.rollup_cfg_path.clone(), self.genesis_path.clone());
+                }
+                let id = chain.chain_id;
+                (
+                    self.rollup_cfg_path
+                        .with_file_name(format!("rollup-{id}.json")),
+                    self.genesis_path
+                        .with_file_name(format!("genesis-{id}.json")),
+                )
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum ProgramHostSource {
     /// Disk-backed preimage server.
@@ -0,0 +1,65 @@
+//! Contains the implementation of [Program] for `fpt`'s builtin preimage server.
+
+use super::Program;
+use crate::{
+    preimage::faults::PreimageFault,
+    registry::program::{ProgramHostInputs, ProgramHostSource},
+};
+use color_eyre::{eyre::bail, Result};
+use std::env;
+
+/// The builtin host, which serves the client of a fault proof program from the fixture's witness
+/// database with `fpt preimage-server`, rather than with the program's host binary.
+#[derive(Debug, Default)]
+pub(crate) struct BuiltinHost {
+    /// The fault to inject into the preimage server's channels, if any.
+    pub(crate) fault: Option<PreimageFault>,
+}
+
+impl Program for BuiltinHost {
+    fn host_cmd(&self, inputs: &ProgramHostInputs) -> Result<Vec<String>> {
+        let ProgramHostSource::Disk { path } = &inputs.source else {
+            bail!("The builtin host only supports disk-backed preimage sources");
+        };
+        if inputs.fixture_inputs.interop.is_some() {
+            bail!("The builtin host does not support interop fixtures");
+        }
+
+        let mut cmd = vec![
+            env::current_exe()?.display().to_string(),
+            "preimage-server".to_string(),
+            "--l1-head".to_string(),
+            inputs.fixture_inputs.l1_head.to_string(),
+            "--l2-head".to_string(),
+            inputs.fixture_inputs.l2_head.to_string(),
+            "--l2-output-root".to_string(),
+            inputs.fixture_inputs.l2_output_root.to_string(),
+            "--l2-claim".to_string(),
+            inputs.fixture_inputs.l2_claim.to_string(),
+            "--l2-block-number".to_string(),
+            inputs.fixture_inputs.l2_block_number.to_string(),
+            "--l2-chain-id".to_string(),
+            inputs.fixture_inputs.l2_chain_id.to_string(),
+            "--rollup-config".to_string(),
+            inputs.rollup_cfg_path.display().to_string(),
+            "--datadir".to_string(),
+            path.display().to_string(),
+        ];
+        // The chain configuration is read from its own file once it was parsed out of the genesis,
+        // rather than parsing the genesis again.
+        match inputs.chain_config_path.as_ref() {
+            Some(chain_config) => cmd.extend([
+                "--chain-config".to_string(),
+                chain_config.display().to_string(),
+            ]),
+            None => cmd.extend([
+                "--genesis".to_string(),
+                inputs.genesis_path.display().to_string(),
+            ]),
+        }
+        if let Some(fault) = self.fault {
+            cmd.extend(["--fault".to_string(), fault.to_string()]);
+        }
+        Ok(cmd)
+    }
+}
@@ -0,0 +1,136 @@
+//! Contains the implementation of [Program] for `kona`
+
+use std::path::PathBuf;
+
+use super::Program;
+use crate::{
+    fixture::InteropInputs,
+    registry::program::{ProgramHostInputs, ProgramHostSource},
+};
+use color_eyre::{eyre::bail, Result};
+
+/// The `kona` fault proof program, served by `kona-host`.
+pub(crate) struct Kona {
+    /// The path to the `kona-host` binary.
+    pub(crate) binary: PathBuf,
+    /// The mode that the host runs in.
+    pub(crate) mode: KonaHostMode,
+}
+
+/// The modes that `kona-host` can run in.
+pub(crate) enum KonaHostMode {
+    /// Serve the preimages to a client running on a VM, over the host's file descriptors.
+    Server,
+    /// Run the native client binary as a child process, and serve its preimages.
+    Native {
+        /// The path to the native client binary.
+        client: PathBuf,
+    },
+}
+
+impl Kona {
+    /// Create a new `Kona` instance.
+    pub(crate) fn new(binary: PathBuf, mode: KonaHostMode) -> Self {
+        Self { binary, mode }
+    }
+
+    /// Appends the flags of the host's mode to the command.
+    fn push_mode_flags(&self, cmd: &mut Vec<String>) {
+        match &self.mode {
+            KonaHostMode::Server => cmd.push("--server".to_string()),
+            KonaHostMode::Native { client } => {
+                cmd.extend(vec!["--exec".to_string(), client.display().to_string()]);
+            }
+        }
+    }
+
+    /// Returns the arguments for the host's `super` subcommand, which proves a super root over
+    /// the chains of an interop fixture from the agreed prestate.
+    fn interop_host_cmd(
+        &self,
+        inputs: &ProgramHostInputs,
+        interop: &InteropInputs,
+    ) -> Result<Vec<String>> {
+        let ProgramHostSource::Disk { path } = &inputs.source else {
+            bail!("Interop fixtures are only supported with disk-backed preimage sources");
+        };
+
+        let rollup_cfgs = inputs
+            .interop_chain_configs(interop)
+            .into_iter()
+            .map(|(rollup_cfg, _)| rollup_cfg.display().to_string())
+            .collect::<Vec<_>>();
+        let mut cmd = vec![
+            self.binary.display().to_string(),
+            "super".to_string(),
+            "--l1-head".to_string(),
+            inputs.fixture_inputs.l1_head.to_string(),
+            "--agreed-l2-pre-state".to_string(),
+            interop.agreed_prestate().to_string(),
+            "--claimed-l2-post-state".to_string(),
+            inputs.fixture_inputs.l2_claim.to_string(),
+            "--claimed-l2-timestamp".to_string(),
+            interop.claimed_timestamp.to_string(),
+            "--rollup-config-paths".to_string(),
+            rollup_cfgs.join(","),
+        ];
+        self.push_mode_flags(&mut cmd);
+        cmd.extend(["--data-dir".to_string(), path.display().to_string()]);
+
+        Ok(cmd)
+    }
+}
+
+/// The `kona` fault proof program.
+impl Program for Kona {
+    fn host_cmd(&self, inputs: &ProgramHostInputs) -> Result<Vec<String>> {
+        if let Some(interop) = inputs.fixture_inputs.interop.as_ref() {
+            return self.interop_host_cmd(inputs, interop);
+        }
+
+        let mut cmd = vec![
+            self.binary.display().to_string(),
+            "--l1-head".to_string(),
+            inputs.fixture_inputs.l1_head.to_string(),
+            "--l2-head".to_string(),
+            inputs.fixture_inputs.l2_head.to_string(),
+            "--l2-output-root".to_string(),
+            inputs.fixture_inputs.l2_output_root.to_string(),
+            "--l2-claim".to_string(),
+            inputs.fixture_inputs.l2_claim.to_string(),
+            "--l2-block-number".to_string(),
+            inputs.fixture_inputs.l2_block_number.to_string(),
+            "--rollup-config-path".to_string(),
+            inputs.rollup_cfg_path.display().to_string(),
+        ];
+
+        self.push_mode_flags(&mut cmd);
+
+        // Set up the data source flags. Without node addresses, the host runs offline, serving
+        // only the preimages within the data directory.
+        match inputs.source.clone() {
+            ProgramHostSource::Disk { path } => {
+                cmd.extend(vec!["--data-dir".to_string(), path.display().to_string()]);
+            }
+            ProgramHostSource::Rpc {
+                l1,
+                l1_beacon,
+                l2,
+                path,
+            } => {
+                cmd.extend(vec![
+                    "--l1-node-address".to_string(),
+                    l1,
+                    "--l1-beacon-address".to_string(),
+                    l1_beacon,
+                    "--l2-node-address".to_string(),
+                    l2,
+                    "--data-dir".to_string(),
+                    path.display().to_string(),
+                ]);
+            }
+        }
+
+        Ok(cmd)
+    }
+}
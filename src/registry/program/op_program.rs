@@ -3,8 +3,11 @@
 use std::path::PathBuf;
 
 use super::Program;
-use crate::registry::program::{ProgramHostInputs, ProgramHostSource};
-use color_eyre::Result;
+use crate::{
+    fixture::InteropInputs,
+    registry::program::{ProgramHostInputs, ProgramHostSource},
+};
+use color_eyre::{eyre::bail, Result};
 
 /// The `op-program` fault proof program.
 pub(crate) struct OpProgram {
@@ -25,6 +28,10 @@ impl OpProgram {
 /// The `op-program` fault proof program.
 impl Program for OpProgram {
     fn host_cmd(&self, inputs: &ProgramHostInputs) -> Result<Vec<String>> {
+        if let Some(interop) = inputs.fixture_inputs.interop.as_ref() {
+            return self.interop_host_cmd(inputs, interop);
+        }
+
         let mut cmd = vec![
             self.binary.display().to_string(),
             "--l1.head".to_string(),
@@ -75,3 +82,50 @@ impl Program for OpProgram {
         Ok(cmd)
     }
 }
+
+impl OpProgram {
+    /// Returns the arguments for the host in interop mode, which proves a super root over the
+    /// chains of an interop fixture from the agreed prestate.
+    fn interop_host_cmd(
+        &self,
+        inputs: &ProgramHostInputs,
+        interop: &InteropInputs,
+    ) -> Result<Vec<String>> {
+        let ProgramHostSource::Disk { path } = &inputs.source else {
+            bail!("Interop fixtures are only supported with disk-backed preimage sources");
+        };
+
+        let (rollup_cfgs, genesis): (Vec<_>, Vec<_>) = inputs
+            .interop_chain_configs(interop)
+            .into_iter()
+            .map(|(rollup_cfg, genesis)| {
+                (
+                    rollup_cfg.display().to_string(),
+                    genesis.display().to_string(),
+                )
+            })
+            .unzip();
+        let mut cmd = vec![
+            self.binary.display().to_string(),
+            "--interop".to_string(),
+            "--l1.head".to_string(),
+            inputs.fixture_inputs.l1_head.to_string(),
+            "--l2.agreed-prestate".to_string(),
+            interop.agreed_prestate().to_string(),
+            "--l2.claim".to_string(),
+            inputs.fixture_inputs.l2_claim.to_string(),
+            "--l2.blocknumber".to_string(),
+            interop.claimed_timestamp.to_string(),
+            "--rollup.config".to_string(),
+            rollup_cfgs.join(","),
+            "--l2.genesis".to_string(),
+            genesis.join(","),
+        ];
+        if self.server_mode {
+            cmd.push("--server".to_string());
+        }
+        cmd.extend(["--datadir".to_string(), path.display().to_string()]);
+
+        Ok(cmd)
+    }
+}
@@ -0,0 +1,183 @@
+//! Contains the [ValidatorDefinition]s, external commands configured in the registry that are ran
+//! after each test with its outcome and artifacts, and may veto its pass (e.g. by scanning the
+//! host's output for error patterns, or checking the output root against an external source).
+//!
+//! A validator is ran with `sh -c` in the working directory of the test, with:
+//! - [RESULT_ENV] - The [TestResult] of the test, as JSON.
+//! - [WORKDIR_ENV] - The working directory of the test, holding the VM's state and the output
+//!   logs of the platform.
+//! - [FIXTURE_DIR_ENV] - The directory of the test's fixture.
+//! - [OUTPUT_LOG_ENV] - The combined output of the platform and the host, if it was written.
+//!
+//! It passes the test by exiting with status 0. The last line of its output is recorded as the
+//! message of its verdict.
+
+use super::program::ProgramKind;
+use crate::report::TestResult;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+/// The environment variable that holds the test result, as JSON.
+pub(crate) const RESULT_ENV: &str = "FPT_RESULT";
+
+/// The environment variable that holds the path to the working directory of the test.
+pub(crate) const WORKDIR_ENV: &str = "FPT_WORKDIR";
+
+/// The environment variable that holds the path to the directory of the test's fixture.
+pub(crate) const FIXTURE_DIR_ENV: &str = "FPT_FIXTURE_DIR";
+
+/// The environment variable that holds the path to the output log of the test, if it was written.
+pub(crate) const OUTPUT_LOG_ENV: &str = "FPT_OUTPUT_LOG";
+
+/// A validator of test results.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct ValidatorDefinition {
+    /// The command that validates a test, ran with `sh -c`.
+    pub(crate) command: String,
+    /// The programs whose tests are validated (default = those of every program).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) programs: Option<Vec<ProgramKind>>,
+}
+
+/// The verdict of a validator on a test.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct ValidatorVerdict {
+    /// The name of the validator in the registry.
+    pub(crate) validator: String,
+    /// Whether or not the validator passed the test.
+    pub(crate) passed: bool,
+    /// The last line of the validator's output, if it wrote any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) message: Option<String>,
+}
+
+/// The outcome and artifacts of a test, which validators are ran with.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ValidatorInputs<'a> {
+    /// The result of the test.
+    pub(crate) result: &'a TestResult,
+    /// The working directory of the test.
+    pub(crate) workdir: &'a Path,
+    /// The directory of the test's fixture.
+    pub(crate) fixture_dir: &'a Path,
+    /// The output log of the test, if it was written.
+    pub(crate) output_log: Option<&'a Path>,
+}
+
+impl ValidatorDefinition {
+    /// Returns whether or not the validator validates the tests of the program.
+    pub(crate) fn applies_to(&self, program: ProgramKind) -> bool {
+        self.programs
+            .as_ref()
+            .is_none_or(|programs| programs.contains(&program))
+    }
+
+    /// Runs the validator on a test. A validator that cannot be ran fails the test.
+    ///
+    /// ## Takes
+    /// - `name` - The name of the validator in the registry.
+    /// - `inputs` - The outcome and artifacts of the test.
+    ///
+    /// ## Returns
+    /// - `ValidatorVerdict` - The verdict of the validator.
+    pub(crate) async fn run(&self, name: &str, inputs: ValidatorInputs<'_>) -> ValidatorVerdict {
+        let verdict = |passed, message: Option<String>| ValidatorVerdict {
+            validator: name.to_string(),
+            passed,
+            message,
+        };
+        let result = match serde_json::to_string(inputs.result) {
+            Ok(result) => result,
+            Err(e) => return verdict(false, Some(format!("Failed to encode the result: {e}"))),
+        };
+
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&self.command)
+            .current_dir(inputs.workdir)
+            .env(RESULT_ENV, result)
+            .env(WORKDIR_ENV, inputs.workdir)
+            .env(FIXTURE_DIR_ENV, inputs.fixture_dir);
+        if let Some(output_log) = inputs.output_log {
+            command.env(OUTPUT_LOG_ENV, output_log);
+        }
+        match command.output().await {
+            Ok(output) => {
+                let last_line = |bytes: &[u8]| {
+                    String::from_utf8_lossy(bytes)
+                        .lines()
+                        .map(str::trim)
+                        .rfind(|line| !line.is_empty())
+                        .map(String::from)
+                };
+                verdict(
+                    output.status.success(),
+                    last_line(&output.stdout).or_else(|| last_line(&output.stderr)),
+                )
+            }
+            Err(e) => verdict(false, Some(format!("Failed to run the validator: {e}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registry::platform::PlatformKind, report::TestResult};
+    use std::fs;
+
+    #[tokio::test]
+    async fn run_validators() {
+        let workdir = tempfile::tempdir().unwrap();
+        let output_log = workdir.path().join("output.log");
+        fs::write(
+            &output_log,
+            "INFO derived the output root\nERROR panicked\n",
+        )
+        .unwrap();
+        let result = TestResult {
+            platform: PlatformKind::Native,
+            program: ProgramKind::KonaNative,
+            ..TestResult::mock("basic")
+        };
+        let inputs = ValidatorInputs {
+            result: &result,
+            workdir: workdir.path(),
+            fixture_dir: Path::new("tests/basic"),
+            output_log: Some(&output_log),
+        };
+
+        let status = ValidatorDefinition {
+            command: "echo \"$FPT_RESULT\" | grep -q '\"status\":\"pass\"' && echo \"ok in $FPT_FIXTURE_DIR\"".to_string(),
+            programs: Some(vec![ProgramKind::KonaNative]),
+        };
+        assert!(status.applies_to(ProgramKind::KonaNative));
+        assert!(!status.applies_to(ProgramKind::OpProgramNative));
+        assert_eq!(
+            status.run("status", inputs).await,
+            ValidatorVerdict {
+                validator: "status".to_string(),
+                passed: true,
+                message: Some("ok in tests/basic".to_string()),
+            }
+        );
+
+        let errors = ValidatorDefinition {
+            command: "! grep ERROR \"$FPT_OUTPUT_LOG\"".to_string(),
+            programs: None,
+        };
+        assert!(errors.applies_to(ProgramKind::OpProgramNative));
+        assert_eq!(
+            errors.run("host-errors", inputs).await,
+            ValidatorVerdict {
+                validator: "host-errors".to_string(),
+                passed: false,
+                message: Some("ERROR panicked".to_string()),
+            }
+        );
+    }
+}
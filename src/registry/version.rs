@@ -0,0 +1,93 @@
+//! Contains the [RevVersion], the semantic version of a component revision, used to compare the
+//! revisions that the registry builds against the revisions that fixtures require.
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Report,
+};
+use std::{cmp::Ordering, str::FromStr};
+
+/// The semantic version of a component revision, parsed from tags such as `v1.3.1`, or
+/// `op-program/v1.3.1-rc.2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RevVersion {
+    /// The major, minor, and patch versions.
+    pub(crate) core: (u64, u64, u64),
+    /// The dot-separated pre-release identifiers, if any.
+    pub(crate) pre: Vec<String>,
+}
+
+impl FromStr for RevVersion {
+    type Err = Report;
+
+    fn from_str(rev: &str) -> Result<Self, Self::Err> {
+        // Strip the component prefix of monorepo tags, and the `v` prefix.
+        let version = rev.rsplit('/').next().unwrap_or(rev);
+        let version = version.strip_prefix('v').unwrap_or(version);
+
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').map(str::to_string).collect()),
+            None => (version, Vec::new()),
+        };
+        let parts = core
+            .split('.')
+            .map(|p| p.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| eyre!("Revision `{rev}` is not a semantic version"))?;
+        let [major, minor, patch] = parts[..] else {
+            bail!("Revision `{rev}` is not a semantic version");
+        };
+        Ok(Self {
+            core: (major, minor, patch),
+            pre,
+        })
+    }
+}
+
+impl Ord for RevVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // A pre-release precedes its release, and numeric identifiers precede alphanumeric ones.
+        let pre = match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => self
+                .pre
+                .iter()
+                .zip(other.pre.iter())
+                .map(|(a, b)| match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => a.cmp(b),
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| self.pre.len().cmp(&other.pre.len())),
+        };
+        self.core.cmp(&other.core).then(pre)
+    }
+}
+
+impl PartialOrd for RevVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_rev_versions() {
+        let v = |rev: &str| rev.parse::<RevVersion>().unwrap();
+        assert_eq!(v("op-program/v1.3.1-rc.2").core, (1, 3, 1));
+        assert!(v("op-program/v1.3.1-rc.2") < v("op-program/v1.3.1-rc.10"));
+        assert!(v("v1.3.1-rc.2") < v("v1.3.1"));
+        assert!(v("v1.3.1") < v("1.4.0-alpha"));
+        assert!(v("v1.4.0-alpha") < v("v1.4.0-alpha.1"));
+        assert!(v("v1.4.0-1") < v("v1.4.0-alpha"));
+        assert!("main".parse::<RevVersion>().is_err());
+        assert!("d1a2b3c".parse::<RevVersion>().is_err());
+    }
+}
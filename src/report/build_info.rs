@@ -0,0 +1,81 @@
+//! Contains the [BuildInfo] of `fpt`, printed by `fpt --version --verbose` and embedded in every
+//! report, so that differences in behavior between runs can be attributed to the harness itself.
+
+use super::FPT_VERSION;
+use crate::registry::embedded_registry_hash;
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The git commit that `fpt` was built from, or empty if it was not built from a git checkout.
+const GIT_SHA: &str = env!("FPT_GIT_SHA");
+
+/// Whether or not the git checkout that `fpt` was built from had uncommitted changes.
+const GIT_DIRTY: &str = env!("FPT_GIT_DIRTY");
+
+/// The cargo features that `fpt` was compiled with, delineated by commas.
+const FEATURES: &str = env!("FPT_FEATURES");
+
+/// The build info of `fpt`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct BuildInfo {
+    /// The version of `fpt`.
+    pub(crate) version: String,
+    /// The git commit that `fpt` was built from, if it was built from a git checkout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) git_sha: Option<String>,
+    /// Whether or not the git checkout had uncommitted changes.
+    #[serde(default)]
+    pub(crate) dirty: bool,
+    /// The keccak256 hash of the registry embedded into `fpt`.
+    pub(crate) registry_hash: B256,
+    /// The cargo features that `fpt` was compiled with.
+    #[serde(default)]
+    pub(crate) features: Vec<String>,
+}
+
+impl BuildInfo {
+    /// Returns the build info of the running `fpt` binary.
+    pub(crate) fn current() -> Self {
+        Self {
+            version: FPT_VERSION.to_string(),
+            git_sha: (!GIT_SHA.is_empty()).then(|| GIT_SHA.to_string()),
+            dirty: GIT_DIRTY == "true",
+            registry_hash: embedded_registry_hash(),
+            features: FEATURES
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Returns the build info as `(key, value)` pairs, in the order that they are printed.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut git_sha = self.git_sha.clone().unwrap_or("unknown".to_string());
+        if self.dirty {
+            git_sha.push_str(" (dirty)");
+        }
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        vec![
+            ("version", self.version.clone()),
+            ("git-sha", git_sha),
+            ("registry-hash", self.registry_hash.to_string()),
+            ("features", features),
+        ]
+    }
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in self.fields() {
+            writeln!(f, "{key:<14} {value}")?;
+        }
+        Ok(())
+    }
+}
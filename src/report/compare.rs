@@ -0,0 +1,193 @@
+//! Contains the [Comparison] of two test runs, which shows the blast radius of a change (e.g. a
+//! bumped component revision) across the test matrix.
+
+use super::{TestId, TestReport, TestResult, TestStatus};
+use crate::units::Elapsed;
+use cli_table::{Cell, Style, Table, TableStruct};
+use color_eyre::owo_colors::OwoColorize;
+use std::{collections::BTreeMap, time::Duration};
+
+/// A test case, compared across two runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ComparedTest {
+    /// The test case.
+    pub(crate) id: TestId,
+    /// The result of the test case in the first run, if it was ran.
+    pub(crate) a: Option<TestResult>,
+    /// The result of the test case in the second run, if it was ran.
+    pub(crate) b: Option<TestResult>,
+}
+
+impl ComparedTest {
+    /// Returns whether or not the status of the test case differs between the runs, including if
+    /// it was only ran in one of them.
+    pub(crate) fn status_changed(&self) -> bool {
+        self.a.as_ref().map(|r| r.status) != self.b.as_ref().map(|r| r.status)
+    }
+
+    /// Returns the change in the wall time of the test case between the runs, as a percentage of
+    /// its wall time in the first run.
+    pub(crate) fn duration_delta(&self) -> Option<f64> {
+        let (a, b) = (self.a.as_ref()?, self.b.as_ref()?);
+        (a.duration_ms > 0)
+            .then(|| (b.duration_ms as f64 - a.duration_ms as f64) / a.duration_ms as f64 * 100.0)
+    }
+}
+
+/// A side-by-side comparison of two test runs.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Comparison {
+    /// The test cases of either run, ordered by platform, program, and fixture.
+    pub(crate) tests: Vec<ComparedTest>,
+    /// The percentage by which the wall time of a test case must change to be highlighted.
+    pub(crate) threshold: f64,
+}
+
+impl Comparison {
+    /// Compares the results of two runs.
+    ///
+    /// ## Takes
+    /// - `a` - The report of the first run.
+    /// - `b` - The report of the second run.
+    /// - `threshold` - The percentage by which the wall time of a test case must change to be
+    ///   highlighted.
+    pub(crate) fn new(a: &TestReport, b: &TestReport, threshold: f64) -> Self {
+        let mut tests = BTreeMap::<_, ComparedTest>::new();
+        for (result, in_a) in a
+            .results
+            .iter()
+            .map(|r| (r, true))
+            .chain(b.results.iter().map(|r| (r, false)))
+        {
+            let id = result.id();
+            let test = tests
+                .entry((
+                    id.platform.to_string(),
+                    id.program.to_string(),
+                    id.fixture.clone(),
+                ))
+                .or_insert_with(|| ComparedTest {
+                    id,
+                    a: None,
+                    b: None,
+                });
+            if in_a {
+                test.a = Some(result.clone());
+            } else {
+                test.b = Some(result.clone());
+            }
+        }
+
+        Self {
+            tests: tests.into_values().collect(),
+            threshold,
+        }
+    }
+
+    /// Returns the number of test cases whose status changed between the runs.
+    pub(crate) fn status_changes(&self) -> usize {
+        self.tests.iter().filter(|t| t.status_changed()).count()
+    }
+
+    /// Returns the number of test cases whose wall time changed by more than the threshold.
+    pub(crate) fn duration_changes(&self) -> usize {
+        self.tests
+            .iter()
+            .filter_map(|t| t.duration_delta())
+            .filter(|d| d.abs() > self.threshold)
+            .count()
+    }
+
+    /// Renders the comparison as a table, highlighting changed statuses, and changes in wall time
+    /// beyond the threshold.
+    pub(crate) fn table(&self) -> TableStruct {
+        let status = |result: Option<&TestResult>, changed: bool| {
+            let status = result.map_or("-".to_string(), |r| format!("{:?}", r.status));
+            match result.map(|r| r.status) {
+                _ if !changed => status,
+                Some(TestStatus::Pass) => status.green().bold().to_string(),
+                Some(_) => status.red().bold().to_string(),
+                None => status.yellow().bold().to_string(),
+            }
+        };
+        let duration = |result: Option<&TestResult>| {
+            result.map_or("-".to_string(), |r| {
+                Elapsed(Duration::from_millis(r.duration_ms)).to_string()
+            })
+        };
+
+        self.tests
+            .iter()
+            .map(|t| {
+                let changed = t.status_changed();
+                let delta = t.duration_delta().map_or("-".to_string(), |d| {
+                    let delta = format!("{d:+.1}%");
+                    if d > self.threshold {
+                        delta.red().to_string()
+                    } else if d < -self.threshold {
+                        delta.green().to_string()
+                    } else {
+                        delta
+                    }
+                });
+                vec![
+                    t.id.to_string().cell(),
+                    status(t.a.as_ref(), changed).cell(),
+                    status(t.b.as_ref(), changed).cell(),
+                    duration(t.a.as_ref()).cell(),
+                    duration(t.b.as_ref()).cell(),
+                    delta.cell(),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Test".cell(),
+                "Status (A)".cell(),
+                "Status (B)".cell(),
+                "Wall Time (A)".cell(),
+                "Wall Time (B)".cell(),
+                "Delta".cell(),
+            ])
+            .bold(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_runs() {
+        let result = |fixture: &str, status, duration_ms| TestResult {
+            status,
+            duration_ms,
+            ..TestResult::mock(fixture)
+        };
+        let a = TestReport::new(
+            vec![
+                result("steady", TestStatus::Pass, 1000),
+                result("broken", TestStatus::Pass, 1000),
+                result("removed", TestStatus::Pass, 1000),
+            ],
+            None,
+        );
+        let b = TestReport::new(
+            vec![
+                result("steady", TestStatus::Pass, 1100),
+                result("broken", TestStatus::Fail, 3000),
+            ],
+            None,
+        );
+
+        let comparison = Comparison::new(&a, &b, 20.0);
+        let fixtures = comparison
+            .tests
+            .iter()
+            .map(|t| t.id.fixture.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fixtures, vec!["broken", "removed", "steady"]);
+        assert_eq!(comparison.status_changes(), 2);
+        assert_eq!(comparison.duration_changes(), 1);
+        assert_eq!(comparison.tests[2].duration_delta(), Some(10.0));
+    }
+}
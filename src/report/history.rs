@@ -0,0 +1,144 @@
+//! Contains the history of test runs, which records the [TestReport] of every run under
+//! [StateDir::history_dir] and scores the flakiness of each test case across them.
+
+use super::{TestId, TestReport, TestStatus};
+use crate::state::StateDir;
+use color_eyre::Result;
+use std::{
+    collections::HashMap,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// The number of most recent runs of a test case that its statistics are computed over.
+pub(crate) const HISTORY_WINDOW: usize = 20;
+
+/// Records the report of a run in the history.
+pub(crate) fn record(report: &TestReport) -> Result<()> {
+    let history_dir = StateDir::get().history_dir();
+    fs::create_dir_all(&history_dir)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    report.write(&history_dir.join(format!("{timestamp}.json")))
+}
+
+/// Loads the reports of past runs from the history, oldest first. Reports that are incompatible
+/// with this version of `fpt` are skipped.
+pub(crate) fn load() -> Result<Vec<TestReport>> {
+    let Ok(entries) = fs::read_dir(StateDir::get().history_dir()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| match TestReport::read(&path) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                warn!(target: "history", "Skipping {}: {e}", path.display());
+                None
+            }
+        })
+        .collect())
+}
+
+/// The statuses of a test case across its most recent runs.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TestHistory {
+    /// The test case.
+    pub(crate) id: TestId,
+    /// The statuses of the test case, oldest first.
+    pub(crate) statuses: Vec<TestStatus>,
+}
+
+impl TestHistory {
+    /// Collects the history of every test case in the given reports, over the most recent
+    /// [HISTORY_WINDOW] runs of each.
+    pub(crate) fn from_reports(reports: &[TestReport]) -> Vec<Self> {
+        let mut histories = HashMap::<TestId, Vec<TestStatus>>::new();
+        for result in reports.iter().flat_map(|r| r.results.iter()) {
+            histories
+                .entry(result.id())
+                .or_default()
+                .push(result.status);
+        }
+
+        histories
+            .into_iter()
+            .map(|(id, mut statuses)| {
+                statuses.drain(..statuses.len().saturating_sub(HISTORY_WINDOW));
+                Self { id, statuses }
+            })
+            .collect()
+    }
+
+    /// Returns the number of runs in which the test case did not pass.
+    pub(crate) fn failures(&self) -> usize {
+        self.statuses
+            .iter()
+            .filter(|s| **s != TestStatus::Pass)
+            .count()
+    }
+
+    /// Returns the status of the most recent run, and the number of consecutive runs ending with
+    /// it that share its outcome.
+    pub(crate) fn streak(&self) -> Option<(TestStatus, usize)> {
+        let last = *self.statuses.last()?;
+        let passed = last == TestStatus::Pass;
+        let len = self
+            .statuses
+            .iter()
+            .rev()
+            .take_while(|s| (**s == TestStatus::Pass) == passed)
+            .count();
+        Some((last, len))
+    }
+
+    /// Returns the flakiness score of the test case, in `[0, 1]`: the fraction of consecutive runs
+    /// whose outcome flipped between passing and not passing. Tests that consistently pass or
+    /// consistently fail score 0.
+    pub(crate) fn flakiness(&self) -> f64 {
+        if self.statuses.len() < 2 {
+            return 0.0;
+        }
+        let flips = self
+            .statuses
+            .windows(2)
+            .filter(|w| (w[0] == TestStatus::Pass) != (w[1] == TestStatus::Pass))
+            .count();
+        flips as f64 / (self.statuses.len() - 1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{platform::PlatformKind, program::ProgramKind};
+
+    #[test]
+    fn score_flakiness() {
+        let history = |statuses: &[TestStatus]| TestHistory {
+            id: TestId {
+                platform: PlatformKind::Cannon,
+                program: ProgramKind::OpProgramMips,
+                fixture: "fixture".to_string(),
+            },
+            statuses: statuses.to_vec(),
+        };
+        use TestStatus::*;
+
+        assert_eq!(history(&[Pass, Pass, Pass]).flakiness(), 0.0);
+        assert_eq!(history(&[Fail, Stalled, Fail]).flakiness(), 0.0);
+        assert_eq!(history(&[Pass, Fail, Pass, Fail, Pass]).flakiness(), 1.0);
+
+        let flaky = history(&[Pass, Pass, Fail, Pass, Stalled]);
+        assert_eq!(flaky.flakiness(), 0.75);
+        assert_eq!(flaky.failures(), 2);
+        assert_eq!(flaky.streak(), Some((Stalled, 1)));
+    }
+}
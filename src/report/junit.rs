@@ -0,0 +1,154 @@
+//! Rendering of [TestReport]s as JUnit XML, for consumption by CI systems.
+
+use super::{TestId, TestReport, TestResult, TestStatus};
+use itertools::Itertools;
+use std::fmt::Write;
+
+impl TestReport {
+    /// Renders the report as a JUnit XML document, with one test suite per platform and program
+    /// pair, and one test case per fixture. The build info of `fpt` is recorded in the properties
+    /// of each test suite.
+    pub(crate) fn to_junit(&self) -> String {
+        let seconds = |results: &[&TestResult]| {
+            results.iter().map(|r| r.duration_ms).sum::<u64>() as f64 / 1000.0
+        };
+        let failures = |results: &[&TestResult]| {
+            results
+                .iter()
+                .filter(|r| r.status != TestStatus::Pass)
+                .count()
+        };
+
+        let all = self.results.iter().collect::<Vec<_>>();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites name=\"fpt\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            all.len(),
+            failures(&all),
+            seconds(&all)
+        );
+
+        let suites = self
+            .results
+            .iter()
+            .sorted_by_key(|r| (r.platform.to_string(), r.program.to_string(), &r.fixture))
+            .chunk_by(|r| format!("{}{}{}", r.platform, TestId::SEPARATOR, r.program));
+        for (suite, results) in suites.into_iter() {
+            let results = results.collect::<Vec<_>>();
+            let _ = writeln!(
+                xml,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+                escape(&suite),
+                results.len(),
+                failures(&results),
+                seconds(&results)
+            );
+            if let Some(build) = self.build.as_ref() {
+                xml.push_str("    <properties>\n");
+                for (key, value) in build.fields() {
+                    let _ = writeln!(
+                        xml,
+                        "      <property name=\"fpt.{key}\" value=\"{}\"/>",
+                        escape(&value)
+                    );
+                }
+                xml.push_str("    </properties>\n");
+            }
+            for result in results.iter() {
+                let _ = write!(
+                    xml,
+                    "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                    escape(&result.fixture),
+                    escape(&suite),
+                    result.duration_ms as f64 / 1000.0
+                );
+                // The failures of reports that predate the error taxonomy are described from their
+                // status.
+                let message = match (result.status, &result.error, result.exit_status) {
+                    (TestStatus::Pass, ..) => {
+                        xml.push_str("/>\n");
+                        continue;
+                    }
+                    (_, Some(error), _) => error.to_string(),
+                    (TestStatus::Fail, None, Some(status)) => format!(
+                        "Expected exit status {}, got {status}",
+                        result.expected_status
+                    ),
+                    (TestStatus::Fail, None, None) => "Program did not exit".to_string(),
+                    (TestStatus::Stalled, ..) => "Program stalled and was killed".to_string(),
+                    (TestStatus::Timeout, ..) => "Test timed out and was killed".to_string(),
+                };
+                let _ = writeln!(
+                    xml,
+                    ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                    escape(&message)
+                );
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Escapes the special characters of an XML attribute value.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::build_info::BuildInfo;
+    use alloy_primitives::B256;
+
+    #[test]
+    fn render_junit() {
+        let result = |fixture: &str, status, exit_status| TestResult {
+            status,
+            duration_ms: 1500,
+            exit_status,
+            ..TestResult::mock(fixture)
+        };
+        let mut report = TestReport::new(
+            vec![
+                result("b<c>", TestStatus::Fail, Some(1)),
+                result("a", TestStatus::Pass, Some(0)),
+            ],
+            None,
+        );
+        report.build = Some(BuildInfo {
+            version: "0.1.0".to_string(),
+            git_sha: Some("5dcbc96".to_string()),
+            dirty: true,
+            registry_hash: B256::ZERO,
+            features: Vec::new(),
+        });
+
+        assert_eq!(
+            report.to_junit(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="fpt" tests="2" failures="1" time="3.000">
+  <testsuite name="cannon/op-program-mips" tests="2" failures="1" time="3.000">
+    <properties>
+      <property name="fpt.version" value="0.1.0"/>
+      <property name="fpt.git-sha" value="5dcbc96 (dirty)"/>
+      <property name="fpt.registry-hash" value="0x0000000000000000000000000000000000000000000000000000000000000000"/>
+      <property name="fpt.features" value="none"/>
+    </properties>
+    <testcase name="a" classname="cannon/op-program-mips" time="1.500"/>
+    <testcase name="b&lt;c&gt;" classname="cannon/op-program-mips" time="1.500">
+      <failure message="Expected exit status 0, got 1"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Merging of the [TestReport]s produced by a sharded test run.
+
+use super::{TestId, TestReport, TestResult};
+use crate::pipeline::partition::Partition;
+use color_eyre::{
+    eyre::{bail, ensure},
+    Result,
+};
+use std::collections::{BTreeSet, HashMap};
+use tracing::warn;
+
+impl TestReport {
+    /// Merges the reports of a sharded test run into a single, consolidated report.
+    ///
+    /// ## Takes
+    /// - `reports` - The reports to merge.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The merged report, which is incomplete if any of the reports is. Errors if
+    ///   the shards are incomplete, or if two shards disagree on the result of the same test.
+    pub(crate) fn merge(reports: Vec<Self>) -> Result<Self> {
+        ensure!(!reports.is_empty(), "No reports to merge");
+        Self::check_shards(&reports)?;
+        let incomplete = reports.iter().any(|r| r.incomplete);
+
+        let mut merged = HashMap::<TestId, TestResult>::new();
+        for result in reports.into_iter().flat_map(|r| r.results) {
+            let key = result.id();
+            match merged.get(&key) {
+                Some(existing) if existing.status != result.status => {
+                    bail!(
+                        "Conflicting results for {key}: {:?} and {:?}",
+                        existing.status,
+                        result.status
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    merged.insert(key, result);
+                }
+            }
+        }
+
+        let mut results = merged.into_values().collect::<Vec<_>>();
+        results.sort_by(|a, b| {
+            (a.platform.to_string(), a.program.to_string(), &a.fixture).cmp(&(
+                b.platform.to_string(),
+                b.program.to_string(),
+                &b.fixture,
+            ))
+        });
+        Ok(Self {
+            incomplete,
+            ..Self::new(results, None)
+        })
+    }
+
+    /// Checks that the partitioned reports cover every shard of the run exactly once.
+    fn check_shards(reports: &[Self]) -> Result<()> {
+        let partitions = reports
+            .iter()
+            .filter_map(|r| r.partition)
+            .collect::<Vec<_>>();
+        if partitions.is_empty() {
+            return Ok(());
+        }
+        if partitions.len() != reports.len() {
+            warn!(target: "report", "Some reports are not partitioned; skipping shard completeness check");
+            return Ok(());
+        }
+
+        let total = partitions[0].total;
+        ensure!(
+            partitions.iter().all(|p| p.total == total),
+            "Reports are from runs with differing partition counts"
+        );
+
+        let mut seen = BTreeSet::new();
+        for partition in partitions.iter() {
+            ensure!(
+                seen.insert(partition.index),
+                "Shard {partition} is present more than once"
+            );
+        }
+        let missing = (1..=total)
+            .filter(|i| !seen.contains(i))
+            .map(|index| Partition { index, total }.to_string())
+            .collect::<Vec<_>>();
+        ensure!(missing.is_empty(), "Missing shards: {}", missing.join(", "));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(fixture: &str) -> TestResult {
+        TestResult::mock(fixture)
+    }
+
+    #[test]
+    fn merge_sharded_reports() {
+        let shard = |index, fixtures: &[&str]| {
+            TestReport::new(
+                fixtures.iter().map(|f| result(f)).collect(),
+                Some(Partition { index, total: 2 }),
+            )
+        };
+
+        let merged = TestReport::merge(vec![shard(2, &["b", "a"]), shard(1, &["a", "c"])]).unwrap();
+        assert_eq!(merged.partition, None);
+        assert_eq!(
+            merged
+                .results
+                .iter()
+                .map(|r| r.fixture.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+
+        let missing = TestReport::merge(vec![shard(1, &["a"])]).unwrap_err();
+        assert_eq!(missing.to_string(), "Missing shards: 2/2");
+    }
+}
@@ -0,0 +1,160 @@
+//! Contains the [VmMetrics] of a test, which measure the work and resources that its execution
+//! took, and the metrics file that `fpt test --metrics` writes, so that the performance of fault
+//! proof programs can be tracked across revisions.
+//!
+//! The metrics file holds one row per test that did not time out, in CSV if its path ends in
+//! `.csv`, and as a JSON array of objects otherwise.
+
+use super::{write_atomic, TestResult, TestStatus};
+use crate::registry::{platform::PlatformKind, program::ProgramKind};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The columns of the metrics file, in order.
+const COLUMNS: [&str; 8] = [
+    "platform",
+    "program",
+    "fixture",
+    "status",
+    "duration-ms",
+    "vm-duration-ms",
+    "instructions",
+    "peak-rss",
+];
+
+/// The metrics of a test's execution.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct VmMetrics {
+    /// The number of instructions that the FPVM executed, if it reports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) instructions: Option<u64>,
+    /// The wall time of the platform's run of the program, excluding loading the client into it,
+    /// in milliseconds.
+    pub(crate) vm_duration_ms: u64,
+    /// The peak resident set size of the platform's processes, in bytes, if it could be sampled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) peak_rss: Option<u64>,
+}
+
+/// A row of the metrics file.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct MetricsRow<'a> {
+    platform: PlatformKind,
+    program: ProgramKind,
+    fixture: &'a str,
+    status: TestStatus,
+    duration_ms: u64,
+    vm_duration_ms: u64,
+    instructions: Option<u64>,
+    peak_rss: Option<u64>,
+}
+
+/// Writes the metrics of the tests that did not time out to the file, in CSV if its path ends in
+/// `.csv`, and in JSON otherwise.
+///
+/// ## Takes
+/// - `path` - The path of the metrics file.
+/// - `results` - The results of the test run.
+///
+/// ## Returns
+/// - `Result<()>` - Errors if the file cannot be written.
+pub(crate) fn write_metrics(path: &Path, results: &[TestResult]) -> Result<()> {
+    let rows = results
+        .iter()
+        .filter_map(|r| {
+            let metrics = r.metrics.as_ref()?;
+            Some(MetricsRow {
+                platform: r.platform,
+                program: r.program,
+                fixture: &r.fixture,
+                status: r.status,
+                duration_ms: r.duration_ms,
+                vm_duration_ms: metrics.vm_duration_ms,
+                instructions: metrics.instructions,
+                peak_rss: metrics.peak_rss,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let contents = if path.extension().is_some_and(|ext| ext == "csv") {
+        to_csv(&rows)?
+    } else {
+        serde_json::to_string_pretty(&rows)?
+    };
+    write_atomic(path, contents.as_bytes())
+}
+
+/// Renders the rows as CSV, with a header line. Absent values are left empty.
+fn to_csv(rows: &[MetricsRow<'_>]) -> Result<String> {
+    let mut csv = COLUMNS.join(",");
+    csv.push('\n');
+    for row in rows {
+        let row = serde_json::to_value(row)?;
+        let cells = COLUMNS.map(|column| match &row[column] {
+            serde_json::Value::Null => String::new(),
+            serde_json::Value::String(s) if s.contains([',', '"', '\n']) => {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            }
+            serde_json::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        });
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn write_metrics_files() {
+        let result = |fixture: &str, metrics: Option<VmMetrics>| TestResult {
+            duration_ms: 1500,
+            metrics,
+            ..TestResult::mock(fixture)
+        };
+        let results = [
+            result(
+                "basic",
+                Some(VmMetrics {
+                    instructions: Some(123_456),
+                    vm_duration_ms: 1200,
+                    peak_rss: Some(64 << 20),
+                }),
+            ),
+            result(
+                "a,b",
+                Some(VmMetrics {
+                    vm_duration_ms: 900,
+                    ..Default::default()
+                }),
+            ),
+            // Tests that timed out have no metrics.
+            result("timed-out", None),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("metrics.csv");
+        write_metrics(&csv, &results).unwrap();
+        assert_eq!(
+            fs::read_to_string(&csv).unwrap(),
+            "platform,program,fixture,status,duration-ms,vm-duration-ms,instructions,peak-rss\n\
+             cannon,op-program-mips,basic,pass,1500,1200,123456,67108864\n\
+             cannon,op-program-mips,\"a,b\",pass,1500,900,,\n"
+        );
+
+        let json = dir.path().join("metrics.json");
+        write_metrics(&json, &results).unwrap();
+        let rows =
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&json).unwrap()).unwrap();
+        assert_eq!(rows.as_array().unwrap().len(), 2);
+        assert_eq!(rows[0]["instructions"], 123_456);
+        assert!(rows[1]["peak-rss"].is_null());
+    }
+}
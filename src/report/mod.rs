@@ -0,0 +1,497 @@
+//! Contains the [TestReport] format, which records the results of a test run for machine
+//! consumption.
+//!
+//! ## Schema
+//! A report is a JSON object with the following fields:
+//! - `schema-version` - The version of the report schema. Bumped on any incompatible change.
+//! - `fpt-version` - The version of `fpt` that produced the report.
+//! - `build` - The [BuildInfo] of the `fpt` binary that produced the report: its version, git
+//!   commit, embedded registry hash, and compiled features. Absent from older reports.
+//! - `partition` - The shard of the test matrix that the report covers (e.g. `2/4`), if the run was
+//!   partitioned.
+//! - `incomplete` - Present and `true` if the run did not finish, e.g. because it crashed or was
+//!   killed while the report was streamed.
+//! - `results` - The list of [TestResult]s, one per (platform, program, fixture) combination.
+//!
+//! The `ndjson` format holds the same report as newline-delimited JSON: a header line with every
+//! field but `results`, followed by one line per [TestResult].
+//!
+//! ## Versions
+//! - `1` - The initial schema, with `pass` and `fail` results and their exit statuses.
+//! - `2` - Adds the `build`, `partition` and `incomplete` fields; the `stalled` and `timeout`
+//!   statuses; and the optional `output-root`, `bundle-url`, `quarantined`, `retries`, `queue-ms`,
+//!   `witness-reads`, `metrics`, `validators`, `hintless` and `error` fields of results, whose
+//!   `exit-status` may be `null`.
+//!
+//! Every version adds to the previous one, so reports of older versions are still read, while
+//! reports of newer versions are rejected rather than misread.
+
+use crate::{
+    pipeline::{
+        error::TestError, hint_check::HintlessOutcome, partition::Partition,
+        witness_reads::WitnessReads,
+    },
+    registry::{platform::PlatformKind, program::ProgramKind, validators::ValidatorVerdict},
+    report::{build_info::BuildInfo, metrics::VmMetrics},
+};
+use alloy_primitives::B256;
+use clap::ValueEnum;
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    owo_colors::OwoColorize,
+    Report, Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use tempfile::NamedTempFile;
+use tracing::warn;
+
+pub(crate) mod build_info;
+pub(crate) mod compare;
+pub(crate) mod history;
+mod junit;
+mod merge;
+pub(crate) mod metrics;
+pub(crate) mod signing;
+pub(crate) mod stream;
+pub(crate) mod summary;
+
+/// The version of the report schema produced by this version of `fpt`.
+pub(crate) const SCHEMA_VERSION: u32 = 2;
+
+/// The oldest version of the report schema that this version of `fpt` reads.
+const MIN_SCHEMA_VERSION: u32 = 1;
+
+/// The version of `fpt`.
+pub(crate) const FPT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A report of a test run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct TestReport {
+    /// The version of the report schema.
+    pub(crate) schema_version: u32,
+    /// The version of `fpt` that produced the report.
+    pub(crate) fpt_version: String,
+    /// The build info of the `fpt` binary that produced the report.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) build: Option<BuildInfo>,
+    /// The shard of the test matrix that the report covers, if the run was partitioned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) partition: Option<Partition>,
+    /// Whether or not the run did not finish, leaving a partial report.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) incomplete: bool,
+    /// The results of the test run.
+    #[serde(default)]
+    pub(crate) results: Vec<TestResult>,
+}
+
+impl TestReport {
+    /// Create a new [TestReport] for the current schema version from the given [TestResult]s.
+    pub(crate) fn new(results: Vec<TestResult>, partition: Option<Partition>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            fpt_version: FPT_VERSION.to_string(),
+            build: Some(BuildInfo::current()),
+            partition,
+            incomplete: false,
+            results,
+        }
+    }
+
+    /// Reads and validates a [TestReport] from disk, in either the JSON or the `ndjson` format.
+    ///
+    /// ## Takes
+    /// - `path` - The path to the report.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - Ok if the report is compatible with the current schema, Err otherwise.
+    pub(crate) fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        match serde_json::from_str::<serde_json::Value>(&contents) {
+            Ok(raw) => Self::from_value(raw),
+            Err(_) => Self::from_ndjson(&contents),
+        }
+    }
+
+    /// Decodes a report in the `ndjson` format. The last line of a report that was streamed by a
+    /// killed run may be truncated, in which case it is ignored.
+    fn from_ndjson(contents: &str) -> Result<Self> {
+        let mut lines = contents.lines().filter(|l| !l.trim().is_empty()).peekable();
+        let header = lines.next().ok_or(eyre!("Report is empty"))?;
+        let mut report = Self::from_value(serde_json::from_str(header)?)?;
+        while let Some(line) = lines.next() {
+            match serde_json::from_str::<TestResult>(line) {
+                Ok(result) => report.results.push(result),
+                Err(e) if lines.peek().is_none() && report.incomplete => {
+                    warn!(target: "report", "Ignoring the truncated last result of an incomplete report: {e}");
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Validates and decodes a report from its JSON value.
+    fn from_value(raw: serde_json::Value) -> Result<Self> {
+        // Check the schema version before attempting to decode the rest of the report, so that
+        // incompatible reports are rejected with an actionable error.
+        let schema_version = raw
+            .get("schema-version")
+            .and_then(|v| v.as_u64())
+            .ok_or(eyre!("Report is missing the `schema-version` field"))?;
+        ensure!(
+            (MIN_SCHEMA_VERSION as u64..=SCHEMA_VERSION as u64).contains(&schema_version),
+            "Report schema version {schema_version} is incompatible with fpt v{FPT_VERSION} (schema versions {MIN_SCHEMA_VERSION} to {SCHEMA_VERSION})"
+        );
+
+        Ok(serde_json::from_value(raw)?)
+    }
+
+    /// Writes the [TestReport] to disk.
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Writes the [TestReport] to the [ReportTarget], in its format. The report is written to a
+    /// temporary file that replaces the target, so that readers never observe a partial write.
+    pub(crate) fn write_to(&self, target: &ReportTarget) -> Result<()> {
+        match target.format {
+            ReportFormat::Json => self.write(&target.path),
+            ReportFormat::Ndjson => write_atomic(&target.path, self.to_ndjson()?.as_bytes()),
+            ReportFormat::Junit => write_atomic(&target.path, self.to_junit().as_bytes()),
+        }
+    }
+
+    /// Renders the header line of the report in the `ndjson` format.
+    pub(crate) fn ndjson_header(&self) -> Result<String> {
+        let mut header = serde_json::to_value(self)?;
+        if let Some(header) = header.as_object_mut() {
+            header.remove("results");
+        }
+        Ok(format!("{header}\n"))
+    }
+
+    /// Renders the report in the `ndjson` format.
+    fn to_ndjson(&self) -> Result<String> {
+        let mut ndjson = self.ndjson_header()?;
+        for result in self.results.iter() {
+            ndjson.push_str(&serde_json::to_string(result)?);
+            ndjson.push('\n');
+        }
+        Ok(ndjson)
+    }
+}
+
+/// Writes the contents to a temporary file alongside the path, and renames it into place.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(contents)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// The format of a written report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReportFormat {
+    /// The JSON [TestReport] format.
+    Json,
+    /// The [TestReport] as newline-delimited JSON, with one line per result, which is appended to
+    /// as tests complete.
+    Ndjson,
+    /// JUnit XML.
+    Junit,
+}
+
+/// A destination for a report, specified as `[format:]path`, e.g. `junit:results.xml`. Defaults
+/// to the JSON format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ReportTarget {
+    /// The format of the report.
+    pub(crate) format: ReportFormat,
+    /// The path to write the report to.
+    pub(crate) path: PathBuf,
+}
+
+impl FromStr for ReportTarget {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once(':') {
+            Some(("json", path)) => (ReportFormat::Json, path),
+            Some(("ndjson", path)) => (ReportFormat::Ndjson, path),
+            Some(("junit", path)) => (ReportFormat::Junit, path),
+            Some((format, _)) if !format.contains(['/', '.']) => {
+                bail!("Unknown report format `{format}`, expected `json`, `ndjson`, or `junit`")
+            }
+            _ => (ReportFormat::Json, s),
+        };
+        ensure!(!path.is_empty(), "Missing report path in `{s}`");
+        Ok(Self {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// The result of a single test case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct TestResult {
+    /// The platform that the test was ran on.
+    pub(crate) platform: PlatformKind,
+    /// The program that the test was ran with.
+    pub(crate) program: ProgramKind,
+    /// The name of the test fixture.
+    pub(crate) fixture: String,
+    /// The status of the test.
+    pub(crate) status: TestStatus,
+    /// The wall time of the test, in milliseconds.
+    pub(crate) duration_ms: u64,
+    /// The expected exit status of the program.
+    pub(crate) expected_status: u8,
+    /// The actual exit status of the program, if it exited.
+    pub(crate) exit_status: Option<u8>,
+    /// The output root that the program reported, if its output was tapped and it logged one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) output_root: Option<B256>,
+    /// The URL of the uploaded reproduction bundle, if the test failed and bundles were uploaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) bundle_url: Option<String>,
+    /// Whether or not the test is quarantined, excluding it from the pass / fail gate of the run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) quarantined: bool,
+    /// The number of times that the test was retried after failing or stalling.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub(crate) retries: u32,
+    /// The time that the test waited for a worker after it was scheduled, in milliseconds.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub(crate) queue_ms: u64,
+    /// The reads of the witness database by the test's processes, if they were traced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) witness_reads: Option<WitnessReads>,
+    /// The metrics of the test's execution, unless it timed out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) metrics: Option<VmMetrics>,
+    /// The verdicts of the registry's validators on the test.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) validators: Vec<ValidatorVerdict>,
+    /// The outcome of the test's execution with its hints ignored, if it was hint-checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) hintless: Option<HintlessOutcome>,
+    /// The cause of the test's failure, if it did not pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<TestError>,
+}
+
+/// Returns whether or not the number is zero, to omit default counts from reports.
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+impl TestResult {
+    /// Creates the result of the test case with the given status, recording none of its exit
+    /// status, timings, or diagnostics.
+    ///
+    /// ## Takes
+    /// - `id` - The test case.
+    /// - `status` - The status of the test.
+    /// - `expected_status` - The expected exit status of the program.
+    pub(crate) fn new(id: TestId, status: TestStatus, expected_status: u8) -> Self {
+        Self {
+            platform: id.platform,
+            program: id.program,
+            fixture: id.fixture,
+            status,
+            duration_ms: 0,
+            expected_status,
+            exit_status: None,
+            output_root: None,
+            bundle_url: None,
+            quarantined: false,
+            retries: 0,
+            queue_ms: 0,
+            witness_reads: None,
+            metrics: None,
+            validators: Vec::new(),
+            hintless: None,
+            error: None,
+        }
+    }
+
+    /// Returns a passing result of the fixture on `cannon` with `op-program`, which exited with
+    /// status 0 after a millisecond, for tests to override the fields that they check.
+    #[cfg(test)]
+    pub(crate) fn mock(fixture: &str) -> Self {
+        let id = TestId {
+            platform: PlatformKind::Cannon,
+            program: ProgramKind::OpProgramMips,
+            fixture: fixture.to_string(),
+        };
+        Self {
+            duration_ms: 1,
+            exit_status: Some(0),
+            ..Self::new(id, TestStatus::Pass, 0)
+        }
+    }
+
+    /// Returns the [TestId] of the test case.
+    pub(crate) fn id(&self) -> TestId {
+        TestId {
+            platform: self.platform,
+            program: self.program,
+            fixture: self.fixture.clone(),
+        }
+    }
+
+    /// Fails the test with the error, if it passed. The error of a test that already failed is
+    /// kept, as the first cause of its failure.
+    pub(crate) fn veto(&mut self, error: TestError) {
+        if self.status == TestStatus::Pass {
+            self.status = error.status();
+            self.error = Some(error);
+        }
+    }
+}
+
+/// The canonical, stable identifier of a test case, displayed and parsed as
+/// `<platform>/<program>/<fixture>`. Selections, reports, the history, and the quarantine all key
+/// test cases by it. The `<platform>::<program>::<fixture>` form of older reports and quarantine
+/// files is still parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct TestId {
+    /// The platform that the test runs on.
+    pub(crate) platform: PlatformKind,
+    /// The program that the test runs with.
+    pub(crate) program: ProgramKind,
+    /// The name of the test fixture.
+    pub(crate) fixture: String,
+}
+
+impl TestId {
+    /// The separator of the components of a [TestId].
+    pub(crate) const SEPARATOR: &'static str = "/";
+
+    /// The separator of the components of a [TestId] in older reports and quarantine files.
+    const LEGACY_SEPARATOR: &'static str = "::";
+
+    /// Renders the [TestId] for the terminal, with each of its components colored.
+    pub(crate) fn colored(&self) -> String {
+        [
+            self.platform.magenta().to_string(),
+            self.program.cyan().to_string(),
+            self.fixture.blue().to_string(),
+        ]
+        .join(Self::SEPARATOR)
+    }
+}
+
+impl Display for TestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sep = Self::SEPARATOR;
+        write!(
+            f,
+            "{}{sep}{}{sep}{}",
+            self.platform, self.program, self.fixture
+        )
+    }
+}
+
+impl FromStr for TestId {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = match s.splitn(3, Self::SEPARATOR).collect::<Vec<_>>() {
+            parts if parts.len() == 3 => parts,
+            _ => s.splitn(3, Self::LEGACY_SEPARATOR).collect(),
+        };
+        let [platform, program, fixture] = parts[..] else {
+            bail!("Invalid test id `{s}`, expected `<platform>/<program>/<fixture>`");
+        };
+        ensure!(!fixture.is_empty(), "Missing fixture in test id `{s}`");
+        Ok(Self {
+            platform: <PlatformKind as ValueEnum>::from_str(platform, false)
+                .map_err(|_| eyre!("Unknown platform `{platform}` in test id `{s}`"))?,
+            program: <ProgramKind as ValueEnum>::from_str(program, false)
+                .map_err(|_| eyre!("Unknown program `{program}` in test id `{s}`"))?,
+            fixture: fixture.to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for TestId {
+    type Error = Report;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TestId> for String {
+    fn from(id: TestId) -> Self {
+        id.to_string()
+    }
+}
+
+/// The status of a test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum TestStatus {
+    /// The program exited with the expected status.
+    Pass,
+    /// The program exited with an unexpected status.
+    Fail,
+    /// The program stalled, producing no output for longer than the stall timeout, whether or not
+    /// it was killed.
+    Stalled,
+    /// The test did not complete within the test timeout, and its processes were killed.
+    Timeout,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn read_schema_versions() {
+        let report = |schema_version: u32| {
+            json!({
+                "schema-version": schema_version,
+                "fpt-version": "0.1.0",
+                "results": [{
+                    "platform": "cannon",
+                    "program": "op-program-mips",
+                    "fixture": "basic-success",
+                    "status": "pass",
+                    "duration-ms": 1000,
+                    "expected-status": 0,
+                    "exit-status": 0,
+                }],
+            })
+        };
+
+        // Reports of older versions are read, with the fields they lack defaulted.
+        let old = TestReport::from_value(report(MIN_SCHEMA_VERSION)).unwrap();
+        assert_eq!(old.results[0].exit_status, Some(0));
+        assert_eq!(old.results[0].retries, 0);
+
+        // Reports of newer versions are rejected before they are decoded.
+        let mut new = report(SCHEMA_VERSION + 1);
+        new["results"][0]["status"] = json!("unknown");
+        let err = TestReport::from_value(new).unwrap_err();
+        assert!(err.to_string().contains("is incompatible"));
+    }
+}
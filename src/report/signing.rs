@@ -0,0 +1,117 @@
+//! Contains the signing of reports, so that the release processes that consume reports from
+//! shared storage can verify that they were produced by a trusted run, and were not tampered with.
+//!
+//! A report is signed with an ed25519 key over its exact bytes, and the signature is written
+//! alongside it to `<report>.sig`, hex-encoded. Keys are hex-encoded 32-byte seeds.
+
+use alloy_primitives::hex;
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The extension appended to the path of a report to get the path of its signature.
+const SIGNATURE_EXTENSION: &str = "sig";
+
+/// Returns the path that the signature of the report is written to: `<report>.sig`.
+pub(crate) fn signature_path(report: &Path) -> PathBuf {
+    let mut path = OsString::from(report.as_os_str());
+    path.push(".");
+    path.push(SIGNATURE_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Reads the signing key from a file holding its hex-encoded 32-byte seed.
+///
+/// ## Takes
+/// - `path` - The path to the key file.
+///
+/// ## Returns
+/// - `Result<SigningKey>` - Errors if the file cannot be read, or does not hold a valid seed.
+pub(crate) fn read_signing_key(path: &Path) -> Result<SigningKey> {
+    let seed = fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read the signing key {}", path.display()))?;
+    let seed = hex::decode(seed.trim())?;
+    let seed = <[u8; 32]>::try_from(seed.as_slice()).map_err(|_| {
+        eyre!(
+            "The signing key must be a 32-byte seed, got {} bytes",
+            seed.len()
+        )
+    })?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Parses a hex-encoded ed25519 public key, e.g. from the command line.
+pub(crate) fn parse_public_key(s: &str) -> Result<VerifyingKey, String> {
+    let key = hex::decode(s).map_err(|e| e.to_string())?;
+    let key = <[u8; 32]>::try_from(key.as_slice())
+        .map_err(|_| format!("The public key must be 32 bytes, got {}", key.len()))?;
+    VerifyingKey::from_bytes(&key).map_err(|e| e.to_string())
+}
+
+/// Signs the report on disk, and writes its signature alongside it.
+///
+/// ## Takes
+/// - `report` - The path to the report.
+/// - `key` - The key to sign the report with.
+///
+/// ## Returns
+/// - `Result<PathBuf>` - The path of the signature.
+pub(crate) fn sign(report: &Path, key: &SigningKey) -> Result<PathBuf> {
+    let signature = key.sign(&fs::read(report)?);
+    let path = signature_path(report);
+    fs::write(&path, hex::encode(signature.to_bytes()))?;
+    Ok(path)
+}
+
+/// Verifies the signature written alongside the report on disk.
+///
+/// ## Takes
+/// - `report` - The path to the report.
+/// - `key` - The public key of the trusted signer.
+///
+/// ## Returns
+/// - `Result<()>` - Errors if the report has no signature, or if it was not signed by the key
+///   over its current contents.
+pub(crate) fn verify(report: &Path, key: &VerifyingKey) -> Result<()> {
+    let path = signature_path(report);
+    let signature = fs::read_to_string(&path)
+        .wrap_err_with(|| format!("Failed to read the signature {}", path.display()))?;
+    let signature = Signature::from_slice(&hex::decode(signature.trim())?)?;
+    key.verify(&fs::read(report)?, &signature)
+        .map_err(|_| eyre!("The signature of {} does not match", report.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("signing.key");
+        fs::write(&key_path, format!("{}\n", hex::encode([7u8; 32]))).unwrap();
+        let key = read_signing_key(&key_path).unwrap();
+        let public_key = parse_public_key(&hex::encode(key.verifying_key().to_bytes())).unwrap();
+
+        let report = dir.path().join("report.json");
+        fs::write(&report, "{\"results\":[]}").unwrap();
+        assert_eq!(
+            sign(&report, &key).unwrap(),
+            dir.path().join("report.json.sig")
+        );
+        verify(&report, &public_key).unwrap();
+
+        // Another signer's key, and tampered contents, are rejected.
+        let other = SigningKey::from_bytes(&[8u8; 32]).verifying_key();
+        assert!(verify(&report, &other).is_err());
+        fs::write(&report, "{\"results\":[{}]}").unwrap();
+        assert!(verify(&report, &public_key).is_err());
+    }
+}
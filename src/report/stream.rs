@@ -0,0 +1,145 @@
+//! Contains the [ReportStream], which writes the reports of a test run as its tests complete, so
+//! that a run that crashes or is killed still leaves a usable partial report behind.
+
+use super::{ReportFormat, ReportTarget, TestReport, TestResult};
+use crate::pipeline::partition::Partition;
+use color_eyre::Result;
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    time::{Duration, Instant},
+};
+
+/// The minimum interval between rewrites of the reports that cannot be appended to. `ndjson`
+/// reports are appended to as every test completes.
+const REWRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Streams the [TestReport] of a run to its [ReportTarget]s. Until the run finishes, the reports
+/// are marked `incomplete`.
+#[derive(Debug)]
+pub(crate) struct ReportStream {
+    /// The report of the run so far.
+    report: TestReport,
+    /// The targets that are rewritten as a whole.
+    rewritten: Vec<ReportTarget>,
+    /// The `ndjson` targets, and their files opened for appending.
+    appended: Vec<(ReportTarget, File)>,
+    /// The time that the rewritten targets were last written.
+    last_rewrite: Instant,
+}
+
+impl ReportStream {
+    /// Writes the empty, incomplete report of a run to each target.
+    ///
+    /// ## Takes
+    /// - `targets` - The targets to write the report to.
+    /// - `partition` - The shard of the test matrix that the run covers, if it is partitioned.
+    ///
+    /// ## Returns
+    /// - `Result<Self>` - The stream, or Err if a target could not be written.
+    pub(crate) fn create(targets: &[ReportTarget], partition: Option<Partition>) -> Result<Self> {
+        let mut report = TestReport::new(Vec::new(), partition);
+        report.incomplete = true;
+
+        let mut rewritten = Vec::new();
+        let mut appended = Vec::new();
+        for target in targets.iter() {
+            report.write_to(target)?;
+            if target.format == ReportFormat::Ndjson {
+                let file = OpenOptions::new().append(true).open(&target.path)?;
+                appended.push((target.clone(), file));
+            } else {
+                rewritten.push(target.clone());
+            }
+        }
+
+        Ok(Self {
+            report,
+            rewritten,
+            appended,
+            last_rewrite: Instant::now(),
+        })
+    }
+
+    /// Records the result of a completed test. The result is appended to the `ndjson` targets
+    /// with a single write, and the other targets are rewritten at most every
+    /// [REWRITE_INTERVAL].
+    pub(crate) fn push(&mut self, result: TestResult) -> Result<()> {
+        let line = format!("{}\n", serde_json::to_string(&result)?);
+        for (_, file) in self.appended.iter_mut() {
+            file.write_all(line.as_bytes())?;
+        }
+        self.report.results.push(result);
+
+        if self.last_rewrite.elapsed() >= REWRITE_INTERVAL {
+            for target in self.rewritten.iter() {
+                self.report.write_to(target)?;
+            }
+            self.last_rewrite = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// Returns the results recorded so far.
+    pub(crate) fn results(&self) -> &[TestResult] {
+        &self.report.results
+    }
+
+    /// Marks the report as complete, and writes it to every target.
+    ///
+    /// ## Returns
+    /// - `Result<TestReport>` - The complete report of the run.
+    pub(crate) fn finish(mut self) -> Result<TestReport> {
+        self.report.incomplete = false;
+        for target in self
+            .rewritten
+            .iter()
+            .chain(self.appended.iter().map(|(target, _)| target))
+        {
+            self.report.write_to(target)?;
+        }
+        Ok(self.report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn stream_partial_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let targets = ["ndjson:report.ndjson", "json:report.json"]
+            .map(|t| t.replace(':', &format!(":{}/", dir.path().display())))
+            .map(|t| t.parse::<ReportTarget>().unwrap());
+        let result = TestResult::mock;
+
+        let mut stream = ReportStream::create(&targets, None).unwrap();
+        stream.push(result("a")).unwrap();
+        stream.push(result("b")).unwrap();
+
+        // A killed run leaves the streamed results, and possibly a truncated line, behind.
+        let partial = TestReport::read(&targets[0].path).unwrap();
+        assert!(partial.incomplete);
+        assert_eq!(partial.results, stream.results());
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&targets[0].path)
+            .unwrap();
+        file.write_all(b"{\"platform\":\"can").unwrap();
+        assert_eq!(
+            TestReport::read(&targets[0].path).unwrap().results,
+            stream.results()
+        );
+        assert!(TestReport::read(&targets[1].path).unwrap().incomplete);
+
+        let report = stream.finish().unwrap();
+        for target in targets.iter() {
+            assert_eq!(TestReport::read(&target.path).unwrap(), report);
+        }
+        assert!(!fs::read_to_string(&targets[0].path)
+            .unwrap()
+            .contains("incomplete"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Contains the [RunSummary], the plain-text summary of a test run printed once it completes, and
+//! the [MatrixBreakdown] of its results per platform and program.
+
+use super::{TestResult, TestStatus};
+use crate::{
+    registry::{platform::PlatformKind, program::ProgramKind},
+    units::Elapsed,
+};
+use cli_table::{Cell, Style, Table, TableStruct};
+use color_eyre::owo_colors::OwoColorize;
+use itertools::Itertools;
+use std::{fmt::Display, time::Duration};
+
+/// A summary of the results of a test run.
+///
+/// The summary is rendered without colors or locale-dependent formatting, so that it is identical
+/// across terminals and can be parsed by downstream tooling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RunSummary {
+    /// The number of tests that were ran.
+    pub(crate) total: usize,
+    /// The number of tests that passed.
+    pub(crate) passed: usize,
+    /// The number of tests that failed.
+    pub(crate) failed: usize,
+    /// The number of tests that stalled.
+    pub(crate) stalled: usize,
+    /// The number of tests that timed out.
+    pub(crate) timed_out: usize,
+    /// The number of tests that were retried after failing or stalling.
+    pub(crate) retried: usize,
+    /// The wall time of the run.
+    pub(crate) elapsed: Duration,
+}
+
+impl RunSummary {
+    /// Create a new [RunSummary] from the results of a run.
+    pub(crate) fn new(results: &[TestResult], elapsed: Duration) -> Self {
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+        Self {
+            total: results.len(),
+            passed: count(TestStatus::Pass),
+            failed: count(TestStatus::Fail),
+            stalled: count(TestStatus::Stalled),
+            timed_out: count(TestStatus::Timeout),
+            retried: results.iter().filter(|r| r.retries > 0).count(),
+            elapsed,
+        }
+    }
+}
+
+impl Display for RunSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Completed {} tests in {}: {} passed, {} failed, {} stalled, {} timed out",
+            self.total,
+            Elapsed(self.elapsed),
+            self.passed,
+            self.failed,
+            self.stalled,
+            self.timed_out
+        )?;
+        if self.retried > 0 {
+            write!(f, " ({} retried)", self.retried)?;
+        }
+        Ok(())
+    }
+}
+
+/// The time that the tests of a run waited for a worker after they were scheduled, and how busy
+/// the workers were, telling whether the run was limited by the number of workers or by the
+/// tests themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct QueueStats {
+    /// The mean time that a test waited for a worker.
+    pub(crate) mean: Duration,
+    /// The 95th percentile of the time that a test waited for a worker.
+    pub(crate) p95: Duration,
+    /// The longest time that a test waited for a worker.
+    pub(crate) max: Duration,
+    /// The share of the tests' time that was spent waiting for a worker, in percent.
+    pub(crate) queued_share: u64,
+    /// The share of the workers' capacity over the run that was spent running tests, in percent.
+    pub(crate) utilization: u64,
+}
+
+impl QueueStats {
+    /// Create new [QueueStats] from the results of a run.
+    ///
+    /// ## Takes
+    /// - `results` - The results of the run.
+    /// - `workers` - The number of workers in the pool.
+    /// - `elapsed` - The wall time of the run.
+    ///
+    /// ## Returns
+    /// - `Option<Self>` - The statistics, or None if no tests were ran.
+    pub(crate) fn new(results: &[TestResult], workers: usize, elapsed: Duration) -> Option<Self> {
+        let waits = results
+            .iter()
+            .map(|r| r.queue_ms)
+            .sorted()
+            .collect::<Vec<_>>();
+        let max = *waits.last()?;
+        let queued = waits.iter().sum::<u64>();
+        let ran = results.iter().map(|r| r.duration_ms).sum::<u64>();
+        let capacity = workers as u128 * elapsed.as_millis();
+        Some(Self {
+            mean: Duration::from_millis(queued / waits.len() as u64),
+            p95: Duration::from_millis(waits[(waits.len() * 95).div_ceil(100) - 1]),
+            max: Duration::from_millis(max),
+            queued_share: percent(queued as u128, (queued + ran) as u128),
+            utilization: percent(ran as u128, capacity).min(100),
+        })
+    }
+}
+
+/// Returns the share of the part in the whole, in percent, or 0 if the whole is empty.
+fn percent(part: u128, whole: u128) -> u64 {
+    if whole == 0 {
+        0
+    } else {
+        (part * 100 / whole) as u64
+    }
+}
+
+impl Display for QueueStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Queue wait: mean {}, p95 {}, max {} ({}% of the tests' time) | Worker utilization: {}%",
+            Elapsed(self.mean),
+            Elapsed(self.p95),
+            Elapsed(self.max),
+            self.queued_share,
+            self.utilization
+        )
+    }
+}
+
+/// The results of a test run for a single platform and program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MatrixBreakdown {
+    /// The platform that the tests were ran on.
+    pub(crate) platform: PlatformKind,
+    /// The program that the tests were ran with.
+    pub(crate) program: ProgramKind,
+    /// The summary of the tests ran on the platform with the program.
+    pub(crate) summary: RunSummary,
+    /// The slowest test, and its wall time.
+    pub(crate) slowest: Option<(String, Duration)>,
+}
+
+impl MatrixBreakdown {
+    /// Groups the results of a run by platform and program. The wall time of each group is the
+    /// sum of the wall times of its tests.
+    pub(crate) fn new(results: &[TestResult]) -> Vec<Self> {
+        results
+            .iter()
+            .sorted_by_key(|r| (r.platform.to_string(), r.program.to_string()))
+            .chunk_by(|r| (r.platform, r.program))
+            .into_iter()
+            .map(|((platform, program), group)| {
+                let group = group.cloned().collect::<Vec<_>>();
+                let wall_time = group.iter().map(|r| r.duration_ms).sum();
+                Self {
+                    platform,
+                    program,
+                    summary: RunSummary::new(&group, Duration::from_millis(wall_time)),
+                    slowest: group
+                        .iter()
+                        .max_by_key(|r| r.duration_ms)
+                        .map(|r| (r.fixture.clone(), Duration::from_millis(r.duration_ms))),
+                }
+            })
+            .collect()
+    }
+
+    /// Renders the breakdowns of a run as a table.
+    pub(crate) fn table(breakdowns: &[Self]) -> TableStruct {
+        breakdowns
+            .iter()
+            .map(|b| {
+                vec![
+                    b.platform.magenta().to_string().cell(),
+                    b.program.cyan().to_string().cell(),
+                    b.summary.passed.green().to_string().cell(),
+                    b.summary.failed.red().to_string().cell(),
+                    b.summary.stalled.yellow().to_string().cell(),
+                    b.summary.timed_out.yellow().to_string().cell(),
+                    Elapsed(b.summary.elapsed).cell(),
+                    b.slowest
+                        .as_ref()
+                        .map_or("-".to_string(), |(fixture, duration)| {
+                            format!("{fixture} ({})", Elapsed(*duration))
+                        })
+                        .cell(),
+                ]
+            })
+            .table()
+            .title(vec![
+                "Platform".cell(),
+                "Program".cell(),
+                "Passed".cell(),
+                "Failed".cell(),
+                "Stalled".cell(),
+                "Timed Out".cell(),
+                "Wall Time".cell(),
+                "Slowest".cell(),
+            ])
+            .bold(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_stats() {
+        assert_eq!(QueueStats::new(&[], 4, Duration::from_secs(1)), None);
+
+        let results = (1..=20)
+            .map(|i| TestResult {
+                duration_ms: 1000,
+                queue_ms: i * 100,
+                ..TestResult::mock(&format!("fixture-{i}"))
+            })
+            .collect::<Vec<_>>();
+        let stats = QueueStats::new(&results, 4, Duration::from_secs(10)).unwrap();
+        assert_eq!(stats.mean, Duration::from_millis(1050));
+        assert_eq!(stats.p95, Duration::from_millis(1900));
+        assert_eq!(stats.max, Duration::from_millis(2000));
+        // 21s queued against 20s ran, by 4 workers over 10s.
+        assert_eq!(stats.queued_share, 51);
+        assert_eq!(stats.utilization, 50);
+    }
+}
@@ -0,0 +1,456 @@
+//! Contains the [RpcServer] of `fpt rpc`, a JSON-RPC 2.0 control interface through which tooling
+//! that is not written in Rust lists tests, triggers test runs, and queries their results.
+//!
+//! Requests are POSTed to the server over HTTP/1.1. Runs take the arguments of `fpt test`, and are
+//! ran one at a time, in the order they were triggered.
+//!
+//! | Method          | Params                 | Result                                               |
+//! |-----------------|------------------------|------------------------------------------------------|
+//! | `fpt_listTests` | `fpt test` arguments   | The `<platform>/<program>/<fixture>` ids of a run.   |
+//! | `fpt_startRun`  | `fpt test` arguments   | The id of the triggered run.                         |
+//! | `fpt_getRun`    | `[id]`                 | The state of the run, and its report so far.         |
+//! | `fpt_runTest`   | `[test id, ...args]`   | The result of the test, once it completes.           |
+
+use crate::{
+    cli::{Cli, CliSubcommand, TestConfig},
+    pipeline::{self, TestPipeline},
+    registry::FP_REGISTRY,
+    report::{ReportFormat, ReportTarget, TestId, TestReport},
+    state::StateDir,
+};
+use clap::Parser;
+use color_eyre::{
+    eyre::{bail, ensure, eyre},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf, sync::Arc};
+use tempfile::TempDir;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// The JSON-RPC error code of a request body that is not valid JSON.
+const PARSE_ERROR: i64 = -32700;
+
+/// The JSON-RPC error code of a request that is not a JSON-RPC 2.0 request object.
+const INVALID_REQUEST: i64 = -32600;
+
+/// The JSON-RPC error code of an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The JSON-RPC error code of invalid method parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+/// The JSON-RPC error code of a method that failed.
+const INTERNAL_ERROR: i64 = -32603;
+
+/// A JSON-RPC 2.0 request.
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    /// The version of the protocol, which must be `2.0`.
+    jsonrpc: String,
+    /// The id of the request, echoed in its response.
+    #[serde(default)]
+    id: Value,
+    /// The method to call.
+    method: String,
+    /// The parameters of the method.
+    #[serde(default)]
+    params: Value,
+}
+
+/// A JSON-RPC 2.0 response, carrying either a result or an error.
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    /// The version of the protocol.
+    jsonrpc: &'static str,
+    /// The id of the request.
+    id: Value,
+    /// The result of the method, if it succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    /// The error of the method, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+/// The error object of a failed JSON-RPC call.
+#[derive(Debug, Serialize)]
+struct RpcError {
+    /// The JSON-RPC error code.
+    code: i64,
+    /// The description of the error.
+    message: String,
+}
+
+impl RpcResponse {
+    /// Returns the response to a failed call.
+    fn error(id: Value, code: i64, message: impl ToString) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.to_string(),
+            }),
+        }
+    }
+}
+
+/// The state of a run triggered over RPC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum RunState {
+    /// The run waits for the runs triggered before it.
+    Queued,
+    /// The run's pipeline is being set up, ran, or torn down.
+    Running,
+    /// The run completed, and its report is final.
+    Complete,
+    /// The run failed before it completed.
+    Failed,
+}
+
+/// A run triggered over RPC.
+#[derive(Debug)]
+struct RpcRun {
+    /// The state of the run.
+    state: RunState,
+    /// The number of tests that did not pass, once the run is complete.
+    failures: Option<usize>,
+    /// The error that the run failed with, if it failed.
+    error: Option<String>,
+    /// The `ndjson` report that the run streams its results to.
+    report: PathBuf,
+}
+
+/// The JSON-RPC control interface of `fpt`.
+#[derive(Debug)]
+pub(crate) struct RpcServer {
+    /// The runs triggered over RPC, by id.
+    runs: Mutex<HashMap<u64, RpcRun>>,
+    /// Held by the active run, so that runs are ran one at a time.
+    run_lock: Mutex<()>,
+    /// The directory that the reports of the runs are written to.
+    reports_dir: TempDir,
+}
+
+impl RpcServer {
+    /// Creates a new [RpcServer], with no runs.
+    pub(crate) fn new() -> Result<Self> {
+        Ok(Self {
+            runs: Mutex::new(HashMap::new()),
+            run_lock: Mutex::new(()),
+            reports_dir: StateDir::get().tempdir()?,
+        })
+    }
+
+    /// Serves JSON-RPC requests on the given address, until the process is stopped.
+    ///
+    /// ## Takes
+    /// - `addr` - The address to listen on.
+    pub(crate) async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        info!(target: "rpc", "Listening for JSON-RPC requests on http://{}", listener.local_addr()?);
+
+        let server = Arc::new(self);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.serve_connection(stream).await {
+                    debug!(target: "rpc", "Connection from {peer} closed: {e}");
+                }
+            });
+        }
+    }
+
+    /// Answers the HTTP/1.1 requests on a connection, until the client closes it.
+    async fn serve_connection(self: &Arc<Self>, stream: TcpStream) -> Result<()> {
+        let mut stream = BufReader::new(stream);
+        loop {
+            // Read the request line and headers.
+            let mut request_line = String::new();
+            if stream.read_line(&mut request_line).await? == 0 {
+                return Ok(());
+            }
+            let method = request_line
+                .split_whitespace()
+                .next()
+                .ok_or(eyre!("Missing request method"))?
+                .to_string();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                stream.read_line(&mut header).await?;
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse()?;
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            stream.read_exact(&mut body).await?;
+
+            let (status, body) = if method == "POST" {
+                let response = self.handle(&body).await;
+                ("200 OK", serde_json::to_vec(&response)?)
+            } else {
+                ("405 Method Not Allowed", Vec::new())
+            };
+            let head = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            );
+            let stream = stream.get_mut();
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(&body).await?;
+        }
+    }
+
+    /// Answers a JSON-RPC request body.
+    async fn handle(self: &Arc<Self>, body: &[u8]) -> RpcResponse {
+        let raw = match serde_json::from_slice::<Value>(body) {
+            Ok(raw) => raw,
+            Err(e) => return RpcResponse::error(Value::Null, PARSE_ERROR, e),
+        };
+        let request = match serde_json::from_value::<RpcRequest>(raw) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            Ok(request) => {
+                return RpcResponse::error(request.id, INVALID_REQUEST, "Expected JSON-RPC 2.0")
+            }
+            Err(e) => return RpcResponse::error(Value::Null, INVALID_REQUEST, e),
+        };
+
+        let result = match request.method.as_str() {
+            "fpt_listTests" => match test_config(&request.params) {
+                Ok(cfg) => self.list_tests(&cfg).map_err(|e| (INTERNAL_ERROR, e)),
+                Err(e) => Err((INVALID_PARAMS, e)),
+            },
+            "fpt_startRun" => match test_config(&request.params) {
+                Ok(cfg) => self.start_run(cfg).await.map_err(|e| (INTERNAL_ERROR, e)),
+                Err(e) => Err((INVALID_PARAMS, e)),
+            },
+            "fpt_runTest" => match test_case(&request.params) {
+                Ok((id, cfg)) => self
+                    .run_test(&id, &cfg)
+                    .await
+                    .map_err(|e| (INTERNAL_ERROR, e)),
+                Err(e) => Err((INVALID_PARAMS, e)),
+            },
+            "fpt_getRun" => match serde_json::from_value::<[u64; 1]>(request.params) {
+                Ok([id]) => self.get_run(id).await.map_err(|e| (INVALID_PARAMS, e)),
+                Err(e) => Err((INVALID_PARAMS, eyre!("Expected `[id]`: {e}"))),
+            },
+            method => Err((METHOD_NOT_FOUND, eyre!("Unknown method `{method}`"))),
+        };
+        match result {
+            Ok(result) => RpcResponse {
+                jsonrpc: "2.0",
+                id: request.id,
+                result: Some(result),
+                error: None,
+            },
+            Err((code, e)) => RpcResponse::error(request.id, code, e),
+        }
+    }
+
+    /// Lists the ids of the tests that a run with the configuration would run.
+    fn list_tests(&self, cfg: &TestConfig) -> Result<Value> {
+        let matrix = FP_REGISTRY.resolve_matrix(Some(cfg))?;
+        let tests = TestPipeline::new(cfg, matrix).list_tests()?;
+        Ok(json!(tests))
+    }
+
+    /// Queues a run with the configuration, which streams its results to an `ndjson` report.
+    async fn start_run(self: &Arc<Self>, mut cfg: TestConfig) -> Result<Value> {
+        let mut runs = self.runs.lock().await;
+        let id = runs.len() as u64 + 1;
+        let report = self.reports_dir.path().join(format!("run-{id}.ndjson"));
+        cfg.report.push(ReportTarget {
+            format: ReportFormat::Ndjson,
+            path: report.clone(),
+        });
+        runs.insert(
+            id,
+            RpcRun {
+                state: RunState::Queued,
+                failures: None,
+                error: None,
+                report,
+            },
+        );
+        drop(runs);
+
+        let server = self.clone();
+        tokio::spawn(async move {
+            let _active = server.run_lock.lock().await;
+            server
+                .update_run(id, |run| run.state = RunState::Running)
+                .await;
+            info!(target: "rpc", "Starting run {id}");
+
+            let outcome = pipeline::run_tests(&cfg, CancellationToken::new()).await;
+            server
+                .update_run(id, |run| match outcome {
+                    Ok(failures) => {
+                        run.state = RunState::Complete;
+                        run.failures = Some(failures);
+                    }
+                    Err(e) => {
+                        warn!(target: "rpc", "Run {id} failed: {e}");
+                        run.state = RunState::Failed;
+                        run.error = Some(e.to_string());
+                    }
+                })
+                .await;
+        });
+        Ok(json!(id))
+    }
+
+    /// Runs a single test case with the configuration, once the runs triggered before it complete.
+    async fn run_test(&self, id: &TestId, cfg: &TestConfig) -> Result<Value> {
+        let _active = self.run_lock.lock().await;
+        info!(target: "rpc", "Running {id}");
+        let result = pipeline::run_single(cfg, id, CancellationToken::new()).await?;
+        Ok(json!(result))
+    }
+
+    /// Returns the state of a run, and its report so far.
+    async fn get_run(&self, id: u64) -> Result<Value> {
+        let runs = self.runs.lock().await;
+        let Some(run) = runs.get(&id) else {
+            bail!("Unknown run {id}");
+        };
+        let report = run
+            .report
+            .exists()
+            .then(|| TestReport::read(&run.report))
+            .transpose()?;
+        Ok(json!({
+            "id": id,
+            "state": run.state,
+            "failures": run.failures,
+            "error": run.error,
+            "report": report,
+        }))
+    }
+
+    /// Updates the run with the given id.
+    async fn update_run(&self, id: u64, f: impl FnOnce(&mut RpcRun)) {
+        if let Some(run) = self.runs.lock().await.get_mut(&id) {
+            f(run);
+        }
+    }
+}
+
+/// Parses the parameters of a method that takes the arguments of `fpt test`, e.g.
+/// `["--vm", "cannon", "--test", "deposit-*"]`, applying the selected run profile.
+fn test_config(params: &Value) -> Result<TestConfig> {
+    let args = match params {
+        Value::Null => Vec::new(),
+        params => serde_json::from_value::<Vec<String>>(params.clone())
+            .map_err(|e| eyre!("Expected the arguments of `fpt test`: {e}"))?,
+    };
+    let cli = Cli::try_parse_from(["fpt", "test"].into_iter().map(String::from).chain(args))?;
+    let Some(CliSubcommand::Test(mut cfg)) = cli.subcommand else {
+        bail!("Expected the arguments of `fpt test`");
+    };
+    cfg.normalize()?;
+    Ok(cfg)
+}
+
+/// Parses the parameters of a method that takes a test case and the arguments of `fpt test`, e.g.
+/// `["cannon/op-program-mips/deposit", "--timeout", "600"]`.
+fn test_case(params: &Value) -> Result<(TestId, TestConfig)> {
+    let mut args = serde_json::from_value::<Vec<String>>(params.clone())
+        .map_err(|e| eyre!("Expected a test id, and the arguments of `fpt test`: {e}"))?;
+    ensure!(
+        !args.is_empty(),
+        "Expected a test id, and the arguments of `fpt test`"
+    );
+    let id = args.remove(0).parse::<TestId>()?;
+    Ok((id, test_config(&json!(args))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{FixtureMetadata, TestFixture};
+    use std::fs;
+
+    /// Calls a method on the server, returning its result or error.
+    async fn call(server: &Arc<RpcServer>, method: &str, params: Value) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response = server.handle(request.to_string().as_bytes()).await;
+        assert_eq!(response.id, json!(1));
+        match (response.result, response.error) {
+            (Some(result), None) => result,
+            (None, Some(error)) => json!(error.code),
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_json_rpc() {
+        let tests_dir = tempfile::tempdir().unwrap();
+        let fixture_dir = tests_dir.path().join("devnet/deposit");
+        fs::create_dir_all(&fixture_dir).unwrap();
+        let fixture = TestFixture {
+            metadata: FixtureMetadata {
+                name: "deposit".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        fs::write(
+            fixture_dir.join("fixture.toml"),
+            toml::to_string(&fixture).unwrap(),
+        )
+        .unwrap();
+
+        let server = Arc::new(RpcServer::new().unwrap());
+        let args = json!([
+            "--tests-dir",
+            tests_dir.path(),
+            "--vm",
+            "native",
+            "--program",
+            "op-program-native"
+        ]);
+        assert_eq!(
+            call(&server, "fpt_listTests", args).await,
+            json!(["native/op-program-native/deposit"])
+        );
+
+        assert_eq!(
+            call(&server, "fpt_startRun", json!(["--unknown"])).await,
+            json!(INVALID_PARAMS)
+        );
+        assert_eq!(
+            call(&server, "fpt_getRun", json!([1])).await,
+            json!(INVALID_PARAMS)
+        );
+        assert_eq!(
+            call(&server, "fpt_runTest", json!(["deposit"])).await,
+            json!(INVALID_PARAMS)
+        );
+        assert_eq!(
+            call(&server, "fpt_stopRun", Value::Null).await,
+            json!(METHOD_NOT_FOUND)
+        );
+        assert_eq!(server.handle(b"{").await.error.unwrap().code, PARSE_ERROR);
+    }
+}
@@ -0,0 +1,170 @@
+//! Contains the [StateDir], the directory that `fpt` keeps its state in: the installed components,
+//! the history of test runs, the kept artifacts of tests, the decompressed fixtures, and the
+//! registry overlay. With `--hermetic <dir>`, the state lives under the given directory instead,
+//! along with the scratch files and docker configuration of the run, so that CI runs cannot be
+//! influenced by leftover local state.
+
+use color_eyre::{eyre::ensure, Result};
+use once_cell::sync::OnceCell;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tempfile::{NamedTempFile, TempDir};
+use tracing::info;
+
+/// The default state directory.
+const DEFAULT_STATE_DIR: &str = concat!(env!("HOME"), "/.fpt");
+
+/// The state directory of the run, if it was set with [StateDir::set_hermetic].
+static STATE_DIR: OnceCell<StateDir> = OnceCell::new();
+
+/// The directory that `fpt` keeps its state in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StateDir {
+    /// The root of the state directory.
+    root: PathBuf,
+    /// Whether or not the state directory was given for a hermetic run.
+    hermetic: bool,
+}
+
+impl StateDir {
+    /// Returns the state directory of the run.
+    pub(crate) fn get() -> &'static Self {
+        STATE_DIR.get_or_init(|| Self {
+            root: PathBuf::from(DEFAULT_STATE_DIR),
+            hermetic: false,
+        })
+    }
+
+    /// Moves the state of the run under the given directory, and stops `git` from reading the
+    /// global and system configuration when cloning and fetching components. Must be called
+    /// before the state directory is first accessed, and before any thread uses `git`.
+    ///
+    /// ## Takes
+    /// - `dir` - The directory to keep the state of the run in.
+    ///
+    /// ## Returns
+    /// - `Result<()>` - Errors if the directory cannot be created, or if the state directory is
+    ///   already in use.
+    pub(crate) fn set_hermetic(dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        let state = Self {
+            root: std::path::absolute(dir)?,
+            hermetic: true,
+        };
+        fs::create_dir_all(state.tmp_dir())?;
+
+        let empty = state.root.join("git-config");
+        fs::create_dir_all(&empty)?;
+        for level in [
+            git2::ConfigLevel::System,
+            git2::ConfigLevel::XDG,
+            git2::ConfigLevel::Global,
+        ] {
+            // SAFETY: No other thread has used `git` yet, as the CLI sets the state directory
+            // before it runs the subcommand.
+            unsafe { git2::opts::set_search_path(level, &empty)? };
+        }
+
+        info!(target: "state", "Running hermetically in {}", state.root.display());
+        ensure!(
+            STATE_DIR.set(state).is_ok(),
+            "The state directory was already in use"
+        );
+        Ok(())
+    }
+
+    /// Returns whether or not the run is hermetic.
+    pub(crate) fn is_hermetic(&self) -> bool {
+        self.hermetic
+    }
+
+    /// Returns the directory that the components are cloned and built in.
+    pub(crate) fn components_dir(&self) -> PathBuf {
+        self.root.join("components")
+    }
+
+    /// Returns the directory that the reports of past runs are recorded in.
+    pub(crate) fn history_dir(&self) -> PathBuf {
+        self.root.join("history")
+    }
+
+    /// Returns the directory that the kept artifacts of tests are moved to.
+    pub(crate) fn artifacts_dir(&self) -> PathBuf {
+        self.root.join("artifacts")
+    }
+
+    /// Returns the directory that fixtures are decompressed into, so that the tests directory is
+    /// only ever read.
+    pub(crate) fn scratch_dir(&self) -> PathBuf {
+        self.root.join("scratch")
+    }
+
+    /// Returns the path to the registry overlay.
+    pub(crate) fn registry_overlay(&self) -> PathBuf {
+        self.root.join("registry-overrides.toml")
+    }
+
+    /// Returns the configuration directory of the docker client, if the run is hermetic, in place
+    /// of `~/.docker`.
+    pub(crate) fn docker_config(&self) -> Option<PathBuf> {
+        self.is_hermetic().then(|| self.root.join("docker"))
+    }
+
+    /// Returns the directory that the scratch files of the run are created in: the system's
+    /// temporary directory, unless the run is hermetic.
+    pub(crate) fn tmp_dir(&self) -> PathBuf {
+        if self.hermetic {
+            self.root.join("tmp")
+        } else {
+            std::env::temp_dir()
+        }
+    }
+
+    /// Creates a scratch directory within the [Self::tmp_dir], removed when dropped.
+    pub(crate) fn tempdir(&self) -> Result<TempDir> {
+        Ok(tempfile::tempdir_in(self.tmp_dir())?)
+    }
+
+    /// Creates a scratch file within the [Self::tmp_dir], removed when dropped.
+    pub(crate) fn tempfile(&self) -> Result<NamedTempFile> {
+        Ok(NamedTempFile::new_in(self.tmp_dir())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hermetic_state_dir() {
+        let local = StateDir::get();
+        assert!(!local.is_hermetic());
+        assert_eq!(local.tmp_dir(), std::env::temp_dir());
+        assert_eq!(local.docker_config(), None);
+
+        let root = tempfile::tempdir().unwrap();
+        let hermetic = StateDir {
+            root: root.path().to_path_buf(),
+            hermetic: true,
+        };
+        for path in [
+            hermetic.components_dir(),
+            hermetic.history_dir(),
+            hermetic.artifacts_dir(),
+            hermetic.scratch_dir(),
+            hermetic.registry_overlay(),
+            hermetic.docker_config().unwrap(),
+            hermetic.tmp_dir(),
+        ] {
+            assert!(path.starts_with(root.path()));
+        }
+        fs::create_dir_all(hermetic.tmp_dir()).unwrap();
+        assert!(hermetic
+            .tempdir()
+            .unwrap()
+            .path()
+            .starts_with(root.path().join("tmp")));
+    }
+}
@@ -0,0 +1,66 @@
+//! Contains the display types used to render durations and sizes consistently across `fpt`'s
+//! output, independent of the locale or terminal.
+
+use std::{fmt::Display, time::Duration};
+
+/// The binary size units, in increasing order of magnitude.
+const SIZE_UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// A duration, rendered with millisecond precision (e.g. `0.250s`, `2m05.000s`, `1h02m05.000s`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Elapsed(pub(crate) Duration);
+
+impl Display for Elapsed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let millis = self.0.as_millis();
+        let (hours, minutes) = (millis / 3_600_000, (millis / 60_000) % 60);
+        let (seconds, millis) = ((millis / 1000) % 60, millis % 1000);
+
+        if hours > 0 {
+            write!(f, "{hours}h{minutes:02}m{seconds:02}.{millis:03}s")
+        } else if minutes > 0 {
+            write!(f, "{minutes}m{seconds:02}.{millis:03}s")
+        } else {
+            write!(f, "{seconds}.{millis:03}s")
+        }
+    }
+}
+
+/// A size in bytes, rendered in binary units with two decimal places (e.g. `512 B`, `1.50 KiB`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Bytes(pub(crate) u64);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+
+        let mut size = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{size:.2} {}", SIZE_UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_units() {
+        assert_eq!(Elapsed(Duration::from_millis(250)).to_string(), "0.250s");
+        assert_eq!(Elapsed(Duration::from_secs(125)).to_string(), "2m05.000s");
+        assert_eq!(
+            Elapsed(Duration::from_secs(3725)).to_string(),
+            "1h02m05.000s"
+        );
+
+        assert_eq!(Bytes(512).to_string(), "512 B");
+        assert_eq!(Bytes(1536).to_string(), "1.50 KiB");
+        assert_eq!(Bytes(3 << 30).to_string(), "3.00 GiB");
+    }
+}